@@ -0,0 +1,364 @@
+//! A small terminal frontend for the chess engine, for playing
+//! human-vs-human or human-vs-engine games and quickly trying things
+//! out.
+//!
+//! Moves are entered in SAN (`Nf3`) or coordinate notation (`g1f3`).
+//! Type `help` to list the other commands.
+
+use chess_engine::eval::MaterialEvaluator;
+use chess_engine::game::{DrawReason, Game, GameResult, GameState};
+use chess_engine::notation::{san, uci};
+use chess_engine::pgn::PgnGame;
+use chess_engine::{search, Board, Color, Move};
+use std::io::{self, BufRead, Write};
+use std::{env, fs, process};
+
+const USAGE: &str = "usage: chess-cli [--engine white|black] [--depth N] [--fen FEN]";
+
+const HELP: &str = "\
+commands:
+  <move>        play a move, in SAN (Nf3) or coordinates (g1f3)
+  moves         list the legal moves
+  undo          take back the last move (and the engine's reply)
+  fen           print the current position as FEN
+  load <fen>    start a new game from a position
+  pgn [file]    print the game as PGN, or save it to a file
+  flip          turn the board around
+  help          show this message
+  quit          exit";
+
+// Options given on the command line
+#[derive(Debug, PartialEq)]
+struct Options {
+    engine: Option<Color>,
+    depth: u32,
+    fen: Option<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        engine: None,
+        depth: 3,
+        fen: None,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("`{}` needs a value", arg))
+        };
+        match arg.as_str() {
+            "--engine" => {
+                let color = value()?;
+                options.engine = Some(color.parse().map_err(|e| format!("{}", e))?);
+            }
+            "--depth" => {
+                let depth = value()?;
+                options.depth = depth
+                    .parse()
+                    .map_err(|_| format!("`{}` is not a valid depth", depth))?;
+            }
+            "--fen" => options.fen = Some(value()?.clone()),
+            _ => return Err(format!("unknown argument `{}`", arg)),
+        }
+    }
+    Ok(options)
+}
+
+// A line of input from the player
+#[derive(Debug, PartialEq)]
+enum Command<'a> {
+    Move(&'a str),
+    Moves,
+    Undo,
+    Fen,
+    Load(&'a str),
+    Pgn(Option<&'a str>),
+    Flip,
+    Help,
+    Quit,
+}
+
+fn parse_command(line: &str) -> Option<Command<'_>> {
+    let line = line.trim();
+    let (word, rest) = match line.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, Some(rest.trim())),
+        None => (line, None),
+    };
+    Some(match (word, rest) {
+        ("", _) => return None,
+        ("moves", None) => Command::Moves,
+        ("undo", None) => Command::Undo,
+        ("fen", None) => Command::Fen,
+        ("load", Some(fen)) => Command::Load(fen),
+        ("pgn", file) => Command::Pgn(file),
+        ("flip", None) => Command::Flip,
+        ("help", None) => Command::Help,
+        ("quit" | "exit", None) => Command::Quit,
+        (m, None) => Command::Move(m),
+        _ => return None,
+    })
+}
+
+// Read a move in either SAN or coordinate notation
+fn parse_move(board: &Board, s: &str) -> Option<Move> {
+    match uci::parse(board, s) {
+        Ok(m) if board.is_legal(m, board.turn()) => Some(m),
+        _ => san::parse(board, s).ok(),
+    }
+}
+
+struct Session {
+    game: Game,
+    engine: Option<Color>,
+    depth: u32,
+    perspective: Color,
+}
+
+impl Session {
+    fn new(options: Options) -> Result<Session, String> {
+        let game = match options.fen {
+            Some(fen) => Game::from_board(Board::load_fen(&fen).map_err(|e| format!("{}", e))?),
+            None => Game::new(),
+        };
+        Ok(Session {
+            game,
+            engine: options.engine,
+            depth: options.depth,
+            perspective: options.engine.map_or(Color::White, |c| c.opposite()),
+        })
+    }
+
+    fn is_over(&self) -> bool {
+        matches!(self.game.state(), GameState::Finished(_))
+    }
+
+    fn print_board(&self) {
+        println!(
+            "{}",
+            self.game.current_board().to_grid_string(self.perspective)
+        );
+        let turn = match self.game.next_player() {
+            Color::White => "White",
+            Color::Black => "Black",
+        };
+        match self.game.state() {
+            GameState::Ongoing { check: false } => println!("{} to move", turn),
+            GameState::Ongoing { check: true } => println!("{} to move, in check", turn),
+            GameState::Finished(GameResult::Win { .. }) => println!("Checkmate, {} lost", turn),
+            GameState::Finished(GameResult::Draw(DrawReason::Stalemate)) => println!("Stalemate"),
+            GameState::Finished(GameResult::Draw(DrawReason::FiftyMoveRule)) => {
+                println!("Draw by the fifty move rule")
+            }
+            GameState::Finished(GameResult::Draw(DrawReason::Agreement)) => {
+                println!("Draw by agreement")
+            }
+            GameState::Finished(GameResult::Draw(DrawReason::Adjudication)) => {
+                println!("Draw by adjudication")
+            }
+            GameState::Finished(GameResult::Draw(_)) => println!("Draw"),
+        }
+    }
+
+    // Play a move, printing it in SAN
+    fn play(&mut self, m: Move) {
+        let text = san::format(self.game.current_board(), m);
+        match self.game.next_player() {
+            Color::White => println!("White played {}", text),
+            Color::Black => println!("Black played {}", text),
+        }
+        let _ = self.game.make_move(m);
+    }
+
+    fn engine_move(&mut self) {
+        let board = *self.game.current_board();
+        let (_, line) = search::best_line(&board, &MaterialEvaluator, self.depth);
+        if let Some(&m) = line.first() {
+            self.play(m);
+        }
+    }
+
+    // Handle a command, returning whether to keep going
+    fn handle(&mut self, command: Command<'_>) -> bool {
+        match command {
+            Command::Move(s) => {
+                if self.is_over() {
+                    println!("The game is over, use `undo` or `load`");
+                    return true;
+                }
+                match parse_move(self.game.current_board(), s) {
+                    Some(m) => {
+                        self.play(m);
+                        if self.engine == Some(self.game.next_player()) && !self.is_over() {
+                            self.engine_move();
+                        }
+                        self.print_board();
+                    }
+                    None => println!("`{}` is not a legal move", s),
+                }
+            }
+            Command::Moves => {
+                let board = self.game.current_board();
+                let mut moves = board
+                    .get_all_legal_moves()
+                    .into_iter()
+                    .map(|m| san::format(board, m))
+                    .collect::<Vec<_>>();
+                moves.sort();
+                println!("{}", moves.join(" "));
+            }
+            Command::Undo => {
+                if self.game.undo_move().is_none() {
+                    println!("There is no move to take back");
+                } else if self.engine == Some(self.game.next_player()) {
+                    // take back the engine's reply as well, unless
+                    // the engine made the very first move
+                    if self.game.get_moves().is_empty() {
+                        self.engine_move();
+                    } else {
+                        let _ = self.game.undo_move();
+                    }
+                }
+                self.print_board();
+            }
+            Command::Fen => println!("{}", self.game.current_board()),
+            Command::Load(fen) => match Board::load_fen(fen) {
+                Ok(board) => {
+                    self.game = Game::from_board(board);
+                    if self.engine == Some(self.game.next_player()) && !self.is_over() {
+                        self.engine_move();
+                    }
+                    self.print_board();
+                }
+                Err(e) => println!("{}", e),
+            },
+            Command::Pgn(file) => {
+                let mut pgn = PgnGame::new(self.game.clone());
+                for (name, value) in &mut pgn.tags {
+                    match name.as_str() {
+                        "Event" => *value = "chess-cli game".to_string(),
+                        "White" | "Black" => {
+                            let color = if name == "White" {
+                                Color::White
+                            } else {
+                                Color::Black
+                            };
+                            *value = if self.engine == Some(color) {
+                                "chess-engine".to_string()
+                            } else {
+                                "Human".to_string()
+                            };
+                        }
+                        _ => (),
+                    }
+                }
+                match file {
+                    Some(file) => match fs::write(file, pgn.to_string()) {
+                        Ok(()) => println!("Saved the game to {}", file),
+                        Err(e) => println!("Couldn't save the game: {}", e),
+                    },
+                    None => print!("{}", pgn),
+                }
+            }
+            Command::Flip => {
+                self.perspective = self.perspective.opposite();
+                self.print_board();
+            }
+            Command::Help => println!("{}", HELP),
+            Command::Quit => return false,
+        }
+        true
+    }
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    let mut session = match Session::new(options) {
+        Ok(session) => session,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+
+    if session.engine == Some(session.game.next_player()) && !session.is_over() {
+        session.engine_move();
+    }
+    session.print_board();
+
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => (),
+        }
+        match parse_command(&line) {
+            Some(command) => {
+                if !session.handle(command) {
+                    break;
+                }
+            }
+            None if line.trim().is_empty() => (),
+            None => println!("Unknown command, type `help` for a list of commands"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_args, parse_command, parse_move, Command, Options};
+    use chess_engine::{Board, Color};
+
+    #[test]
+    fn commands() {
+        assert_eq!(parse_command("Nf3\n"), Some(Command::Move("Nf3")));
+        assert_eq!(parse_command(" undo "), Some(Command::Undo));
+        assert_eq!(parse_command("pgn"), Some(Command::Pgn(None)));
+        assert_eq!(
+            parse_command("pgn game.pgn"),
+            Some(Command::Pgn(Some("game.pgn")))
+        );
+        assert_eq!(
+            parse_command("load 8/8/8/8/8/8/8/K6k w - - 0 1"),
+            Some(Command::Load("8/8/8/8/8/8/8/K6k w - - 0 1"))
+        );
+        assert_eq!(parse_command(""), None);
+        assert_eq!(parse_command("undo twice"), None);
+    }
+
+    #[test]
+    fn args() {
+        let args = |s: &str| s.split_whitespace().map(String::from).collect::<Vec<_>>();
+
+        assert_eq!(
+            parse_args(&args("--engine black --depth 2")),
+            Ok(Options {
+                engine: Some(Color::Black),
+                depth: 2,
+                fen: None
+            })
+        );
+        assert!(parse_args(&args("--depth")).is_err());
+        assert!(parse_args(&args("--engine red")).is_err());
+        assert!(parse_args(&args("--colour white")).is_err());
+    }
+
+    #[test]
+    fn moves() {
+        let board = Board::default_board();
+
+        assert_eq!(parse_move(&board, "Nf3"), parse_move(&board, "g1f3"));
+        assert!(parse_move(&board, "Nf3").is_some());
+        assert!(parse_move(&board, "e2e5").is_none());
+    }
+}
@@ -0,0 +1,353 @@
+//! Module for analysing finished or ongoing games move by move,
+//! scoring every move against the best one found by a search and
+//! classifying the mistakes, for frontends to show as a game review.
+//! It also has one-call helpers for teaching frontends, [`hint`] and
+//! [`is_blunder`], which look at a single position.
+
+use crate::board::{Board, Move};
+use crate::eval::{self, Evaluator, MaterialEvaluator};
+use crate::game::Game;
+use crate::piece::Color;
+use crate::puzzle::BLUNDER_THRESHOLD;
+use crate::search::{self, MATE_SCORE};
+
+/// How much (in centipawns) a move has to lose compared to the best
+/// move to be classified as an inaccuracy.
+pub const INACCURACY_THRESHOLD: i32 = 50;
+
+/// How much (in centipawns) a move has to lose compared to the best
+/// move to be classified as a mistake. Losing at least
+/// [`BLUNDER_THRESHOLD`] makes it a blunder, just like for
+/// [`find_puzzles`](crate::puzzle::find_puzzles).
+pub const MISTAKE_THRESHOLD: i32 = 100;
+
+/// Scores are capped at this many centipawns when working out how
+/// much a move lost, so that e.g. going from a won position to a
+/// mate doesn't count as a loss of a million centipawns.
+pub const SCORE_CAP: i32 = 1000;
+
+/// How many plies deep [`is_blunder`] searches
+pub const BLUNDER_CHECK_DEPTH: u32 = 3;
+
+// Scores this close to `MATE_SCORE` are forced mates
+const MATE_BOUND: i32 = MATE_SCORE - 1000;
+
+/// How good a move was compared to the best move
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MoveClass {
+    /// The move was as good as the best move found
+    Best,
+    /// The move lost less than [`INACCURACY_THRESHOLD`]
+    Good,
+    /// The move lost at least [`INACCURACY_THRESHOLD`]
+    Inaccuracy,
+    /// The move lost at least [`MISTAKE_THRESHOLD`]
+    Mistake,
+    /// The move lost at least [`BLUNDER_THRESHOLD`]
+    Blunder,
+}
+
+impl MoveClass {
+    /// Classify a move by its centipawn loss
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::analysis::MoveClass;
+    /// assert_eq!(MoveClass::from_loss(0), MoveClass::Best);
+    /// assert_eq!(MoveClass::from_loss(70), MoveClass::Inaccuracy);
+    /// assert_eq!(MoveClass::from_loss(900), MoveClass::Blunder);
+    /// ```
+    pub fn from_loss(loss: i32) -> MoveClass {
+        if loss >= BLUNDER_THRESHOLD {
+            MoveClass::Blunder
+        } else if loss >= MISTAKE_THRESHOLD {
+            MoveClass::Mistake
+        } else if loss >= INACCURACY_THRESHOLD {
+            MoveClass::Inaccuracy
+        } else if loss > 0 {
+            MoveClass::Good
+        } else {
+            MoveClass::Best
+        }
+    }
+
+    /// The annotation symbol used for the class in PGN, e.g. `??` for
+    /// a blunder, which is empty for good moves
+    pub fn symbol(self) -> &'static str {
+        match self {
+            MoveClass::Best | MoveClass::Good => "",
+            MoveClass::Inaccuracy => "?!",
+            MoveClass::Mistake => "?",
+            MoveClass::Blunder => "??",
+        }
+    }
+}
+
+/// The analysis of a single move in a game
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MoveAnalysis {
+    /// The index of the move in the game, starting from 0
+    pub ply: usize,
+    /// The player who made the move
+    pub color: Color,
+    /// The move that was played
+    pub played: Move,
+    /// The best move found by the search
+    pub best: Move,
+    /// The score of the position before the move with the best move
+    /// played, for the player making the move
+    pub best_score: i32,
+    /// The score of the position after the played move, for the
+    /// player who made it
+    pub played_score: i32,
+    /// How many centipawns the move lost compared to the best move,
+    /// with the scores capped at [`SCORE_CAP`]
+    pub loss: i32,
+    /// How good the move was
+    pub class: MoveClass,
+    /// Whether the player had a forced mate, and the move let it slip
+    pub missed_mate: bool,
+}
+
+/// The analysis of a whole game, as produced by [`analyze`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnalysisReport {
+    /// The analysis of each move, in the order they were played
+    pub moves: Vec<MoveAnalysis>,
+    /// How many plies deep the moves were searched
+    pub depth: u32,
+}
+
+impl AnalysisReport {
+    /// Count the moves of a player that got a certain class
+    pub fn count(&self, color: Color, class: MoveClass) -> usize {
+        self.moves
+            .iter()
+            .filter(|m| m.color == color && m.class == class)
+            .count()
+    }
+
+    /// Get the average centipawn loss of a player's moves, or 0 if
+    /// they haven't made any
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::analysis;
+    /// # use chess_engine::eval::MaterialEvaluator;
+    /// # use chess_engine::game::Game;
+    /// # use chess_engine::piece::Color;
+    /// let mut game = Game::new();
+    /// // 1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6?? 4. Qxf7#
+    /// for m in &["e2e4", "e7e5", "d1h5", "b8c6", "f1c4", "g8f6", "h5f7"] {
+    ///     game.make_move(m.parse().unwrap());
+    /// }
+    /// let report = analysis::analyze(&game, &MaterialEvaluator, 2);
+    ///
+    /// assert_eq!(report.average_centipawn_loss(Color::White), 0.0);
+    /// assert!(report.average_centipawn_loss(Color::Black) > 100.0);
+    /// assert!(report.accuracy(Color::White) > report.accuracy(Color::Black));
+    /// ```
+    #[allow(clippy::cast_precision_loss)]
+    pub fn average_centipawn_loss(&self, color: Color) -> f32 {
+        let losses: Vec<_> = self.player_moves(color).map(|m| m.loss).collect();
+        if losses.is_empty() {
+            return 0.0;
+        }
+        losses.iter().sum::<i32>() as f32 / losses.len() as f32
+    }
+
+    /// Get the accuracy of a player's moves as a percentage, the
+    /// average of the accuracy of each move. A move's accuracy goes
+    /// down from 100 the more of the expected score it gives away, as
+    /// given by [`eval::expected_score`], with the same curve as
+    /// popular analysis sites, so a player who hasn't moved has an
+    /// accuracy of 100.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn accuracy(&self, color: Color) -> f32 {
+        let accuracies: Vec<_> = self
+            .player_moves(color)
+            .map(|m| {
+                let ply = m.ply as u32;
+                let before = eval::expected_score(m.best_score, ply) * 100.0;
+                let after = eval::expected_score(m.played_score, ply + 1) * 100.0;
+                let lost = (before - after).max(0.0);
+                (103.166_8 * (-0.043_54 * lost).exp() - 3.166_9).clamp(0.0, 100.0)
+            })
+            .collect();
+        if accuracies.is_empty() {
+            return 100.0;
+        }
+        accuracies.iter().sum::<f32>() / accuracies.len() as f32
+    }
+
+    // Iterate over the analysis of a player's moves
+    fn player_moves(&self, color: Color) -> impl Iterator<Item = &MoveAnalysis> {
+        self.moves.iter().filter(move |m| m.color == color)
+    }
+}
+
+/// Analyse every move of a game by searching the position before it
+/// `depth` plies deep with `evaluator`, and comparing the move
+/// played with the best one found. A depth of 0 is taken as 1.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::analysis::{self, MoveClass};
+/// # use chess_engine::eval::MaterialEvaluator;
+/// # use chess_engine::game::Game;
+/// # use chess_engine::piece::Color;
+/// let mut game = Game::new();
+/// // 1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6?? 4. Qxf7#
+/// for m in &["e2e4", "e7e5", "d1h5", "b8c6", "f1c4", "g8f6", "h5f7"] {
+///     game.make_move(m.parse().unwrap());
+/// }
+/// let report = analysis::analyze(&game, &MaterialEvaluator, 2);
+///
+/// assert_eq!(report.moves.len(), 7);
+/// assert_eq!(report.moves[5].class, MoveClass::Blunder);
+/// assert_eq!(report.moves[6].class, MoveClass::Best);
+/// assert_eq!(report.count(Color::Black, MoveClass::Blunder), 1);
+/// ```
+pub fn analyze<E: Evaluator + ?Sized>(game: &Game, evaluator: &E, depth: u32) -> AnalysisReport {
+    let depth = depth.max(1);
+    let boards: Vec<_> = game.boards().collect();
+
+    let moves = boards
+        .iter()
+        .zip(game.get_moves())
+        .zip(&boards[1..])
+        .enumerate()
+        .map(|(ply, ((before, &played), after))| {
+            let (best_score, line) = search::best_line(before, evaluator, depth);
+            let best = line.first().copied().unwrap_or(played);
+            // the best move is searched just as deep inside `best_line`,
+            // so it gets exactly the same score
+            let played_score = if played == best {
+                best_score
+            } else {
+                -search::best_line(after, evaluator, depth - 1).0
+            };
+
+            let capped = |score: i32| score.clamp(-SCORE_CAP, SCORE_CAP);
+            let loss = (capped(best_score) - capped(played_score)).max(0);
+            MoveAnalysis {
+                ply,
+                color: before.turn(),
+                played,
+                best,
+                best_score,
+                played_score,
+                loss,
+                class: MoveClass::from_loss(loss),
+                missed_mate: best_score > MATE_BOUND && played_score <= MATE_BOUND,
+            }
+        })
+        .collect();
+
+    AnalysisReport { moves, depth }
+}
+
+/// Suggest a move for the player to move, the best one found by
+/// searching `depth` plies deep with [`MaterialEvaluator`], so deeper
+/// searches give stronger hints. A depth of 0 is taken as 1. Returns
+/// [`None`] if the game is over.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::analysis;
+/// # use chess_engine::board::Board;
+/// // the queen on d5 is hanging
+/// let board = Board::load_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+///
+/// assert_eq!(analysis::hint(&board, 2), Some("d1d5".parse().unwrap()));
+/// ```
+pub fn hint(board: &Board, depth: u32) -> Option<Move> {
+    search::best_line(board, &MaterialEvaluator, depth.max(1))
+        .1
+        .first()
+        .copied()
+}
+
+/// Check whether a move loses at least `threshold` centipawns compared
+/// to the best move, searching [`BLUNDER_CHECK_DEPTH`] plies deep with
+/// [`MaterialEvaluator`], e.g. to offer a takeback before the move is
+/// made. The scores are capped at [`SCORE_CAP`] like in [`analyze`],
+/// and illegal moves are never blunders.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::analysis;
+/// # use chess_engine::board::Board;
+/// # use chess_engine::puzzle::BLUNDER_THRESHOLD;
+/// let board = Board::load_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+///
+/// // walking away from the free queen
+/// assert!(analysis::is_blunder(&board, "d1c1".parse().unwrap(), BLUNDER_THRESHOLD));
+/// assert!(!analysis::is_blunder(&board, "d1d5".parse().unwrap(), BLUNDER_THRESHOLD));
+/// ```
+pub fn is_blunder(board: &Board, m: Move, threshold: i32) -> bool {
+    let after = match board.perform_move(m) {
+        Some(after) => after,
+        None => return false,
+    };
+    let (best_score, _) = search::best_line(board, &MaterialEvaluator, BLUNDER_CHECK_DEPTH);
+    let played_score = -search::best_line(&after, &MaterialEvaluator, BLUNDER_CHECK_DEPTH - 1).0;
+
+    let capped = |score: i32| score.clamp(-SCORE_CAP, SCORE_CAP);
+    capped(best_score) - capped(played_score) >= threshold
+}
+
+#[cfg(all(test, feature = "fen"))]
+mod tests {
+    use super::{analyze, hint, is_blunder, MoveClass};
+    use crate::board::Board;
+    use crate::eval::MaterialEvaluator;
+    use crate::game::Game;
+    use crate::piece::Color;
+
+    #[test]
+    fn missed_mate() {
+        let board = Board::load_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let mut game = Game::from_board(board);
+        let _ = game.make_move("a1a7".parse().unwrap());
+
+        let report = analyze(&game, &MaterialEvaluator, 1);
+        let m = &report.moves[0];
+        assert!(m.missed_mate);
+        assert_eq!(m.best, "a1a8".parse().unwrap());
+        assert_eq!(m.class, MoveClass::Blunder);
+        // a rook against three pawns is only 200 up
+        assert_eq!(m.loss, super::SCORE_CAP - 200);
+    }
+
+    #[test]
+    fn equal_moves_are_best() {
+        let mut game = Game::new();
+        let _ = game.make_move("g1f3".parse().unwrap());
+
+        let report = analyze(&game, &MaterialEvaluator, 2);
+        assert_eq!(report.moves[0].loss, 0);
+        assert_eq!(report.moves[0].class, MoveClass::Best);
+        assert!(!report.moves[0].missed_mate);
+        assert!((report.accuracy(Color::White) - 100.0).abs() < 0.01);
+        assert!((report.accuracy(Color::Black) - 100.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn hints_and_blunders() {
+        let board = Board::load_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        assert_eq!(hint(&board, 1), Some("a1a8".parse().unwrap()));
+        assert!(is_blunder(&board, "a1a7".parse().unwrap(), 500));
+        assert!(!is_blunder(&board, "a1a8".parse().unwrap(), 1));
+        // not even a legal move
+        assert!(!is_blunder(&board, "a1b2".parse().unwrap(), 1));
+
+        let mate = Board::load_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1").unwrap();
+        assert_eq!(hint(&mate, 3), None);
+    }
+}
@@ -0,0 +1,222 @@
+//! Module with `Arbitrary` implementations for property-based
+//! testing with [`proptest`] or [`quickcheck`], each behind a feature
+//! of the same name.
+//!
+//! Boards are generated by playing a random number of random moves
+//! from the default position, so they're always reachable in a real
+//! game. With proptest, generated boards shrink towards positions
+//! closer to the start of the game.
+
+use crate::board::{Board, Move};
+use crate::game::Game;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+// The most random moves played to generate a board
+const MAX_PLIES: u32 = 120;
+
+/// A board together with one of its legal moves, for testing
+/// properties of moves, which can't be generated on their own.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BoardWithMove {
+    /// The board the move is played on
+    pub board: Board,
+    /// A legal move on the board
+    pub legal_move: Move,
+}
+
+// Play up to `plies` random moves, seeded with `seed`
+fn random_game(seed: u64, plies: u32) -> (Game, StdRng) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut game = Game::new();
+    let _ = game.random_playout(&mut rng, plies);
+    (game, rng)
+}
+
+fn random_board(seed: u64, plies: u32) -> Board {
+    *random_game(seed, plies).0.current_board()
+}
+
+fn random_board_with_move(seed: u64, plies: u32) -> BoardWithMove {
+    let (game, mut rng) = random_game(seed, plies);
+    // the playout might have ended the game, but the default board
+    // always has legal moves to fall back on
+    game.get_boards()
+        .iter()
+        .rev()
+        .find_map(|board| {
+            let legal_move = *board.get_all_legal_moves().choose(&mut rng)?;
+            Some(BoardWithMove {
+                board: *board,
+                legal_move,
+            })
+        })
+        .expect("the default board has legal moves")
+}
+
+#[cfg(feature = "proptest")]
+mod proptest_impls {
+    use super::{random_board, random_board_with_move, BoardWithMove, MAX_PLIES};
+    use crate::board::{Board, SquareSpec};
+    use crate::piece::{Color, PieceType};
+    use proptest::prelude::*;
+
+    impl Arbitrary for Board {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Board>;
+
+        fn arbitrary_with((): ()) -> Self::Strategy {
+            (any::<u64>(), 0..=MAX_PLIES)
+                .prop_map(|(seed, plies)| random_board(seed, plies))
+                .boxed()
+        }
+    }
+
+    impl Arbitrary for BoardWithMove {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<BoardWithMove>;
+
+        fn arbitrary_with((): ()) -> Self::Strategy {
+            (any::<u64>(), 0..=MAX_PLIES)
+                .prop_map(|(seed, plies)| random_board_with_move(seed, plies))
+                .boxed()
+        }
+    }
+
+    impl Arbitrary for SquareSpec {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<SquareSpec>;
+
+        fn arbitrary_with((): ()) -> Self::Strategy {
+            (0..8_u32, 0..8_u32)
+                .prop_map(|(rank, file)| SquareSpec::new(rank, file))
+                .boxed()
+        }
+    }
+
+    impl Arbitrary for Color {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Color>;
+
+        fn arbitrary_with((): ()) -> Self::Strategy {
+            prop_oneof![Just(Color::White), Just(Color::Black)].boxed()
+        }
+    }
+
+    impl Arbitrary for PieceType {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<PieceType>;
+
+        fn arbitrary_with((): ()) -> Self::Strategy {
+            prop_oneof![
+                Just(PieceType::Pawn),
+                Just(PieceType::Rook),
+                Just(PieceType::Bishop),
+                Just(PieceType::Queen),
+                Just(PieceType::Knight),
+                Just(PieceType::King),
+            ]
+            .boxed()
+        }
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+mod quickcheck_impls {
+    use super::{random_board, random_board_with_move, BoardWithMove, MAX_PLIES};
+    use crate::board::{Board, SquareSpec};
+    use crate::piece::{Color, PieceType};
+    use quickcheck::{Arbitrary, Gen};
+
+    impl Arbitrary for Board {
+        fn arbitrary(g: &mut Gen) -> Board {
+            random_board(u64::arbitrary(g), u32::arbitrary(g) % (MAX_PLIES + 1))
+        }
+    }
+
+    impl Arbitrary for BoardWithMove {
+        fn arbitrary(g: &mut Gen) -> BoardWithMove {
+            random_board_with_move(u64::arbitrary(g), u32::arbitrary(g) % (MAX_PLIES + 1))
+        }
+    }
+
+    impl Arbitrary for SquareSpec {
+        fn arbitrary(g: &mut Gen) -> SquareSpec {
+            SquareSpec::new(u32::arbitrary(g) % 8, u32::arbitrary(g) % 8)
+        }
+    }
+
+    impl Arbitrary for Color {
+        fn arbitrary(g: &mut Gen) -> Color {
+            *g.choose(&[Color::White, Color::Black]).unwrap()
+        }
+    }
+
+    impl Arbitrary for PieceType {
+        fn arbitrary(g: &mut Gen) -> PieceType {
+            use PieceType::*;
+            *g.choose(&[Pawn, Rook, Bishop, Queen, Knight, King])
+                .unwrap()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BoardWithMove;
+    use crate::board::Board;
+
+    #[cfg(feature = "proptest")]
+    proptest::proptest! {
+        // every case plays a whole random game, so keep it quick
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn generated_moves_are_legal(b in proptest::prelude::any::<BoardWithMove>()) {
+            proptest::prop_assert!(b.board.get_all_legal_moves().contains(&b.legal_move));
+            proptest::prop_assert!(b.board.perform_move(b.legal_move).is_some());
+        }
+
+        #[test]
+        fn fen_round_trips(board in proptest::prelude::any::<Board>()) {
+            proptest::prop_assert_eq!(Board::load_fen(&board.to_string()).unwrap(), board);
+        }
+
+        #[test]
+        fn staged_generation_matches(board in proptest::prelude::any::<Board>()) {
+            let mut all = board.get_all_legal_moves();
+            all.sort();
+            let mut staged = board.generate_captures();
+            staged.append(&mut board.generate_quiets());
+            staged.sort();
+            proptest::prop_assert_eq!(&staged, &all);
+
+            let mut evasions = board.generate_evasions();
+            evasions.sort();
+            if board.in_check() {
+                proptest::prop_assert_eq!(evasions, all);
+            } else {
+                proptest::prop_assert!(evasions.is_empty());
+            }
+        }
+
+        #[test]
+        fn waiting_side_is_not_in_check(board in proptest::prelude::any::<Board>()) {
+            let waiting = board.turn().opposite();
+            let king = board.king(waiting).unwrap();
+            proptest::prop_assert!(!board.is_threatened(waiting, king));
+        }
+    }
+
+    #[cfg(feature = "quickcheck")]
+    quickcheck::quickcheck! {
+        fn quickcheck_moves_are_legal(b: BoardWithMove) -> bool {
+            b.board.is_legal(b.legal_move, b.board.turn())
+        }
+
+        fn quickcheck_boards_have_kings(board: Board) -> bool {
+            board.king(board.turn()).is_some() && board.king(board.turn().opposite()).is_some()
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use super::{Board, Move, SquareSpec};
+use std::ops::{Deref, Range};
+
+/// A board along with all of its legal moves, generated once when it's
+/// created, so that asking for the moves of a square again and again
+/// is cheap. This is meant for UIs that look up the moves of the
+/// hovered or selected piece every frame, where
+/// [`Board::get_legal_moves`] would generate them every time.
+///
+/// It dereferences to the [`Board`], so everything else about the
+/// position is available as usual. Create a new one whenever the
+/// position changes.
+///
+/// # Examples
+/// ```
+/// # use chess_engine::board::{AnalyzedBoard, Board, SquareSpec};
+/// let board = AnalyzedBoard::new(Board::default_board());
+///
+/// assert_eq!(board.legal_moves(SquareSpec::G1).len(), 2);
+/// assert!(board.legal_moves(SquareSpec::E4).is_empty());
+/// assert_eq!(board.all_legal_moves().len(), 20);
+/// assert!(board.is_legal("e2e4".parse().unwrap()));
+/// // the usual methods still work
+/// assert!(!board.in_check());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnalyzedBoard {
+    board: Board,
+    // all the legal moves, grouped by the square they're made from
+    moves: Vec<Move>,
+    // the range of `moves` from each square, by square index
+    ranges: [Range<u16>; 64],
+}
+
+impl AnalyzedBoard {
+    /// Generate the legal moves of `board`
+    pub fn new(board: Board) -> AnalyzedBoard {
+        const EMPTY: Range<u16> = 0..0;
+        let mut moves = Vec::new();
+        let mut ranges = [EMPTY; 64];
+        for sq in SquareSpec::all() {
+            let start = moves.len() as u16;
+            moves.append(&mut board.get_legal_moves(sq));
+            ranges[sq.index() as usize] = start..moves.len() as u16;
+        }
+        AnalyzedBoard {
+            board,
+            moves,
+            ranges,
+        }
+    }
+
+    /// Get the board
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Get the legal moves for the piece on this square, the same as
+    /// [`Board::get_legal_moves`]
+    pub fn legal_moves(&self, sq: SquareSpec) -> &[Move] {
+        let Range { start, end } = self.ranges[sq.index() as usize];
+        &self.moves[start as usize..end as usize]
+    }
+
+    /// Get all the legal moves, the same as
+    /// [`Board::get_all_legal_moves`]
+    pub fn all_legal_moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// Whether `m` is a legal move
+    pub fn is_legal(&self, m: Move) -> bool {
+        self.legal_moves(m.from(self.board.turn())).contains(&m)
+    }
+}
+
+impl From<Board> for AnalyzedBoard {
+    fn from(board: Board) -> AnalyzedBoard {
+        AnalyzedBoard::new(board)
+    }
+}
+
+impl Deref for AnalyzedBoard {
+    type Target = Board;
+
+    fn deref(&self) -> &Board {
+        &self.board
+    }
+}
+
+#[cfg(all(test, feature = "fen"))]
+mod tests {
+    use super::AnalyzedBoard;
+    use crate::board::{Board, SquareSpec};
+
+    #[test]
+    fn matches_board() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ];
+        for fen in &fens {
+            let board = Board::load_fen(fen).unwrap();
+            let analyzed = AnalyzedBoard::new(board);
+            for sq in SquareSpec::all() {
+                assert_eq!(analyzed.legal_moves(sq), &board.get_legal_moves(sq)[..]);
+            }
+            assert_eq!(analyzed.all_legal_moves(), &board.get_all_legal_moves()[..]);
+            assert!(analyzed
+                .all_legal_moves()
+                .iter()
+                .all(|&m| analyzed.is_legal(m)));
+        }
+    }
+}
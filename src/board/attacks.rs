@@ -0,0 +1,268 @@
+//! Low-level functions for the squares pieces attack, for tools that
+//! reason about attacks without generating whole moves.
+//!
+//! The occupancy of the board is given as a 64-bit mask with a bit set
+//! for each occupied square, at the square's [index](SquareSpec::index),
+//! as produced by [`Board::occupancy`]. The color of the pieces on the
+//! squares doesn't matter here, so the attacked squares include the
+//! squares of pieces of the same color, i.e. the pieces they defend.
+//!
+//! The squares knights and kings attack don't depend on the occupancy,
+//! so they're looked up in [`KNIGHT_ATTACKS`] and [`KING_ATTACKS`],
+//! which are built at compile time.
+
+use super::{Board, Direction, SquareDiff, SquareSpec};
+use crate::piece::{Color, PieceType};
+
+// The steps a knight can take
+const KNIGHT_STEPS: [(i32, i32); 8] = [
+    (2, 1),
+    (2, -1),
+    (-2, 1),
+    (-2, -1),
+    (1, 2),
+    (1, -2),
+    (-1, 2),
+    (-1, -2),
+];
+
+// The steps a king can take
+const KING_STEPS: [(i32, i32); 8] = [
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, -1),
+    (0, 1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+/// The squares a knight attacks from each square, as masks indexed by
+/// [`SquareSpec::index`] like the occupancy
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::{attacks, SquareSpec};
+/// let from_a1 = attacks::KNIGHT_ATTACKS[SquareSpec::A1.index() as usize];
+///
+/// assert_eq!(from_a1, 1 << SquareSpec::B3.index() | 1 << SquareSpec::C2.index());
+/// ```
+pub const KNIGHT_ATTACKS: [u64; 64] = step_table(&KNIGHT_STEPS);
+
+/// The squares a king attacks from each square, as masks indexed by
+/// [`SquareSpec::index`] like the occupancy
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::{attacks, SquareSpec};
+/// assert_eq!(attacks::KING_ATTACKS[SquareSpec::E4.index() as usize].count_ones(), 8);
+/// assert_eq!(attacks::KING_ATTACKS[SquareSpec::H8.index() as usize].count_ones(), 3);
+/// ```
+pub const KING_ATTACKS: [u64; 64] = step_table(&KING_STEPS);
+
+// Build a table of the squares reached from each square with each of
+// the steps that stay on the board
+const fn step_table(steps: &[(i32, i32); 8]) -> [u64; 64] {
+    let mut table = [0; 64];
+    let mut from = 0;
+    while from < 64 {
+        let (rank, file) = ((from / 8) as i32, (from % 8) as i32);
+        let mut i = 0;
+        while i < steps.len() {
+            let (to_rank, to_file) = (rank + steps[i].0, file + steps[i].1);
+            if to_rank >= 0 && to_rank < 8 && to_file >= 0 && to_file < 8 {
+                table[from] |= 1 << (to_rank * 8 + to_file);
+            }
+            i += 1;
+        }
+        from += 1;
+    }
+    table
+}
+
+/// Iterate over the squares in a mask like the occupancy or the
+/// attack tables, in the order of their indices
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::{attacks, SquareSpec};
+/// let squares: Vec<_> =
+///     attacks::squares(attacks::KNIGHT_ATTACKS[SquareSpec::H1.index() as usize]).collect();
+///
+/// assert_eq!(squares, [SquareSpec::F2, SquareSpec::G3]);
+/// ```
+pub fn squares(mut mask: u64) -> impl Iterator<Item = SquareSpec> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let index = mask.trailing_zeros() as u8;
+        mask &= mask - 1;
+        SquareSpec::from_index(index)
+    })
+}
+
+/// Iterate over the squares a piece of type `piece` and color `color`
+/// on `from` attacks, given the occupancy of the board. Pawns attack
+/// both squares diagonally in front of them, whether they're occupied
+/// or not, and sliders attack up to and including the first occupied
+/// square in each direction.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::{attacks, Board, SquareSpec};
+/// # use chess_engine::piece::{Color, PieceType};
+/// let board = Board::default_board();
+/// let attacked: Vec<_> =
+///     attacks::piece_attacks(PieceType::Rook, Color::White, SquareSpec::A1, board.occupancy())
+///         .collect();
+///
+/// // the rook is boxed in by the a2 pawn and the b1 knight
+/// assert_eq!(attacked, [SquareSpec::A2, SquareSpec::B1]);
+///
+/// let mut attacked: Vec<_> =
+///     attacks::piece_attacks(PieceType::Pawn, Color::Black, SquareSpec::E7, 0).collect();
+/// attacked.sort();
+/// assert_eq!(attacked, [SquareSpec::D6, SquareSpec::F6]);
+/// ```
+pub fn piece_attacks(
+    piece: PieceType,
+    color: Color,
+    from: SquareSpec,
+    occupancy: u64,
+) -> impl Iterator<Item = SquareSpec> {
+    let squares: Vec<_> = match piece {
+        PieceType::Pawn => {
+            let d_rank = match color {
+                Color::White => 1,
+                Color::Black => -1,
+            };
+            [-1, 1]
+                .iter()
+                .filter_map(|&d_file| from.checked_add(SquareDiff { d_rank, d_file }))
+                .collect()
+        }
+        PieceType::Knight => squares(KNIGHT_ATTACKS[from.index() as usize]).collect(),
+        PieceType::King => squares(KING_ATTACKS[from.index() as usize]).collect(),
+        PieceType::Rook => slides(from, &Direction::ORTHOGONAL, occupancy),
+        PieceType::Bishop => slides(from, &Direction::DIAGONAL, occupancy),
+        PieceType::Queen => slides(from, &Direction::ALL, occupancy),
+    };
+    squares.into_iter()
+}
+
+/// Iterate over the squares a slider on `from` attacks in one
+/// direction, given the occupancy of the board: the squares up to
+/// and including the first occupied one.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::{attacks, Direction, SquareSpec};
+/// let occupancy = 1 << SquareSpec::E5.index();
+/// let ray: Vec<_> = attacks::ray_attacks(SquareSpec::E2, Direction::North, occupancy).collect();
+///
+/// assert_eq!(ray, [SquareSpec::E3, SquareSpec::E4, SquareSpec::E5]);
+/// ```
+pub fn ray_attacks(
+    from: SquareSpec,
+    direction: Direction,
+    occupancy: u64,
+) -> impl Iterator<Item = SquareSpec> {
+    let mut blocked = false;
+    from.ray(direction).take_while(move |sq| {
+        let attacked = !blocked;
+        blocked = blocked || occupancy & (1 << sq.index()) != 0;
+        attacked
+    })
+}
+
+fn slides(from: SquareSpec, directions: &[Direction], occupancy: u64) -> Vec<SquareSpec> {
+    directions
+        .iter()
+        .flat_map(|&direction| ray_attacks(from, direction, occupancy))
+        .collect()
+}
+
+impl Board {
+    /// Get the occupancy of the board as a mask with a bit set for
+    /// each square with a piece on it, at the square's
+    /// [index](SquareSpec::index), as used in [`attacks`](super::attacks)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::board::{Board, SquareSpec};
+    /// let occupancy = Board::default_board().occupancy();
+    ///
+    /// assert_eq!(occupancy.count_ones(), 32);
+    /// assert_ne!(occupancy & (1 << SquareSpec::E2.index()), 0);
+    /// assert_eq!(occupancy & (1 << SquareSpec::E4.index()), 0);
+    /// ```
+    pub fn occupancy(&self) -> u64 {
+        SquareSpec::all()
+            .filter(|&sq| self[sq].is_some())
+            .fold(0, |mask, sq| mask | 1 << sq.index())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{piece_attacks, squares, KING_ATTACKS};
+    #[cfg(feature = "fen")]
+    use crate::board::Board;
+    use crate::board::{Direction, SquareSpec};
+    use crate::piece::{Color, PieceType};
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn blocked_sliders() {
+        let board = Board::load_fen("4k3/8/8/3p4/8/1N1Q4/8/4K3 w - - 0 1").unwrap();
+        let mut attacked: Vec<_> = piece_attacks(
+            PieceType::Queen,
+            Color::White,
+            SquareSpec::D3,
+            board.occupancy(),
+        )
+        .collect();
+        attacked.sort();
+
+        // blocked by the pawn on d5 and the knight on b3, but not by
+        // the empty squares around them
+        assert!(attacked.contains(&SquareSpec::D5));
+        assert!(!attacked.contains(&SquareSpec::D6));
+        assert!(attacked.contains(&SquareSpec::B3));
+        assert!(!attacked.contains(&SquareSpec::A3));
+        assert!(attacked.contains(&SquareSpec::H7));
+        assert_eq!(attacked.len(), 21);
+    }
+
+    #[test]
+    fn edge_pawns_and_knights() {
+        let pawn: Vec<_> =
+            piece_attacks(PieceType::Pawn, Color::White, SquareSpec::H2, 0).collect();
+        assert_eq!(pawn, [SquareSpec::G3]);
+
+        let knight = piece_attacks(PieceType::Knight, Color::Black, SquareSpec::A8, 0).count();
+        assert_eq!(knight, 2);
+    }
+
+    #[test]
+    fn king_table() {
+        for from in SquareSpec::all() {
+            let mut expected: Vec<_> = Direction::ALL
+                .iter()
+                .filter_map(|direction| from.checked_add(direction.diff()))
+                .collect();
+            expected.sort();
+            let table: Vec<_> = squares(KING_ATTACKS[from.index() as usize]).collect();
+
+            assert_eq!(table, expected, "king on {}", from);
+        }
+    }
+}
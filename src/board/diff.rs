@@ -0,0 +1,129 @@
+use super::{Board, SquareSpec};
+use crate::piece::Piece;
+
+/// A change to a single square between two boards, as produced by
+/// [`Board::diff`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SquareChange {
+    /// A piece appeared on a previously empty square
+    Appeared {
+        /// The square the piece appeared on
+        square: SquareSpec,
+        /// The piece that appeared
+        piece: Piece,
+    },
+    /// The piece on a square disappeared, leaving it empty
+    Disappeared {
+        /// The square that was emptied
+        square: SquareSpec,
+        /// The piece that disappeared
+        piece: Piece,
+    },
+    /// The piece on a square was replaced by a different piece, e.g.
+    /// by a capture
+    Changed {
+        /// The square that changed
+        square: SquareSpec,
+        /// The piece that was on the square before
+        from: Piece,
+        /// The piece that's on the square now
+        to: Piece,
+    },
+}
+
+impl SquareChange {
+    /// Get the square this change happened on
+    pub fn square(&self) -> SquareSpec {
+        match *self {
+            SquareChange::Appeared { square, .. }
+            | SquareChange::Disappeared { square, .. }
+            | SquareChange::Changed { square, .. } => square,
+        }
+    }
+}
+
+impl Board {
+    /// Get all the squares that differ between this board and
+    /// `other`, ordered by rank and then file. Only the pieces are
+    /// compared, not the turn, castling rights or clocks.
+    ///
+    /// This is mainly intended for animating the transition between
+    /// two boards, which is easier than working it out from the
+    /// [`Move`](super::Move) for castling and en passant.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::board::{Board, Castling, Move, SquareChange};
+    /// # use chess_engine::piece::{Color, Piece, PieceType};
+    /// let before = Board::load_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+    /// let after = before.perform_move(Move::Castling(Castling::Short)).unwrap();
+    /// let king = Piece::new(PieceType::King, Color::White);
+    /// let rook = Piece::new(PieceType::Rook, Color::White);
+    ///
+    /// assert_eq!(
+    ///     before.diff(&after),
+    ///     vec![
+    ///         SquareChange::Disappeared { square: "e1".parse().unwrap(), piece: king },
+    ///         SquareChange::Appeared { square: "f1".parse().unwrap(), piece: rook },
+    ///         SquareChange::Appeared { square: "g1".parse().unwrap(), piece: king },
+    ///         SquareChange::Disappeared { square: "h1".parse().unwrap(), piece: rook },
+    ///     ]
+    /// );
+    /// ```
+    pub fn diff(&self, other: &Board) -> Vec<SquareChange> {
+        let mut changes = Vec::new();
+
+        for (rank, (old_row, new_row)) in self.board.iter().zip(other.board.iter()).enumerate() {
+            for (file, (&old, &new)) in old_row.iter().zip(new_row.iter()).enumerate() {
+                let square = SquareSpec::new(rank as u32, file as u32);
+                match (old, new) {
+                    (None, Some(piece)) => changes.push(SquareChange::Appeared { square, piece }),
+                    (Some(piece), None) => {
+                        changes.push(SquareChange::Disappeared { square, piece });
+                    }
+                    (Some(from), Some(to)) if from != to => {
+                        changes.push(SquareChange::Changed { square, from, to });
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Board;
+    #[cfg(feature = "fen")]
+    use super::{super::Move, SquareChange};
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn en_passant_diff() {
+        let before = Board::load_fen("4k3/8/8/4pP2/8/8/8/4K3 w - e6 0 1").unwrap();
+        let after = before
+            .perform_move(Move::Normal {
+                from: "f5".parse().unwrap(),
+                to: "e6".parse().unwrap(),
+            })
+            .unwrap();
+        let squares = before
+            .diff(&after)
+            .iter()
+            .map(SquareChange::square)
+            .map(|sq| sq.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(squares, ["e5", "f5", "e6"]);
+    }
+
+    #[test]
+    fn identical_boards_have_no_diff() {
+        let board = Board::default_board();
+
+        assert!(board.diff(&board).is_empty());
+    }
+}
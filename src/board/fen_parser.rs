@@ -1,13 +1,12 @@
 use super::CastlingFlags;
-use super::{Board, SquareSpec};
+use super::{Board, BoardSize, Castling, SquareSpec};
 use crate::error::Error;
 use crate::piece::{Color, Piece, PieceType};
-use std::convert::TryInto;
 
 pub(crate) fn parse(s: &str) -> Result<Board, Error> {
     let mut parts = s.split(' ');
 
-    let board = parse_boardstate(
+    let (board, size) = parse_boardstate(
         parts
             .next()
             .ok_or_else(|| Error::InvalidFen(s.to_string()))?,
@@ -17,25 +16,9 @@ pub(crate) fn parse(s: &str) -> Result<Board, Error> {
         Some("b") => Color::Black,
         _ => return Err(Error::InvalidFen(s.to_string())),
     };
-    let castling = {
-        let c_str = parts
-            .next()
-            .ok_or_else(|| Error::InvalidFen(s.to_string()))?;
-        let mut flags = CastlingFlags::empty();
-        if c_str.contains('K') {
-            flags |= CastlingFlags::WHITE_SHORT;
-        }
-        if c_str.contains('k') {
-            flags |= CastlingFlags::BLACK_SHORT;
-        }
-        if c_str.contains('Q') {
-            flags |= CastlingFlags::WHITE_LONG;
-        }
-        if c_str.contains('q') {
-            flags |= CastlingFlags::BLACK_LONG;
-        }
-        flags
-    };
+    let castling = parts
+        .next()
+        .ok_or_else(|| Error::InvalidFen(s.to_string()))?;
     let en_passant = {
         let en_passant_str = parts
             .next()
@@ -59,37 +42,148 @@ pub(crate) fn parse(s: &str) -> Result<Board, Error> {
         .ok_or_else(|| Error::InvalidFen(s.to_string()))?
         .parse::<u32>()
         .map_err(|_| Error::InvalidFen(s.to_string()))?;
+    // the fullmove number starts at 1
+    if fullmove == 0 {
+        return Err(Error::InvalidFen(s.to_string()));
+    }
+    // the en passant square is the one skipped by a double step of the
+    // side that just moved, which pawns can only make on the standard
+    // board
+    if let Some(sq) = en_passant {
+        let skipped_rank = match turn {
+            Color::White => 5,
+            Color::Black => 2,
+        };
+        if !size.allows_double_step() || sq.rank != skipped_rank {
+            return Err(Error::InvalidFen(s.to_string()));
+        }
+    }
 
-    Ok(Board {
+    let mut board = Board {
         board,
         turn,
-        castling,
+        castling: CastlingFlags::empty(),
         en_passant,
         halfmove,
         fullmove,
-    })
+        size,
+    };
+    board.castling =
+        parse_castling(&board, castling).ok_or_else(|| Error::InvalidFen(s.to_string()))?;
+    Ok(board)
+}
+
+// Parse the castling rights, which are either `KQkq` or the files of
+// the rooks as in Shredder-FEN, e.g. `HAha`. The king and rook of
+// `KQkq` have to be on their starting squares, while a file only needs
+// a rook on it on the home rank, with the king also on the home rank,
+// and whether the right is short or long depends on which side of the
+// king the rook is. Castling is still only played from the usual
+// squares though.
+fn parse_castling(board: &Board, s: &str) -> Option<CastlingFlags> {
+    if s == "-" {
+        return Some(CastlingFlags::empty());
+    }
+    if s.is_empty() {
+        return None;
+    }
+    // the rights written as `KQkq`, which are checked together in the
+    // end, and all of them
+    let mut standard = CastlingFlags::empty();
+    let mut flags = CastlingFlags::empty();
+    for c in s.chars() {
+        let flag = if "KQkq".contains(c) {
+            let flag = CastlingFlags::from_fen_str(c.encode_utf8(&mut [0; 4])).ok()?;
+            standard |= flag;
+            flag
+        } else {
+            rook_file_castling(board, c)?
+        };
+        if flags.contains(flag) {
+            return None;
+        }
+        flags |= flag;
+    }
+
+    let fit = Board {
+        castling: standard,
+        ..*board
+    }
+    .castling_rights_fit();
+    if !fit || !board.size.allows_castling() {
+        return None;
+    }
+    Some(flags)
 }
 
-fn parse_boardstate(s: &str) -> Result<[[Option<Piece>; 8]; 8], Error> {
-    let mut lines = vec![];
+// Get the castling right given by the file of its rook in Shredder-FEN
+fn rook_file_castling(board: &Board, c: char) -> Option<CastlingFlags> {
+    let color = if c.is_ascii_uppercase() {
+        Color::White
+    } else {
+        Color::Black
+    };
+    let file = match c.to_ascii_lowercase() {
+        c @ 'a'..='h' => c as u32 - 'a' as u32,
+        _ => return None,
+    };
+    let rank = color.home_rank();
+    let king = Piece::new(PieceType::King, color);
+    let king_file =
+        (0..board.size.files).find(|&f| board[SquareSpec::new(rank, f)] == Some(king))?;
+    if file >= board.size.files
+        || board[SquareSpec::new(rank, file)] != Some(Piece::new(PieceType::Rook, color))
+    {
+        return None;
+    }
+    if file > king_file {
+        Some(Castling::Short.flag(color))
+    } else {
+        Some(Castling::Long.flag(color))
+    }
+}
+
+// Parse the piece placement, which is 8x8 for standard chess. With
+// the `variants` feature, smaller boards are read too, with their
+// size taken from the number of ranks and the width of the first one.
+// The ranks are read into the top of the board as they come, from the
+// 8th rank down, and moved down to the first rank once the number of
+// ranks is known, so nothing has to be allocated.
+fn parse_boardstate(s: &str) -> Result<([[Option<Piece>; 8]; 8], BoardSize), Error> {
+    let invalid = || Error::InvalidFen(s.to_string());
+    let mut board = [[None; 8]; 8];
+    let mut files = None;
+    let mut ranks = 0;
     for row in s.split('/') {
-        let mut cur_line = vec![];
+        if ranks == 8 {
+            return Err(invalid());
+        }
+        let line = &mut board[7 - ranks];
+        let mut file = 0;
         for c in row.chars() {
-            match parse_piece(c).ok_or_else(|| Error::InvalidFen(s.to_string()))? {
-                PieceResult::Piece(p) => cur_line.push(Some(p)),
-                PieceResult::Empty(n) => cur_line.extend(std::iter::repeat(None).take(n as usize)),
+            match parse_piece(c).ok_or_else(invalid)? {
+                PieceResult::Piece(p) => {
+                    *line.get_mut(file).ok_or_else(invalid)? = Some(p);
+                    file += 1;
+                }
+                PieceResult::Empty(n) => file += n as usize,
             }
         }
-        if cur_line.len() == 8 {
-            lines.push(cur_line.try_into().unwrap());
-        } else {
-            return Err(Error::InvalidFen(s.to_string()));
+        if file > 8 || *files.get_or_insert(file) != file {
+            return Err(invalid());
         }
+        ranks += 1;
+    }
+    board.rotate_left(8 - ranks);
+
+    let size = BoardSize {
+        files: files.unwrap_or(0) as u32,
+        ranks: ranks as u32,
+    };
+    if !size.is_supported() {
+        return Err(invalid());
     }
-    lines.reverse();
-    lines
-        .try_into()
-        .map_err(|_| Error::InvalidFen(s.to_string()))
+    Ok((board, size))
 }
 
 #[allow(variant_size_differences)]
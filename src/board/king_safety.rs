@@ -0,0 +1,110 @@
+use super::{attacks, Board, SquareSpec};
+use crate::piece::{Color, PieceType};
+
+// How many attack units each attack by a piece type on a square of the
+// king zone is worth, roughly how dangerous the piece is near a king
+fn attack_weight(piece: PieceType) -> u32 {
+    match piece {
+        PieceType::Pawn => 1,
+        PieceType::Knight | PieceType::Bishop => 2,
+        PieceType::Rook => 3,
+        PieceType::Queen => 5,
+        PieceType::King => 0,
+    }
+}
+
+impl Board {
+    /// Get the squares of the pieces of `color`'s opponent attacking
+    /// the king zone of `color`, which is the king's square and the
+    /// squares around it. The enemy king isn't counted. The squares
+    /// are ordered by rank and then file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::board::{Board, SquareSpec};
+    /// # use chess_engine::piece::Color;
+    /// let board = Board::load_fen("6k1/b4ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+    ///
+    /// // the bishop attacks the f2 pawn in front of the white king
+    /// assert_eq!(board.king_zone_attackers(Color::White), [SquareSpec::A7]);
+    /// // but the rook doesn't get near the black king
+    /// assert_eq!(board.king_zone_attackers(Color::Black), []);
+    /// ```
+    pub fn king_zone_attackers(&self, color: Color) -> Vec<SquareSpec> {
+        self.zone_attacks(color)
+            .into_iter()
+            .map(|(sq, _, _)| sq)
+            .collect()
+    }
+
+    /// Get a simple king safety score of `color`, in attack units:
+    /// each attack by an enemy piece on a square of the king zone, see
+    /// [`Board::king_zone_attackers`], counts 1 for a pawn, 2 for a
+    /// knight or bishop, 3 for a rook and 5 for a queen. The higher
+    /// the score, the more danger the king is in, which makes it
+    /// usable both as an evaluation term and for showing how exposed a
+    /// king is. Without a king, the score is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// # use chess_engine::piece::Color;
+    /// assert_eq!(Board::default_board().king_danger(Color::White), 0);
+    ///
+    /// // the queen on h5 attacks the f7 pawn next to the black king
+    /// let board = Board::load_fen("rnbqkb1r/pppp1ppp/5n2/4p2Q/4P3/8/PPPP1PPP/RNB1KBNR b KQkq - 0 1")
+    ///     .unwrap();
+    /// assert_eq!(board.king_danger(Color::Black), 5);
+    /// ```
+    pub fn king_danger(&self, color: Color) -> u32 {
+        self.zone_attacks(color)
+            .into_iter()
+            .map(|(_, piece, count)| attack_weight(piece) * count)
+            .sum()
+    }
+
+    // Get each enemy piece attacking `color`'s king zone, with how many
+    // of its squares the piece attacks
+    fn zone_attacks(&self, color: Color) -> Vec<(SquareSpec, PieceType, u32)> {
+        let king = match self.king(color) {
+            Some(king) => king,
+            None => return vec![],
+        };
+        let zone = attacks::KING_ATTACKS[king.index() as usize] | 1 << king.index();
+        let occupancy = self.occupancy();
+
+        self.pieces(color.opposite())
+            .filter(|(_, piece)| piece.piece != PieceType::King)
+            .filter_map(|(from, piece)| {
+                let count = attacks::piece_attacks(piece.piece, piece.color, from, occupancy)
+                    .filter(|sq| zone & 1 << sq.index() != 0)
+                    .count() as u32;
+                if count > 0 {
+                    Some((from, piece.piece, count))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, feature = "fen"))]
+mod tests {
+    use super::Board;
+    use crate::piece::Color;
+
+    #[test]
+    fn exposed_king() {
+        let sheltered = Board::load_fen("6k1/5ppp/8/8/8/8/6PP/R4RK1 b - - 0 1").unwrap();
+        let exposed = Board::load_fen("6k1/8/8/8/8/8/6PP/R4RK1 b - - 0 1").unwrap();
+
+        // with the f7 pawn gone, the f1 rook attacks f8 as well
+        assert_eq!(sheltered.king_danger(Color::Black), 3);
+        assert_eq!(exposed.king_danger(Color::Black), 6);
+        assert_eq!(exposed.king_zone_attackers(Color::Black).len(), 1);
+        assert_eq!(exposed.king_danger(Color::White), 0);
+    }
+}
@@ -1,10 +1,11 @@
 //! this module is responsible for checking all the low level rules and whatnot
 
-use super::{Board, Castling, Move, SquareDiff, SquareSpec};
+use super::{
+    attacks, Board, BoardSize, Castling, CastlingAvailability, Direction, Move, SquareDiff,
+    SquareSpec,
+};
 use crate::piece::{Color, Piece, PieceType};
-
-const DIAGONALS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
-const AXES: [(i32, i32); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+use std::iter;
 
 // Enumerate all possible legal moves for a certain pieces. We use a
 // boolean flag for whether this function should filter out moves that
@@ -16,14 +17,6 @@ pub(crate) fn enumerate_legal_moves(
     board: &Board,
     account_for_check: bool,
 ) -> Vec<Move> {
-    let diagonals = DIAGONALS
-        .iter()
-        .map(|&(d_rank, d_file)| SquareDiff { d_rank, d_file });
-
-    let axes = AXES
-        .iter()
-        .map(|&(d_rank, d_file)| SquareDiff { d_rank, d_file });
-
     let mut moves = match piece.piece {
         PieceType::Pawn => {
             let mut moves = Vec::new();
@@ -34,12 +27,7 @@ pub(crate) fn enumerate_legal_moves(
                     }
                     PawnMove::Normal(to) => moves.push(Move::Normal { from: location, to }),
                     PawnMove::Promotion(to) => {
-                        for piece in [
-                            PieceType::Queen,
-                            PieceType::Knight,
-                            PieceType::Bishop,
-                            PieceType::Rook,
-                        ] {
+                        for piece in PieceType::PROMOTION_TARGETS {
                             moves.push(Move::Promotion {
                                 from: location,
                                 to,
@@ -57,61 +45,36 @@ pub(crate) fn enumerate_legal_moves(
             .map(|to| Move::Normal { from: location, to })
             .collect(),
         PieceType::Rook => {
-            get_moves_directions(piece.color, board, location, &axes.collect::<Vec<_>>())
+            get_moves_directions(piece.color, board, location, &Direction::ORTHOGONAL)
                 .into_iter()
                 .map(|to| Move::Normal { from: location, to })
                 .collect()
         }
         PieceType::Bishop => {
-            get_moves_directions(piece.color, board, location, &diagonals.collect::<Vec<_>>())
+            get_moves_directions(piece.color, board, location, &Direction::DIAGONAL)
                 .into_iter()
                 .map(|to| Move::Normal { from: location, to })
                 .collect()
         }
-        PieceType::Queen => get_moves_directions(
-            piece.color,
-            board,
-            location,
-            &axes.chain(diagonals).collect::<Vec<_>>(),
-        )
-        .into_iter()
-        .map(|to| Move::Normal { from: location, to })
-        .collect(),
+        PieceType::Queen => get_moves_directions(piece.color, board, location, &Direction::ALL)
+            .into_iter()
+            .map(|to| Move::Normal { from: location, to })
+            .collect(),
     };
 
+    if board.size != BoardSize::STANDARD {
+        // the board is a corner of the 8x8 grid, so the moves can be
+        // generated as on a full board, and the ones leaving the
+        // board dropped, since a line can't leave it and come back
+        moves.retain(|m| board.size.contains(m.to(piece.color)));
+    }
+
     if account_for_check {
-        moves.retain(|m| {
-            let new_board = board.unchecked_perform_move(*m);
-            let king = match new_board.king(board.turn()) {
-                Some(k) => k,
-                _ => return true,
-            };
-
-            for (rank, row) in new_board.board.iter().enumerate() {
-                for (file, p) in row.iter().enumerate() {
-                    if let Some(p) = p {
-                        if p.color != piece.color {
-                            for m_other in enumerate_legal_moves(
-                                *p,
-                                SquareSpec {
-                                    rank: rank as u32,
-                                    file: file as u32,
-                                },
-                                &new_board,
-                                false,
-                            ) {
-                                if let Move::Normal { to, .. } = m_other {
-                                    if to == king {
-                                        return false;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            true
-        });
+        // the moves have to be played by the rules here, as e.g. an en
+        // passant capture also removes the captured pawn, which can
+        // expose the king along the rank
+        let mut scratch = *board;
+        moves.retain(|&m| !leaves_king_in_check(&mut scratch, m, piece.color));
     }
 
     moves
@@ -125,88 +88,24 @@ pub(crate) fn get_moves_king(
 ) -> Vec<Move> {
     let mut moves = Vec::new();
 
-    let diagonals = DIAGONALS
-        .iter()
-        .map(|&(d_rank, d_file)| SquareDiff { d_rank, d_file });
-
-    let axes = AXES
-        .iter()
-        .map(|&(d_rank, d_file)| SquareDiff { d_rank, d_file });
-
-    for dir in axes.chain(diagonals) {
-        if let Some(sq) = orig_sq.checked_add(dir) {
-            match board[sq] {
-                Some(Piece { color, .. }) if color == k_col => (),
-                _ => moves.push(Move::Normal {
-                    from: orig_sq,
-                    to: sq,
-                }),
-            }
+    for sq in attacks::squares(attacks::KING_ATTACKS[orig_sq.index() as usize]) {
+        match board[sq] {
+            Some(Piece { color, .. }) if color == k_col => (),
+            _ => moves.push(Move::Normal {
+                from: orig_sq,
+                to: sq,
+            }),
         }
     }
-    if check_castling {
-        'castle: {
-            if board.is_threatened(k_col, orig_sq) {
-                break 'castle;
-            }
-            'long: {
-                if board.can_castle(Castling::Long, k_col) {
-                    let (bn, cn, dn) = {
-                        let rank = k_col.home_rank();
-                        (
-                            SquareSpec { rank, file: 1 },
-                            SquareSpec { rank, file: 2 },
-                            SquareSpec { rank, file: 3 },
-                        )
-                    };
-                    match (board[bn], board[cn], board[dn]) {
-                        (None, None, None) => (),
-                        _ => break 'long,
-                    };
-
-                    // we only need to check the intermediate square as the
-                    // other check is handled by enumerate_legal_moves
-                    if board.is_threatened(
-                        k_col,
-                        orig_sq
-                            + SquareDiff {
-                                d_rank: 0,
-                                d_file: -1,
-                            },
-                    ) {
-                        break 'long;
-                    }
-
-                    moves.push(Move::Castling(Castling::Long));
-                }
-            }
-            'short: {
-                if board.can_castle(Castling::Short, k_col) {
-                    let (r#fn, gn) = {
-                        let rank = k_col.home_rank();
-                        (SquareSpec { rank, file: 5 }, SquareSpec { rank, file: 6 })
-                    };
-                    match (board[r#fn], board[gn]) {
-                        (None, None) => (),
-                        _ => break 'short,
-                    };
-
-                    // once again, we only need to check the intermediate
-                    // square as the other check is handled by
-                    // enumerate_legal_moves
-                    if board.is_threatened(
-                        k_col,
-                        orig_sq
-                            + SquareDiff {
-                                d_rank: 0,
-                                d_file: 1,
-                            },
-                    ) {
-                        break 'short;
-                    }
-
-                    moves.push(Move::Castling(Castling::Short));
-                }
+    if check_castling && !board.is_threatened(k_col, orig_sq) {
+        for &c in &[Castling::Long, Castling::Short] {
+            // we only need to check the square the king passes over,
+            // as the one it lands on is handled by enumerate_legal_moves
+            if board.can_castle(c, k_col)
+                && c.empty_squares(k_col).iter().all(|&sq| board[sq].is_none())
+                && !board.is_threatened(k_col, c.king_path(k_col)[0])
+            {
+                moves.push(Move::Castling(c));
             }
         }
     }
@@ -239,12 +138,12 @@ fn get_moves_pawn(p_col: Color, board: &Board, orig_sq: SquareSpec) -> Vec<PawnM
         .map(|sq| (sq, board[sq]))
     {
         // check for promotion
-        if sq.rank == p_col.opposite().home_rank() {
+        if sq.rank == board.size.promotion_rank(p_col) {
             moves.push(Promotion(sq));
         } else {
             moves.push(Normal(sq));
             // if we can move twice
-            if orig_sq.rank == p_col.pawn_home_rank() {
+            if orig_sq.rank == p_col.pawn_home_rank() && board.size.allows_double_step() {
                 if let Some((sq2, None)) = sq.checked_add(pawn_direction).map(|sq| (sq, board[sq]))
                 {
                     moves.push(Normal(sq2));
@@ -288,17 +187,17 @@ fn get_moves_pawn(p_col: Color, board: &Board, orig_sq: SquareSpec) -> Vec<PawnM
     // impossible for the en passant square to contain a takeable
     // piece
 
-    // check left diagonal
-    if let Some((sq, Some(Piece { color, .. }))) = left_diag {
-        if p_col != color {
-            moves.push(Normal(sq));
-        }
-    }
-
-    // check right diagonal
-    if let Some((sq, Some(Piece { color, .. }))) = right_diag {
-        if p_col != color {
-            moves.push(Normal(sq));
+    // check both diagonals, remembering that taking onto the last
+    // rank is also a promotion
+    for diag in [left_diag, right_diag] {
+        if let Some((sq, Some(Piece { color, .. }))) = diag {
+            if p_col != color {
+                if sq.rank == board.size.promotion_rank(p_col) {
+                    moves.push(Promotion(sq));
+                } else {
+                    moves.push(Normal(sq));
+                }
+            }
         }
     }
 
@@ -306,48 +205,29 @@ fn get_moves_pawn(p_col: Color, board: &Board, orig_sq: SquareSpec) -> Vec<PawnM
 }
 
 fn get_moves_knight(k_col: Color, board: &Board, orig_sq: SquareSpec) -> Vec<SquareSpec> {
-    let mut moves = [
-        (2, 1),
-        (2, -1),
-        (-2, 1),
-        (-2, -1),
-        (1, 2),
-        (1, -2),
-        (-1, 2),
-        (-1, -2),
-    ]
-    .iter()
-    .map(|&(d_rank, d_file)| SquareDiff { d_rank, d_file })
-    .filter_map(|sd| orig_sq.checked_add(sd))
-    .collect::<Vec<_>>();
-
-    moves.retain(|x| !matches!(board[*x], Some(Piece { color, .. }) if k_col == color));
-
-    moves
+    attacks::squares(attacks::KNIGHT_ATTACKS[orig_sq.index() as usize])
+        .filter(|&sq| !matches!(board[sq], Some(Piece { color, .. }) if k_col == color))
+        .collect()
 }
 
 fn get_moves_directions(
     piece_col: Color,
     board: &Board,
     orig_sq: SquareSpec,
-    directions: &[SquareDiff],
+    directions: &[Direction],
 ) -> Vec<SquareSpec> {
-    // assumes all of the directions are unit vectors
-
     let mut moves = Vec::new();
 
-    'dir: for direction in directions {
-        let mut sq_i = orig_sq;
-        while let Some(sq) = sq_i.checked_add(*direction) {
-            sq_i = sq;
-            match board[sq_i] {
-                Some(Piece { color, .. }) if color == piece_col => continue 'dir,
+    for &direction in directions {
+        for sq in orig_sq.ray(direction) {
+            match board[sq] {
+                Some(Piece { color, .. }) if color == piece_col => break,
                 Some(Piece { .. }) => {
-                    moves.push(sq_i);
-                    continue 'dir;
+                    moves.push(sq);
+                    break;
                 }
                 None => {
-                    moves.push(sq_i);
+                    moves.push(sq);
                 }
             }
         }
@@ -356,9 +236,234 @@ fn get_moves_directions(
     moves
 }
 
-#[cfg(test)]
+// Check whether a single move is legal for `side` without generating
+// every move of the piece: first the move's geometry and the
+// occupancy of the squares involved, and then whether it leaves the
+// king in check. Only the player to move has legal moves, and only
+// with their own pieces.
+pub(crate) fn is_legal_move(board: &Board, m: Move, side: Color) -> bool {
+    if side != board.turn() {
+        return false;
+    }
+    let (from, to) = match m {
+        Move::Normal { from, to } | Move::Promotion { from, to, .. } => (from, to),
+        Move::Castling(c) => return is_legal_castling(board, c, side),
+    };
+
+    if !board.size.contains(from) || !board.size.contains(to) || from == to {
+        return false;
+    }
+    let piece = match board[from] {
+        Some(piece) if piece.color == side => piece,
+        _ => return false,
+    };
+    if matches!(board[to], Some(Piece { color, .. }) if color == side) {
+        return false;
+    }
+
+    let diff = to - from;
+    let geometry_ok = match piece.piece {
+        PieceType::Pawn => is_pawn_move(board, side, m, from, to),
+        // only pawns can promote
+        _ if matches!(m, Move::Promotion { .. }) => false,
+        PieceType::Knight => matches!((diff.abs().d_rank, diff.abs().d_file), (1, 2) | (2, 1)),
+        PieceType::King => from.chebyshev_distance(to) == 1,
+        PieceType::Rook => (from.same_rank(to) || from.same_file(to)) && is_clear(board, from, to),
+        PieceType::Bishop => from.same_diagonal(to) && is_clear(board, from, to),
+        PieceType::Queen => diff.as_unit().is_some() && is_clear(board, from, to),
+    };
+
+    let mut scratch = *board;
+    geometry_ok && !leaves_king_in_check(&mut scratch, m, side)
+}
+
+fn is_clear(board: &Board, from: SquareSpec, to: SquareSpec) -> bool {
+    SquareSpec::between(from, to)
+        .into_iter()
+        .all(|sq| board[sq].is_none())
+}
+
+fn is_pawn_move(board: &Board, side: Color, m: Move, from: SquareSpec, to: SquareSpec) -> bool {
+    let forward = match side {
+        Color::White => 1,
+        Color::Black => -1,
+    };
+    let diff = to - from;
+
+    // moving to the last rank has to be a promotion to a valid piece,
+    // and nothing else may be a promotion
+    let promotes = to.rank == board.size.promotion_rank(side);
+    match m {
+        Move::Promotion { target, .. }
+            if !promotes || !PieceType::PROMOTION_TARGETS.contains(&target) =>
+        {
+            return false;
+        }
+        Move::Normal { .. } if promotes => return false,
+        _ => (),
+    }
+
+    if diff.d_file == 0 {
+        // pushes can't take anything
+        board[to].is_none()
+            && (diff.d_rank == forward
+                || (diff.d_rank == 2 * forward
+                    && from.rank == side.pawn_home_rank()
+                    && board.size.allows_double_step()
+                    && board[from + SquareDiff::new(forward, 0)].is_none()))
+    } else {
+        diff.d_file.abs() == 1
+            && diff.d_rank == forward
+            && (board[to].is_some() || board.en_passant == Some(to))
+    }
+}
+
+fn is_legal_castling(board: &Board, c: Castling, side: Color) -> bool {
+    castling_availability(board, c, side) == CastlingAvailability::Available
+}
+
+pub(crate) fn castling_availability(
+    board: &Board,
+    c: Castling,
+    side: Color,
+) -> CastlingAvailability {
+    let king_from = c.king_from(side);
+    let rook_from = c.rook_from(side);
+    let king = Piece::new(PieceType::King, side);
+    let rook = Piece::new(PieceType::Rook, side);
+
+    // the pieces not being there can only happen with a bad FEN,
+    // but it means they've moved all the same
+    if !board.can_castle(c, side)
+        || board[king_from] != Some(king)
+        || board[rook_from] != Some(rook)
+    {
+        return CastlingAvailability::RightsLost;
+    }
+    if !is_clear(board, king_from, rook_from) {
+        return CastlingAvailability::Blocked;
+    }
+    // the squares can be checked on the current board, since the
+    // king is in the way of any attack along the rank that castling
+    // would open up, and the rook ends up shielding the king
+    let attacked = iter::once(king_from)
+        .chain(c.king_path(side))
+        .any(|sq| board.is_threatened(side, sq));
+    if attacked {
+        CastlingAvailability::Check
+    } else {
+        CastlingAvailability::Available
+    }
+}
+
+// The moves of the player to move that follow the movement rules,
+// but might leave the king in check. Castling isn't included.
+fn pseudo_legal_moves(board: &Board) -> Vec<Move> {
+    let mut moves = Vec::new();
+    for sq in SquareSpec::all() {
+        if let Some(piece) = board[sq] {
+            if piece.color == board.turn() {
+                moves.append(&mut enumerate_legal_moves(piece, sq, board, false));
+            }
+        }
+    }
+    moves
+}
+
+fn is_capture(board: &Board, m: Move) -> bool {
+    match m {
+        Move::Normal { from, to } => {
+            board[to].is_some()
+                || (board.en_passant == Some(to)
+                    && matches!(board[from], Some(p) if p.piece == PieceType::Pawn))
+        }
+        Move::Promotion { to, .. } => board[to].is_some(),
+        Move::Castling(_) => false,
+    }
+}
+
+pub(crate) fn captures(board: &Board) -> Vec<Move> {
+    let mut moves = pseudo_legal_moves(board);
+    let mut scratch = *board;
+    moves.retain(|&m| is_capture(board, m) && !leaves_king_in_check(&mut scratch, m, board.turn()));
+    moves
+}
+
+pub(crate) fn quiets(board: &Board) -> Vec<Move> {
+    let mut moves = pseudo_legal_moves(board);
+    let mut scratch = *board;
+    moves
+        .retain(|&m| !is_capture(board, m) && !leaves_king_in_check(&mut scratch, m, board.turn()));
+    for &c in &[Castling::Short, Castling::Long] {
+        if is_legal_castling(board, c, board.turn()) {
+            moves.push(Move::Castling(c));
+        }
+    }
+    moves
+}
+
+// Only moves that could possibly get out of check are tried: king
+// moves, and with a single checker, capturing it or blocking its line
+// to the king
+pub(crate) fn evasions(board: &Board) -> Vec<Move> {
+    let side = board.turn();
+    let king = match board.king(side) {
+        Some(king) => king,
+        None => return vec![],
+    };
+    let checkers = SquareSpec::all()
+        .filter(|&sq| match board[sq] {
+            Some(piece) if piece.color != side => enumerate_legal_moves(piece, sq, board, false)
+                .iter()
+                .any(|m| matches!(*m, Move::Normal { to, .. } | Move::Promotion { to, .. } if to == king)),
+            _ => false,
+        })
+        .collect::<Vec<_>>();
+    let targets = match *checkers.as_slice() {
+        [] => return vec![],
+        [checker] => {
+            let mut targets = SquareSpec::between(checker, king);
+            targets.push(checker);
+            targets
+        }
+        // only the king can get out of double check
+        _ => vec![],
+    };
+
+    let mut moves = pseudo_legal_moves(board);
+    let mut scratch = *board;
+    moves.retain(|&m| {
+        let (from, to) = match m {
+            Move::Normal { from, to } | Move::Promotion { from, to, .. } => (from, to),
+            Move::Castling(_) => return false,
+        };
+        let en_passant_capture = board.en_passant == Some(to)
+            && checkers.contains(&SquareSpec::new(from.rank, to.file))
+            && is_capture(board, m);
+        (from == king || targets.contains(&to) || en_passant_capture)
+            && !leaves_king_in_check(&mut scratch, m, side)
+    });
+    moves
+}
+
+// Play the move on the board and take it back again, which leaves
+// the board as it was, so one copy of it can be used for trying all
+// the moves of a position
+fn leaves_king_in_check(board: &mut Board, m: Move, side: Color) -> bool {
+    let undo = board.make_move(m);
+    let in_check = match board.king(side) {
+        Some(king) => board.is_threatened(side, king),
+        // there's no king to put in check
+        None => false,
+    };
+    board.unmake_move(m, undo);
+    in_check
+}
+
+#[cfg(all(test, feature = "fen"))]
 mod tests {
-    use super::{super::Castling, Board, Move, SquareSpec};
+    use super::{super::Castling, Board, CastlingAvailability, Move, SquareSpec};
+    use crate::piece::Color;
 
     /// Macro to make writing tests wayyy easier, by simply writing
     /// out a board state in FEN, saying which piece we check, and
@@ -443,6 +548,52 @@ mod tests {
         }
     }
 
+    #[test]
+    fn is_legal_matches_generated_moves() {
+        use crate::piece::PieceType;
+        use std::collections::HashSet;
+
+        for fen in [
+            Board::STARTPOS_FEN,
+            Board::KIWIPETE_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+            Board::PERFT_POSITION_3_FEN,
+            Board::PERFT_POSITION_5_FEN,
+            "8/8/8/4pP2/8/8/8/K6k w - e6 0 1",
+            "4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1",
+            "4k3/8/8/8/8/8/8/R3K2r w Q - 0 1",
+        ] {
+            let board = Board::load_fen(fen).unwrap();
+            let generated = board
+                .get_all_legal_moves()
+                .into_iter()
+                .collect::<HashSet<_>>();
+
+            let mut candidates = vec![
+                Move::Castling(Castling::Short),
+                Move::Castling(Castling::Long),
+            ];
+            for from in SquareSpec::all() {
+                for to in SquareSpec::all() {
+                    candidates.push(Move::Normal { from, to });
+                    for target in [PieceType::Queen, PieceType::Rook, PieceType::King] {
+                        candidates.push(Move::Promotion { from, to, target });
+                    }
+                }
+            }
+
+            for m in candidates {
+                assert_eq!(
+                    board.is_legal(m, board.turn()),
+                    generated.contains(&m),
+                    "is_legal disagrees with the move generation for {} in {}",
+                    m,
+                    fen
+                );
+            }
+        }
+    }
+
     #[test]
     fn get_move_directions_stops_for_same_color() {
         basic_test! {
@@ -506,6 +657,45 @@ mod tests {
         }
     }
 
+    #[test]
+    fn only_the_player_to_move_moves() {
+        let board = Board::default_board();
+        let e5 = "e7e5".parse().unwrap();
+        assert!(!board.is_legal(e5, Color::White));
+        assert!(!board.is_legal(e5, Color::Black));
+        assert!(!board.is_legal("g1f3".parse().unwrap(), Color::Black));
+
+        let board = board.perform_move("e2e4".parse().unwrap()).unwrap();
+        assert!(board.is_legal(e5, Color::Black));
+        assert!(!board.is_legal(e5, Color::White));
+        assert!(!board.is_legal("d2d4".parse().unwrap(), Color::Black));
+    }
+
+    #[test]
+    fn en_passant_exposing_king() {
+        // taking en passant removes both pawns from the rank, leaving
+        // the king in check from the rook
+        let fen = "8/8/8/KPp4r/8/8/8/4k3 w - c6 0 1";
+        basic_test! {
+            fen: fen,
+            piece: b5,
+            legal_moves: [b6],
+        }
+        let board = Board::load_fen(fen).unwrap();
+        let en_passant = "b5c6".parse().unwrap();
+        assert!(!board.is_legal(en_passant, Color::White));
+        assert!(!board.get_all_legal_moves().contains(&en_passant));
+        assert!(!board.generate_captures().contains(&en_passant));
+
+        // but it's fine when the capturing pawn is the one pinned on
+        // the diagonal
+        basic_test! {
+            fen: "4b3/8/8/1Pp5/K7/8/8/4k3 w - c6 0 1",
+            piece: b5,
+            legal_moves: [c6],
+        }
+    }
+
     #[test]
     fn pawn_promotion() {
         basic_test! {
@@ -520,6 +710,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn pawn_take_promotion() {
+        basic_test! {
+            fen: "3rr3/4P3/8/8/8/8/8/8 w - - 0 1",
+            piece: e7,
+            legal_moves: [
+                [d8=B],
+                [d8=R],
+                [d8=N],
+                [d8=Q]
+            ],
+        }
+    }
+
+    #[test]
+    fn promoting_pawn_gives_check() {
+        // the pawn on d7 attacks e8 even though taking it would be a
+        // promotion, so the rook can't move
+        let board = Board::load_fen("r3k3/3P4/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+
+        assert!(board.in_check());
+        assert!(board.get_legal_moves("a8".parse().unwrap()).is_empty());
+        basic_test! {
+            fen: "r3k3/3P4/8/8/8/8/8/4K3 b - - 0 1",
+            piece: e8,
+            legal_moves: [d8, e7, f7, f8, d7],
+        }
+    }
+
     #[test]
     fn pawn_take() {
         basic_test! {
@@ -623,6 +842,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn castling_availability() {
+        let board = Board::load_fen("r3k2r/8/8/8/8/8/8/R3K1NR b Kkq - 0 1").unwrap();
+        let available = |c, side| board.castling_available(c, side);
+
+        assert_eq!(
+            available(Castling::Short, Color::White),
+            CastlingAvailability::Blocked
+        );
+        assert_eq!(
+            available(Castling::Long, Color::White),
+            CastlingAvailability::RightsLost
+        );
+        assert_eq!(
+            available(Castling::Long, Color::Black),
+            CastlingAvailability::Available
+        );
+
+        let board = Board::load_fen("r3k2r/8/8/8/8/8/8/R3K1NR w Kkq - 0 1")
+            .unwrap()
+            .perform_move("a1a8".parse().unwrap())
+            .unwrap();
+        assert_eq!(
+            board.castling_available(Castling::Short, Color::Black),
+            CastlingAvailability::Check
+        );
+        assert_eq!(
+            board.castling_available(Castling::Long, Color::Black),
+            CastlingAvailability::RightsLost
+        );
+    }
+
     #[test]
     fn cant_move_pinned_piece() {
         basic_test! {
@@ -4,13 +4,35 @@ use crate::piece::{Color, Piece, PieceType};
 use bitflags::bitflags;
 use std::fmt;
 
+mod analyzed;
+pub mod attacks;
+mod diff;
+#[cfg(feature = "fen")]
 mod fen_parser;
+mod king_safety;
 mod legal_moves;
 mod move_types;
+mod pawns;
+mod phase;
+mod setup;
+mod size;
+mod snapshot;
 mod squarespec;
+mod symmetry;
+mod zobrist;
 
-pub use move_types::{Castling, Move};
-pub use squarespec::{SquareDiff, SquareSpec};
+pub use analyzed::AnalyzedBoard;
+pub use diff::SquareChange;
+pub use move_types::{
+    Castling, CastlingAvailability, Move, MoveInfo, MoveKind, MoveOutcome, MoveUndo, ResolvedMove,
+};
+pub use pawns::PawnStructure;
+pub use phase::{GamePhase, MAX_PHASE};
+pub use setup::BoardMeta;
+pub use size::BoardSize;
+pub use snapshot::BoardSnapshot;
+pub use squarespec::{Direction, SquareDiff, SquareSpec};
+pub use symmetry::Transform;
 
 bitflags! {
     /// [bitflags] struct
@@ -38,7 +60,25 @@ bitflags! {
 }
 
 /// A struct containing all the information required to represent a position
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+///
+/// Boards are equal, and hash the same, only if everything about them
+/// is the same, including the move counters and the en passant
+/// square. To find repeated positions, compare their
+/// [Zobrist hashes](Board::zobrist) instead, or boards with the same
+/// [counters](Board::with_counters).
+///
+/// # Examples
+/// ```
+/// # use chess_engine::board::Board;
+/// # use std::collections::HashSet;
+/// let board = Board::default_board();
+/// let after_e4 = board.perform_move("e2e4".parse().unwrap()).unwrap();
+/// let seen: HashSet<_> = [board, after_e4, board].iter().copied().collect();
+///
+/// assert_eq!(seen.len(), 2);
+/// assert!(seen.contains(&Board::default_board()));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Board {
     board: [[Option<Piece>; 8]; 8],
     turn: Color,
@@ -46,9 +86,60 @@ pub struct Board {
     en_passant: Option<SquareSpec>,
     halfmove: u32,
     fullmove: u32,
+    size: BoardSize,
 }
 
 impl Board {
+    /// The FEN of the starting position, the same as
+    /// [`Board::default_board`].
+    ///
+    /// The standard perft node counts, the number of move sequences of
+    /// each length from 1 ply up, are 20, 400, 8902 and 197281.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// assert_eq!(Board::load_fen(Board::STARTPOS_FEN).unwrap(), Board::default_board());
+    /// ```
+    pub const STARTPOS_FEN: &'static str =
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    /// The FEN of "Kiwipete", the second of the standard perft test
+    /// positions, which is full of castling, en passant, promotions
+    /// and pins.
+    ///
+    /// The perft node counts are 48, 2039, 97862 and 4085603.
+    pub const KIWIPETE_FEN: &'static str =
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    /// The FEN of the third standard perft test position, an endgame
+    /// with en passant captures that expose the king to rooks along
+    /// the rank.
+    ///
+    /// The perft node counts are 14, 191, 2812 and 43238.
+    pub const PERFT_POSITION_3_FEN: &'static str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+
+    /// The FEN of the fourth standard perft test position, with lots
+    /// of promotions and black castling rights.
+    ///
+    /// The perft node counts are 6, 264, 9467 and 422333.
+    pub const PERFT_POSITION_4_FEN: &'static str =
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+
+    /// The FEN of the fifth standard perft test position, where a
+    /// pawn on d7 can promote by capturing.
+    ///
+    /// The perft node counts are 44, 1486, 62379 and 2103487.
+    pub const PERFT_POSITION_5_FEN: &'static str =
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+
+    /// The FEN of the sixth standard perft test position, a
+    /// symmetrical middlegame.
+    ///
+    /// The perft node counts are 46, 2079, 89890 and 3894594.
+    pub const PERFT_POSITION_6_FEN: &'static str =
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
     /// Create a new empty `Board`
     pub fn new(turn: Color, castling: CastlingFlags) -> Board {
         Board {
@@ -58,6 +149,7 @@ impl Board {
             en_passant: None,
             halfmove: 0,
             fullmove: 1,
+            size: BoardSize::STANDARD,
         }
     }
 
@@ -76,13 +168,255 @@ impl Board {
 
     /// Load a board from a string containing (FEN)[<https://en.wikipedia.org/wiki/Forsyth%E2%80%93Edwards_Notation>]
     ///
+    /// The castling rights can also be given by the files of the rooks
+    /// as in Shredder-FEN, for any rook on the home rank of its color.
+    /// Castling itself is only played with the king and rook on their
+    /// usual squares though, so such rights can't be used.
+    ///
     /// # Errors
     ///
     /// Will return an error if the string is not valid FEN
+    #[cfg(feature = "fen")]
     pub fn load_fen(s: &str) -> Result<Board, Error> {
         fen_parser::parse(s)
     }
 
+    /// Set up a board like the UCI `position` command does, from a
+    /// FEN (or `startpos` for the starting position) followed by
+    /// moves in UCI notation, which are played in order.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the FEN is invalid, or if one of the
+    /// moves can't be parsed or is illegal
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// let board = Board::from_fen_and_moves(
+    ///     "r3k3/8/8/8/8/8/8/4K3 b q - 0 1",
+    ///     &["e8c8", "e1e2"],
+    /// )
+    /// .unwrap();
+    /// assert_eq!(board.to_fen(), "2kr4/8/8/8/8/8/4K3/8 b - - 2 2");
+    ///
+    /// let board = Board::from_fen_and_moves("startpos", &["e2e4"]).unwrap();
+    /// assert_eq!(board, Board::default_board().perform_move("e2e4".parse().unwrap()).unwrap());
+    ///
+    /// assert!(Board::from_fen_and_moves("startpos", &["e2e5"]).is_err());
+    /// ```
+    #[cfg(feature = "fen")]
+    pub fn from_fen_and_moves(fen: &str, moves: &[&str]) -> Result<Board, Error> {
+        let mut board = match fen {
+            "startpos" => Board::default_board(),
+            fen => Board::load_fen(fen)?,
+        };
+        for s in moves {
+            let m = crate::notation::uci::parse(&board, s)?;
+            board = board
+                .perform_move(m)
+                .ok_or_else(|| Error::IllegalMove(board.to_fen(), m))?;
+        }
+        Ok(board)
+    }
+
+    /// Get the board as a FEN string. This is the same as what the
+    /// [`Display`](fmt::Display) implementation produces.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    ///
+    /// assert_eq!(Board::default_board().to_fen(), fen);
+    /// ```
+    pub fn to_fen(&self) -> String {
+        self.to_string()
+    }
+
+    /// Write the board as FEN to `out`, without allocating anything
+    /// along the way, e.g. to reuse one buffer for many boards
+    ///
+    /// # Errors
+    ///
+    /// Will only return an error if `out` does
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// let mut fen = String::new();
+    /// Board::default_board().write_fen(&mut fen).unwrap();
+    ///
+    /// assert_eq!(fen, Board::default_board().to_fen());
+    /// ```
+    pub fn write_fen<W: fmt::Write + ?Sized>(&self, out: &mut W) -> fmt::Result {
+        self.write_fen_with(out, CastlingStyle::Standard)
+    }
+
+    /// Get the board as FEN with the castling rights written in a
+    /// certain style, e.g. Shredder-FEN for engines that expect it.
+    /// [`Board::load_fen`] reads either style. Rights whose rook isn't
+    /// in the corner are written with the file of the rook either way.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{Board, CastlingStyle};
+    /// let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1";
+    /// let board = Board::default_board();
+    ///
+    /// assert_eq!(board.to_fen_with(CastlingStyle::Shredder), fen);
+    /// assert_eq!(Board::load_fen(fen).unwrap(), board);
+    /// ```
+    pub fn to_fen_with(&self, style: CastlingStyle) -> String {
+        let mut fen = String::new();
+        let _ = self.write_fen_with(&mut fen, style);
+        fen
+    }
+
+    /// Write the board as FEN to `out` with the castling rights written
+    /// in a certain style, see [`Board::write_fen`] and
+    /// [`Board::to_fen_with`]
+    ///
+    /// # Errors
+    ///
+    /// Will only return an error if `out` does
+    pub fn write_fen_with<W: fmt::Write + ?Sized>(
+        &self,
+        out: &mut W,
+        style: CastlingStyle,
+    ) -> fmt::Result {
+        for (i, rank) in self.board[..self.size.ranks as usize]
+            .iter()
+            .rev()
+            .enumerate()
+        {
+            if i != 0 {
+                out.write_char('/')?;
+            }
+            let mut empty_squares = 0;
+            for piece in &rank[..self.size.files as usize] {
+                if let Some(piece) = piece {
+                    if empty_squares != 0 {
+                        write!(out, "{}", empty_squares)?;
+                        empty_squares = 0;
+                    }
+                    write!(out, "{}", piece)?;
+                } else {
+                    empty_squares += 1;
+                }
+            }
+            if empty_squares != 0 {
+                write!(out, "{}", empty_squares)?;
+            }
+        }
+
+        let turn = match self.turn {
+            Color::White => 'w',
+            Color::Black => 'b',
+        };
+        write!(out, " {} ", turn)?;
+        self.write_castling(out, style)?;
+        out.write_char(' ')?;
+        match self.en_passant {
+            Some(sq) => write!(out, "{}", sq)?,
+            None => out.write_char('-')?,
+        }
+        write!(out, " {} {}", self.halfmove, self.fullmove)
+    }
+
+    // Write the castling rights in a certain style. Rights whose king
+    // and rook aren't on their usual squares, which can only come from
+    // Shredder-FEN, are written with the file of the rook either way.
+    fn write_castling<W: fmt::Write + ?Sized>(
+        &self,
+        out: &mut W,
+        style: CastlingStyle,
+    ) -> fmt::Result {
+        if self.castling.is_empty() {
+            return out.write_char('-');
+        }
+        for &color in &[Color::White, Color::Black] {
+            for &castle in &Castling::ALL {
+                let flag = castle.flag(color);
+                if !self.castling.contains(flag) {
+                    continue;
+                }
+                let in_place = self[castle.king_from(color)]
+                    == Some(Piece::new(PieceType::King, color))
+                    && self[castle.rook_from(color)] == Some(Piece::new(PieceType::Rook, color));
+                if style == CastlingStyle::Standard && in_place {
+                    flag.write_fen(out, style)?;
+                    continue;
+                }
+                let file = self
+                    .castling_rook(castle, color)
+                    .map_or(castle.rook_from(color).file, |sq| sq.file);
+                let letter = (b'a' + file as u8) as char;
+                out.write_char(match color {
+                    Color::White => letter.to_ascii_uppercase(),
+                    Color::Black => letter,
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Render the board as an 8x8 grid of text, with rank and file
+    /// labels, as seen from `perspective`'s side of the board. White
+    /// pieces are uppercase, black pieces lowercase and empty squares
+    /// are shown as dots. Mostly useful for terminal frontends and
+    /// debugging.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// # use chess_engine::piece::Color;
+    /// let board = Board::load_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+    ///
+    /// assert_eq!(
+    ///     board.to_grid_string(Color::Black),
+    ///     "1 . . . K . . . R
+    /// 2 . . . . . . . .
+    /// 3 . . . . . . . .
+    /// 4 . . . . . . . .
+    /// 5 . . . . . . . .
+    /// 6 . . . . . . . .
+    /// 7 . . . . . . . .
+    /// 8 . . . k . . . .
+    ///   h g f e d c b a
+    /// "
+    /// );
+    /// ```
+    pub fn to_grid_string(&self, perspective: Color) -> String {
+        let mut ranks: Vec<u32> = (0..self.size.ranks).rev().collect();
+        let mut files: Vec<u32> = (0..self.size.files).collect();
+        if perspective == Color::Black {
+            ranks.reverse();
+            files.reverse();
+        }
+
+        let mut s = String::new();
+        for &rank in &ranks {
+            s.push_str(&(rank + 1).to_string());
+            for &file in &files {
+                s.push(' ');
+                match self[SquareSpec::new(rank, file)] {
+                    Some(piece) => s.push_str(&piece.to_string()),
+                    None => s.push('.'),
+                }
+            }
+            s.push('\n');
+        }
+        s.push(' ');
+        for &file in &files {
+            s.push(' ');
+            s.push((b'a' + file as u8) as char);
+        }
+        s.push('\n');
+
+        s
+    }
+
     /// Create a board initialised in the default chess starting
     /// position
     pub fn default_board() -> Board {
@@ -105,35 +439,146 @@ impl Board {
             en_passant: None,
             halfmove: 0,
             fullmove: 1,
+            size: BoardSize::STANDARD,
         }
     }
 
+    /// Create a board in the starting position of Los Alamos chess,
+    /// played on a 6x6 board without bishops, castling, double pawn
+    /// steps or en passant
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{Board, BoardSize};
+    /// let board = Board::los_alamos();
+    ///
+    /// assert_eq!(board.size(), BoardSize::LOS_ALAMOS);
+    /// assert_eq!(board.to_fen(), "rnqknr/pppppp/6/6/PPPPPP/RNQKNR w - - 0 1");
+    /// assert_eq!(board.get_all_legal_moves().len(), 10);
+    /// ```
+    #[cfg(feature = "variants")]
+    pub fn los_alamos() -> Board {
+        Board::load_fen("rnqknr/pppppp/6/6/PPPPPP/RNQKNR w - - 0 1")
+            .expect("the Los Alamos starting position is valid FEN")
+    }
+
+    /// Create a board in the starting position of Gardner's
+    /// minichess, played on a 5x5 board without castling, double pawn
+    /// steps or en passant
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{Board, BoardSize};
+    /// let board = Board::gardner();
+    ///
+    /// assert_eq!(board.size(), BoardSize::GARDNER);
+    /// assert_eq!(board.get_all_legal_moves().len(), 7);
+    /// ```
+    #[cfg(feature = "variants")]
+    pub fn gardner() -> Board {
+        Board::load_fen("rnbqk/ppppp/5/PPPPP/RNBQK w - - 0 1")
+            .expect("the Gardner minichess starting position is valid FEN")
+    }
+
+    /// Get the dimensions of the board, which are 8x8 unless the board
+    /// is set up for a small board variant
+    pub fn size(&self) -> BoardSize {
+        self.size
+    }
+
     // this function only checks if castling is at all allowed
     fn can_castle(&self, castle: Castling, color: Color) -> bool {
-        (self.castling
-            & match color {
-                Color::White => CastlingFlags::WHITE,
-                Color::Black => CastlingFlags::BLACK,
-            }
-            & match castle {
-                Castling::Long => CastlingFlags::LONG,
-                Castling::Short => CastlingFlags::SHORT,
-            })
-        .bits()
-            != 0
+        self.size.allows_castling() && self.castling.contains(castle.flag(color))
+    }
+
+    // Find the rook that `color` castles with a certain way: the
+    // outermost rook on that side of the king on the home rank, as in
+    // X-FEN. This is only used for the file letters of Shredder-FEN,
+    // since castling is only played with the rooks in the corners.
+    pub(crate) fn castling_rook(&self, castle: Castling, color: Color) -> Option<SquareSpec> {
+        let rank = color.home_rank();
+        let on_home_rank =
+            |file, piece| self[SquareSpec::new(rank, file)] == Some(Piece::new(piece, color));
+        let king = (0..self.size.files).find(|&file| on_home_rank(file, PieceType::King))?;
+        let mut rooks = (0..self.size.files).filter(|&file| {
+            let side = match castle {
+                Castling::Short => file > king,
+                Castling::Long => file < king,
+            };
+            side && on_home_rank(file, PieceType::Rook)
+        });
+        let file = match castle {
+            Castling::Short => rooks.last(),
+            Castling::Long => rooks.next(),
+        }?;
+        Some(SquareSpec::new(rank, file))
+    }
+
+    /// Check whether `color` can castle a certain way, and if not,
+    /// why. This doesn't depend on whose turn it is, so it tells
+    /// whether the player could castle if it were their turn.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{Board, Castling, CastlingAvailability};
+    /// # use chess_engine::piece::Color;
+    /// let board = Board::load_fen("r3k2r/8/8/8/8/8/6p1/RN2K2R w Kk - 0 1").unwrap();
+    ///
+    /// assert_eq!(
+    ///     board.castling_available(Castling::Short, Color::White),
+    ///     CastlingAvailability::Check
+    /// );
+    /// assert_eq!(
+    ///     board.castling_available(Castling::Long, Color::White),
+    ///     CastlingAvailability::RightsLost
+    /// );
+    /// assert_eq!(
+    ///     board.castling_available(Castling::Short, Color::Black),
+    ///     CastlingAvailability::Available
+    /// );
+    /// ```
+    pub fn castling_available(&self, castle: Castling, color: Color) -> CastlingAvailability {
+        legal_moves::castling_availability(self, castle, color)
     }
 
-    /// Check if a certain move is legal to perform
+    /// Check if a certain move is legal for `side` to perform, which
+    /// it never is if `side` isn't the player to move or the piece on
+    /// the starting square isn't theirs. This checks the single move
+    /// directly rather than generating all of the piece's moves, so
+    /// it's cheap enough for validating moves coming from e.g. the
+    /// network.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{Board, Move};
+    /// # use chess_engine::piece::Color;
+    /// let board = Board::default_board();
+    ///
+    /// assert!(board.is_legal("e2e4".parse().unwrap(), Color::White));
+    /// assert!(!board.is_legal("e2e5".parse().unwrap(), Color::White));
+    /// assert!(!board.is_legal("O-O".parse().unwrap(), Color::White));
+    /// ```
     pub fn is_legal(&self, m: Move, side: Color) -> bool {
-        match m {
-            Move::Normal { from, .. } | Move::Promotion { from, .. } => {
-                self[from].map_or(false, |piece| {
-                    let legal_moves = legal_moves::enumerate_legal_moves(piece, from, self, true);
-                    legal_moves.into_iter().any(|x| x == m)
-                })
-            }
-            Move::Castling(c) => self.can_castle(c, side),
+        legal_moves::is_legal_move(self, m, side)
+    }
+
+    /// Get the contents of a square, or [`None`] if the square isn't
+    /// on the board. Unlike indexing, this never panics.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{Board, SquareSpec};
+    /// let board = Board::default_board();
+    ///
+    /// assert!(board.get(SquareSpec::E1).unwrap().is_some());
+    /// assert!(board.get(SquareSpec::E4).unwrap().is_none());
+    /// assert!(board.get(SquareSpec::new(9, 42)).is_none());
+    /// ```
+    pub fn get(&self, sq: SquareSpec) -> Option<&Option<Piece>> {
+        if !self.size.contains(sq) {
+            return None;
         }
+        self.board.get(sq.rank as usize)?.get(sq.file as usize)
     }
 
     /// Get the inner board array
@@ -143,28 +588,170 @@ impl Board {
 
     /// Perform a move and return the next board. Returns [None] if
     /// the move was illegal.
-    #[allow(clippy::missing_panics_doc)]
     pub fn perform_move(&self, m: Move) -> Option<Board> {
-        // local function because this snippet occurs 3 times
-        fn rook_taken_castling(flags: &mut CastlingFlags, file: u32, color: Color) {
-            if file == 0 {
-                *flags &= !match color {
-                    Color::White => CastlingFlags::WHITE_LONG,
-                    Color::Black => CastlingFlags::BLACK_LONG,
-                };
-            } else if file == 7 {
-                *flags &= !match color {
-                    Color::White => CastlingFlags::WHITE_SHORT,
-                    Color::Black => CastlingFlags::BLACK_SHORT,
-                };
-            }
-        }
-
         if !self.is_legal(m, self.turn) {
             return None;
         }
 
+        Some(self.perform_move_unvalidated(m))
+    }
+
+    /// Perform a move like [`Board::perform_move`], and also tell
+    /// whether it put the opponent in check, checkmate or stalemate.
+    /// This is cheaper than calling [`Board::in_check`] and
+    /// [`Board::get_all_legal_moves`] on the new board, since the
+    /// legal moves are only generated until one is found. Returns
+    /// [None] if the move was illegal.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// let board = Board::load_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    ///
+    /// let outcome = board.perform_move_with_outcome("a1a8".parse().unwrap()).unwrap();
+    /// assert!(outcome.check && outcome.mate && !outcome.stalemate);
+    ///
+    /// let outcome = board.perform_move_with_outcome("a1a7".parse().unwrap()).unwrap();
+    /// assert!(!outcome.check && !outcome.mate && !outcome.stalemate);
+    /// assert_eq!(outcome.board, board.perform_move("a1a7".parse().unwrap()).unwrap());
+    /// ```
+    pub fn perform_move_with_outcome(&self, m: Move) -> Option<MoveOutcome> {
+        self.perform_move(m).map(MoveOutcome::new)
+    }
+
+    /// Get the [`MoveInfo`] of a move, as if it were played on this
+    /// board, e.g. to tell what it captures or which castling rights
+    /// it takes away. Returns [None] if the move was illegal.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{Board, CastlingFlags};
+    /// # use chess_engine::piece::PieceType;
+    /// let board = Board::load_fen("r3k2r/1P6/8/8/8/8/8/4K3 w kq - 0 1").unwrap();
+    /// let info = board.move_info("b7a8=Q".parse().unwrap()).unwrap();
+    ///
+    /// // capturing the rook while promoting takes away black's long castling
+    /// assert_eq!(info.captured.unwrap().piece, PieceType::Rook);
+    /// assert_eq!(info.promotion, Some(PieceType::Queen));
+    /// assert_eq!(info.revoked_castling, CastlingFlags::BLACK_LONG);
+    /// assert!(info.check);
+    /// ```
+    pub fn move_info(&self, m: Move) -> Option<MoveInfo> {
+        self.perform_move(m)
+            .map(|after| MoveInfo::new(self, m, &after))
+    }
+
+    /// Get everything about a move played on this board, see
+    /// [`ResolvedMove`]. Returns [None] if the move was illegal.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{Board, SquareSpec};
+    /// # use chess_engine::piece::{Color, Piece, PieceType};
+    /// let board = Board::load_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+    /// let resolved = board.resolve("e5d6".parse().unwrap()).unwrap();
+    ///
+    /// assert_eq!(resolved.piece, Piece::new(PieceType::Pawn, Color::White));
+    /// assert_eq!(
+    ///     resolved.captured,
+    ///     Some((SquareSpec::D5, Piece::new(PieceType::Pawn, Color::Black)))
+    /// );
+    /// assert!(!resolved.check);
+    /// assert!(board.resolve("e5e7".parse().unwrap()).is_none());
+    /// ```
+    pub fn resolve(&self, m: Move) -> Option<ResolvedMove> {
+        if self.is_legal(m, self.turn) {
+            Some(m.resolve(self))
+        } else {
+            None
+        }
+    }
+
+    /// Perform a move without checking that it's legal, and return
+    /// the next board. Unlike [`Board::unchecked_perform_move`], this
+    /// fully updates en passant, castling rights and the move
+    /// counters, exactly like [`Board::perform_move`] does.
+    ///
+    /// This is meant for trusted callers like searches, which only
+    /// play moves from [`Board::get_all_legal_moves`] and shouldn't
+    /// pay for checking them again. The result is unspecified if the
+    /// move isn't legal.
+    ///
+    /// # Panics
+    ///
+    /// May panic if the move doesn't move a piece.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// let board = Board::default_board();
+    /// let m = board.get_all_legal_moves()[0];
+    ///
+    /// assert_eq!(board.perform_move_unvalidated(m), board.perform_move(m).unwrap());
+    /// ```
+    pub fn perform_move_unvalidated(&self, m: Move) -> Board {
+        let mut new_board = *self;
+        let _ = new_board.make_move(m);
+        new_board
+    }
+
+    // Pass the turn to the opponent without moving, for null move
+    // pruning in searches. Not a legal move, so the result is only
+    // meaningful if the current player isn't in check.
+    #[cfg(feature = "search")]
+    pub(crate) fn null_move(&self) -> Board {
         let mut new_board = *self;
+        new_board.en_passant = None;
+        new_board.turn = self.turn.opposite();
+        new_board
+    }
+
+    /// Perform a move in place, without checking that it's legal, and
+    /// return what's needed to take it back with
+    /// [`Board::unmake_move`]. This updates the board exactly like
+    /// [`Board::perform_move_unvalidated`], but without copying it,
+    /// which is what searches and legality checks that try many moves
+    /// on the same board want.
+    ///
+    /// The result is unspecified if the move isn't legal.
+    ///
+    /// # Panics
+    ///
+    /// May panic if the move doesn't move a piece.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// let board = Board::load_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+    /// let m = "e5d6".parse().unwrap();
+    ///
+    /// let mut scratch = board;
+    /// let undo = scratch.make_move(m);
+    /// assert_eq!(scratch, board.perform_move(m).unwrap());
+    ///
+    /// scratch.unmake_move(m, undo);
+    /// assert_eq!(scratch, board);
+    /// ```
+    pub fn make_move(&mut self, m: Move) -> MoveUndo {
+        // local function because this snippet occurs 3 times. Only
+        // rooks on their starting squares matter, as a rook that has
+        // moved away already took its castling right with it.
+        fn rook_taken_castling(flags: &mut CastlingFlags, sq: SquareSpec, color: Color) {
+            for &c in &Castling::ALL {
+                if sq == c.rook_from(color) {
+                    *flags &= !c.flag(color);
+                }
+            }
+        }
+
+        let undo = MoveUndo {
+            captured: None,
+            castling: self.castling,
+            en_passant: self.en_passant,
+            halfmove: self.halfmove,
+            fullmove: self.fullmove,
+        };
+        let mut captured = None;
         let mut new_en_passant = None;
         let mut reset_halfmove = false;
 
@@ -177,14 +764,14 @@ impl Board {
                         color,
                     } => {
                         // disable castling in one direction
-                        rook_taken_castling(&mut new_board.castling, from.file, color);
+                        rook_taken_castling(&mut self.castling, from, color);
                     }
                     Piece {
                         piece: PieceType::King,
                         color,
                     } => {
                         // disable castling in both directions
-                        new_board.castling &= !match color {
+                        self.castling &= !match color {
                             Color::White => CastlingFlags::WHITE,
                             Color::Black => CastlingFlags::BLACK,
                         }
@@ -198,64 +785,54 @@ impl Board {
                             Color::White => SquareDiff::new(1, 0),
                             Color::Black => SquareDiff::new(-1, 0),
                         };
-                        if let Some(en_passant) = self.en_passant {
-                            if en_passant == to {
-                                debug_assert!(
-                                    new_board[to + dir] == Some(Piece::new(PieceType::Pawn, color.opposite())),
-                                    "The piece taken by en passant wasn't a pawn, this is most likely a bug"
-                                );
-                                new_board[to + dir] = None;
-                            }
+                        if self.en_passant == Some(to) {
+                            debug_assert!(
+                                self[to + dir] == Some(Piece::new(PieceType::Pawn, color.opposite())),
+                                "The piece taken by en passant wasn't a pawn, this is most likely a bug"
+                            );
+                            captured = self[to + dir].map(|p| (to + dir, p));
+                            self[to + dir] = None;
                         } else if (to - from).abs().d_rank == 2 {
                             // if a pawn moved two squares, we need to
-                            // set the new en passant square
-                            new_en_passant = Some(from + dir);
+                            // set the new en passant square, which is
+                            // the one it skipped over
+                            new_en_passant = Some(from - dir);
                         }
                     }
                     _ => (),
                 };
 
-                if self[to].is_some() {
+                if let Some(piece) = self[to] {
                     reset_halfmove = true;
+                    captured = Some((to, piece));
+                    // disable castling in that direction if the rook was taken
+                    if piece.piece == PieceType::Rook {
+                        rook_taken_castling(&mut self.castling, to, piece.color);
+                    }
                 }
 
-                // disable castling in that direction if the rook was taken
-                if let Some(Piece {
-                    piece: PieceType::Rook,
-                    color,
-                }) = self[to]
-                {
-                    rook_taken_castling(&mut new_board.castling, to.file, color);
-                }
-
-                new_board[to] = self[from];
-                new_board[from] = None;
+                self[to] = self[from];
+                self[from] = None;
             }
             Move::Castling(c) => {
-                use Castling::{Long, Short};
-
                 let color = self.turn;
-                let rank = color.home_rank();
-                let king_from = SquareSpec::new(rank, 4);
-
-                let (rf, kt, rt) = match c {
-                    Short => (7, 6, 5),
-                    Long => (0, 2, 3),
-                };
-
-                let rook_from = SquareSpec::new(rank, rf);
-                let king_to = SquareSpec::new(rank, kt);
-                let rook_to = SquareSpec::new(rank, rt);
+                let (king_from, king_to, rook_from, rook_to) = (
+                    c.king_from(color),
+                    c.king_to(color),
+                    c.rook_from(color),
+                    c.rook_to(color),
+                );
 
-                new_board.castling &= !match color {
+                self.castling &= !match color {
                     Color::White => CastlingFlags::WHITE,
                     Color::Black => CastlingFlags::BLACK,
                 };
 
-                new_board[king_to] = self[king_from];
-                new_board[king_from] = None;
-                new_board[rook_to] = self[rook_from];
-                new_board[rook_from] = None;
+                let (king, rook) = (self[king_from], self[rook_from]);
+                self[king_from] = None;
+                self[rook_from] = None;
+                self[king_to] = king;
+                self[rook_to] = rook;
             }
             Move::Promotion { from, to, target } => {
                 // since promotions are always pawn moves, this must
@@ -264,91 +841,292 @@ impl Board {
 
                 // yet again have to double check if either of the
                 // rooks were taken
-                if let Some(Piece {
-                    piece: PieceType::Rook,
-                    color,
-                }) = self[to]
-                {
-                    rook_taken_castling(&mut new_board.castling, to.file, color);
+                if let Some(piece) = self[to] {
+                    captured = Some((to, piece));
+                    if piece.piece == PieceType::Rook {
+                        rook_taken_castling(&mut self.castling, to, piece.color);
+                    }
                 }
 
                 // again, the move is guaranteed to be valid, so this
                 // unwrap can't panic
-                new_board[to] = Some(Piece::new(target, self[from].unwrap().color));
-                new_board[from] = None;
+                self[to] = Some(Piece::new(target, self[from].unwrap().color));
+                self[from] = None;
             }
         }
 
-        new_board.en_passant = new_en_passant;
-        new_board.turn = self.turn.opposite();
+        self.en_passant = new_en_passant;
+        // the counters saturate rather than overflow, since they can
+        // start out at anything through FEN
         if self.turn == Color::Black {
-            new_board.fullmove += 1;
+            self.fullmove = self.fullmove.saturating_add(1);
         }
         if reset_halfmove {
-            new_board.halfmove = 0;
+            self.halfmove = 0;
         } else {
-            new_board.halfmove += 1;
+            self.halfmove = self.halfmove.saturating_add(1);
         }
+        self.turn = self.turn.opposite();
 
-        Some(new_board)
-    }
-
-    /// Returns whether the current player is in check
-    pub fn in_check(&self) -> bool {
-        self.is_threatened(
-            self.turn,
-            match self.king(self.turn) {
-                Some(king) => king,
-                // we can't be checked if there's no king to check
-                _ => return false,
-            },
-        )
-    }
-
-    /// Get the current halfmove
-    pub fn halfmove(&self) -> u32 {
-        self.halfmove
+        MoveUndo { captured, ..undo }
     }
 
-    /// Performs a move with wanton abandon for the rules, effectively
-    /// taking any piece on the resulting squares regardless of color.
-    /// Moving an empty piece will also result in a phantom take.
-    /// Needless to say, this function shouldn't really be used by
-    /// anyone except internally, but if you need it, it's there.  Oh
-    /// yeah, castling is also unchecked and will produce wildly wrong
-    /// results if used illegally
-    pub fn unchecked_perform_move(&self, m: Move) -> Board {
-        let mut new_board = *self;
-
+    /// Take back a move made with [`Board::make_move`], given what it
+    /// returned. Moves have to be taken back in the reverse order
+    /// they were made in, or the board ends up in an unspecified
+    /// state.
+    ///
+    /// # Panics
+    ///
+    /// May panic if `m` isn't the last move made on the board.
+    pub fn unmake_move(&mut self, m: Move, undo: MoveUndo) {
+        let mover = self.turn.opposite();
         match m {
             Move::Normal { from, to } => {
-                new_board[to] = self[from];
-                new_board[from] = None;
+                self[from] = self[to];
+                self[to] = None;
             }
             Move::Castling(c) => {
-                let rank = self.turn.home_rank();
-                let kf = 4;
-                let (rf, kt, rt) = match c {
-                    Castling::Long => (0, 2, 3),
-                    Castling::Short => (7, 6, 5),
-                };
-
-                let (king_from, rook_from, king_to, rook_to) = (
-                    SquareSpec::new(rank, kf),
-                    SquareSpec::new(rank, rf),
-                    SquareSpec::new(rank, kt),
-                    SquareSpec::new(rank, rt),
+                let (king_from, king_to, rook_from, rook_to) = (
+                    c.king_from(mover),
+                    c.king_to(mover),
+                    c.rook_from(mover),
+                    c.rook_to(mover),
                 );
-
-                new_board[king_to] = self[king_from];
-                new_board[king_from] = None;
-                new_board[rook_to] = self[rook_from];
-                new_board[rook_from] = None;
+                let (king, rook) = (self[king_to], self[rook_to]);
+                self[king_to] = None;
+                self[rook_to] = None;
+                self[king_from] = king;
+                self[rook_from] = rook;
             }
-            Move::Promotion { from, to, target } => {
-                new_board[to] = self[from];
-                new_board[from] = None;
-                if let Some(Piece { color, .. }) = new_board[to] {
+            Move::Promotion { from, to, .. } => {
+                self[from] = Some(Piece::new(PieceType::Pawn, mover));
+                self[to] = None;
+            }
+        }
+        if let Some((sq, piece)) = undo.captured {
+            self[sq] = Some(piece);
+        }
+
+        self.turn = mover;
+        self.castling = undo.castling;
+        self.en_passant = undo.en_passant;
+        self.halfmove = undo.halfmove;
+        self.fullmove = undo.fullmove;
+    }
+
+    /// Check whether performing a move would put the opponent in
+    /// check. Returns `false` if the move is illegal.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// let board = Board::load_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+    ///
+    /// assert!(board.gives_check("a1a8".parse().unwrap()));
+    /// assert!(!board.gives_check("a1a7".parse().unwrap()));
+    /// ```
+    pub fn gives_check(&self, m: Move) -> bool {
+        self.perform_move(m).map_or(false, |board| board.in_check())
+    }
+
+    /// Check whether performing a move would checkmate the opponent.
+    /// Returns `false` if the move is illegal.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// let board = Board::load_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    ///
+    /// assert!(board.gives_mate("a1a8".parse().unwrap()));
+    /// assert!(!board.gives_mate("a1a7".parse().unwrap()));
+    /// ```
+    pub fn gives_mate(&self, m: Move) -> bool {
+        self.perform_move_with_outcome(m)
+            .map_or(false, |outcome| outcome.mate)
+    }
+
+    /// Check whether performing a move would give a discovered check,
+    /// where the opponent is checked by a piece other than the one that
+    /// moved, because the move got out of its way. Returns `false` if
+    /// the move is illegal.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// let board = Board::load_fen("4k3/8/8/8/4N3/8/8/4R1K1 w - - 0 1").unwrap();
+    ///
+    /// assert!(board.is_discovered_check("e4c3".parse().unwrap()));
+    /// // moving the rook gives check directly instead
+    /// assert!(!board.is_discovered_check("e1a1".parse().unwrap()));
+    /// ```
+    pub fn is_discovered_check(&self, m: Move) -> bool {
+        let Some(board) = self.perform_move(m) else {
+            return false;
+        };
+        // the moved pieces end up on squares touched by the move, and
+        // no other piece can be on them
+        let moved = m.squares_touched(self);
+        board.checkers().into_iter().any(|sq| !moved.contains(&sq))
+    }
+
+    /// Check whether performing a move would give double check, with
+    /// two pieces checking the opponent at once. Returns `false` if
+    /// the move is illegal.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// let board = Board::load_fen("4k3/8/8/8/4N3/8/8/4R1K1 w - - 0 1").unwrap();
+    ///
+    /// assert!(board.is_double_check("e4d6".parse().unwrap()));
+    /// assert!(!board.is_double_check("e4c3".parse().unwrap()));
+    /// ```
+    pub fn is_double_check(&self, m: Move) -> bool {
+        self.perform_move(m)
+            .map_or(false, |board| board.checkers().len() > 1)
+    }
+
+    /// Get the squares of the pieces checking the current player
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{Board, SquareSpec};
+    /// let board = Board::load_fen("4k3/8/3N4/8/8/8/8/4R1K1 b - - 0 1").unwrap();
+    /// let mut checkers = board.checkers();
+    /// checkers.sort();
+    ///
+    /// assert_eq!(checkers, [SquareSpec::E1, SquareSpec::D6]);
+    /// ```
+    pub fn checkers(&self) -> Vec<SquareSpec> {
+        match self.king(self.turn) {
+            Some(king) => self.attackers(self.turn, king),
+            None => vec![],
+        }
+    }
+
+    /// Returns whether the current player is in check
+    pub fn in_check(&self) -> bool {
+        // we can't be checked if there's no king to check
+        let Some(king) = self.king(self.turn) else {
+            return false;
+        };
+        self.is_threatened(self.turn, king)
+    }
+
+    /// Returns whether the current player is checkmated, i.e. in
+    /// check without any legal moves
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// let board = Board::load_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+    ///
+    /// assert!(board.is_checkmate());
+    /// assert!(!board.is_stalemate());
+    /// ```
+    pub fn is_checkmate(&self) -> bool {
+        self.in_check() && !self.has_legal_moves()
+    }
+
+    /// Returns whether the current player is stalemated, i.e. not in
+    /// check but without any legal moves
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// let board = Board::load_fen("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1").unwrap();
+    ///
+    /// assert!(board.is_stalemate());
+    /// assert!(!board.is_checkmate());
+    /// ```
+    pub fn is_stalemate(&self) -> bool {
+        !self.in_check() && !self.has_legal_moves()
+    }
+
+    /// Get the current halfmove
+    pub fn halfmove(&self) -> u32 {
+        self.halfmove
+    }
+
+    /// Get the current fullmove number, which starts at 1 and is
+    /// incremented after each move by black
+    pub fn fullmove(&self) -> u32 {
+        self.fullmove
+    }
+
+    /// Get the Zobrist hash of the position, a 64-bit key that's the
+    /// same for positions that count as the same for repetitions.
+    /// The move counters aren't part of it, and the en passant square
+    /// only is when a pawn could capture there.
+    ///
+    /// The keys are fixed, so hashes can be stored and compared
+    /// between runs.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// let board = Board::default_board();
+    /// let knights_out_and_back = ["g1f3", "g8f6", "f3g1", "f6g8"]
+    ///     .iter()
+    ///     .fold(board, |b, m| b.perform_move(m.parse().unwrap()).unwrap());
+    ///
+    /// assert_ne!(board, knights_out_and_back);
+    /// assert_eq!(board.zobrist(), knights_out_and_back.zobrist());
+    /// ```
+    pub fn zobrist(&self) -> u64 {
+        zobrist::hash(self)
+    }
+
+    /// Get the board with its halfmove clock and fullmove number
+    /// replaced, e.g. to set up positions close to the fifty move
+    /// rule. A fullmove number of 0 is taken to be 1, the lowest
+    /// valid one.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// let board = Board::default_board().with_counters(12, 30);
+    ///
+    /// assert_eq!(board.halfmove(), 12);
+    /// assert_eq!(board.fullmove(), 30);
+    /// assert_eq!(Board::default_board().with_counters(0, 0).fullmove(), 1);
+    /// ```
+    pub fn with_counters(self, halfmove: u32, fullmove: u32) -> Board {
+        Board {
+            halfmove,
+            fullmove: fullmove.max(1),
+            ..self
+        }
+    }
+
+    /// Performs a move with wanton abandon for the rules, effectively
+    /// taking any piece on the resulting squares regardless of color.
+    /// Moving an empty piece will also result in a phantom take.
+    /// Needless to say, this function shouldn't really be used by
+    /// anyone except internally, but if you need it, it's there.  Oh
+    /// yeah, castling is also unchecked and will produce wildly wrong
+    /// results if used illegally. If you just want to skip the
+    /// legality check, use [`Board::perform_move_unvalidated`].
+    pub fn unchecked_perform_move(&self, m: Move) -> Board {
+        let mut new_board = *self;
+
+        match m {
+            Move::Normal { from, to } => {
+                new_board[to] = self[from];
+                new_board[from] = None;
+            }
+            Move::Castling(c) => {
+                let color = self.turn;
+                new_board[c.king_to(color)] = self[c.king_from(color)];
+                new_board[c.king_from(color)] = None;
+                new_board[c.rook_to(color)] = self[c.rook_from(color)];
+                new_board[c.rook_from(color)] = None;
+            }
+            Move::Promotion { from, to, target } => {
+                new_board[to] = self[from];
+                new_board[from] = None;
+                if let Some(Piece { color, .. }) = new_board[to] {
                     new_board[to] = Some(Piece {
                         color,
                         piece: target,
@@ -370,7 +1148,9 @@ impl Board {
 
     /// Get all the legal moves for the piece on this square. If the
     /// square is empty, or if the selected piece is unavailable this
-    /// turn, this will return an empty vector.
+    /// turn, this will return an empty vector. The moves are generated
+    /// on every call, see [`AnalyzedBoard`] for looking them up
+    /// repeatedly.
     pub fn get_legal_moves(&self, piece_location: SquareSpec) -> Vec<Move> {
         if let Some(piece) = self[piece_location] {
             if piece.color != self.turn {
@@ -382,6 +1162,41 @@ impl Board {
         }
     }
 
+    /// Get the squares the piece on this square can legally move to,
+    /// with what kind of move gets it there. The four promotions to
+    /// each square are collapsed into a single
+    /// [`MoveKind::Promotion`], and castling is given as the square
+    /// the king ends up on, which is what UIs highlighting the
+    /// destinations of a piece need.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{Board, Castling, MoveKind, SquareSpec};
+    /// let board = Board::load_fen("3rk3/4P3/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+    ///
+    /// assert_eq!(
+    ///     board.legal_destinations(SquareSpec::E7),
+    ///     vec![(SquareSpec::D8, MoveKind::Promotion)]
+    /// );
+    /// assert!(board
+    ///     .legal_destinations(SquareSpec::E1)
+    ///     .contains(&(SquareSpec::G1, MoveKind::Castling(Castling::Short))));
+    /// ```
+    pub fn legal_destinations(&self, from: SquareSpec) -> Vec<(SquareSpec, MoveKind)> {
+        let mut destinations = Vec::new();
+        for m in self.get_legal_moves(from) {
+            let destination = match m {
+                Move::Normal { to, .. } => (to, MoveKind::Normal),
+                Move::Castling(c) => (c.king_to(self.turn), MoveKind::Castling(c)),
+                Move::Promotion { to, .. } => (to, MoveKind::Promotion),
+            };
+            if !destinations.contains(&destination) {
+                destinations.push(destination);
+            }
+        }
+        destinations
+    }
+
     /// Like [`get_legal_moves`], but for getting all the legal moves possible on this turn
     pub fn get_all_legal_moves(&self) -> Vec<Move> {
         let mut all_moves = Vec::new();
@@ -400,6 +1215,122 @@ impl Board {
         all_moves
     }
 
+    /// Get all the legal moves this turn that capture a piece,
+    /// including en passant and promotions that capture. This is
+    /// cheaper than filtering [`Board::get_all_legal_moves`], since
+    /// only the captures are checked for legality.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// let board = Board::load_fen("4k3/8/8/3p4/4P3/8/8/4K2R w K - 0 1").unwrap();
+    ///
+    /// assert_eq!(board.generate_captures(), vec!["e4d5".parse().unwrap()]);
+    /// ```
+    pub fn generate_captures(&self) -> Vec<Move> {
+        legal_moves::captures(self)
+    }
+
+    /// Get all the legal moves this turn that don't capture anything,
+    /// including castling and promotions that don't capture. Together
+    /// with [`Board::generate_captures`], these are all the legal
+    /// moves.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// let board = Board::load_fen("4k3/8/8/3p4/4P3/8/8/4K2R w K - 0 1").unwrap();
+    /// let quiets = board.generate_quiets();
+    ///
+    /// assert!(quiets.contains(&"O-O".parse().unwrap()));
+    /// assert!(quiets.contains(&"e4e5".parse().unwrap()));
+    /// assert_eq!(quiets.len() + 1, board.get_all_legal_moves().len());
+    /// ```
+    pub fn generate_quiets(&self) -> Vec<Move> {
+        legal_moves::quiets(self)
+    }
+
+    /// Get all the legal moves that get the current player out of
+    /// check, which are all their legal moves, or an empty list if
+    /// they're not in check. This is cheaper than
+    /// [`Board::get_all_legal_moves`], since only king moves and
+    /// moves capturing or blocking the checking piece are tried.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// // the rook on a1 can be captured, or blocked on c1 or d1
+    /// let board = Board::load_fen("4k3/8/8/8/8/1N6/3PBP2/r3K3 w - - 0 1").unwrap();
+    /// let mut evasions = board.generate_evasions();
+    /// evasions.sort();
+    /// let mut expected = vec!["b3a1", "b3c1", "e2d1"]
+    ///     .into_iter()
+    ///     .map(|m| m.parse().unwrap())
+    ///     .collect::<Vec<_>>();
+    /// expected.sort();
+    ///
+    /// assert_eq!(evasions, expected);
+    /// assert!(Board::default_board().generate_evasions().is_empty());
+    /// ```
+    pub fn generate_evasions(&self) -> Vec<Move> {
+        legal_moves::evasions(self)
+    }
+
+    /// Returns whether the current player has any legal moves. This
+    /// stops at the first piece with a legal move, so it's cheaper
+    /// than checking whether [`Board::get_all_legal_moves`] is empty.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// assert!(Board::default_board().has_legal_moves());
+    /// // stalemate
+    /// assert!(!Board::load_fen("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1").unwrap().has_legal_moves());
+    /// ```
+    pub fn has_legal_moves(&self) -> bool {
+        SquareSpec::all().any(|sq| match self[sq] {
+            Some(piece) if piece.color == self.turn => {
+                !legal_moves::enumerate_legal_moves(piece, sq, self, true).is_empty()
+            }
+            _ => false,
+        })
+    }
+
+    /// Get all the legal moves this turn which land on a certain
+    /// square. Castling counts as landing on the king's destination
+    /// square.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{Board, SquareSpec};
+    /// let moves = Board::default_board().legal_moves_to(SquareSpec::F3);
+    ///
+    /// assert_eq!(moves, vec!["g1f3".parse().unwrap(), "f2f3".parse().unwrap()]);
+    /// ```
+    pub fn legal_moves_to(&self, sq: SquareSpec) -> Vec<Move> {
+        let mut moves = self.get_all_legal_moves();
+        moves.retain(|m| m.to(self.turn) == sq);
+        moves
+    }
+
+    /// Get all the legal moves this turn made by a certain type of
+    /// piece. Castling counts as a king move.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// # use chess_engine::piece::PieceType;
+    /// let board = Board::default_board();
+    ///
+    /// assert_eq!(board.legal_moves_of_type(PieceType::Knight).len(), 4);
+    /// assert!(board.legal_moves_of_type(PieceType::Queen).is_empty());
+    /// ```
+    pub fn legal_moves_of_type(&self, piece: PieceType) -> Vec<Move> {
+        let mut moves = self.get_all_legal_moves();
+        moves.retain(|m| matches!(self[m.from(self.turn)], Some(p) if p.piece == piece));
+        moves
+    }
+
     /// Get a particular color's king's square (if there is one)
     ///
     /// # Example
@@ -430,31 +1361,150 @@ impl Board {
         None
     }
 
-    /// Check if a certain square on the board is threatened
-    pub fn is_threatened(&self, color: Color, sq: SquareSpec) -> bool {
-        for (rank, row) in self.board.iter().enumerate().map(|(c, i)| (c as u32, i)) {
-            for (file, piece) in row
-                .iter()
-                .enumerate()
-                .filter_map(|(c, p)| p.map(|x| (c as u32, x)))
+    // Iterate over the pieces of a color along with their squares
+    fn pieces(&self, color: Color) -> impl Iterator<Item = (SquareSpec, Piece)> + '_ {
+        SquareSpec::all().filter_map(move |sq| match self[sq] {
+            Some(piece) if piece.color == color => Some((sq, piece)),
+            _ => None,
+        })
+    }
+
+    /// Get how many of `color`'s pieces attack each square, indexed
+    /// by rank and then file. A square counts as attacked whether
+    /// it's empty or holds a piece of either color, so a piece
+    /// defended twice has a count of 2.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{Board, SquareSpec};
+    /// # use chess_engine::piece::Color;
+    /// let board = Board::default_board();
+    /// let control = board.control_map(Color::White);
+    ///
+    /// // the f3 square is covered by the g1 knight and the e2 and g2 pawns
+    /// assert_eq!(control[2][5], 3);
+    /// // the a1 rook isn't defended, but the queen is by the king
+    /// assert_eq!(control[0][0], 0);
+    /// assert_eq!(control[0][3], 1);
+    /// assert_eq!(control[3][4], 0);
+    /// ```
+    pub fn control_map(&self, color: Color) -> [[u8; 8]; 8] {
+        let mut control = [[0; 8]; 8];
+        let occupancy = self.occupancy();
+        for (from, piece) in self.pieces(color) {
+            for sq in attacks::piece_attacks(piece.piece, piece.color, from, occupancy)
+                .filter(|&sq| self.size.contains(sq))
             {
-                if piece.color == color.opposite() {
-                    let legal_moves = legal_moves::enumerate_legal_moves(
-                        piece,
-                        SquareSpec { rank, file },
-                        self,
-                        false,
-                    );
-                    if legal_moves.into_iter().any(|m| match m {
-                        Move::Normal { to, .. } => to == sq,
-                        _ => false,
-                    }) {
-                        return true;
-                    }
-                }
+                control[sq.rank as usize][sq.file as usize] += 1;
             }
         }
-        false
+        control
+    }
+
+    /// Get the squares attacked by any of `by`'s pieces, as a mask with
+    /// a bit set at each square's [index](SquareSpec::index), e.g. to
+    /// draw the squares that are dangerous for the other color. Like
+    /// [`Board::is_attacked`], a square counts whatever is on it.
+    /// [`attacks::squares`] turns the mask into squares.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{attacks, Board, SquareSpec};
+    /// # use chess_engine::piece::Color;
+    /// let board = Board::load_fen("4k3/8/8/8/8/8/6p1/4K2R w K - 0 1").unwrap();
+    /// let threatened = board.threatened_squares(Color::Black);
+    ///
+    /// assert_ne!(threatened & (1 << SquareSpec::F1.index()), 0);
+    /// assert_eq!(threatened & (1 << SquareSpec::G1.index()), 0);
+    /// assert!(attacks::squares(threatened).all(|sq| board.is_attacked(sq, Color::Black)));
+    /// ```
+    pub fn threatened_squares(&self, by: Color) -> u64 {
+        let occupancy = self.occupancy();
+        self.pieces(by)
+            .flat_map(|(from, piece)| {
+                attacks::piece_attacks(piece.piece, piece.color, from, occupancy)
+            })
+            .fold(0, |mask, sq| mask | 1 << sq.index())
+            & self.size.mask()
+    }
+
+    /// Get the mobility of `color`, the number of squares its pieces
+    /// can move to, ignoring whether the moves would leave its king in
+    /// check. Castling isn't counted, and neither is more than one of
+    /// the promotions to a square. This can be used for either color,
+    /// whoever's turn it is.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// # use chess_engine::piece::Color;
+    /// let board = Board::default_board();
+    ///
+    /// assert_eq!(board.mobility(Color::White), 20);
+    /// assert_eq!(board.mobility(Color::Black), 20);
+    /// ```
+    pub fn mobility(&self, color: Color) -> u32 {
+        let mut mobility = 0;
+        for (from, piece) in self.pieces(color) {
+            let mut targets: Vec<_> = legal_moves::enumerate_legal_moves(piece, from, self, false)
+                .into_iter()
+                .filter_map(|m| match m {
+                    Move::Normal { to, .. } | Move::Promotion { to, .. } => Some(to),
+                    Move::Castling(_) => None,
+                })
+                .collect();
+            targets.dedup();
+            mobility += targets.len() as u32;
+        }
+        mobility
+    }
+
+    // Get the squares of the pieces of `color`'s opponent that could
+    // capture on a square
+    fn attackers(&self, color: Color, sq: SquareSpec) -> Vec<SquareSpec> {
+        self.attacking(sq, color.opposite()).collect()
+    }
+
+    // Iterate over the squares of the pieces of `by` attacking a
+    // square. A piece of each type placed on the square attacks
+    // exactly the squares a piece of that type could attack it from,
+    // so only those squares have to be looked at.
+    fn attacking(&self, sq: SquareSpec, by: Color) -> impl Iterator<Item = SquareSpec> + '_ {
+        let occupancy = self.occupancy();
+        PieceType::ALL.iter().flat_map(move |&piece| {
+            // pawns attack towards the other side, so the pawns
+            // attacking the square are where a pawn of the other color
+            // on it would attack
+            attacks::piece_attacks(piece, by.opposite(), sq, occupancy)
+                .filter(move |&from| self[from] == Some(Piece::new(piece, by)))
+        })
+    }
+
+    /// Check whether any piece of color `by` attacks a square, i.e.
+    /// could capture a piece of the other color on it. The square
+    /// doesn't have to be empty or hold a piece of the other color.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{Board, SquareSpec};
+    /// # use chess_engine::piece::Color;
+    /// let board = Board::load_fen("4k3/8/8/8/8/8/6p1/4K2R w K - 0 1").unwrap();
+    ///
+    /// // the pawn attacks the empty square next to it
+    /// assert!(board.is_attacked(SquareSpec::F1, Color::Black));
+    /// // and the rook defends the square the pawn is on
+    /// assert!(board.is_attacked(SquareSpec::H2, Color::White));
+    /// assert!(!board.is_attacked(SquareSpec::G1, Color::Black));
+    /// ```
+    pub fn is_attacked(&self, sq: SquareSpec, by: Color) -> bool {
+        self.attacking(sq, by).next().is_some()
+    }
+
+    /// Check if a certain square on the board is threatened, i.e.
+    /// attacked by a piece of `color`'s opponent, see
+    /// [`Board::is_attacked`]
+    pub fn is_threatened(&self, color: Color, sq: SquareSpec) -> bool {
+        self.is_attacked(sq, color.opposite())
     }
 }
 
@@ -480,66 +1530,150 @@ impl std::ops::IndexMut<SquareSpec> for Board {
     }
 }
 
-impl fmt::Display for CastlingFlags {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut s = String::new();
-        if self.contains(CastlingFlags::WHITE_SHORT) {
-            s.push('K');
+/// How the castling rights are written in FEN
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CastlingStyle {
+    /// `KQkq`, as in standard FEN and X-FEN
+    Standard,
+    /// The files of the rooks that can castle, `HAha`, as in
+    /// Shredder-FEN
+    Shredder,
+}
+
+impl CastlingFlags {
+    // the flags in the order they're written in FEN
+    const FEN_ORDER: [(CastlingFlags, char); 4] = [
+        (CastlingFlags::WHITE_SHORT, 'K'),
+        (CastlingFlags::WHITE_LONG, 'Q'),
+        (CastlingFlags::BLACK_SHORT, 'k'),
+        (CastlingFlags::BLACK_LONG, 'q'),
+    ];
+
+    // the flags in the order they're written in Shredder-FEN, with the
+    // rooks on their usual a- and h-files
+    const SHREDDER_ORDER: [(CastlingFlags, char); 4] = [
+        (CastlingFlags::WHITE_SHORT, 'H'),
+        (CastlingFlags::WHITE_LONG, 'A'),
+        (CastlingFlags::BLACK_SHORT, 'h'),
+        (CastlingFlags::BLACK_LONG, 'a'),
+    ];
+
+    fn order(style: CastlingStyle) -> &'static [(CastlingFlags, char); 4] {
+        match style {
+            CastlingStyle::Standard => &CastlingFlags::FEN_ORDER,
+            CastlingStyle::Shredder => &CastlingFlags::SHREDDER_ORDER,
         }
-        if self.contains(CastlingFlags::WHITE_LONG) {
-            s.push('Q');
+    }
+
+    /// Parse the castling field of FEN, e.g. `KQkq`, `Kq` or `-` for
+    /// no castling rights. The rook files of Shredder-FEN are read too,
+    /// but only for rooks on the a- and h-files, `HAha`, since any
+    /// other file depends on where the king is. [`Board::load_fen`]
+    /// reads every file.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`Error::InvalidFen`] if the string is empty, has
+    /// characters other than `KQkq` and `HAha`, gives the same right
+    /// twice, or combines them with `-`
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::CastlingFlags;
+    /// assert_eq!(
+    ///     CastlingFlags::from_fen_str("Kq").unwrap(),
+    ///     CastlingFlags::WHITE_SHORT | CastlingFlags::BLACK_LONG
+    /// );
+    /// assert_eq!(CastlingFlags::from_fen_str("-").unwrap(), CastlingFlags::empty());
+    /// assert!(CastlingFlags::from_fen_str("").is_err());
+    /// assert!(CastlingFlags::from_fen_str("KK").is_err());
+    /// assert_eq!(CastlingFlags::from_fen_str("HAha").unwrap(), CastlingFlags::DEFAULT);
+    /// assert!(CastlingFlags::from_fen_str("KH").is_err());
+    /// ```
+    pub fn from_fen_str(s: &str) -> Result<CastlingFlags, Error> {
+        if s == "-" {
+            return Ok(CastlingFlags::empty());
         }
-        if self.contains(CastlingFlags::BLACK_SHORT) {
-            s.push('k');
+        if s.is_empty() {
+            return Err(Error::InvalidFen(s.to_string()));
         }
-        if self.contains(CastlingFlags::BLACK_SHORT) {
-            s.push('q');
+        let mut flags = CastlingFlags::empty();
+        for c in s.chars() {
+            let Some(&(flag, _)) = CastlingFlags::FEN_ORDER
+                .iter()
+                .chain(&CastlingFlags::SHREDDER_ORDER)
+                .find(|&&(_, fc)| fc == c)
+            else {
+                return Err(Error::InvalidFen(s.to_string()));
+            };
+            if flags.contains(flag) {
+                return Err(Error::InvalidFen(s.to_string()));
+            }
+            flags |= flag;
         }
-        write!(f, "{}", s)
+        Ok(flags)
+    }
+
+    /// Write the flags as the castling field of FEN, which is `-` if
+    /// neither player can castle.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::CastlingFlags;
+    /// assert_eq!(CastlingFlags::DEFAULT.to_fen_str(), "KQkq");
+    /// assert_eq!(CastlingFlags::LONG.to_fen_str(), "Qq");
+    /// assert_eq!(CastlingFlags::empty().to_fen_str(), "-");
+    /// ```
+    pub fn to_fen_str(self) -> String {
+        self.to_fen_str_with(CastlingStyle::Standard)
+    }
+
+    /// Write the flags as the castling field of FEN in a certain
+    /// style, see [`CastlingFlags::to_fen_str`]
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{CastlingFlags, CastlingStyle};
+    /// assert_eq!(CastlingFlags::DEFAULT.to_fen_str_with(CastlingStyle::Shredder), "HAha");
+    /// assert_eq!(CastlingFlags::LONG.to_fen_str_with(CastlingStyle::Shredder), "Aa");
+    /// ```
+    pub fn to_fen_str_with(self, style: CastlingStyle) -> String {
+        let mut s = String::new();
+        let _ = self.write_fen(&mut s, style);
+        s
+    }
+
+    fn write_fen<W: fmt::Write + ?Sized>(self, out: &mut W, style: CastlingStyle) -> fmt::Result {
+        if self.is_empty() {
+            return out.write_char('-');
+        }
+        for &(flag, c) in CastlingFlags::order(style) {
+            if self.contains(flag) {
+                out.write_char(c)?;
+            }
+        }
+        Ok(())
     }
 }
 
-impl fmt::Display for Board {
+impl fmt::Display for CastlingFlags {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        use std::fmt::Write;
+        self.write_fen(f, CastlingStyle::Standard)
+    }
+}
 
-        let mut board = String::new();
-        for rank in self.board.iter().rev() {
-            let mut empty_squares = 0;
-            for piece in rank.iter() {
-                if let Some(piece) = piece {
-                    if empty_squares != 0 {
-                        write!(&mut board, "{}", empty_squares)?;
-                        empty_squares = 0;
-                    }
-                    write!(&mut board, "{}", piece)?;
-                } else {
-                    empty_squares += 1;
-                }
-            }
-            if empty_squares != 0 {
-                write!(&mut board, "{}", empty_squares)?;
-            }
-            board.push('/');
-        }
-        // we added one too many slashes
-        let _ = board.pop();
-        write!(
-            f,
-            "{board} {turn} {castling} {en_passant} {halfmove} {fullmove}",
-            board = board,
-            turn = match self.turn {
-                Color::White => 'w',
-                Color::Black => 'b',
-            },
-            castling = self.castling,
-            en_passant = match self.en_passant {
-                Some(sq) => format!("{}", sq),
-                None => "-".to_string(),
-            },
-            halfmove = self.halfmove,
-            fullmove = self.fullmove
-        )
+impl std::str::FromStr for CastlingFlags {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<CastlingFlags, Error> {
+        CastlingFlags::from_fen_str(s)
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_fen(f)
     }
 }
 
@@ -562,6 +1696,7 @@ mod tests {
         assert_eq!(&s, DEFAULT_BOARD);
     }
 
+    #[cfg(feature = "fen")]
     #[test]
     fn parsing_fen_of_default() {
         let parsed = Board::load_fen(DEFAULT_BOARD).unwrap();
@@ -570,6 +1705,7 @@ mod tests {
         assert_eq!(parsed, constructed);
     }
 
+    #[cfg(feature = "fen")]
     #[test]
     fn parsing_en_passant() {
         let parsed = Board::load_fen("8/8/8/5Pp1/8/8/8/8 w - g6 0 1").unwrap();
@@ -581,6 +1717,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "fen")]
     #[test]
     fn en_passant_works() {
         let board = Board::load_fen("8/8/8/4pP2/8/8/8/8 w - e6 0 1").unwrap();
@@ -596,6 +1733,386 @@ mod tests {
         assert!(new[e5].is_none(), "en passant wasn't taken");
     }
 
+    #[test]
+    fn double_step_sets_en_passant() {
+        let e2: SquareSpec = "e2".parse().unwrap();
+        let e4: SquareSpec = "e4".parse().unwrap();
+        let board = Board::default_board()
+            .perform_move(Move::Normal { from: e2, to: e4 })
+            .unwrap();
+
+        assert_eq!(board.en_passant, Some("e3".parse().unwrap()));
+
+        let d7: SquareSpec = "d7".parse().unwrap();
+        let d5: SquareSpec = "d5".parse().unwrap();
+        let board = board
+            .perform_move(Move::Normal { from: d7, to: d5 })
+            .unwrap();
+
+        assert_eq!(board.en_passant, Some("d6".parse().unwrap()));
+    }
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn castling_flags_fen() {
+        for bits in 0..=CastlingFlags::DEFAULT.bits() {
+            let flags = CastlingFlags::from_bits(bits).unwrap();
+            let s = flags.to_fen_str();
+
+            assert_eq!(CastlingFlags::from_fen_str(&s).unwrap(), flags, "{}", s);
+            assert_eq!(s.parse::<CastlingFlags>().unwrap(), flags, "{}", s);
+        }
+        assert_eq!(
+            CastlingFlags::from_fen_str("qK").unwrap(),
+            CastlingFlags::WHITE_SHORT | CastlingFlags::BLACK_LONG
+        );
+        assert!(CastlingFlags::from_fen_str("K-").is_err());
+        assert!(CastlingFlags::from_fen_str("--").is_err());
+        assert!(CastlingFlags::from_fen_str("KQx").is_err());
+    }
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn fen_without_castling_rights() {
+        let fen = "4k3/8/8/8/8/8/8/4K3 b - - 3 40";
+        let board = Board::load_fen(fen).unwrap();
+
+        assert_eq!(board.to_string(), fen);
+        assert!(Board::load_fen("4k3/8/8/8/8/8/8/4K3 b  - 3 40").is_err());
+        assert!(Board::load_fen("4k3/8/8/8/8/8/8/4K3 b X - 3 40").is_err());
+    }
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn counters() {
+        let e2: SquareSpec = "e2".parse().unwrap();
+        let e3: SquareSpec = "e3".parse().unwrap();
+        let board = Board::load_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 7 9").unwrap();
+
+        let moved = board
+            .perform_move(Move::Normal { from: e2, to: e3 })
+            .unwrap();
+        assert_eq!((moved.halfmove(), moved.fullmove()), (0, 9));
+
+        let saturated = board
+            .with_counters(u32::MAX, u32::MAX)
+            .perform_move(Move::Normal {
+                from: "e1".parse().unwrap(),
+                to: "d1".parse().unwrap(),
+            })
+            .unwrap()
+            .perform_move(Move::Normal {
+                from: "e8".parse().unwrap(),
+                to: "d8".parse().unwrap(),
+            })
+            .unwrap();
+        assert_eq!(
+            (saturated.halfmove(), saturated.fullmove()),
+            (u32::MAX, u32::MAX)
+        );
+
+        assert!(Board::load_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 7 0").is_err());
+        assert!(Board::load_fen("4k3/8/8/8/8/8/4P3/4K3 w - - -1 9").is_err());
+    }
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn move_outcome() {
+        let board = Board::load_fen("k7/8/1K6/8/8/8/8/2Q5 w - - 0 1").unwrap();
+        let c1: SquareSpec = "c1".parse().unwrap();
+
+        let stalemate = board
+            .perform_move_with_outcome(Move::Normal {
+                from: c1,
+                to: "c7".parse().unwrap(),
+            })
+            .unwrap();
+        assert!(stalemate.stalemate && !stalemate.check && !stalemate.mate);
+
+        let check = board
+            .perform_move_with_outcome(Move::Normal {
+                from: c1,
+                to: "c8".parse().unwrap(),
+            })
+            .unwrap();
+        assert!(check.mate && check.check && !check.stalemate);
+
+        assert!(board
+            .perform_move_with_outcome(Move::Normal {
+                from: c1,
+                to: "d3".parse().unwrap(),
+            })
+            .is_none());
+    }
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn castling_through_pawn_attack() {
+        // the pawn on g2 attacks f1, which the king would pass
+        let board = Board::load_fen("4k3/8/8/8/8/8/6p1/4K2R w K - 0 1").unwrap();
+        assert!(board.is_threatened(Color::White, SquareSpec::F1));
+        assert!(!board
+            .get_all_legal_moves()
+            .contains(&Move::Castling(Castling::Short)));
+
+        // pawns don't attack the squares in front of them
+        let board = Board::load_fen("4k3/8/8/8/8/4p3/8/4K3 w - - 0 1").unwrap();
+        assert!(!board.is_threatened(Color::White, SquareSpec::E2));
+        assert!(board
+            .get_all_legal_moves()
+            .contains(&"e1e2".parse().unwrap()));
+    }
+
+    // Count the move sequences of `depth` plies from a board
+    #[cfg(feature = "fen")]
+    fn perft(board: &Board, depth: u32) -> usize {
+        if depth == 0 {
+            return 1;
+        }
+        board
+            .get_all_legal_moves()
+            .into_iter()
+            .map(|m| perft(&board.perform_move_unvalidated(m), depth - 1))
+            .sum()
+    }
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn perft_positions() {
+        for &(fen, nodes) in &[
+            (Board::STARTPOS_FEN, 400),
+            (Board::KIWIPETE_FEN, 2039),
+            (Board::PERFT_POSITION_3_FEN, 191),
+            (Board::PERFT_POSITION_4_FEN, 264),
+            (Board::PERFT_POSITION_5_FEN, 1486),
+            (Board::PERFT_POSITION_6_FEN, 2079),
+        ] {
+            assert_eq!(perft(&Board::load_fen(fen).unwrap(), 2), nodes, "{}", fen);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "variants")]
+    fn small_boards() {
+        let board = Board::load_fen("k5/2P3/6/6/4p1/K5 w - - 0 1").unwrap();
+        assert_eq!(board.size(), BoardSize::LOS_ALAMOS);
+        assert_eq!(board.to_fen(), "k5/2P3/6/6/4p1/K5 w - - 0 1");
+        // the pawn promotes on the sixth rank
+        assert!(board.is_legal("c5c6=Q".parse().unwrap(), Color::White));
+        assert!(!board.is_legal("c5c6".parse().unwrap(), Color::White));
+        // and the king can't leave the board
+        assert!(board
+            .get_legal_moves(SquareSpec::A1)
+            .iter()
+            .all(|m| { BoardSize::LOS_ALAMOS.contains(m.to(Color::White)) }));
+
+        let board = Board::gardner();
+        assert!(!board.is_legal("a2a4".parse().unwrap(), Color::White));
+        assert!(Board::load_fen("rnbqk/ppppp/5/PPPP/RNBQK w - - 0 1").is_err());
+
+        // there's no castling or en passant on small boards
+        assert!(Board::load_fen("r3k/5/5/5/R3K w KQkq - 0 1").is_err());
+        assert!(Board::load_fen("r3k/5/5/5/R3K w Q - 0 1").is_err());
+        assert!(Board::load_fen("4k/5/5/5/4K w - h8 0 1").is_err());
+        assert!(Board::load_fen("4k/5/5/5/4K w - c4 0 1").is_err());
+        let mut board = Board::load_fen("4k/5/5/5/R3K w - - 0 1").unwrap();
+        board.castling = CastlingFlags::WHITE_LONG;
+        assert_eq!(
+            board.castling_available(Castling::Long, Color::White),
+            CastlingAvailability::RightsLost
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "variants")]
+    fn small_board_threats() {
+        let board = Board::load_fen("k5/6/6/6/6/5R w - - 0 1").unwrap();
+        let threatened = board.threatened_squares(Color::White);
+
+        assert_eq!(threatened & !BoardSize::LOS_ALAMOS.mask(), 0);
+        assert_eq!(threatened.count_ones(), 10);
+        for sq in [SquareSpec::G1, SquareSpec::H1, SquareSpec::G2] {
+            assert_eq!(threatened & (1 << sq.index()), 0, "{}", sq);
+            assert_eq!(
+                board.control_map(Color::White)[sq.rank as usize][sq.file as usize],
+                0
+            );
+        }
+    }
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn castling_and_en_passant_fit_the_pieces() {
+        assert!(Board::load_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").is_ok());
+        assert!(Board::load_fen("r3k3/8/8/8/8/8/8/R3K2R w KQkq - 0 1").is_err());
+        assert!(Board::load_fen("r3k2r/8/8/8/8/8/8/R2K3R w KQkq - 0 1").is_err());
+        assert!(Board::load_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQq - 0 1").is_ok());
+
+        assert!(Board::load_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").is_ok());
+        assert!(Board::load_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d3 0 1").is_err());
+        assert!(Board::load_fen("4k3/8/8/3pP3/8/8/8/4K3 b - d6 0 1").is_err());
+    }
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn shredder_fen_rook_files() {
+        let fen = "1r2k1r1/8/8/8/8/8/8/1R2K1R1 w GBgb - 0 1";
+        let board = Board::load_fen(fen).unwrap();
+        assert_eq!(board.castling, CastlingFlags::DEFAULT);
+        assert_eq!(board.to_fen_with(CastlingStyle::Shredder), fen);
+        assert_eq!(board.to_fen(), fen);
+        assert_eq!(
+            board.castling_available(Castling::Short, Color::White),
+            CastlingAvailability::RightsLost
+        );
+
+        let board = Board::load_fen("4k1r1/8/8/8/8/8/8/R3K1R1 w GAg - 0 1").unwrap();
+        assert_eq!(board.to_fen(), "4k1r1/8/8/8/8/8/8/R3K1R1 w GQg - 0 1");
+        assert_eq!(
+            board.to_fen_with(CastlingStyle::Shredder),
+            "4k1r1/8/8/8/8/8/8/R3K1R1 w GAg - 0 1"
+        );
+
+        // the rook has to be on the file, and the king on the home rank
+        assert!(Board::load_fen("4k1r1/8/8/8/8/8/8/R3K1R1 w F - 0 1").is_err());
+        assert!(Board::load_fen("4k1r1/8/8/8/8/8/8/R3K1R1 w Gh - 0 1").is_err());
+        assert!(Board::load_fen("6r1/4k3/8/8/8/8/8/R3K1R1 w g - 0 1").is_err());
+        assert!(Board::load_fen("4k1r1/8/8/8/8/8/8/R3K1R1 w GK - 0 1").is_err());
+        assert!(Board::load_fen("4k1r1/8/8/8/8/8/8/R3K1R1 w Gi - 0 1").is_err());
+    }
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn unmake_restores_board() {
+        for &fen in &[
+            Board::KIWIPETE_FEN,
+            Board::PERFT_POSITION_3_FEN,
+            Board::PERFT_POSITION_4_FEN,
+            Board::PERFT_POSITION_5_FEN,
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+        ] {
+            let board = Board::load_fen(fen).unwrap();
+            let mut scratch = board;
+            for m in board.get_all_legal_moves() {
+                let undo = scratch.make_move(m);
+                assert_eq!(scratch, board.perform_move(m).unwrap(), "{} {}", fen, m);
+                scratch.unmake_move(m, undo);
+                assert_eq!(scratch, board, "{} {}", fen, m);
+            }
+        }
+    }
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn castling_rights_from_rook_squares() {
+        // only rooks on their starting squares take castling rights
+        // with them when they move or are captured
+        let board = Board::load_fen("r3k2r/8/8/R7/7r/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let info = board.move_info("a5a6".parse().unwrap()).unwrap();
+        assert!(info.revoked_castling.is_empty());
+        let info = board.move_info("h1h4".parse().unwrap()).unwrap();
+        assert_eq!(info.revoked_castling, CastlingFlags::WHITE_SHORT);
+        let info = board.move_info("a1a5".parse().unwrap());
+        assert!(info.is_none());
+
+        // promoting on the rook's square takes the right away as well
+        let board = Board::load_fen("r3k2r/6P1/8/8/8/8/8/4K3 w kq - 0 1").unwrap();
+        let info = board.move_info("g7h8=N".parse().unwrap()).unwrap();
+        assert_eq!(info.revoked_castling, CastlingFlags::BLACK_SHORT);
+        assert!(!info.check);
+        let info = board.move_info("g7g8=Q".parse().unwrap()).unwrap();
+        assert!(info.revoked_castling.is_empty());
+    }
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn promotion_with_discovered_mate() {
+        // the pawn on e7 shields the black king from the rook on a7
+        let board = Board::load_fen("8/R3P2k/6pp/8/8/8/8/K7 w - - 0 1").unwrap();
+
+        let info = board.move_info("e7e8=R".parse().unwrap()).unwrap();
+        assert_eq!(info.promotion, Some(PieceType::Rook));
+        assert!(info.discovered_check && info.checkmate);
+
+        // a knight doesn't cover the back rank, so the king gets away
+        let info = board.move_info("e7e8=N".parse().unwrap()).unwrap();
+        assert!(info.discovered_check && !info.checkmate);
+
+        let info = board.move_info("a7a8".parse().unwrap()).unwrap();
+        assert!(!info.check && !info.discovered_check);
+    }
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn discovered_checks() {
+        // taking en passant opens the rank for the rook
+        let board = Board::load_fen("8/8/8/k2pP2R/8/8/8/6K1 w - d6 0 1").unwrap();
+        assert!(board.is_discovered_check("e5d6".parse().unwrap()));
+        assert!(!board.is_double_check("e5d6".parse().unwrap()));
+
+        // the rook checks directly after castling
+        let board = Board::load_fen("5k2/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        assert!(board.gives_check("O-O".parse().unwrap()));
+        assert!(!board.is_discovered_check("O-O".parse().unwrap()));
+
+        // and illegal moves don't check at all
+        let board = Board::load_fen("4k3/8/8/8/4N3/8/8/4R1K1 w - - 0 1").unwrap();
+        assert!(!board.is_discovered_check("e4e5".parse().unwrap()));
+    }
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn mate_and_stalemate() {
+        // (fen, checkmate, stalemate)
+        let positions = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                false,
+                false,
+            ),
+            (
+                "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3",
+                true,
+                false,
+            ),
+            ("4k3/4Q3/4K3/8/8/8/8/8 b - - 0 1", true, false),
+            ("4k3/8/4K3/8/8/8/8/4R3 b - - 0 1", false, false),
+            ("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", false, true),
+            ("8/8/8/8/8/5k2/5p2/5K2 w - - 0 1", false, true),
+        ];
+        for &(fen, checkmate, stalemate) in &positions {
+            let board = Board::load_fen(fen).unwrap();
+            assert_eq!(board.is_checkmate(), checkmate, "{}", fen);
+            assert_eq!(board.is_stalemate(), stalemate, "{}", fen);
+            assert_eq!(
+                board.has_legal_moves(),
+                !board.get_all_legal_moves().is_empty(),
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn threatened_squares_match_is_attacked() {
+        let board =
+            Board::load_fen("r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4")
+                .unwrap();
+        for color in [Color::White, Color::Black] {
+            let threatened = board.threatened_squares(color);
+            for sq in SquareSpec::all() {
+                assert_eq!(
+                    threatened & (1 << sq.index()) != 0,
+                    board.is_attacked(sq, color),
+                    "{} attacked by {:?}",
+                    sq,
+                    color
+                );
+            }
+        }
+    }
+
     // TODO: Tests that need to be written:
     // - pawn moves work
     // - promotion works
@@ -1,9 +1,30 @@
-use super::SquareSpec;
-use crate::piece::{Color, PieceType};
-use std::fmt;
+use super::{Board, CastlingFlags, SquareSpec};
+use crate::error::Error;
+use crate::piece::{Color, Piece, PieceType};
+use smallvec::{smallvec, SmallVec};
+use std::fmt::{self, Write};
 
 /// The general type to represent moves.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+///
+/// Moves are ordered first by kind, with normal moves before castling
+/// and castling before promotions, and then by their squares (see
+/// [`SquareSpec`]'s ordering) and pieces. This is mainly useful for
+/// getting a canonical order for move lists.
+///
+/// ```
+/// # use chess_engine::board::{Board, Move};
+/// let mut moves = Board::default_board().get_all_legal_moves();
+/// moves.sort();
+///
+/// assert_eq!(
+///     moves[0],
+///     Move::Normal {
+///         from: "b1".parse().unwrap(),
+///         to: "a3".parse().unwrap()
+///     }
+/// );
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[allow(missing_docs)]
 pub enum Move {
     /// A "normal" move between two squares. This covers most moves,
@@ -21,30 +42,247 @@ pub enum Move {
 }
 
 impl Move {
+    /// Get the square the move is made from when played by `color`,
+    /// which for castling is the king's square. When the board the
+    /// move is played on is at hand, [`Move::resolve`] saves having
+    /// to know who's moving.
     pub fn from(&self, color: Color) -> SquareSpec {
         match self {
             Move::Normal { from, .. } | Move::Promotion { from, .. } => *from,
-            Move::Castling(_) => {
-                let rank = color.home_rank();
-                SquareSpec::new(rank, 4)
-            }
+            Move::Castling(c) => c.king_from(color),
         }
     }
 
+    /// Get the square the move is made to when played by `color`,
+    /// which for castling is where the king ends up
     pub fn to(&self, color: Color) -> SquareSpec {
         match self {
             Move::Normal { to, .. } | Move::Promotion { to, .. } => *to,
+            Move::Castling(c) => c.king_to(color),
+        }
+    }
+
+    /// Work out everything about the move when played on `board` by
+    /// the player to move, like the squares it's played between, what
+    /// it captures and whether it gives check. This doesn't check that
+    /// the move is legal, for that use [`Board::resolve`].
+    ///
+    /// # Panics
+    ///
+    /// May panic if the move doesn't move a piece.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::board::{Board, Castling, Move, SquareSpec};
+    /// # use chess_engine::piece::Color;
+    /// let board = Board::load_fen("r3k3/8/8/8/8/8/8/3K4 b q - 0 1").unwrap();
+    /// let resolved = Move::Castling(Castling::Long).resolve(&board);
+    ///
+    /// assert_eq!(resolved.color, Color::Black);
+    /// assert_eq!((resolved.from, resolved.to), (SquareSpec::E8, SquareSpec::C8));
+    /// assert_eq!(resolved.rook, Some((SquareSpec::A8, SquareSpec::D8)));
+    /// assert!(resolved.check);
+    /// ```
+    pub fn resolve(&self, board: &Board) -> ResolvedMove {
+        let color = board.turn();
+        let (from, to) = (self.from(color), self.to(color));
+        let piece = board[from].expect("there has to be a piece on the square moved from");
+
+        let captured = match *self {
+            Move::Normal { to, .. }
+                if piece.piece == PieceType::Pawn && board.en_passant == Some(to) =>
+            {
+                // the captured pawn is next to the one capturing it
+                let sq = SquareSpec::new(from.rank, to.file);
+                board[sq].map(|p| (sq, p))
+            }
+            Move::Normal { to, .. } | Move::Promotion { to, .. } => board[to].map(|p| (to, p)),
+            Move::Castling(_) => None,
+        };
+        let (rook, promotion) = match *self {
+            Move::Castling(c) => (Some((c.rook_from(color), c.rook_to(color))), None),
+            Move::Promotion { target, .. } => (None, Some(target)),
+            Move::Normal { .. } => (None, None),
+        };
+
+        ResolvedMove {
+            m: *self,
+            color,
+            piece,
+            from,
+            to,
+            captured,
+            rook,
+            promotion,
+            check: board.perform_move_unvalidated(*self).in_check(),
+        }
+    }
+}
+
+/// Everything about a [`Move`] played on a certain board, as worked
+/// out by [`Board::resolve`] or [`Move::resolve`]. Unlike a [`Move`],
+/// this can be shown or sent somewhere without the board at hand,
+/// e.g. when writing PGN, drawing the move or encoding it for the
+/// network.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct ResolvedMove {
+    /// The move itself
+    pub m: Move,
+    /// The color of the player making the move
+    pub color: Color,
+    /// The piece that moved, which for castling is the king, and for
+    /// promotions is the pawn
+    pub piece: Piece,
+    /// The square the move is made from, which for castling is the
+    /// king's square
+    pub from: SquareSpec,
+    /// The square the move is made to, which for castling is where
+    /// the king ends up
+    pub to: SquareSpec,
+    /// The piece the move captured and the square it was on, which
+    /// isn't `to` for en passant
+    pub captured: Option<(SquareSpec, Piece)>,
+    /// The squares the rook castles from and to
+    pub rook: Option<(SquareSpec, SquareSpec)>,
+    /// The piece a pawn was promoted to
+    pub promotion: Option<PieceType>,
+    /// Whether the move put the opponent in check
+    pub check: bool,
+}
+
+impl Move {
+    /// Get every square affected by this move on the given board, for
+    /// highlighting the last move or redrawing only what changed.
+    /// The origin and destination squares come first, followed by
+    /// the rook's squares when castling, or the captured pawn's
+    /// square when taking en passant.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::board::{Board, Castling, Move, SquareSpec};
+    /// let board = Board::load_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+    /// let squares = Move::Castling(Castling::Long).squares_touched(&board);
+    ///
+    /// assert_eq!(
+    ///     squares.iter().map(|sq| sq.to_string()).collect::<Vec<_>>(),
+    ///     ["e1", "c1", "a1", "d1"]
+    /// );
+    /// ```
+    ///
+    /// ```
+    /// # use chess_engine::board::{Board, Move, SquareSpec};
+    /// let board = Board::load_fen("4k3/8/8/4pP2/8/8/8/4K3 w - e6 0 1").unwrap();
+    /// let en_passant = Move::Normal {
+    ///     from: "f5".parse().unwrap(),
+    ///     to: "e6".parse().unwrap(),
+    /// };
+    ///
+    /// assert_eq!(
+    ///     en_passant.squares_touched(&board).iter().map(|sq| sq.to_string()).collect::<Vec<_>>(),
+    ///     ["f5", "e6", "e5"]
+    /// );
+    /// ```
+    pub fn squares_touched(&self, board: &Board) -> SmallVec<[SquareSpec; 4]> {
+        match *self {
+            Move::Normal { from, to } => {
+                let mut squares: SmallVec<[SquareSpec; 4]> = smallvec![from, to];
+                let is_pawn = matches!(board[from], Some(p) if p.piece == PieceType::Pawn);
+                if is_pawn && board.en_passant == Some(to) {
+                    // the captured pawn is on the same file as the
+                    // destination, but on the rank we moved from
+                    squares.push(SquareSpec::new(from.rank, to.file));
+                }
+                squares
+            }
+            Move::Promotion { from, to, .. } => smallvec![from, to],
             Move::Castling(c) => {
-                let rank = color.home_rank();
+                let color = board.turn();
+                smallvec![
+                    c.king_from(color),
+                    c.king_to(color),
+                    c.rook_from(color),
+                    c.rook_to(color),
+                ]
+            }
+        }
+    }
 
-                let kt = match c {
-                    Short => 6,
-                    Long => 2,
-                };
+    /// Describe the move as played on `board` in plain English, e.g.
+    /// "Knight from g1 captures bishop on f3, check", for screen
+    /// readers and chat bots announcing moves. Illegal moves are only
+    /// described by their squares, e.g. "e2 to e5".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::board::{Board, Castling, Move};
+    /// let board = Board::load_fen("4k3/1P6/8/8/8/5b2/3N4/4K2R w K - 0 1").unwrap();
+    ///
+    /// assert_eq!(
+    ///     "d2f3".parse::<Move>().unwrap().describe_verbose(&board),
+    ///     "Knight from d2 captures bishop on f3"
+    /// );
+    /// assert_eq!(
+    ///     "b7b8=Q".parse::<Move>().unwrap().describe_verbose(&board),
+    ///     "Pawn from b7 to b8, promotes to queen, check"
+    /// );
+    /// assert_eq!(
+    ///     Move::Castling(Castling::Short).describe_verbose(&board),
+    ///     "Castles kingside"
+    /// );
+    /// ```
+    pub fn describe_verbose(&self, board: &Board) -> String {
+        let (from, to) = (self.from(board.turn()), self.to(board.turn()));
+        let after = match board.perform_move(*self) {
+            Some(after) => after,
+            None => return format!("{} to {}", from, to),
+        };
+        let info = MoveInfo::new(board, *self, &after);
 
-                SquareSpec::new(rank, kt)
+        let mut s = match info.castling {
+            Some(Castling::Short) => "Castles kingside".to_string(),
+            Some(Castling::Long) => "Castles queenside".to_string(),
+            None => {
+                let mut s = format!("{} from {}", piece_name(info.piece.piece), from);
+                match info.captured {
+                    Some(captured) => {
+                        let _ =
+                            write!(s, " captures {}", piece_name(captured.piece).to_lowercase());
+                        if info.en_passant {
+                            s.push_str(" en passant");
+                        }
+                        let _ = write!(s, " on {}", to);
+                    }
+                    None => {
+                        let _ = write!(s, " to {}", to);
+                    }
+                }
+                s
             }
+        };
+        if let Some(target) = info.promotion {
+            let _ = write!(s, ", promotes to {}", piece_name(target).to_lowercase());
+        }
+        if info.checkmate {
+            s.push_str(", checkmate");
+        } else if info.check {
+            s.push_str(", check");
         }
+        s
+    }
+}
+
+fn piece_name(piece: PieceType) -> &'static str {
+    match piece {
+        PieceType::Pawn => "Pawn",
+        PieceType::Rook => "Rook",
+        PieceType::Bishop => "Bishop",
+        PieceType::Queen => "Queen",
+        PieceType::Knight => "Knight",
+        PieceType::King => "King",
     }
 }
 
@@ -59,11 +297,339 @@ impl fmt::Display for Move {
     }
 }
 
-/// Enum for the two ways you can castle
+impl std::str::FromStr for Move {
+    type Err = Error;
+
+    /// Parse a move in coordinate notation, which doesn't need a
+    /// board to be interpreted. This is the same format as the
+    /// [`Display`](fmt::Display) implementation produces, i.e.
+    /// `e2e4`, `e7e8=Q` and `O-O`, but the UCI style of promotion
+    /// (`e7e8q`) is also accepted.
+    ///
+    /// ```
+    /// # use chess_engine::board::{Castling, Move};
+    /// # use chess_engine::piece::PieceType;
+    /// assert_eq!(
+    ///     "e7e8=Q".parse::<Move>().unwrap(),
+    ///     Move::Promotion {
+    ///         from: "e7".parse().unwrap(),
+    ///         to: "e8".parse().unwrap(),
+    ///         target: PieceType::Queen,
+    ///     }
+    /// );
+    /// assert_eq!("e7e8q".parse::<Move>().unwrap(), "e7e8=Q".parse().unwrap());
+    /// assert_eq!("O-O".parse::<Move>().unwrap(), Move::Castling(Castling::Short));
+    /// assert!("e2".parse::<Move>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Move, Error> {
+        if let Ok(c) = s.parse::<Castling>() {
+            return Ok(Move::Castling(c));
+        }
+
+        let invalid = || Error::InvalidMove(s.to_string());
+        let from = s
+            .get(0..2)
+            .ok_or_else(invalid)?
+            .parse()
+            .map_err(|_| invalid())?;
+        let to = s
+            .get(2..4)
+            .ok_or_else(invalid)?
+            .parse()
+            .map_err(|_| invalid())?;
+        let promotion = s.get(4..).ok_or_else(invalid)?;
+        let promotion = promotion.strip_prefix('=').unwrap_or(promotion);
+
+        if promotion.is_empty() {
+            return Ok(Move::Normal { from, to });
+        }
+
+        let target = promotion
+            .to_ascii_uppercase()
+            .parse::<PieceType>()
+            .map_err(|_| invalid())?;
+        if PieceType::PROMOTION_TARGETS.contains(&target) {
+            Ok(Move::Promotion { from, to, target })
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+/// Extra information about a move that has been played, such as
+/// whether it captured anything or gave check.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct MoveInfo {
+    /// The piece that moved, which for castling is the king
+    pub piece: Piece,
+    /// The piece that was captured, if any
+    pub captured: Option<Piece>,
+    /// Whether the move was an en passant capture
+    pub en_passant: bool,
+    /// Which way the player castled, if the move was castling
+    pub castling: Option<Castling>,
+    /// The piece type the pawn was promoted to, if the move was a
+    /// promotion
+    pub promotion: Option<PieceType>,
+    /// The castling rights the move took away, from either player,
+    /// e.g. by moving the king or a rook or by capturing a rook on its
+    /// starting square
+    pub revoked_castling: CastlingFlags,
+    /// Whether the move put the opponent in check
+    pub check: bool,
+    /// Whether the check was given by a piece other than the one that
+    /// moved, which got out of the way
+    pub discovered_check: bool,
+    /// Whether the move put the opponent in checkmate
+    pub checkmate: bool,
+}
+
+impl MoveInfo {
+    // Work out the info for a move that was played on `before`,
+    // resulting in `after`. The move must be legal on `before`.
+    pub(crate) fn new(before: &Board, m: Move, after: &Board) -> MoveInfo {
+        let from = m.from(before.turn());
+        let piece = before[from].expect("there has to be a piece on the square moved from");
+
+        let en_passant = match m {
+            Move::Normal { to, .. } => {
+                piece.piece == PieceType::Pawn && before.en_passant == Some(to)
+            }
+            _ => false,
+        };
+        let captured = match m {
+            Move::Normal { to, .. } if en_passant => before[SquareSpec::new(from.rank, to.file)],
+            Move::Normal { to, .. } | Move::Promotion { to, .. } => before[to],
+            Move::Castling(_) => None,
+        };
+        let castling = match m {
+            Move::Castling(c) => Some(c),
+            _ => None,
+        };
+        let promotion = match m {
+            Move::Promotion { target, .. } => Some(target),
+            _ => None,
+        };
+        let check = after.in_check();
+        let checkmate = check && !after.has_legal_moves();
+        // the moved pieces end up on squares touched by the move
+        let discovered_check = check && {
+            let moved = m.squares_touched(before);
+            after.checkers().iter().any(|sq| !moved.contains(sq))
+        };
+
+        MoveInfo {
+            piece,
+            captured,
+            en_passant,
+            castling,
+            promotion,
+            revoked_castling: before.castling & !after.castling,
+            check,
+            discovered_check,
+            checkmate,
+        }
+    }
+
+    /// Whether the move captured a piece, including en passant
+    pub fn is_capture(&self) -> bool {
+        self.captured.is_some()
+    }
+}
+
+/// The result of performing a move with
+/// [`Board::perform_move_with_outcome`]: the new board, along with
+/// whether the move ended the game or put the opponent in check.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MoveOutcome {
+    /// The board after the move
+    pub board: Board,
+    /// Whether the player to move is in check
+    pub check: bool,
+    /// Whether the player to move is checkmated
+    pub mate: bool,
+    /// Whether the player to move is stalemated
+    pub stalemate: bool,
+}
+
+impl MoveOutcome {
+    // Work out the outcome of having reached `board`. The legal moves
+    // are only generated until one is found, which is all it takes to
+    // rule out mate and stalemate.
+    pub(crate) fn new(board: Board) -> MoveOutcome {
+        let check = board.in_check();
+        let stuck = !board.has_legal_moves();
+        MoveOutcome {
+            board,
+            check,
+            mate: check && stuck,
+            stalemate: !check && stuck,
+        }
+    }
+}
+
+/// What [`Board::make_move`] changed on the board besides moving the
+/// pieces, which [`Board::unmake_move`] needs to take the move back
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MoveUndo {
+    // the piece the move captured, and where it stood, which isn't
+    // where the capturing piece ended up for en passant
+    pub(super) captured: Option<(SquareSpec, Piece)>,
+    pub(super) castling: CastlingFlags,
+    pub(super) en_passant: Option<SquareSpec>,
+    pub(super) halfmove: u32,
+    pub(super) fullmove: u32,
+}
+
+/// The kind of move that takes a piece to a square, as given by
+/// [`Board::legal_destinations`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MoveKind {
+    /// A move of a single piece, including captures and en passant
+    Normal,
+    /// Castling, with the king moving to the square
+    Castling(Castling),
+    /// A pawn promoting on the square, to any of the
+    /// [promotion targets](PieceType::PROMOTION_TARGETS)
+    Promotion,
+}
+
+/// Enum for the two ways you can castle. Its squares are those of the
+/// standard board, as castling isn't played on smaller boards.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Castling {
     /// Castling king-side
     Short,
     /// Castling queen-side
     Long,
 }
+
+/// Whether a player can castle a certain way, and if not, why. See
+/// [`Board::castling_available`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CastlingAvailability {
+    /// Castling is possible
+    Available,
+    /// The king or the rook has moved, so castling this way isn't
+    /// possible for the rest of the game
+    RightsLost,
+    /// There are pieces between the king and the rook
+    Blocked,
+    /// The king is in check, or would pass through or end up on an
+    /// attacked square
+    Check,
+}
+
+impl Castling {
+    /// Both ways of castling, short first
+    pub const ALL: [Castling; 2] = [Castling::Short, Castling::Long];
+
+    /// Get the square the king castles from, which is the e file of
+    /// `color`'s home rank
+    pub fn king_from(self, color: Color) -> SquareSpec {
+        SquareSpec::new(color.home_rank(), 4)
+    }
+
+    /// Get the square the king ends up on, the g file when castling
+    /// short and the c file when castling long
+    ///
+    /// ```
+    /// # use chess_engine::board::{Castling, SquareSpec};
+    /// # use chess_engine::piece::Color;
+    /// assert_eq!(Castling::Short.king_to(Color::White), SquareSpec::G1);
+    /// assert_eq!(Castling::Long.king_to(Color::Black), SquareSpec::C8);
+    /// ```
+    pub fn king_to(self, color: Color) -> SquareSpec {
+        let file = match self {
+            Castling::Short => 6,
+            Castling::Long => 2,
+        };
+        SquareSpec::new(color.home_rank(), file)
+    }
+
+    /// Get the square the rook castles from, the corner on the side
+    /// the king castles to
+    pub fn rook_from(self, color: Color) -> SquareSpec {
+        let file = match self {
+            Castling::Short => 7,
+            Castling::Long => 0,
+        };
+        SquareSpec::new(color.home_rank(), file)
+    }
+
+    /// Get the square the rook ends up on, which is the one the king
+    /// passes over
+    pub fn rook_to(self, color: Color) -> SquareSpec {
+        let file = match self {
+            Castling::Short => 5,
+            Castling::Long => 3,
+        };
+        SquareSpec::new(color.home_rank(), file)
+    }
+
+    /// Get the squares the king passes over and lands on, in order,
+    /// none of which may be attacked for castling to be legal. The
+    /// square the king starts on isn't included.
+    ///
+    /// ```
+    /// # use chess_engine::board::{Castling, SquareSpec};
+    /// # use chess_engine::piece::Color;
+    /// assert_eq!(
+    ///     Castling::Long.king_path(Color::White),
+    ///     vec![SquareSpec::D1, SquareSpec::C1]
+    /// );
+    /// ```
+    pub fn king_path(self, color: Color) -> Vec<SquareSpec> {
+        let mut path = SquareSpec::between(self.king_from(color), self.king_to(color));
+        path.push(self.king_to(color));
+        path
+    }
+
+    /// Get the squares between the king and the rook, all of which
+    /// have to be empty for castling to be legal
+    ///
+    /// ```
+    /// # use chess_engine::board::{Castling, SquareSpec};
+    /// # use chess_engine::piece::Color;
+    /// assert_eq!(
+    ///     Castling::Long.empty_squares(Color::Black),
+    ///     vec![SquareSpec::D8, SquareSpec::C8, SquareSpec::B8]
+    /// );
+    /// ```
+    pub fn empty_squares(self, color: Color) -> Vec<SquareSpec> {
+        SquareSpec::between(self.king_from(color), self.rook_from(color))
+    }
+
+    /// Get the castling right that castling this way takes for
+    /// `color`
+    pub fn flag(self, color: Color) -> CastlingFlags {
+        match (color, self) {
+            (Color::White, Castling::Short) => CastlingFlags::WHITE_SHORT,
+            (Color::White, Castling::Long) => CastlingFlags::WHITE_LONG,
+            (Color::Black, Castling::Short) => CastlingFlags::BLACK_SHORT,
+            (Color::Black, Castling::Long) => CastlingFlags::BLACK_LONG,
+        }
+    }
+}
+
+impl std::str::FromStr for Castling {
+    type Err = Error;
+
+    /// Parse castling written as `O-O` or `O-O-O`. Zeroes are also
+    /// accepted in place of the letter O.
+    ///
+    /// ```
+    /// # use chess_engine::board::Castling;
+    /// assert_eq!("O-O-O".parse::<Castling>().unwrap(), Castling::Long);
+    /// assert_eq!("0-0".parse::<Castling>().unwrap(), Castling::Short);
+    /// ```
+    fn from_str(s: &str) -> Result<Castling, Error> {
+        match s {
+            "O-O" | "0-0" => Ok(Castling::Short),
+            "O-O-O" | "0-0-0" => Ok(Castling::Long),
+            _ => Err(Error::InvalidMove(s.to_string())),
+        }
+    }
+}
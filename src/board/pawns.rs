@@ -0,0 +1,155 @@
+use super::{Board, SquareSpec};
+use crate::piece::{Color, Piece, PieceType};
+
+/// The pawn structure of one side, as produced by
+/// [`Board::pawn_structure`]. The squares are ordered by rank and then
+/// file, and a pawn can be in several of the lists, e.g. both isolated
+/// and passed.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PawnStructure {
+    /// Pawns with another pawn of the same color in front of them on
+    /// their file. Of two doubled pawns, only the one behind is listed.
+    pub doubled: Vec<SquareSpec>,
+    /// Pawns with no pawns of the same color on the neighbouring files
+    pub isolated: Vec<SquareSpec>,
+    /// Pawns with no enemy pawns in front of them on their own or the
+    /// neighbouring files, which can't be stopped by pawns
+    pub passed: Vec<SquareSpec>,
+    /// Pawns that have fallen behind the pawns of the same color on
+    /// the neighbouring files, so none of them can support it, and
+    /// that can't advance safely as the square in front of them is
+    /// attacked by an enemy pawn. Isolated pawns aren't counted.
+    pub backward: Vec<SquareSpec>,
+    /// Files without any pawns
+    pub open_files: Vec<u32>,
+    /// Files with pawns of the other color but none of this color
+    pub half_open_files: Vec<u32>,
+}
+
+impl Board {
+    /// Get the pawn structure of `color`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::board::{Board, SquareSpec};
+    /// # use chess_engine::piece::Color;
+    /// let board = Board::load_fen("4k3/p7/8/1p6/8/1P6/1P4P1/4K3 w - - 0 1").unwrap();
+    /// let white = board.pawn_structure(Color::White);
+    ///
+    /// assert_eq!(white.doubled, [SquareSpec::B2]);
+    /// assert_eq!(white.isolated, [SquareSpec::B2, SquareSpec::G2, SquareSpec::B3]);
+    /// assert_eq!(white.passed, [SquareSpec::G2]);
+    /// assert_eq!(white.open_files, [2, 3, 4, 5, 7]);
+    /// assert_eq!(white.half_open_files, [0]);
+    /// ```
+    pub fn pawn_structure(&self, color: Color) -> PawnStructure {
+        let own = self.pawns(color);
+        let enemy = self.pawns(color.opposite());
+        // whether `a` is further up the board than `b` for `color`
+        let ahead = |color: Color, a: SquareSpec, b: SquareSpec| {
+            a.relative_rank(color) > b.relative_rank(color)
+        };
+        let neighbours = |a: SquareSpec, b: SquareSpec| (a.file as i32 - b.file as i32).abs() == 1;
+
+        let mut structure = PawnStructure::default();
+        for &pawn in &own {
+            if own
+                .iter()
+                .any(|&other| other.file == pawn.file && ahead(color, other, pawn))
+            {
+                structure.doubled.push(pawn);
+            }
+
+            let isolated = !own.iter().any(|&other| neighbours(other, pawn));
+            if isolated {
+                structure.isolated.push(pawn);
+            }
+
+            if !enemy.iter().any(|&other| {
+                (other.file == pawn.file || neighbours(other, pawn)) && ahead(color, other, pawn)
+            }) {
+                structure.passed.push(pawn);
+            }
+
+            let supportable = own
+                .iter()
+                .any(|&other| neighbours(other, pawn) && !ahead(color, other, pawn));
+            // an enemy pawn attacks the square in front of the pawn if
+            // it's next to the square two steps ahead
+            let stop_attacked = pawn_step(color, pawn)
+                .and_then(|stop| pawn_step(color, stop).map(|front| (stop, front)))
+                .map_or(false, |(stop, front)| {
+                    enemy
+                        .iter()
+                        .any(|&other| other.rank == front.rank && neighbours(other, stop))
+                });
+            if !isolated && !supportable && stop_attacked {
+                structure.backward.push(pawn);
+            }
+        }
+
+        for file in 0..8 {
+            let has_pawn = |pawns: &[SquareSpec]| pawns.iter().any(|sq| sq.file == file);
+            match (has_pawn(&own), has_pawn(&enemy)) {
+                (false, false) => structure.open_files.push(file),
+                (false, true) => structure.half_open_files.push(file),
+                _ => (),
+            }
+        }
+
+        structure
+    }
+
+    // Get the squares of the pawns of a color, ordered by rank and
+    // then file
+    fn pawns(&self, color: Color) -> Vec<SquareSpec> {
+        let pawn = Some(Piece::new(PieceType::Pawn, color));
+        SquareSpec::all().filter(|&sq| self[sq] == pawn).collect()
+    }
+}
+
+// Get the square in front of a pawn, if it isn't on the last rank
+fn pawn_step(color: Color, sq: SquareSpec) -> Option<SquareSpec> {
+    match color {
+        Color::White => SquareSpec::try_new(sq.rank + 1, sq.file),
+        Color::Black => sq
+            .rank
+            .checked_sub(1)
+            .map(|rank| SquareSpec::new(rank, sq.file)),
+    }
+}
+
+#[cfg(all(test, feature = "fen"))]
+mod tests {
+    use super::{Board, SquareSpec};
+    use crate::piece::Color;
+
+    #[test]
+    fn backward_pawns() {
+        // the d3 pawn can't be supported and can't advance past c5
+        let board = Board::load_fen("4k3/8/8/2p5/2P1P3/3P4/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            board.pawn_structure(Color::White).backward,
+            [SquareSpec::D3]
+        );
+
+        // it's fine as long as the e-pawn stays back to support it
+        let board = Board::load_fen("4k3/8/8/2p5/2P5/3P4/4P3/4K3 w - - 0 1").unwrap();
+        assert!(board.pawn_structure(Color::White).backward.is_empty());
+    }
+
+    #[test]
+    fn black_pawn_structure() {
+        let board = Board::load_fen("4k3/pp6/1p6/8/8/8/7P/4K3 b - - 0 1").unwrap();
+        let black = board.pawn_structure(Color::Black);
+
+        assert_eq!(black.doubled, [SquareSpec::B7]);
+        assert!(black.isolated.is_empty());
+        assert_eq!(
+            black.passed,
+            [SquareSpec::B6, SquareSpec::A7, SquareSpec::B7]
+        );
+        assert_eq!(black.half_open_files, [7]);
+    }
+}
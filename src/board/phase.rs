@@ -0,0 +1,143 @@
+use super::Board;
+use crate::piece::PieceType;
+
+/// The phase of the game a position is in, as given by
+/// [`Board::phase`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GamePhase {
+    /// The first moves of the game, while (almost) all the pieces are
+    /// on the board
+    Opening,
+    /// Everything between the opening and the endgame
+    Middlegame,
+    /// Few pieces are left on the board
+    Endgame,
+}
+
+/// The value of [`Board::phase_value`] with all the pieces of the
+/// starting position on the board
+pub const MAX_PHASE: u32 = 256;
+
+// How much each piece type counts towards the phase, with the pieces
+// of the starting position adding up to `PHASE_TOTAL`
+fn phase_weight(piece: PieceType) -> u32 {
+    match piece {
+        PieceType::Pawn | PieceType::King => 0,
+        PieceType::Knight | PieceType::Bishop => 1,
+        PieceType::Rook => 2,
+        PieceType::Queen => 4,
+    }
+}
+const PHASE_TOTAL: u32 = 24;
+
+// The opening lasts for at most this many moves, and only while at most
+// a minor piece has been traded on each side
+const OPENING_MOVES: u32 = 10;
+const OPENING_WEIGHT: u32 = PHASE_TOTAL - 2;
+// The endgame starts once the pieces left weigh at most this much, e.g.
+// a rook and a minor piece each
+const ENDGAME_WEIGHT: u32 = 6;
+
+impl Board {
+    // The phase weights of the pieces on the board, capped at
+    // `PHASE_TOTAL` in case of promotions
+    fn phase_weight(&self) -> u32 {
+        self.board
+            .iter()
+            .flatten()
+            .flatten()
+            .map(|p| phase_weight(p.piece))
+            .sum::<u32>()
+            .min(PHASE_TOTAL)
+    }
+
+    /// Get the phase of the game, from the pieces left on the board
+    /// and the number of moves played. Kings and pawns aren't counted,
+    /// so a position with only those is always an endgame.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{Board, GamePhase};
+    /// assert_eq!(Board::default_board().phase(), GamePhase::Opening);
+    ///
+    /// let middlegame = "r1bq1rk1/pp2bppp/2n1pn2/3p4/3P4/2NBPN2/PP3PPP/R2QK2R w KQ - 0 15";
+    /// assert_eq!(Board::load_fen(middlegame).unwrap().phase(), GamePhase::Middlegame);
+    ///
+    /// let endgame = "8/5pk1/6p1/8/3R4/6P1/r4PK1/8 w - - 0 40";
+    /// assert_eq!(Board::load_fen(endgame).unwrap().phase(), GamePhase::Endgame);
+    /// ```
+    pub fn phase(&self) -> GamePhase {
+        let weight = self.phase_weight();
+        if weight <= ENDGAME_WEIGHT {
+            GamePhase::Endgame
+        } else if weight >= OPENING_WEIGHT && self.fullmove <= OPENING_MOVES {
+            GamePhase::Opening
+        } else {
+            GamePhase::Middlegame
+        }
+    }
+
+    /// Get the phase of the game as a value from 0 to [`MAX_PHASE`],
+    /// from the pieces left on the board: [`MAX_PHASE`] with all the
+    /// pieces of the starting position, and 0 with only kings and
+    /// pawns. Knights and bishops count 1, rooks 2 and queens 4 out of
+    /// a total of 24, scaled up to [`MAX_PHASE`].
+    ///
+    /// Tapered evaluation blends a middlegame score `mg` and an
+    /// endgame score `eg` as
+    /// `(mg * phase + eg * (MAX_PHASE - phase)) / MAX_PHASE`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{Board, MAX_PHASE};
+    /// assert_eq!(Board::default_board().phase_value(), MAX_PHASE);
+    ///
+    /// // without queens
+    /// let board = Board::load_fen("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR w KQkq - 0 1").unwrap();
+    /// assert_eq!(board.phase_value(), MAX_PHASE * 2 / 3);
+    ///
+    /// let board = Board::load_fen("4k3/pppp4/8/8/8/8/4PPPP/4K3 w - - 0 1").unwrap();
+    /// assert_eq!(board.phase_value(), 0);
+    /// ```
+    pub fn phase_value(&self) -> u32 {
+        self.phase_weight() * MAX_PHASE / PHASE_TOTAL
+    }
+}
+
+#[cfg(all(test, feature = "fen"))]
+mod tests {
+    use super::{GamePhase, MAX_PHASE};
+    use crate::board::Board;
+
+    #[test]
+    fn phase_follows_material_and_moves() {
+        let board = Board::default_board();
+        let late =
+            Board::load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 11").unwrap();
+        assert_eq!(late.phase(), GamePhase::Middlegame);
+        assert_eq!(late.phase_value(), board.phase_value());
+
+        // an extra queen doesn't go past the maximum
+        let promoted =
+            Board::load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKQNR w kq - 0 1").unwrap();
+        assert_eq!(promoted.phase_value(), MAX_PHASE);
+
+        let mut last = MAX_PHASE;
+        for fen in &[
+            "r1bqkb1r/pppppppp/8/8/8/8/PPPPPPPP/R1BQKB1R w KQkq - 0 5",
+            "r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 20",
+            "4k3/pppppppp/8/8/8/8/PPPPPPPP/R3K3 w Q - 0 30",
+        ] {
+            let value = Board::load_fen(fen).unwrap().phase_value();
+            assert!(value < last, "{}", fen);
+            last = value;
+        }
+        assert_eq!(
+            Board::load_fen("4k3/pppppppp/8/8/8/8/PPPPPPPP/R3K3 w Q - 0 30")
+                .unwrap()
+                .phase(),
+            GamePhase::Endgame
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use super::{Board, BoardSize, Castling, CastlingFlags, SquareSpec};
+use crate::error::Error;
+use crate::piece::{Color, Piece, PieceType};
+
+/// Everything about a position besides the pieces, for setting up a
+/// board with [`Board::from_array`]. The default is white to move at
+/// the start of the game, with no castling rights or en passant
+/// square.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BoardMeta {
+    /// The side to move
+    pub turn: Color,
+    /// The castling rights of both players
+    pub castling: CastlingFlags,
+    /// The square a pawn just skipped over, if the last move was a
+    /// double pawn push
+    pub en_passant: Option<SquareSpec>,
+    /// The number of halfmoves since the last capture or pawn move
+    pub halfmove: u32,
+    /// The fullmove number, which starts at 1
+    pub fullmove: u32,
+}
+
+impl Default for BoardMeta {
+    fn default() -> BoardMeta {
+        BoardMeta {
+            turn: Color::White,
+            castling: CastlingFlags::empty(),
+            en_passant: None,
+            halfmove: 0,
+            fullmove: 1,
+        }
+    }
+}
+
+impl Board {
+    /// Set up a board from its pieces, indexed by rank and then file
+    /// like [`BoardSnapshot::squares`](super::BoardSnapshot::squares),
+    /// and everything else about the position, without going through
+    /// FEN. Unlike [`Board::new`], the position is checked so that it
+    /// could occur in a game: each side has exactly one king, there
+    /// are no pawns on the first or last rank, the side that just
+    /// moved isn't in check, and the castling rights and en passant
+    /// square fit the pieces.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`Error::InvalidPosition`] if the position couldn't
+    /// occur in a game
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{Board, BoardMeta};
+    /// # use chess_engine::piece::{Color, Piece, PieceType};
+    /// let mut squares = [[None; 8]; 8];
+    /// squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+    /// squares[7][4] = Some(Piece::new(PieceType::King, Color::Black));
+    /// squares[6][0] = Some(Piece::new(PieceType::Pawn, Color::White));
+    /// let board = Board::from_array(squares, BoardMeta::default()).unwrap();
+    ///
+    /// assert_eq!(board.to_fen(), "4k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+    ///
+    /// squares[7][4] = None;
+    /// assert!(Board::from_array(squares, BoardMeta::default()).is_err());
+    /// ```
+    pub fn from_array(squares: [[Option<Piece>; 8]; 8], meta: BoardMeta) -> Result<Board, Error> {
+        let board = Board {
+            board: squares,
+            turn: meta.turn,
+            castling: meta.castling,
+            en_passant: meta.en_passant,
+            halfmove: meta.halfmove,
+            fullmove: meta.fullmove,
+            size: BoardSize::STANDARD,
+        };
+        board.validate()?;
+        Ok(board)
+    }
+
+    // Check that the position could occur in a game of standard chess
+    fn validate(&self) -> Result<(), Error> {
+        let invalid = |reason: &str| Err(Error::InvalidPosition(reason.to_string()));
+
+        for color in [Color::White, Color::Black] {
+            let kings = SquareSpec::all()
+                .filter(|&sq| self[sq] == Some(Piece::new(PieceType::King, color)))
+                .count();
+            if kings != 1 {
+                return invalid("each side must have exactly one king");
+            }
+        }
+        let pawn_on_back_rank = SquareSpec::all().any(|sq| {
+            (sq.rank == 0 || sq.rank == 7)
+                && matches!(self[sq], Some(p) if p.piece == PieceType::Pawn)
+        });
+        if pawn_on_back_rank {
+            return invalid("pawns can't be on the first or last rank");
+        }
+        let waiting = self.turn.opposite();
+        if self
+            .king(waiting)
+            .map_or(false, |sq| self.is_attacked(sq, self.turn))
+        {
+            return invalid("the side that just moved is in check");
+        }
+
+        if !self.castling_rights_fit() {
+            return invalid("castling rights without the king and rook in place");
+        }
+
+        if let Some(sq) = self.en_passant {
+            // the pawn that just moved two squares is in front of the
+            // square, seen from the side that moved it
+            let (skipped_rank, pawn_rank, from_rank) = match waiting {
+                Color::White => (2, 3, 1),
+                Color::Black => (5, 4, 6),
+            };
+            let pawn = SquareSpec::new(pawn_rank, sq.file);
+            let from = SquareSpec::new(from_rank, sq.file);
+            if sq.rank != skipped_rank
+                || self[sq].is_some()
+                || self[from].is_some()
+                || self[pawn] != Some(Piece::new(PieceType::Pawn, waiting))
+            {
+                return invalid("the en passant square doesn't follow a double pawn push");
+            }
+        }
+
+        if self.fullmove == 0 {
+            return invalid("the fullmove number starts at 1");
+        }
+        Ok(())
+    }
+
+    // Check that the king and rook of each castling right are still on
+    // their starting squares, on a board where castling is played
+    pub(crate) fn castling_rights_fit(&self) -> bool {
+        if self.castling.is_empty() {
+            return true;
+        }
+        if !self.size.allows_castling() {
+            return false;
+        }
+        Castling::ALL.iter().all(|&castle| {
+            [Color::White, Color::Black].iter().all(|&color| {
+                !self.castling.contains(castle.flag(color))
+                    || (self[castle.king_from(color)] == Some(Piece::new(PieceType::King, color))
+                        && self[castle.rook_from(color)]
+                            == Some(Piece::new(PieceType::Rook, color)))
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BoardMeta;
+    use crate::board::{Board, CastlingFlags, SquareSpec};
+    use crate::piece::{Color, Piece, PieceType};
+
+    fn squares(pieces: &[(SquareSpec, PieceType, Color)]) -> [[Option<Piece>; 8]; 8] {
+        let mut squares = [[None; 8]; 8];
+        for &(sq, piece, color) in pieces {
+            squares[sq.rank as usize][sq.file as usize] = Some(Piece::new(piece, color));
+        }
+        squares
+    }
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn matches_fen() {
+        let pieces = squares(&[
+            (SquareSpec::E1, PieceType::King, Color::White),
+            (SquareSpec::H1, PieceType::Rook, Color::White),
+            (SquareSpec::E8, PieceType::King, Color::Black),
+            (SquareSpec::D4, PieceType::Pawn, Color::Black),
+            (SquareSpec::E4, PieceType::Pawn, Color::White),
+        ]);
+        let meta = BoardMeta {
+            turn: Color::Black,
+            castling: CastlingFlags::WHITE_SHORT,
+            en_passant: Some(SquareSpec::E3),
+            halfmove: 0,
+            fullmove: 12,
+        };
+
+        assert_eq!(
+            Board::from_array(pieces, meta).unwrap(),
+            Board::load_fen("4k3/8/8/8/3pP3/8/8/4K2R b K e3 0 12").unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_impossible_positions() {
+        let kings = [
+            (SquareSpec::E1, PieceType::King, Color::White),
+            (SquareSpec::E8, PieceType::King, Color::Black),
+        ];
+        let with = |extra: &[(SquareSpec, PieceType, Color)]| {
+            let mut pieces = kings.to_vec();
+            pieces.extend_from_slice(extra);
+            squares(&pieces)
+        };
+        let meta = BoardMeta::default();
+
+        let two_kings = with(&[(SquareSpec::A1, PieceType::King, Color::White)]);
+        assert!(Board::from_array(two_kings, meta).is_err());
+        let back_rank_pawn = with(&[(SquareSpec::A8, PieceType::Pawn, Color::White)]);
+        assert!(Board::from_array(back_rank_pawn, meta).is_err());
+        let black_in_check = with(&[(SquareSpec::E2, PieceType::Rook, Color::White)]);
+        assert!(Board::from_array(black_in_check, meta).is_err());
+        let castling = BoardMeta {
+            castling: CastlingFlags::WHITE_LONG,
+            ..meta
+        };
+        assert!(Board::from_array(with(&[]), castling).is_err());
+        let en_passant = BoardMeta {
+            en_passant: Some(SquareSpec::D6),
+            ..meta
+        };
+        assert!(Board::from_array(with(&[]), en_passant).is_err());
+        assert!(Board::from_array(with(&[]), meta).is_ok());
+    }
+}
@@ -0,0 +1,100 @@
+use super::SquareSpec;
+use crate::piece::Color;
+
+/// The dimensions of a board. Standard chess is played on 8x8, and
+/// smaller boards are used by teaching variants like Los Alamos chess
+/// and minichess.
+///
+/// Smaller boards use the lower left corner of the 8x8 grid, so a1 is
+/// always the same square, and squares outside of the board are
+/// always empty.
+///
+/// # Examples
+/// ```
+/// # use chess_engine::board::{BoardSize, SquareSpec};
+/// # use chess_engine::piece::Color;
+/// let size = BoardSize::LOS_ALAMOS;
+///
+/// assert!(size.contains(SquareSpec::F6));
+/// assert!(!size.contains(SquareSpec::G1));
+/// assert_eq!(size.promotion_rank(Color::White), 5);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoardSize {
+    /// The number of files, at most 8
+    pub files: u32,
+    /// The number of ranks, at most 8
+    pub ranks: u32,
+}
+
+impl BoardSize {
+    /// The 8x8 board of standard chess
+    pub const STANDARD: BoardSize = BoardSize { files: 8, ranks: 8 };
+    /// The 6x6 board of Los Alamos chess
+    pub const LOS_ALAMOS: BoardSize = BoardSize { files: 6, ranks: 6 };
+    /// The 5x5 board of Gardner's minichess
+    pub const GARDNER: BoardSize = BoardSize { files: 5, ranks: 5 };
+
+    /// Check whether a square is on a board of this size
+    pub fn contains(self, sq: SquareSpec) -> bool {
+        sq.rank < self.ranks && sq.file < self.files
+    }
+
+    /// Get the squares of a board of this size as a mask with a bit
+    /// set at each square's [index](SquareSpec::index), like
+    /// [`Board::threatened_squares`](super::Board::threatened_squares)
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{BoardSize, SquareSpec};
+    /// assert_eq!(BoardSize::STANDARD.mask(), u64::MAX);
+    /// assert_eq!(BoardSize::GARDNER.mask().count_ones(), 25);
+    /// assert_eq!(BoardSize::GARDNER.mask() & (1 << SquareSpec::F1.index()), 0);
+    /// ```
+    pub fn mask(self) -> u64 {
+        SquareSpec::all()
+            .filter(|&sq| self.contains(sq))
+            .fold(0, |mask, sq| mask | 1 << sq.index())
+    }
+
+    /// Get the rank `color`'s pawns promote on, which is the last rank
+    /// from their side
+    pub fn promotion_rank(self, color: Color) -> u32 {
+        match color {
+            Color::White => self.ranks - 1,
+            Color::Black => 0,
+        }
+    }
+
+    /// Check whether pawns may move two squares from their starting
+    /// rank, which is only the case on the standard board
+    pub fn allows_double_step(self) -> bool {
+        self == BoardSize::STANDARD
+    }
+
+    /// Check whether castling is played on boards of this size, which
+    /// is only the case on the standard board, where the king and
+    /// rooks start on the squares [`Castling`](super::Castling) moves
+    /// them from
+    pub fn allows_castling(self) -> bool {
+        self == BoardSize::STANDARD
+    }
+
+    // Whether boards of this size can be played on: only the standard
+    // board, or with the `variants` feature anything from 1 to 8 files
+    // and 2 to 8 ranks, so that each side has a rank to start from
+    pub(crate) fn is_supported(self) -> bool {
+        if cfg!(feature = "variants") {
+            (1..=8).contains(&self.files) && (2..=8).contains(&self.ranks)
+        } else {
+            self == BoardSize::STANDARD
+        }
+    }
+}
+
+impl Default for BoardSize {
+    fn default() -> BoardSize {
+        BoardSize::STANDARD
+    }
+}
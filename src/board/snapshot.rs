@@ -0,0 +1,214 @@
+use super::{Board, BoardSize, CastlingFlags, SquareSpec};
+use crate::error::Error;
+use crate::piece::{Color, Piece, PieceType};
+use std::convert::TryFrom;
+
+/// A plain copy of everything about a [`Board`], with all fields
+/// public and no invariants to uphold. It's meant for frontends that
+/// display boards, and for serializing boards or passing them over
+/// FFI, where relying on the internals of [`Board`] would be brittle.
+/// Converting a snapshot back into a [`Board`] gives the same board,
+/// as long as its size is one the engine can play on.
+///
+/// # Examples
+/// ```
+/// # use chess_engine::board::{Board, BoardSnapshot, CastlingFlags};
+/// # use chess_engine::piece::{Color, PieceType};
+/// let board = Board::default_board().perform_move("e2e4".parse().unwrap()).unwrap();
+/// let snapshot = BoardSnapshot::from(&board);
+///
+/// assert_eq!(snapshot.squares[3][4], Some((PieceType::Pawn, Color::White)));
+/// assert_eq!(snapshot.squares[1][4], None);
+/// assert_eq!(snapshot.turn, Color::Black);
+/// assert_eq!(snapshot.en_passant, Some("e3".parse().unwrap()));
+/// assert!(snapshot.castling.contains(CastlingFlags::WHITE_SHORT | CastlingFlags::BLACK_LONG));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoardSnapshot {
+    /// The pieces on the board, indexed by rank and then file, so
+    /// `squares[0][0]` is a1 and `squares[7][0]` is a8
+    pub squares: [[Option<(PieceType, Color)>; 8]; 8],
+    /// The side to move
+    pub turn: Color,
+    /// The castling rights of both players, which are serialized as
+    /// the castling field of FEN, e.g. `KQkq`
+    #[cfg_attr(feature = "serde", serde(with = "castling_fen"))]
+    pub castling: CastlingFlags,
+    /// The square a pawn just skipped over, if the last move was a
+    /// double pawn push
+    pub en_passant: Option<SquareSpec>,
+    /// The number of halfmoves since the last capture or pawn move
+    pub halfmove: u32,
+    /// The fullmove number, which starts at 1 and is incremented
+    /// after each move by black
+    pub fullmove: u32,
+    /// The number of files of the board, which is 8 unless it's set
+    /// up for a small board variant
+    #[cfg_attr(feature = "serde", serde(default = "standard_length"))]
+    pub files: u32,
+    /// The number of ranks of the board
+    #[cfg_attr(feature = "serde", serde(default = "standard_length"))]
+    pub ranks: u32,
+}
+
+// Snapshots from before boards had a size are of standard boards
+#[cfg(feature = "serde")]
+fn standard_length() -> u32 {
+    BoardSize::STANDARD.files
+}
+
+#[cfg(feature = "serde")]
+mod castling_fen {
+    use super::CastlingFlags;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        castling: &CastlingFlags,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&castling.to_fen_str())
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<CastlingFlags, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        CastlingFlags::from_fen_str(&s).map_err(de::Error::custom)
+    }
+}
+
+impl From<&Board> for BoardSnapshot {
+    fn from(board: &Board) -> BoardSnapshot {
+        let mut squares = [[None; 8]; 8];
+        for (row, pieces) in squares.iter_mut().zip(board.board.iter()) {
+            for (square, piece) in row.iter_mut().zip(pieces.iter()) {
+                *square = piece.map(|p| (p.piece, p.color));
+            }
+        }
+
+        BoardSnapshot {
+            squares,
+            turn: board.turn,
+            castling: board.castling,
+            en_passant: board.en_passant,
+            halfmove: board.halfmove,
+            fullmove: board.fullmove,
+            files: board.size.files,
+            ranks: board.size.ranks,
+        }
+    }
+}
+
+impl From<Board> for BoardSnapshot {
+    fn from(board: Board) -> BoardSnapshot {
+        BoardSnapshot::from(&board)
+    }
+}
+
+impl TryFrom<BoardSnapshot> for Board {
+    type Error = Error;
+
+    /// Rebuild a board from a snapshot. Like [`Board::new`], this
+    /// doesn't check that the position is legal, only that the board
+    /// has a size the engine supports, which is 8x8 or, with the
+    /// `variants` feature, 1 to 8 files and 2 to 8 ranks.
+    fn try_from(snapshot: BoardSnapshot) -> Result<Board, Error> {
+        let size = BoardSize {
+            files: snapshot.files,
+            ranks: snapshot.ranks,
+        };
+        if !size.is_supported() {
+            return Err(Error::InvalidPosition(format!(
+                "unsupported board size {}x{}",
+                size.files, size.ranks
+            )));
+        }
+
+        let mut board = [[None; 8]; 8];
+        for (row, squares) in board.iter_mut().zip(snapshot.squares.iter()) {
+            for (piece, square) in row.iter_mut().zip(squares.iter()) {
+                *piece = square.map(|(piece, color)| Piece::new(piece, color));
+            }
+        }
+
+        Ok(Board {
+            board,
+            turn: snapshot.turn,
+            castling: snapshot.castling,
+            en_passant: snapshot.en_passant,
+            halfmove: snapshot.halfmove,
+            fullmove: snapshot.fullmove,
+            size,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Board, BoardSnapshot};
+    #[cfg(feature = "fen")]
+    use crate::board::CastlingFlags;
+    #[cfg(feature = "fen")]
+    use crate::piece::{Color, PieceType};
+    use std::convert::TryFrom;
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn snapshot_mirrors_fen() {
+        let board = Board::load_fen("r3k3/8/8/8/8/8/8/4K2R b Kq - 3 17").unwrap();
+        let snapshot = BoardSnapshot::from(board);
+
+        assert_eq!(
+            snapshot.squares[7][0],
+            Some((PieceType::Rook, Color::Black))
+        );
+        assert_eq!(
+            snapshot.squares[0][4],
+            Some((PieceType::King, Color::White))
+        );
+        assert_eq!(snapshot.squares.iter().flatten().flatten().count(), 4);
+        assert_eq!(snapshot.turn, Color::Black);
+        assert_eq!(
+            snapshot.castling,
+            CastlingFlags::WHITE_SHORT | CastlingFlags::BLACK_LONG
+        );
+        assert_eq!(snapshot.en_passant, None);
+        assert_eq!((snapshot.halfmove, snapshot.fullmove), (3, 17));
+        assert_eq!(Board::try_from(snapshot).unwrap(), board);
+    }
+
+    #[test]
+    fn unsupported_sizes_are_rejected() {
+        let snapshot = BoardSnapshot::from(Board::default_board());
+        for &(files, ranks) in &[(9, 8), (8, 9), (0, 8), (8, 0), (8, 1)] {
+            let snapshot = BoardSnapshot {
+                files,
+                ranks,
+                ..snapshot
+            };
+            assert!(Board::try_from(snapshot).is_err(), "{}x{}", files, ranks);
+        }
+    }
+
+    #[cfg(feature = "variants")]
+    #[test]
+    fn small_boards_round_trip() {
+        let board = Board::load_fen("rnbqkn/pppppp/6/6/PPPPPP/RNBQKN w - - 0 1").unwrap();
+        assert_eq!(Board::try_from(BoardSnapshot::from(board)).unwrap(), board);
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn size_defaults_to_standard() {
+        let board = Board::default_board();
+        let mut json = serde_json::to_value(BoardSnapshot::from(board)).unwrap();
+        let fields = json.as_object_mut().unwrap();
+        let _ = fields.remove("files");
+        let _ = fields.remove("ranks");
+
+        let snapshot: BoardSnapshot = serde_json::from_value(json).unwrap();
+        assert_eq!((snapshot.files, snapshot.ranks), (8, 8));
+        assert_eq!(Board::try_from(snapshot).unwrap(), board);
+    }
+}
@@ -1,14 +1,17 @@
 use crate::error::Error;
+use crate::piece::Color;
+use std::convert::TryFrom;
 use std::fmt;
 use std::ops;
 
-/// A struct representing a particular square on the board
+/// A struct representing a particular square on the board. Squares
+/// are ordered rank by rank, so a1 < h1 < a2.
 /// ```
 /// # use chess_engine::board::SquareSpec;
 /// let a1 = SquareSpec { rank: 0, file: 0 };
 /// assert_eq!(a1, "a1".parse::<SquareSpec>().unwrap());
 /// ```
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct SquareSpec {
     /// The rank of this square, with 0 being rank 1, and so on
     pub rank: u32,
@@ -16,12 +19,292 @@ pub struct SquareSpec {
     pub file: u32,
 }
 
+// Defines constants for all the squares on one rank
+macro_rules! rank_consts {
+    ($rank:expr; $a:ident $b:ident $c:ident $d:ident $e:ident $f:ident $g:ident $h:ident) => {
+        rank_consts!(@square $rank, 0, $a);
+        rank_consts!(@square $rank, 1, $b);
+        rank_consts!(@square $rank, 2, $c);
+        rank_consts!(@square $rank, 3, $d);
+        rank_consts!(@square $rank, 4, $e);
+        rank_consts!(@square $rank, 5, $f);
+        rank_consts!(@square $rank, 6, $g);
+        rank_consts!(@square $rank, 7, $h);
+    };
+    (@square $rank:expr, $file:expr, $name:ident) => {
+        #[doc = concat!("The ", stringify!($name), " square")]
+        pub const $name: SquareSpec = SquareSpec {
+            rank: $rank,
+            file: $file,
+        };
+    };
+}
+
 impl SquareSpec {
-    /// Create a new [`SquareSpec`]
+    rank_consts!(0; A1 B1 C1 D1 E1 F1 G1 H1);
+    rank_consts!(1; A2 B2 C2 D2 E2 F2 G2 H2);
+    rank_consts!(2; A3 B3 C3 D3 E3 F3 G3 H3);
+    rank_consts!(3; A4 B4 C4 D4 E4 F4 G4 H4);
+    rank_consts!(4; A5 B5 C5 D5 E5 F5 G5 H5);
+    rank_consts!(5; A6 B6 C6 D6 E6 F6 G6 H6);
+    rank_consts!(6; A7 B7 C7 D7 E7 F7 G7 H7);
+    rank_consts!(7; A8 B8 C8 D8 E8 F8 G8 H8);
+
+    /// Create a new [`SquareSpec`]. This doesn't check that the
+    /// square is on the board, see [`SquareSpec::try_new`] for that.
     pub fn new(rank: u32, file: u32) -> SquareSpec {
         SquareSpec { rank, file }
     }
 
+    /// Create a new [`SquareSpec`], returning [`None`] if the rank or
+    /// file is outside the board.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::SquareSpec;
+    /// assert_eq!(SquareSpec::try_new(3, 4), Some(SquareSpec::E4));
+    /// assert_eq!(SquareSpec::try_new(9, 42), None);
+    /// ```
+    pub fn try_new(rank: u32, file: u32) -> Option<SquareSpec> {
+        let sq = SquareSpec { rank, file };
+        if sq.is_valid() {
+            Some(sq)
+        } else {
+            None
+        }
+    }
+
+    /// Check if this square is actually on the board
+    pub fn is_valid(self) -> bool {
+        self.rank < 8 && self.file < 8
+    }
+
+    /// Get the index of this square in `0..64`, counting from a1 and
+    /// along each rank, so a1 is 0, h1 is 7, a2 is 8 and h8 is 63.
+    /// This is handy for compact representations like bitboards. The
+    /// square has to be on the board, which is checked in debug
+    /// builds.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::SquareSpec;
+    /// assert_eq!(SquareSpec::A1.index(), 0);
+    /// assert_eq!(SquareSpec::E4.index(), 28);
+    /// assert_eq!(u8::from(SquareSpec::H8), 63);
+    /// ```
+    pub fn index(self) -> u8 {
+        debug_assert!(
+            self.rank < 8 && self.file < 8,
+            "{:?} is outside the board",
+            self
+        );
+        (self.rank * 8 + self.file) as u8
+    }
+
+    /// Create a square from an index as produced by
+    /// [`SquareSpec::index`]. Returns [`None`] if the index is 64 or
+    /// above.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::SquareSpec;
+    /// assert_eq!(SquareSpec::from_index(28), Some(SquareSpec::E4));
+    /// assert_eq!(SquareSpec::from_index(64), None);
+    /// ```
+    pub fn from_index(index: u8) -> Option<SquareSpec> {
+        if index < 64 {
+            Some(SquareSpec {
+                rank: u32::from(index / 8),
+                file: u32::from(index % 8),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Get the square as seen from `color`'s side of the board, which
+    /// is the same square for white and the square mirrored across
+    /// the middle of the board for black. This way, e.g. a square on
+    /// the 7th rank is about to promote for either color, and tables
+    /// indexed by square only have to be written for white.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::SquareSpec;
+    /// # use chess_engine::piece::Color;
+    /// assert_eq!(SquareSpec::E2.relative_to(Color::White), SquareSpec::E2);
+    /// assert_eq!(SquareSpec::E2.relative_to(Color::Black), SquareSpec::E7);
+    /// ```
+    pub fn relative_to(self, color: Color) -> SquareSpec {
+        SquareSpec {
+            rank: color.relative_rank(self.rank),
+            file: self.file,
+        }
+    }
+
+    /// Get the rank of the square as seen from `color`'s side of the
+    /// board, see [`SquareSpec::relative_to`]
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::SquareSpec;
+    /// # use chess_engine::piece::Color;
+    /// assert_eq!(SquareSpec::D7.relative_rank(Color::White), 6);
+    /// assert_eq!(SquareSpec::D7.relative_rank(Color::Black), 1);
+    /// ```
+    pub fn relative_rank(self, color: Color) -> u32 {
+        color.relative_rank(self.rank)
+    }
+
+    /// Iterate over all the squares on the board, starting with a1,
+    /// b1 and so on up to h8.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::SquareSpec;
+    /// let mut squares = SquareSpec::all();
+    ///
+    /// assert_eq!(squares.next(), Some(SquareSpec::A1));
+    /// assert_eq!(squares.next(), Some(SquareSpec::B1));
+    /// assert_eq!(squares.last(), Some(SquareSpec::H8));
+    /// ```
+    pub fn all() -> impl Iterator<Item = SquareSpec> {
+        (0..8).flat_map(SquareSpec::rank_iter)
+    }
+
+    /// Iterate over all the squares on a rank, from the "a" file to
+    /// the "h" file.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::SquareSpec;
+    /// let rank = SquareSpec::rank_iter(3).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(rank.len(), 8);
+    /// assert_eq!(rank[4], SquareSpec::E4);
+    /// ```
+    pub fn rank_iter(rank: u32) -> impl Iterator<Item = SquareSpec> {
+        (0..8).map(move |file| SquareSpec { rank, file })
+    }
+
+    /// Iterate over all the squares on a file, from the first rank
+    /// to the eighth.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::SquareSpec;
+    /// let file = SquareSpec::file_iter(4).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(file.len(), 8);
+    /// assert_eq!(file[3], SquareSpec::E4);
+    /// ```
+    pub fn file_iter(file: u32) -> impl Iterator<Item = SquareSpec> {
+        (0..8).map(move |rank| SquareSpec { rank, file })
+    }
+
+    /// Get the squares strictly between two squares on the same
+    /// rank, file or diagonal, ordered from `a` towards `b`. Returns
+    /// an empty vector if the squares aren't on a line, or are next
+    /// to each other.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::SquareSpec;
+    /// assert_eq!(
+    ///     SquareSpec::between(SquareSpec::A1, SquareSpec::D4),
+    ///     vec![SquareSpec::B2, SquareSpec::C3]
+    /// );
+    /// assert_eq!(
+    ///     SquareSpec::between(SquareSpec::E1, SquareSpec::E3),
+    ///     vec![SquareSpec::E2]
+    /// );
+    /// assert!(SquareSpec::between(SquareSpec::A1, SquareSpec::B3).is_empty());
+    /// ```
+    pub fn between(a: SquareSpec, b: SquareSpec) -> Vec<SquareSpec> {
+        let dir = match (b - a).as_unit() {
+            Some(dir) if a != b => dir,
+            _ => return vec![],
+        };
+
+        let mut squares = Vec::new();
+        let mut sq = a + dir;
+        while sq != b {
+            squares.push(sq);
+            sq += dir;
+        }
+        squares
+    }
+
+    /// Get the Chebyshev distance to another square, i.e. the number
+    /// of moves a king would need to get there.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::SquareSpec;
+    /// assert_eq!(SquareSpec::A1.chebyshev_distance(SquareSpec::H8), 7);
+    /// assert_eq!(SquareSpec::E4.chebyshev_distance(SquareSpec::F6), 2);
+    /// ```
+    pub fn chebyshev_distance(self, other: SquareSpec) -> u32 {
+        let diff = (other - self).abs();
+        diff.d_rank.max(diff.d_file) as u32
+    }
+
+    /// Get the Manhattan distance to another square, i.e. the number
+    /// of moves a rook would need to get there if it could only move
+    /// one square at a time.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::SquareSpec;
+    /// assert_eq!(SquareSpec::A1.manhattan_distance(SquareSpec::H8), 14);
+    /// assert_eq!(SquareSpec::E4.manhattan_distance(SquareSpec::F6), 3);
+    /// ```
+    pub fn manhattan_distance(self, other: SquareSpec) -> u32 {
+        let diff = (other - self).abs();
+        (diff.d_rank + diff.d_file) as u32
+    }
+
+    /// Check if two squares are on the same rank
+    pub fn same_rank(self, other: SquareSpec) -> bool {
+        self.rank == other.rank
+    }
+
+    /// Check if two squares are on the same file
+    pub fn same_file(self, other: SquareSpec) -> bool {
+        self.file == other.file
+    }
+
+    /// Check if two squares are on the same diagonal, in either
+    /// direction. A square is considered to be on the same diagonal
+    /// as itself.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::SquareSpec;
+    /// assert!(SquareSpec::C1.same_diagonal(SquareSpec::H6));
+    /// assert!(SquareSpec::C1.same_diagonal(SquareSpec::A3));
+    /// assert!(!SquareSpec::C1.same_diagonal(SquareSpec::C3));
+    /// ```
+    pub fn same_diagonal(self, other: SquareSpec) -> bool {
+        (other - self).is_diag()
+    }
+
+    /// Iterate over the squares in a direction from this square, not
+    /// including the square itself, until the edge of the board.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{Direction, SquareSpec};
+    /// let ray = SquareSpec::E4.ray(Direction::NorthEast).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(ray, [SquareSpec::F5, SquareSpec::G6, SquareSpec::H7]);
+    /// assert_eq!(SquareSpec::E1.ray(Direction::South).next(), None);
+    /// ```
+    pub fn ray(self, dir: Direction) -> impl Iterator<Item = SquareSpec> {
+        let diff = dir.diff();
+        std::iter::successors(self.checked_add(diff), move |sq| sq.checked_add(diff))
+    }
+
     /// Checked addition with a [`SquareDiff`], making sure that the
     /// result remains in bounds.
     ///
@@ -59,6 +342,91 @@ impl SquareSpec {
     }
 }
 
+/// The eight directions a piece can move in along a line, as seen
+/// from white's side of the board, i.e. north is towards the eighth
+/// rank and east is towards the "h" file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Direction {
+    /// All eight directions, clockwise starting from north
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::South,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest,
+    ];
+
+    /// The four directions a rook moves in
+    pub const ORTHOGONAL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    /// The four directions a bishop moves in
+    pub const DIAGONAL: [Direction; 4] = [
+        Direction::NorthEast,
+        Direction::SouthEast,
+        Direction::SouthWest,
+        Direction::NorthWest,
+    ];
+
+    /// Get the one square step in this direction
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{Direction, SquareDiff};
+    /// assert_eq!(Direction::NorthWest.diff(), SquareDiff::new(1, -1));
+    /// ```
+    pub fn diff(self) -> SquareDiff {
+        use Direction::*;
+
+        let (d_rank, d_file) = match self {
+            North => (1, 0),
+            NorthEast => (1, 1),
+            East => (0, 1),
+            SouthEast => (-1, 1),
+            South => (-1, 0),
+            SouthWest => (-1, -1),
+            West => (0, -1),
+            NorthWest => (1, -1),
+        };
+        SquareDiff { d_rank, d_file }
+    }
+
+    /// Check if this is one of the diagonal directions
+    pub fn is_diagonal(self) -> bool {
+        self.diff().is_diag()
+    }
+
+    /// Get the opposite direction
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Direction;
+    /// assert_eq!(Direction::NorthEast.opposite(), Direction::SouthWest);
+    /// ```
+    pub fn opposite(self) -> Direction {
+        Direction::ALL[(self as usize + 4) % 8]
+    }
+}
+
 /// A struct representing a difference between two squares, mainly
 /// created as [`SquareSpec`] can't contain negative numbers
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -211,6 +579,23 @@ impl ops::AddAssign<SquareDiff> for SquareSpec {
     }
 }
 
+impl TryFrom<(u32, u32)> for SquareSpec {
+    type Error = Error;
+
+    /// Create a square from a `(rank, file)` pair, failing if it's
+    /// outside the board.
+    fn try_from((rank, file): (u32, u32)) -> Result<SquareSpec, Error> {
+        SquareSpec::try_new(rank, file)
+            .ok_or_else(|| Error::InvalidSquare(format!("({}, {})", rank, file)))
+    }
+}
+
+impl From<SquareSpec> for u8 {
+    fn from(sq: SquareSpec) -> u8 {
+        sq.index()
+    }
+}
+
 impl fmt::Display for SquareSpec {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -286,6 +671,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn try_from_tuple() {
+        use std::convert::TryFrom;
+
+        assert_eq!(SquareSpec::try_from((7, 3)).unwrap(), SquareSpec::D8);
+        assert!(SquareSpec::try_from((8, 0)).is_err());
+        assert!(SquareSpec::try_from((0, 8)).is_err());
+    }
+
+    #[test]
+    fn index_round_trips() {
+        for (i, sq) in SquareSpec::all().enumerate() {
+            assert_eq!(usize::from(sq.index()), i);
+            assert_eq!(SquareSpec::from_index(sq.index()), Some(sq));
+        }
+    }
+
     #[test]
     fn parse_printed_is_noop() {
         let constructed = SquareSpec { rank: 0, file: 0 };
@@ -0,0 +1,185 @@
+use super::{Board, CastlingFlags, Move, SquareSpec};
+use crate::piece::{Color, Piece};
+
+/// A symmetry of the board that leaves the rules unchanged, as
+/// returned by [`Board::canonicalize`]. Swapping the colors flips the
+/// board upside down and makes every white piece black and the other
+/// way around, and mirroring the files swaps the king- and queenside,
+/// which is only a symmetry while nobody can castle.
+///
+/// Both are their own inverse, so the same transform maps moves from
+/// the original board to the transformed one and back.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transform {
+    /// Whether the colors are swapped and the ranks flipped
+    pub swap_colors: bool,
+    /// Whether the files are mirrored, so the a-file becomes the
+    /// h-file
+    pub mirror_files: bool,
+}
+
+impl Transform {
+    /// The transform that leaves everything as it is
+    pub const IDENTITY: Transform = Transform {
+        swap_colors: false,
+        mirror_files: false,
+    };
+
+    /// Map a square on a board of the size of `board`
+    pub fn map_square(self, board: &Board, sq: SquareSpec) -> SquareSpec {
+        SquareSpec {
+            rank: if self.swap_colors {
+                board.size.ranks - 1 - sq.rank
+            } else {
+                sq.rank
+            },
+            file: if self.mirror_files {
+                board.size.files - 1 - sq.file
+            } else {
+                sq.file
+            },
+        }
+    }
+
+    /// Map a move made on `board`, or on the board it was transformed
+    /// to. Castling moves stay the same, since they're relative to
+    /// the player making them.
+    pub fn map_move(self, board: &Board, m: Move) -> Move {
+        match m {
+            Move::Normal { from, to } => Move::Normal {
+                from: self.map_square(board, from),
+                to: self.map_square(board, to),
+            },
+            Move::Promotion { from, to, target } => Move::Promotion {
+                from: self.map_square(board, from),
+                to: self.map_square(board, to),
+                target,
+            },
+            Move::Castling(c) => Move::Castling(c),
+        }
+    }
+
+    /// Apply the transform to a whole board, its pieces, side to move,
+    /// castling rights and en passant square. The move counters are
+    /// kept as they are.
+    pub fn map_board(self, board: &Board) -> Board {
+        let mut mapped = *board;
+        mapped.board = [[None; 8]; 8];
+        for sq in SquareSpec::all() {
+            let piece = match board[sq] {
+                Some(piece) => piece,
+                None => continue,
+            };
+            let color = if self.swap_colors {
+                piece.color.opposite()
+            } else {
+                piece.color
+            };
+            mapped[self.map_square(board, sq)] = Some(Piece::new(piece.piece, color));
+        }
+        mapped.en_passant = board.en_passant.map(|sq| self.map_square(board, sq));
+        if self.swap_colors {
+            mapped.turn = board.turn.opposite();
+            for (white, black) in [
+                (CastlingFlags::WHITE_SHORT, CastlingFlags::BLACK_SHORT),
+                (CastlingFlags::WHITE_LONG, CastlingFlags::BLACK_LONG),
+            ] {
+                mapped.castling.set(white, board.castling.contains(black));
+                mapped.castling.set(black, board.castling.contains(white));
+            }
+        }
+        mapped
+    }
+}
+
+impl Board {
+    /// Map the position to a canonical form, returning it along with
+    /// the [`Transform`] that maps between the two. The canonical
+    /// position always has white to move, and while nobody can castle,
+    /// the files are mirrored if that gives the position with the
+    /// smaller [Zobrist hash](Board::zobrist). Positions that are the
+    /// same up to these symmetries have the same canonical form, which
+    /// is useful e.g. for deduplicating datasets.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// let board = Board::default_board().perform_move("e2e4".parse().unwrap()).unwrap();
+    /// let (canonical, transform) = board.canonicalize();
+    ///
+    /// // black's reply is played as white in the canonical position
+    /// let m = "e7e5".parse().unwrap();
+    /// let mapped = transform.map_move(&board, m);
+    /// assert_eq!(mapped, "e2e4".parse().unwrap());
+    /// assert!(canonical.perform_move(mapped).is_some());
+    /// assert_eq!(transform.map_move(&canonical, mapped), m);
+    /// ```
+    pub fn canonicalize(&self) -> (Board, Transform) {
+        let transform = Transform {
+            swap_colors: self.turn == Color::Black,
+            mirror_files: false,
+        };
+        let board = transform.map_board(self);
+        if !board.castling.is_empty() {
+            return (board, transform);
+        }
+
+        let mirror = Transform {
+            mirror_files: true,
+            ..Transform::IDENTITY
+        };
+        let mirrored = mirror.map_board(&board);
+        if mirrored.zobrist() < board.zobrist() {
+            (
+                mirrored,
+                Transform {
+                    mirror_files: true,
+                    ..transform
+                },
+            )
+        } else {
+            (board, transform)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "fen"))]
+mod tests {
+    use super::{Board, Transform};
+    use crate::piece::Color;
+
+    #[test]
+    fn symmetric_positions_share_canonical_form() {
+        let white = Board::load_fen("4k3/8/8/8/8/8/1P6/4K3 w - - 0 1").unwrap();
+        let black = Board::load_fen("4k3/1p6/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+        let mirrored = Board::load_fen("3k4/8/8/8/8/8/6P1/3K4 w - - 0 1").unwrap();
+
+        let (canonical, _) = white.canonicalize();
+        assert_eq!(canonical.turn(), Color::White);
+        assert_eq!(black.canonicalize().0, canonical);
+        assert_eq!(mirrored.canonicalize().0, canonical);
+
+        let (_, transform) = black.canonicalize();
+        assert!(transform.swap_colors);
+        assert_eq!(transform.map_board(&canonical), black);
+    }
+
+    #[test]
+    fn castling_rights_follow_colors() {
+        let board = Board::load_fen("r3k2r/8/8/8/8/8/8/4K2R b Kq - 0 1").unwrap();
+        let (canonical, transform) = board.canonicalize();
+
+        assert_eq!(
+            transform,
+            Transform {
+                swap_colors: true,
+                mirror_files: false
+            }
+        );
+        assert_eq!(
+            canonical,
+            Board::load_fen("4k2r/8/8/8/8/8/8/R3K2R w Qk - 0 1").unwrap()
+        );
+    }
+}
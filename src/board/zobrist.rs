@@ -0,0 +1,124 @@
+//! Zobrist hashing of boards, which gives a 64-bit key for the parts
+//! of a position that matter for repetitions: the pieces, whose turn
+//! it is, castling rights and possible en passant captures.
+//!
+//! The keys are generated at compile time, so hashes are the same
+//! across runs and builds.
+
+use super::{Board, CastlingFlags, SquareSpec};
+use crate::piece::{Color, PieceType};
+
+// A SplitMix64 generator, which is simple enough to run in a const fn
+const fn next(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (state, z ^ (z >> 31))
+}
+
+// One key for each piece on each square, with the pieces in the order
+// of `Piece::index`, followed by the side to move, the four castling
+// rights and the eight files en passant can happen on
+const KEY_COUNT: usize = 6 * 2 * 64 + 1 + 4 + 8;
+const BLACK_TO_MOVE: usize = 6 * 2 * 64;
+const CASTLING: usize = BLACK_TO_MOVE + 1;
+const EN_PASSANT: usize = CASTLING + 4;
+
+const KEYS: [u64; KEY_COUNT] = {
+    let mut keys = [0; KEY_COUNT];
+    let mut state = 0x7468_7374_726f_6368;
+    let mut i = 0;
+    while i < KEY_COUNT {
+        let (new_state, key) = next(state);
+        state = new_state;
+        keys[i] = key;
+        i += 1;
+    }
+    keys
+};
+
+pub(crate) fn hash(board: &Board) -> u64 {
+    let mut hash = 0;
+    for sq in SquareSpec::all() {
+        if let Some(piece) = board[sq] {
+            let square = (sq.rank * 8 + sq.file) as usize;
+            hash ^= KEYS[piece.index() * 64 + square];
+        }
+    }
+    if board.turn == Color::Black {
+        hash ^= KEYS[BLACK_TO_MOVE];
+    }
+    let rights = [
+        CastlingFlags::WHITE_SHORT,
+        CastlingFlags::WHITE_LONG,
+        CastlingFlags::BLACK_SHORT,
+        CastlingFlags::BLACK_LONG,
+    ];
+    for (i, &flag) in rights.iter().enumerate() {
+        if board.castling.contains(flag) {
+            hash ^= KEYS[CASTLING + i];
+        }
+    }
+    if let Some(sq) = board.en_passant {
+        if can_capture_en_passant(board, sq) {
+            hash ^= KEYS[EN_PASSANT + sq.file as usize];
+        }
+    }
+    hash
+}
+
+// The en passant square is set after every double step, but positions
+// only differ if a pawn is there to take it. Whether the capture is
+// legal isn't checked, which at worst tells apart positions that are
+// the same.
+fn can_capture_en_passant(board: &Board, sq: SquareSpec) -> bool {
+    let pawn_rank = match board.turn {
+        Color::White => 4,
+        Color::Black => 3,
+    };
+    [sq.file.wrapping_sub(1), sq.file + 1]
+        .iter()
+        .filter(|&&file| file < 8)
+        .any(|&file| {
+            matches!(
+                board[SquareSpec::new(pawn_rank, file)],
+                Some(p) if p.piece == PieceType::Pawn && p.color == board.turn
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KEYS;
+    use crate::board::Board;
+    use std::collections::HashSet;
+
+    #[test]
+    fn keys_are_unique() {
+        assert_eq!(KEYS.iter().collect::<HashSet<_>>().len(), KEYS.len());
+    }
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn en_passant_only_counts_when_possible() {
+        let hash = |fen| Board::load_fen(fen).unwrap().zobrist();
+
+        // no black pawn can take on e3
+        assert_eq!(
+            hash("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1"),
+            hash("4k3/8/8/8/4P3/8/8/4K3 b - - 0 1")
+        );
+        assert_ne!(
+            hash("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1"),
+            hash("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1")
+        );
+    }
+
+    #[test]
+    fn keys_are_stable() {
+        // hashes may be stored, e.g. in files, so the keys must never
+        // change by accident
+        assert_eq!(Board::default_board().zobrist(), 0xd86b_604a_7d78_1b6b);
+    }
+}
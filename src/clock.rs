@@ -0,0 +1,222 @@
+//! Module for chess clocks, keeping track of how much time each
+//! player has left under a [`TimeControl`].
+
+use crate::piece::Color;
+use std::time::{Duration, Instant};
+
+/// A time control, giving each player `base` time for the game and
+/// adding `increment` after each of their moves
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimeControl {
+    /// The time each player starts with
+    pub base: Duration,
+    /// The time added to a player's clock after each of their moves
+    pub increment: Duration,
+}
+
+/// A chess clock, where at most one player's time is running at once
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::clock::{Clock, TimeControl};
+/// # use chess_engine::piece::Color;
+/// # use std::time::Duration;
+/// let mut clock = Clock::new(TimeControl {
+///     base: Duration::from_secs(60),
+///     increment: Duration::from_secs(1),
+/// });
+///
+/// clock.start(Color::White);
+/// assert!(clock.stop());
+/// // white got the increment, minus the little time it spent
+/// assert!(clock.remaining(Color::White) > Duration::from_secs(60));
+/// assert_eq!(clock.remaining(Color::Black), Duration::from_secs(60));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Clock {
+    time_control: TimeControl,
+    // the time left for white and black when their time last stopped
+    remaining: [Duration; 2],
+    // whose time is running, and since when
+    running: Option<(Color, Instant)>,
+}
+
+impl Clock {
+    /// Create a clock with both players' time stopped
+    pub fn new(time_control: TimeControl) -> Clock {
+        Clock {
+            time_control,
+            remaining: [time_control.base; 2],
+            running: None,
+        }
+    }
+
+    /// Get the time control of the clock
+    pub fn time_control(&self) -> TimeControl {
+        self.time_control
+    }
+
+    fn index(color: Color) -> usize {
+        match color {
+            Color::White => 0,
+            Color::Black => 1,
+        }
+    }
+
+    /// Get how much time a player has left, counting the time that's
+    /// currently running. This is zero if they've run out of time.
+    pub fn remaining(&self, color: Color) -> Duration {
+        let remaining = self.remaining[Clock::index(color)];
+        match self.running {
+            Some((running, since)) if running == color => remaining.saturating_sub(since.elapsed()),
+            _ => remaining,
+        }
+    }
+
+    /// Get whose time is running, if anyone's
+    pub fn running(&self) -> Option<Color> {
+        self.running.map(|(color, _)| color)
+    }
+
+    /// Check whether a player has run out of time
+    pub fn is_flagged(&self, color: Color) -> bool {
+        self.remaining(color) == Duration::from_secs(0)
+    }
+
+    /// Set how much time a player has left, e.g. to follow a clock
+    /// kept by a server. If their time is running, it keeps running
+    /// from the new value.
+    pub fn set_remaining(&mut self, color: Color, remaining: Duration) {
+        self.remaining[Clock::index(color)] = remaining;
+        if let Some((running, since)) = &mut self.running {
+            if *running == color {
+                *since = Instant::now();
+            }
+        }
+    }
+
+    /// Start a player's time, stopping the other player's time
+    /// without an increment if it was running
+    pub fn start(&mut self, color: Color) {
+        if let Some((running, since)) = self.running {
+            let remaining = &mut self.remaining[Clock::index(running)];
+            *remaining = remaining.saturating_sub(since.elapsed());
+        }
+        self.running = Some((color, Instant::now()));
+    }
+
+    /// Stop the running time after a move, adding the increment.
+    /// Returns `false` if the player ran out of time before moving, in
+    /// which case they don't get the increment, and `true` otherwise,
+    /// also if no time was running.
+    pub fn stop(&mut self) -> bool {
+        let (color, since) = match self.running.take() {
+            Some(running) => running,
+            None => return true,
+        };
+        let remaining = &mut self.remaining[Clock::index(color)];
+        match remaining.checked_sub(since.elapsed()) {
+            Some(left) if left > Duration::from_secs(0) => {
+                *remaining = left + self.time_control.increment;
+                true
+            }
+            _ => {
+                *remaining = Duration::from_secs(0);
+                false
+            }
+        }
+    }
+}
+
+/// The state of a [`Clock`] at one moment, which unlike the clock
+/// itself can be stored and serialized. Restoring a clock from it
+/// starts the running player's time again from the moment of
+/// restoring, so the time in between isn't counted.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClockSnapshot {
+    /// The time control of the clock
+    pub time_control: TimeControl,
+    /// The time white had left
+    pub white: Duration,
+    /// The time black had left
+    pub black: Duration,
+    /// Whose time was running, if anyone's
+    pub running: Option<Color>,
+}
+
+impl From<&Clock> for ClockSnapshot {
+    fn from(clock: &Clock) -> ClockSnapshot {
+        ClockSnapshot {
+            time_control: clock.time_control,
+            white: clock.remaining(Color::White),
+            black: clock.remaining(Color::Black),
+            running: clock.running(),
+        }
+    }
+}
+
+impl From<ClockSnapshot> for Clock {
+    fn from(snapshot: ClockSnapshot) -> Clock {
+        Clock {
+            time_control: snapshot.time_control,
+            remaining: [snapshot.white, snapshot.black],
+            running: snapshot.running.map(|color| (color, Instant::now())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Clock, ClockSnapshot, TimeControl};
+    use crate::piece::Color;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn running_out_of_time() {
+        let mut clock = Clock::new(TimeControl {
+            base: Duration::from_millis(5),
+            increment: Duration::from_secs(1),
+        });
+
+        clock.start(Color::Black);
+        assert_eq!(clock.running(), Some(Color::Black));
+        thread::sleep(Duration::from_millis(10));
+        assert!(clock.is_flagged(Color::Black));
+        assert!(!clock.stop());
+        assert_eq!(clock.remaining(Color::Black), Duration::from_secs(0));
+        assert_eq!(clock.running(), None);
+        assert!(!clock.is_flagged(Color::White));
+    }
+
+    #[test]
+    fn switching_sides() {
+        let mut clock = Clock::new(TimeControl {
+            base: Duration::from_secs(10),
+            increment: Duration::from_secs(0),
+        });
+
+        clock.start(Color::White);
+        thread::sleep(Duration::from_millis(5));
+        clock.start(Color::Black);
+        assert!(clock.remaining(Color::White) <= Duration::from_millis(9995));
+        assert_eq!(clock.running(), Some(Color::Black));
+    }
+
+    #[test]
+    fn snapshot_round_trip() {
+        let mut clock = Clock::new(TimeControl {
+            base: Duration::from_secs(10),
+            increment: Duration::from_secs(2),
+        });
+        clock.set_remaining(Color::Black, Duration::from_secs(4));
+        let snapshot = ClockSnapshot::from(&clock);
+
+        assert_eq!(snapshot.black, Duration::from_secs(4));
+        assert_eq!(snapshot.running, None);
+        assert_eq!(Clock::from(snapshot), clock);
+    }
+}
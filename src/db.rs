@@ -0,0 +1,248 @@
+//! Module for storing games in an `SQLite` database and searching them,
+//! e.g. for every game that reached a position, which is the core of
+//! an opening explorer. Needs the `db` feature.
+//!
+//! Games are stored as PGN along with their tags, and every position
+//! of every game is indexed by its [Zobrist hash](Board::zobrist), so
+//! positions are found regardless of the move order that led to them.
+//!
+//! # Examples
+//!
+//! ```
+//! # use chess_engine::db::GameDatabase;
+//! # use chess_engine::pgn;
+//! let mut db = GameDatabase::open_in_memory().unwrap();
+//! let italian = db.add_game(&pgn::parse("1. e4 e5 2. Nf3 Nc6 3. Bc4 *").unwrap()).unwrap();
+//! let _ = db.add_game(&pgn::parse("1. e4 c5 2. Nf3 d6 *").unwrap()).unwrap();
+//! let _ = db.add_game(&pgn::parse("1. Nf3 Nc6 2. e4 e5 3. Bb5 *").unwrap()).unwrap();
+//!
+//! let after_nc6 = *pgn::parse("1. e4 e5 2. Nf3 Nc6 *").unwrap().game.current_board();
+//! assert_eq!(db.games_with_position(&after_nc6).unwrap().len(), 2);
+//! assert_eq!(db.game(italian).unwrap().unwrap().game.get_moves().len(), 5);
+//! ```
+
+use crate::board::{Board, Move};
+use crate::error::IoError;
+use crate::pgn::{self, PgnGame};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+/// The id of a game in a [`GameDatabase`]
+pub type GameId = i64;
+
+// The tables of the database. The Zobrist hashes are stored as
+// signed integers, which is all SQLite has, so they're cast back and
+// forth with the bits unchanged.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS games (
+        id INTEGER PRIMARY KEY,
+        pgn TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS tags (
+        game_id INTEGER NOT NULL REFERENCES games(id),
+        name TEXT NOT NULL,
+        value TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS tags_by_name ON tags(name, value);
+    CREATE TABLE IF NOT EXISTS positions (
+        game_id INTEGER NOT NULL REFERENCES games(id),
+        ply INTEGER NOT NULL,
+        zobrist INTEGER NOT NULL,
+        next_move TEXT
+    );
+    CREATE INDEX IF NOT EXISTS positions_by_zobrist ON positions(zobrist);
+";
+
+/// A database of games, stored in `SQLite`
+#[derive(Debug)]
+pub struct GameDatabase {
+    conn: Connection,
+}
+
+impl GameDatabase {
+    /// Open the database in the file at `path`, creating it if it
+    /// doesn't exist
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the file can't be opened or isn't an
+    /// `SQLite` database
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<GameDatabase, IoError> {
+        GameDatabase::with_connection(Connection::open(path)?)
+    }
+
+    /// Open a new database that only lives in memory, e.g. for tests
+    /// or analysing a collection of games without storing it
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if `SQLite` fails to set up the database
+    pub fn open_in_memory() -> Result<GameDatabase, IoError> {
+        GameDatabase::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<GameDatabase, IoError> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(GameDatabase { conn })
+    }
+
+    /// Add a game to the database, returning its id
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the game couldn't be stored, in which
+    /// case none of it is
+    pub fn add_game(&mut self, game: &PgnGame) -> Result<GameId, IoError> {
+        let tx = self.conn.transaction()?;
+        let _ = tx.execute(
+            "INSERT INTO games (pgn) VALUES (?1)",
+            params![game.to_string()],
+        )?;
+        let id = tx.last_insert_rowid();
+        for (name, value) in &game.tags {
+            let _ = tx.execute(
+                "INSERT INTO tags (game_id, name, value) VALUES (?1, ?2, ?3)",
+                params![id, name, value],
+            )?;
+        }
+        let moves = game.game.get_moves();
+        for (ply, board) in game.game.boards().enumerate() {
+            let _ = tx.execute(
+                "INSERT INTO positions (game_id, ply, zobrist, next_move) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    id,
+                    ply as i64,
+                    board.zobrist() as i64,
+                    moves.get(ply).map(ToString::to_string)
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// Get a game from the database, or [`None`] if there's no game
+    /// with the id
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the database couldn't be read, or if
+    /// the stored PGN is invalid
+    pub fn game(&self, id: GameId) -> Result<Option<PgnGame>, IoError> {
+        let pgn: Option<String> = self
+            .conn
+            .query_row("SELECT pgn FROM games WHERE id = ?1", params![id], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        Ok(pgn.map(|pgn| pgn::parse(&pgn)).transpose()?)
+    }
+
+    /// Get the number of games in the database
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the database couldn't be read
+    pub fn count(&self) -> Result<usize, IoError> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM games", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    /// Get the ids of all games that reached a position, in the order
+    /// they were added. Positions count as the same if their
+    /// [Zobrist hashes](Board::zobrist) are, so the move counters
+    /// don't matter.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the database couldn't be read
+    pub fn games_with_position(&self, board: &Board) -> Result<Vec<GameId>, IoError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT game_id FROM positions WHERE zobrist = ?1 ORDER BY game_id",
+        )?;
+        let ids = stmt
+            .query_map(params![board.zobrist() as i64], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        Ok(ids)
+    }
+
+    /// Get the ids of all games with a tag, e.g. `("White",
+    /// "Carlsen")`, in the order they were added
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the database couldn't be read
+    pub fn games_with_tag(&self, name: &str, value: &str) -> Result<Vec<GameId>, IoError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT game_id FROM tags WHERE name = ?1 AND value = ?2 ORDER BY game_id",
+        )?;
+        let ids = stmt
+            .query_map(params![name, value], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        Ok(ids)
+    }
+
+    /// Get the moves played from a position in the games of the
+    /// database, with how many times each was played, from the most
+    /// to the least played
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the database couldn't be read
+    pub fn next_moves(&self, board: &Board) -> Result<Vec<(Move, u32)>, IoError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT next_move, COUNT(*) FROM positions
+             WHERE zobrist = ?1 AND next_move IS NOT NULL
+             GROUP BY next_move ORDER BY COUNT(*) DESC, next_move",
+        )?;
+        let rows: Vec<(String, u32)> = stmt
+            .query_map(params![board.zobrist() as i64], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .collect::<Result<_, _>>()?;
+        let mut moves = Vec::with_capacity(rows.len());
+        for (m, count) in rows {
+            moves.push((m.parse()?, count));
+        }
+        Ok(moves)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GameDatabase;
+    use crate::board::Board;
+    use crate::pgn;
+
+    #[test]
+    fn explorer_queries() {
+        let mut db = GameDatabase::open_in_memory().unwrap();
+        let games = [
+            "[White \"Tal\"]\n\n1. e4 e5 2. Nf3 Nc6 1-0",
+            "[White \"Petrosian\"]\n\n1. e4 c5 2. Nf3 1/2-1/2",
+            "[White \"Tal\"]\n\n1. d4 d5 0-1",
+        ];
+        let ids: Vec<_> = games
+            .iter()
+            .map(|pgn| db.add_game(&pgn::parse(pgn).unwrap()).unwrap())
+            .collect();
+
+        assert_eq!(db.count().unwrap(), 3);
+        assert_eq!(db.games_with_tag("White", "Tal").unwrap(), [ids[0], ids[2]]);
+
+        let start = Board::default_board();
+        assert_eq!(db.games_with_position(&start).unwrap(), ids);
+        assert_eq!(
+            db.next_moves(&start).unwrap(),
+            [("e2e4".parse().unwrap(), 2), ("d2d4".parse().unwrap(), 1)]
+        );
+
+        let stored = db.game(ids[1]).unwrap().unwrap();
+        assert_eq!(stored.tag("White"), Some("Petrosian"));
+        assert_eq!(stored.result.as_deref(), Some("1/2-1/2"));
+        assert_eq!(stored.game.get_moves().len(), 3);
+        assert!(db.game(42).unwrap().is_none());
+    }
+}
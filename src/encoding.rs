@@ -0,0 +1,244 @@
+//! Module for encoding positions and moves in the layout machine
+//! learning models use, so training pipelines and engines using the
+//! trained models encode them the same way. Needs the `ml` feature.
+//!
+//! A position is encoded as [`PLANE_COUNT`] planes of 64 values each,
+//! one for each square at its [index](SquareSpec::index), so a1 is
+//! first and h8 last. The values are always 0 or 1:
+//!
+//! - planes 0 to 5 are white's pawns, knights, bishops, rooks, queens
+//!   and king, and planes 6 to 11 are black's, in the same order
+//! - planes 12 to 15 are all ones if white may castle short, white may
+//!   castle long, black may castle short and black may castle long
+//! - plane 16 has the en passant square set, if there is one
+//! - plane 17 is all ones if white is to move
+//!
+//! Moves are encoded as indices below [`MOVE_INDEX_COUNT`] by
+//! [`move_index`], for the outputs of policy networks:
+//!
+//! - normal moves are `from * 64 + to`, using the square indices
+//! - promotions come after those, ordered by the color promoting,
+//!   the file promoted from, whether the pawn captures to the left,
+//!   moves straight or captures to the right, and the piece promoted
+//!   to: knight, bishop, rook or queen
+//! - castling short and castling long are the last two indices
+//!
+//! The indices don't depend on the position, so they stay the same
+//! across versions of the crate. A few of them, like the ones for
+//! moving from a square to itself, aren't used by any move.
+
+use crate::board::{Board, BoardSnapshot, Castling, CastlingFlags, Move, SquareSpec};
+use crate::piece::{Color, PieceType};
+
+/// The number of planes in an encoded position
+pub const PLANE_COUNT: usize = 18;
+
+// The first plane of each group
+const CASTLING_PLANES: usize = 12;
+const EN_PASSANT_PLANE: usize = 16;
+const TURN_PLANE: usize = 17;
+
+/// The number of move indices, see [`move_index`]
+pub const MOVE_INDEX_COUNT: u16 = 64 * 64 + PROMOTION_COUNT + 2;
+
+// There are 2 colors, 8 files, 3 directions and 4 pieces to promote to
+const PROMOTION_COUNT: u16 = 2 * 8 * 3 * 4;
+const PROMOTIONS: u16 = 64 * 64;
+const CASTLING: u16 = PROMOTIONS + PROMOTION_COUNT;
+const PROMOTION_TARGETS: [PieceType; 4] = [
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+];
+
+/// Get the index of a move, as described in the
+/// [module documentation](crate::encoding), or [`None`] if it can't be
+/// a legal move in any position, like a promotion to a king or one
+/// that isn't from the second to last rank to the last.
+///
+/// # Examples
+/// ```
+/// # use chess_engine::encoding;
+/// let m = "e2e4".parse().unwrap();
+/// let index = encoding::move_index(m).unwrap();
+///
+/// assert_eq!(index, 12 * 64 + 28);
+/// assert_eq!(encoding::index_move(index), Some(m));
+/// ```
+pub fn move_index(m: Move) -> Option<u16> {
+    match m {
+        Move::Normal { from, to } => Some(u16::from(from.index()) * 64 + u16::from(to.index())),
+        Move::Promotion { from, to, target } => {
+            let color = match (from.rank, to.rank) {
+                (6, 7) => 0,
+                (1, 0) => 1,
+                _ => return None,
+            };
+            let direction = match i64::from(to.file) - i64::from(from.file) {
+                -1 => 0,
+                0 => 1,
+                1 => 2,
+                _ => return None,
+            };
+            let target = PROMOTION_TARGETS.iter().position(|&p| p == target)? as u16;
+            let file = from.file as u16;
+            Some(PROMOTIONS + ((color * 8 + file) * 3 + direction) * 4 + target)
+        }
+        Move::Castling(Castling::Short) => Some(CASTLING),
+        Move::Castling(Castling::Long) => Some(CASTLING + 1),
+    }
+}
+
+/// Get the move with an index, the inverse of [`move_index`], or
+/// [`None`] if the index is at least [`MOVE_INDEX_COUNT`]
+pub fn index_move(index: u16) -> Option<Move> {
+    if index < PROMOTIONS {
+        let square = |i: u16| SquareSpec::from_index(i as u8);
+        return Some(Move::Normal {
+            from: square(index / 64)?,
+            to: square(index % 64)?,
+        });
+    }
+    if index < CASTLING {
+        let index = index - PROMOTIONS;
+        let target = PROMOTION_TARGETS[(index % 4) as usize];
+        let direction = (index / 4 % 3) as u32;
+        let file = (index / 12 % 8) as u32;
+        let (from_rank, to_rank) = if index / 96 == 0 { (6, 7) } else { (1, 0) };
+        let to_file = (file + direction).checked_sub(1)?;
+        return Some(Move::Promotion {
+            from: SquareSpec::try_new(from_rank, file)?,
+            to: SquareSpec::try_new(to_rank, to_file)?,
+            target,
+        });
+    }
+    match index - CASTLING {
+        0 => Some(Move::Castling(Castling::Short)),
+        1 => Some(Move::Castling(Castling::Long)),
+        _ => None,
+    }
+}
+
+impl Board {
+    /// Encode the position as planes, as described in the
+    /// [module documentation](crate::encoding)
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::{Board, SquareSpec};
+    /// let planes = Board::default_board().to_planes();
+    ///
+    /// // a white pawn on e2
+    /// assert_eq!(planes[0][SquareSpec::E2.index() as usize], 1.0);
+    /// // a black king on e8
+    /// assert_eq!(planes[11][SquareSpec::E8.index() as usize], 1.0);
+    /// // white to move
+    /// assert!(planes[17].iter().all(|&v| v == 1.0));
+    /// ```
+    pub fn to_planes(&self) -> [[f32; 64]; PLANE_COUNT] {
+        let mut planes = [[0.0; 64]; PLANE_COUNT];
+        for sq in SquareSpec::all() {
+            if let Some(piece) = self[sq] {
+                planes[piece.index()][sq.index() as usize] = 1.0;
+            }
+        }
+
+        let snapshot = BoardSnapshot::from(self);
+        let rights = [
+            CastlingFlags::WHITE_SHORT,
+            CastlingFlags::WHITE_LONG,
+            CastlingFlags::BLACK_SHORT,
+            CastlingFlags::BLACK_LONG,
+        ];
+        for (plane, &right) in planes[CASTLING_PLANES..].iter_mut().zip(&rights) {
+            if snapshot.castling.contains(right) {
+                *plane = [1.0; 64];
+            }
+        }
+
+        if let Some(sq) = snapshot.en_passant {
+            planes[EN_PASSANT_PLANE][sq.index() as usize] = 1.0;
+        }
+        if self.turn() == Color::White {
+            planes[TURN_PLANE] = [1.0; 64];
+        }
+        planes
+    }
+
+    /// Encode the position like [`Board::to_planes`], but packed as one
+    /// 64-bit mask per plane, with bit `i` set for a value of 1 on the
+    /// square with index `i`
+    ///
+    /// # Examples
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// let bits = Board::default_board().to_bit_planes();
+    ///
+    /// // white's pawns fill the second rank
+    /// assert_eq!(bits[0], 0xff00);
+    /// assert_eq!(bits[16], 0);
+    /// ```
+    pub fn to_bit_planes(&self) -> [u64; PLANE_COUNT] {
+        let mut bits = [0; PLANE_COUNT];
+        for (mask, plane) in bits.iter_mut().zip(self.to_planes().iter()) {
+            *mask = plane
+                .iter()
+                .enumerate()
+                .filter(|&(_, &v)| v != 0.0)
+                .fold(0, |mask, (i, _)| mask | 1 << i);
+        }
+        bits
+    }
+}
+
+#[cfg(all(test, feature = "fen"))]
+mod tests {
+    use super::{index_move, move_index, MOVE_INDEX_COUNT};
+    use crate::board::{Board, SquareSpec};
+    use std::collections::HashSet;
+
+    #[test]
+    fn move_indices_round_trip() {
+        let positions = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+            "1n2k3/P1P5/8/8/8/8/5p1p/4K1N1 w - - 0 1",
+            "1n2k3/P1P5/8/8/8/8/5p1p/4K1N1 b - - 0 1",
+        ];
+        for fen in &positions {
+            let board = Board::load_fen(fen).unwrap();
+            let moves = board.get_all_legal_moves();
+            let indices: HashSet<_> = moves.iter().map(|&m| move_index(m).unwrap()).collect();
+            assert_eq!(indices.len(), moves.len(), "{}", fen);
+            for &m in &moves {
+                assert_eq!(index_move(move_index(m).unwrap()), Some(m), "{}", fen);
+            }
+        }
+
+        // every index that decodes encodes back to itself
+        for index in 0..MOVE_INDEX_COUNT + 10 {
+            if let Some(m) = index_move(index) {
+                assert_eq!(move_index(m), Some(index));
+            }
+        }
+        assert!(index_move(MOVE_INDEX_COUNT).is_none());
+    }
+
+    #[test]
+    fn castling_and_en_passant_planes() {
+        let board = Board::load_fen("4k2r/8/8/3pP3/8/8/8/R3K3 w Qk d6 0 1").unwrap();
+        let bits = board.to_bit_planes();
+
+        assert_eq!(bits[12], 0);
+        assert_eq!(bits[13], u64::MAX);
+        assert_eq!(bits[14], u64::MAX);
+        assert_eq!(bits[15], 0);
+        assert_eq!(bits[16], 1 << SquareSpec::D6.index());
+        assert_eq!(bits[17], u64::MAX);
+        assert_eq!(bits.iter().take(12).map(|b| b.count_ones()).sum::<u32>(), 6);
+
+        let black = board.perform_move("e1d1".parse().unwrap()).unwrap();
+        assert_eq!(black.to_bit_planes()[17], 0);
+    }
+}
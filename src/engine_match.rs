@@ -0,0 +1,477 @@
+//! Module for playing matches between two move selectors, such as two
+//! versions of an engine, over a number of games with alternating
+//! colors. Matches can be played with time controls, exported as
+//! PGN, and stopped early once a sequential probability ratio test
+//! (SPRT) can tell whether the first selector is stronger.
+//!
+//! (The module isn't called `match`, as that's a keyword.)
+
+use crate::board::Board;
+use crate::clock::Clock;
+use crate::game::{Game, GameResult, GameState};
+use crate::pgn::PgnGame;
+use crate::piece::Color;
+use crate::rating;
+use crate::selfplay::MoveSelector;
+
+pub use crate::clock::TimeControl;
+
+/// The settings of a match
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchConfig {
+    /// How many games to play at most
+    pub games: u32,
+    /// How many plies (half-moves) a game can last before it's
+    /// adjudicated a draw
+    pub max_plies: u32,
+    /// The time control of the games, if any. The selectors aren't
+    /// told how much time they have left, but lose a game if they run
+    /// out of it.
+    pub time_control: Option<TimeControl>,
+    /// The positions to start the games from. Each position is played
+    /// twice in a row, once with each selector as white, and they're
+    /// cycled through if there are more games than positions. If
+    /// empty, all games start from the default position.
+    pub openings: Vec<Board>,
+    /// The SPRT to stop the match early with, if any
+    pub sprt: Option<Sprt>,
+}
+
+impl Default for MatchConfig {
+    fn default() -> MatchConfig {
+        MatchConfig {
+            games: 100,
+            max_plies: 400,
+            time_control: None,
+            openings: vec![],
+            sprt: None,
+        }
+    }
+}
+
+/// How a game of a match ended
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Termination {
+    /// The game ended by the rules, e.g. by checkmate or stalemate
+    Normal,
+    /// A player ran out of time and lost
+    TimeForfeit,
+    /// A player's selector stopped selecting moves, which counts as
+    /// resigning
+    Resignation,
+    /// A player's selector picked an illegal move and lost
+    IllegalMove,
+    /// The game reached the move limit and was adjudicated a draw
+    MoveLimit,
+}
+
+/// A game played in a match
+#[derive(Clone, Debug)]
+pub struct MatchGame {
+    /// The game itself
+    pub game: Game,
+    /// Whether the first selector played white
+    pub first_is_white: bool,
+    /// The color of the winner, or [`None`] for a draw
+    pub winner: Option<Color>,
+    /// How the game ended
+    pub termination: Termination,
+}
+
+impl MatchGame {
+    /// The color the first selector played
+    pub fn first_color(&self) -> Color {
+        if self.first_is_white {
+            Color::White
+        } else {
+            Color::Black
+        }
+    }
+
+    /// The score of the first selector in this game: 1 for a win,
+    /// 0.5 for a draw and 0 for a loss
+    pub fn first_score(&self) -> f64 {
+        match self.winner {
+            Some(winner) if winner == self.first_color() => 1.0,
+            Some(_) => 0.0,
+            None => 0.5,
+        }
+    }
+}
+
+/// The result of a match, as returned by [`play_match`]
+#[derive(Clone, Debug)]
+pub struct MatchReport {
+    /// The games that were played, in order
+    pub games: Vec<MatchGame>,
+    /// The state of the SPRT after the last game, if one was used
+    pub sprt: Option<SprtStatus>,
+}
+
+impl MatchReport {
+    /// The wins, draws and losses of the first selector
+    pub fn first_record(&self) -> (u32, u32, u32) {
+        let mut record = (0, 0, 0);
+        for game in &self.games {
+            match game.winner {
+                Some(winner) if winner == game.first_color() => record.0 += 1,
+                Some(_) => record.2 += 1,
+                None => record.1 += 1,
+            }
+        }
+        record
+    }
+
+    /// Write all the games as PGN, with the selectors named `first`
+    /// and `second`. Games that didn't end by the rules get a
+    /// `Termination` tag.
+    pub fn to_pgn(&self, event: &str, first: &str, second: &str) -> String {
+        let mut s = String::new();
+        for (round, game) in self.games.iter().enumerate() {
+            let mut pgn = PgnGame::new(game.game.clone());
+            let (white, black) = if game.first_is_white {
+                (first, second)
+            } else {
+                (second, first)
+            };
+            let result = match game.winner {
+                Some(Color::White) => "1-0",
+                Some(Color::Black) => "0-1",
+                None => "1/2-1/2",
+            };
+            for (name, value) in &mut pgn.tags {
+                let new_value = match name.as_str() {
+                    "Event" => event.to_string(),
+                    "Round" => (round + 1).to_string(),
+                    "White" => white.to_string(),
+                    "Black" => black.to_string(),
+                    "Result" => result.to_string(),
+                    _ => continue,
+                };
+                *value = new_value;
+            }
+            let termination = match game.termination {
+                Termination::Normal => None,
+                Termination::TimeForfeit => Some("time forfeit"),
+                Termination::Resignation => Some("normal"),
+                Termination::IllegalMove => Some("rules infraction"),
+                Termination::MoveLimit => Some("adjudication"),
+            };
+            if let Some(termination) = termination {
+                pgn.tags
+                    .push(("Termination".to_string(), termination.to_string()));
+            }
+            pgn.result = Some(result.to_string());
+
+            if round > 0 {
+                s.push('\n');
+            }
+            s.push_str(&pgn.to_string());
+        }
+        s
+    }
+}
+
+/// A sequential probability ratio test of whether the first selector
+/// is `elo0` or `elo1` Elo stronger than the second, with error rates
+/// `alpha` (of accepting `elo1` wrongly) and `beta` (of accepting
+/// `elo0` wrongly). The log-likelihood ratio is worked out with the
+/// normal approximation used by most engine testing frameworks.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sprt {
+    /// The Elo difference of the null hypothesis
+    pub elo0: f64,
+    /// The Elo difference of the alternative hypothesis
+    pub elo1: f64,
+    /// The probability of accepting the alternative hypothesis when
+    /// the null hypothesis is true
+    pub alpha: f64,
+    /// The probability of accepting the null hypothesis when the
+    /// alternative hypothesis is true
+    pub beta: f64,
+}
+
+/// The state of an SPRT
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SprtStatus {
+    /// More games are needed to tell
+    Continue,
+    /// The null hypothesis was accepted: the first selector isn't
+    /// `elo1` stronger
+    AcceptH0,
+    /// The alternative hypothesis was accepted: the first selector
+    /// isn't only `elo0` stronger
+    AcceptH1,
+}
+
+impl Sprt {
+    /// Create a test with the common error rates of 5%
+    pub fn new(elo0: f64, elo1: f64) -> Sprt {
+        Sprt {
+            elo0,
+            elo1,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+
+    /// Work out the log-likelihood ratio of the hypotheses given the
+    /// first selector's wins, draws and losses. Half a game is added
+    /// to each of the counts, so that e.g. only winning still gives a
+    /// result.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::engine_match::Sprt;
+    /// let sprt = Sprt::new(0.0, 10.0);
+    ///
+    /// assert!(sprt.llr(60, 20, 40) > 0.0);
+    /// assert!(sprt.llr(40, 20, 60) < 0.0);
+    /// ```
+    pub fn llr(&self, wins: u32, draws: u32, losses: u32) -> f64 {
+        let n = f64::from(wins + draws + losses);
+        if n == 0.0 {
+            return 0.0;
+        }
+        let frequency = |count: u32| (f64::from(count) + 0.5) / (n + 1.5);
+        let (w, d, l) = (frequency(wins), frequency(draws), frequency(losses));
+        let score = w + d / 2.0;
+        let variance = w * (1.0 - score).powi(2) + d * (0.5 - score).powi(2) + l * score.powi(2);
+        let (s0, s1) = (
+            rating::expected_score(self.elo0, 0.0),
+            rating::expected_score(self.elo1, 0.0),
+        );
+        n * (s1 - s0) * (2.0 * score - s0 - s1) / (2.0 * variance)
+    }
+
+    /// Check whether the test can stop given the first selector's
+    /// wins, draws and losses
+    pub fn status(&self, wins: u32, draws: u32, losses: u32) -> SprtStatus {
+        let llr = self.llr(wins, draws, losses);
+        if llr >= ((1.0 - self.beta) / self.alpha).ln() {
+            SprtStatus::AcceptH1
+        } else if llr <= (self.beta / (1.0 - self.alpha)).ln() {
+            SprtStatus::AcceptH0
+        } else {
+            SprtStatus::Continue
+        }
+    }
+}
+
+/// Play a match between `first` and `second`, alternating colors
+/// with `first` playing white in the first game. The match stops
+/// after `config.games` games, or earlier if the SPRT reaches a
+/// decision.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::engine_match::{self, MatchConfig};
+/// # use chess_engine::eval::MaterialEvaluator;
+/// # use chess_engine::selfplay::{RandomSelector, SearchSelector};
+/// # use rand::SeedableRng;
+/// let mut engine = SearchSelector::new(MaterialEvaluator, 1);
+/// let mut random = RandomSelector::new(rand::rngs::StdRng::seed_from_u64(3));
+/// let config = MatchConfig {
+///     games: 2,
+///     max_plies: 40,
+///     ..MatchConfig::default()
+/// };
+/// let report = engine_match::play_match(&mut engine, &mut random, &config);
+///
+/// assert_eq!(report.games.len(), 2);
+/// assert!(report.games[0].first_is_white && !report.games[1].first_is_white);
+/// assert!(report.to_pgn("Test", "engine", "random").contains("[White \"random\"]"));
+/// ```
+pub fn play_match<A, B>(first: &mut A, second: &mut B, config: &MatchConfig) -> MatchReport
+where
+    A: MoveSelector + ?Sized,
+    B: MoveSelector + ?Sized,
+{
+    let mut report = MatchReport {
+        games: vec![],
+        sprt: config.sprt.map(|_| SprtStatus::Continue),
+    };
+
+    for i in 0..config.games {
+        let start = if config.openings.is_empty() {
+            Board::default_board()
+        } else {
+            config.openings[(i as usize / 2) % config.openings.len()]
+        };
+        let first_is_white = i % 2 == 0;
+        let played = if first_is_white {
+            play_game(start, first, second, config)
+        } else {
+            play_game(start, second, first, config)
+        };
+        let (game, winner, termination) = played;
+        report.games.push(MatchGame {
+            game,
+            first_is_white,
+            winner,
+            termination,
+        });
+
+        if let Some(sprt) = config.sprt {
+            let (wins, draws, losses) = report.first_record();
+            let status = sprt.status(wins, draws, losses);
+            report.sprt = Some(status);
+            if status != SprtStatus::Continue {
+                break;
+            }
+        }
+    }
+
+    report
+}
+
+// Play a single game, returning it along with the winner and how it
+// ended
+fn play_game<W, B>(
+    start: Board,
+    white: &mut W,
+    black: &mut B,
+    config: &MatchConfig,
+) -> (Game, Option<Color>, Termination)
+where
+    W: MoveSelector + ?Sized,
+    B: MoveSelector + ?Sized,
+{
+    let mut game = Game::from_board(start);
+    let mut clock = config.time_control.map(Clock::new);
+
+    for _ in 0..config.max_plies {
+        if let GameState::Finished(result) = game.state() {
+            return (game, result.winner(), Termination::Normal);
+        }
+
+        let color = game.next_player();
+        if let Some(clock) = &mut clock {
+            clock.start(color);
+        }
+        let m = match color {
+            Color::White => white.select_move(&game),
+            Color::Black => black.select_move(&game),
+        };
+        if !clock.as_mut().map_or(true, Clock::stop) {
+            return (game, Some(color.opposite()), Termination::TimeForfeit);
+        }
+
+        let m = match m {
+            Some(m) => m,
+            None => return (game, Some(color.opposite()), Termination::Resignation),
+        };
+        if game.make_move(m).is_none() {
+            return (game, Some(color.opposite()), Termination::IllegalMove);
+        }
+    }
+
+    match game.state() {
+        GameState::Finished(GameResult::Win { winner, .. }) => {
+            (game, Some(winner), Termination::Normal)
+        }
+        GameState::Finished(GameResult::Draw(_)) => (game, None, Termination::Normal),
+        GameState::Ongoing { .. } => (game, None, Termination::MoveLimit),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{play_match, MatchConfig, Sprt, SprtStatus, Termination, TimeControl};
+    use crate::board::{Board, Move};
+    use crate::game::Game;
+    use crate::piece::Color;
+    use std::time::Duration;
+
+    fn first_move(game: &Game) -> Option<Move> {
+        let mut moves = game.current_board().get_all_legal_moves();
+        moves.sort();
+        moves.first().copied()
+    }
+
+    #[test]
+    fn resigning_and_illegal_moves_lose() {
+        let config = MatchConfig {
+            games: 2,
+            ..MatchConfig::default()
+        };
+        let mut resign = |_: &Game| None;
+        let mut illegal = |_: &Game| Some("e2e5".parse().unwrap());
+
+        let report = play_match(&mut resign, &mut illegal, &config);
+        assert_eq!(report.games[0].termination, Termination::Resignation);
+        assert_eq!(report.games[0].winner, Some(Color::Black));
+        assert_eq!(report.games[1].termination, Termination::IllegalMove);
+        assert_eq!(report.games[1].winner, Some(Color::Black));
+        assert_eq!(report.first_record(), (1, 0, 1));
+    }
+
+    #[test]
+    fn move_limit_and_openings() {
+        let opening = Board::load_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let config = MatchConfig {
+            games: 4,
+            max_plies: 10,
+            openings: vec![opening],
+            ..MatchConfig::default()
+        };
+        let report = play_match(&mut first_move, &mut first_move, &config);
+
+        assert_eq!(report.first_record(), (0, 4, 0));
+        for game in &report.games {
+            assert_eq!(game.game.get_boards()[0], opening);
+            assert_eq!(game.termination, Termination::MoveLimit);
+            assert_eq!(game.game.get_moves().len(), 10);
+        }
+        assert!(report
+            .to_pgn("Test", "a", "b")
+            .contains("[Termination \"adjudication\"]"));
+    }
+
+    #[test]
+    fn time_forfeit() {
+        let config = MatchConfig {
+            games: 1,
+            time_control: Some(TimeControl {
+                base: Duration::from_millis(5),
+                increment: Duration::from_millis(0),
+            }),
+            ..MatchConfig::default()
+        };
+        let mut slow = |game: &Game| {
+            std::thread::sleep(Duration::from_millis(10));
+            first_move(game)
+        };
+
+        let report = play_match(&mut first_move, &mut slow, &config);
+        assert_eq!(report.games[0].termination, Termination::TimeForfeit);
+        assert_eq!(report.games[0].winner, Some(Color::White));
+        assert_eq!(report.games[0].game.get_moves().len(), 1);
+    }
+
+    #[test]
+    fn sprt_stops_the_match() {
+        let config = MatchConfig {
+            games: 1000,
+            sprt: Some(Sprt::new(0.0, 50.0)),
+            ..MatchConfig::default()
+        };
+        let mut resign = |_: &Game| None;
+
+        let report = play_match(&mut first_move, &mut resign, &config);
+        assert_eq!(report.sprt, Some(SprtStatus::AcceptH1));
+        assert!(report.games.len() < 1000);
+    }
+
+    #[test]
+    fn sprt_decisions() {
+        let sprt = Sprt::new(0.0, 10.0);
+
+        assert!(sprt.llr(0, 0, 0).abs() < f64::EPSILON);
+        assert_eq!(sprt.status(0, 50, 0), SprtStatus::Continue);
+        assert_eq!(sprt.status(600, 0, 400), SprtStatus::AcceptH1);
+        assert_eq!(sprt.status(400, 0, 600), SprtStatus::AcceptH0);
+    }
+}
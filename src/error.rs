@@ -1,11 +1,18 @@
 //! General errors that can happen by the chess engine
+//!
+//! The rules engine itself never does any IO, so its errors are kept
+//! apart in [`RulesError`], and only the subsystems talking to the
+//! outside world, like the Lichess bot, use [`IoError`].
 use crate::board::Move;
-use std::io;
+use crate::piece::Color;
 use thiserror::Error;
 
-/// The general error type
+/// The error type of the rules engine, which is what parsing and
+/// playing moves, positions and games can fail with. New variants can
+/// be added as the engine grows, so matches on it need a wildcard arm.
 #[derive(Error, Debug)]
-pub enum Error {
+#[non_exhaustive]
+pub enum RulesError {
     /// Error for illegal moves
     #[error("The move {1} is illegal for the board {0}")]
     IllegalMove(String, Move),
@@ -18,7 +25,76 @@ pub enum Error {
     /// Error for parsing an invalid piece
     #[error("`{0}` is not a valid piece designator")]
     InvalidPiece(String),
+    /// Error for parsing an invalid color
+    #[error("`{0}` is not a valid color")]
+    InvalidColor(String),
+    /// Error for parsing a move that isn't in valid notation
+    #[error("`{0}` is not a valid move")]
+    InvalidMove(String),
+    /// Error for parsing a move in SAN that is malformed or doesn't
+    /// match exactly one legal move
+    #[error("`{text}` is not a valid SAN move: {reason}")]
+    InvalidSan {
+        /// The notation that was parsed
+        text: String,
+        /// What was wrong with it
+        reason: String,
+    },
+    /// Error for setting up a position that can't occur in a game,
+    /// e.g. one without a king
+    #[error("invalid position: {0}")]
+    InvalidPosition(String),
+    /// Error for trying to play on in a game that is already over
+    #[error("the game is already over")]
+    GameOver,
+    /// Error for a player running out of time
+    #[error("{0:?} ran out of time")]
+    ClockExpired(Color),
+    /// Error for moves or positions breaking the rules of a chess
+    /// variant, beyond the rules of standard chess
+    #[cfg(feature = "variants")]
+    #[error("variant rule violation: {0}")]
+    VariantRuleViolation(String),
+    /// Error for trying to parse erroneous PGN
+    #[error("invalid PGN: {0}")]
+    InvalidPgn(String),
+    /// Error for parsing invalid evaluation parameters
+    #[error("invalid evaluation parameters: {0}")]
+    InvalidParams(String),
+    /// Error for parsing invalid positions to tune the evaluation with
+    #[error("invalid training position: {0}")]
+    InvalidTrainingData(String),
+}
+
+/// The general error type, which is the error of the rules engine
+pub type Error = RulesError;
+
+/// The error type of the subsystems doing IO, which can also fail
+/// with any error of the rules engine
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum IoError {
+    /// Error from the rules engine
+    #[error(transparent)]
+    Rules(#[from] RulesError),
+    /// Error for failed requests to the Lichess API
+    #[cfg(feature = "lichess")]
+    #[error("Lichess API error: {0}")]
+    Lichess(String),
+    /// Error for malformed JSON when importing games or talking to
+    /// online services
+    #[cfg(feature = "interop")]
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// Error for failing to draw a board as an image
+    #[cfg(feature = "render-png")]
+    #[error("failed to render the board: {0}")]
+    Render(String),
+    /// Error from the `SQLite` database of games
+    #[cfg(feature = "db")]
+    #[error(transparent)]
+    Database(#[from] rusqlite::Error),
     /// Error for generic IO errors
     #[error(transparent)]
-    Io(#[from] io::Error),
+    Io(#[from] std::io::Error),
 }
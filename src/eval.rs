@@ -0,0 +1,522 @@
+//! Module containing static evaluation of positions, which is what
+//! the searches in [`crate::search`] use to score the leaves of the
+//! game tree.
+
+use crate::board::{Board, MAX_PHASE};
+use crate::error::Error;
+use crate::piece::{Color, Piece, PieceType};
+use std::fmt;
+use std::str::FromStr;
+
+/// Trait for anything that can statically score a position.
+///
+/// Scores are in centipawns and always from the perspective of the
+/// player to move, so a positive score means the player whose turn
+/// it is stands better.
+pub trait Evaluator {
+    /// Score the given position
+    fn evaluate(&self, board: &Board) -> i32;
+}
+
+/// The simplest possible evaluator, only counting material with the
+/// standard 1/3/3/5/9 piece values.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::Board;
+/// # use chess_engine::eval::{Evaluator, MaterialEvaluator};
+/// let board = Board::load_fen("4k3/8/8/8/8/8/8/3QK3 b - - 0 1").unwrap();
+///
+/// assert_eq!(MaterialEvaluator.evaluate(&Board::default_board()), 0);
+/// assert_eq!(MaterialEvaluator.evaluate(&board), -900);
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MaterialEvaluator;
+
+impl Evaluator for MaterialEvaluator {
+    fn evaluate(&self, board: &Board) -> i32 {
+        board
+            .get_board()
+            .iter()
+            .flatten()
+            .filter_map(|p| *p)
+            .map(|Piece { piece, color }| {
+                let value = piece.value() as i32;
+                if color == board.turn() {
+                    value
+                } else {
+                    -value
+                }
+            })
+            .sum()
+    }
+}
+
+/// An evaluator made up of material values and piece-square tables,
+/// whose parameters can be tuned with [`crate::tuning`].
+///
+/// The parameters are indexed by the piece types in the order of
+/// [`EvalParams::PIECES`]. The piece-square tables hold a bonus for
+/// each square, indexed as `rank * 8 + file` from the point of view of
+/// the piece's owner, so index 0 is a1 for white and a8 for black.
+///
+/// The parameters are written one line per table, and can be read
+/// back with [`str::parse`], to save tuned weights.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::Board;
+/// # use chess_engine::eval::{EvalParams, Evaluator};
+/// let mut params = EvalParams::default();
+/// // a knight on e4 is worth a bit more
+/// params.psts[1][3 * 8 + 4] = 20;
+/// let board = Board::load_fen("4k3/8/8/8/4N3/8/8/4K3 w - - 0 1").unwrap();
+/// assert_eq!(params.evaluate(&board), 320);
+///
+/// let saved = params.to_string();
+/// assert_eq!(saved.parse::<EvalParams>().unwrap(), params);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EvalParams {
+    /// The value of each piece type
+    pub material: [i32; 6],
+    /// The piece-square table of each piece type
+    pub psts: [[i32; 64]; 6],
+}
+
+impl Default for EvalParams {
+    /// The values of [`MaterialEvaluator`], with empty piece-square
+    /// tables
+    fn default() -> Self {
+        EvalParams {
+            material: [100, 300, 300, 500, 900, 0],
+            psts: [[0; 64]; 6],
+        }
+    }
+}
+
+impl EvalParams {
+    /// The order of the piece types in the parameters
+    pub const PIECES: [PieceType; 6] = PieceType::ALL;
+
+    /// The number of parameters, when flattened as in
+    /// [`features`](EvalParams::features)
+    pub const LEN: usize = 6 + 6 * 64;
+
+    /// Get the features of a position, such that the evaluation from
+    /// white's point of view is the sum of each feature's coefficient
+    /// times its parameter. The parameters are flattened with the
+    /// material values first, followed by the piece-square tables.
+    pub fn features(board: &Board) -> Vec<(usize, i32)> {
+        let mut features = vec![];
+        for (rank, row) in board.get_board().iter().enumerate() {
+            for (file, piece) in row.iter().enumerate() {
+                let Piece { piece, color } = match piece {
+                    Some(piece) => *piece,
+                    None => continue,
+                };
+                let sign = match color {
+                    Color::White => 1,
+                    Color::Black => -1,
+                };
+                let rank = color.relative_rank(rank as u32) as usize;
+                let index = piece.index();
+                features.push((index, sign));
+                features.push((6 + index * 64 + rank * 8 + file, sign));
+            }
+        }
+        features
+    }
+
+    /// Get the parameters flattened as in
+    /// [`features`](EvalParams::features)
+    pub fn to_vec(&self) -> Vec<i32> {
+        let mut params = self.material.to_vec();
+        params.extend(self.psts.iter().flatten());
+        params
+    }
+
+    /// Create parameters from a flattened list, as made by
+    /// [`to_vec`](EvalParams::to_vec). Returns [`None`] if there are
+    /// not exactly [`EvalParams::LEN`] parameters.
+    pub fn from_slice(params: &[i32]) -> Option<EvalParams> {
+        if params.len() != EvalParams::LEN {
+            return None;
+        }
+        let mut result = EvalParams {
+            material: [0; 6],
+            psts: [[0; 64]; 6],
+        };
+        result.material.copy_from_slice(&params[..6]);
+        for (pst, chunk) in result.psts.iter_mut().zip(params[6..].chunks(64)) {
+            pst.copy_from_slice(chunk);
+        }
+        Some(result)
+    }
+}
+
+impl EvalParams {
+    // The evaluation from white's point of view, the same as summing
+    // the features times the parameters
+    fn white_score(&self, board: &Board) -> i32 {
+        let mut score = 0;
+        for (rank, row) in board.get_board().iter().enumerate() {
+            for (file, piece) in row.iter().enumerate() {
+                let Piece { piece, color } = match piece {
+                    Some(piece) => *piece,
+                    None => continue,
+                };
+                let rank = color.relative_rank(rank as u32) as usize;
+                let index = piece.index();
+                let value = self.material[index] + self.psts[index][rank * 8 + file];
+                score += match color {
+                    Color::White => value,
+                    Color::Black => -value,
+                };
+            }
+        }
+        score
+    }
+}
+
+impl Evaluator for EvalParams {
+    fn evaluate(&self, board: &Board) -> i32 {
+        let white = self.white_score(board);
+        match board.turn() {
+            Color::White => white,
+            Color::Black => -white,
+        }
+    }
+}
+
+impl fmt::Display for EvalParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "material")?;
+        for value in &self.material {
+            write!(f, " {}", value)?;
+        }
+        writeln!(f)?;
+        for (piece, pst) in EvalParams::PIECES.iter().zip(&self.psts) {
+            write!(f, "{}", piece)?;
+            for value in pst {
+                write!(f, " {}", value)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for EvalParams {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<EvalParams, Error> {
+        let invalid = |reason: &str| Error::InvalidParams(reason.to_string());
+        let mut params = EvalParams {
+            material: [0; 6],
+            psts: [[0; 64]; 6],
+        };
+        let mut seen = [false; 7];
+
+        for line in s.lines().filter(|line| !line.trim().is_empty()) {
+            let mut tokens = line.split_whitespace();
+            let (index, values): (usize, &mut [i32]) = match tokens.next() {
+                Some("material") => (6, &mut params.material),
+                Some(piece) => {
+                    let index = piece.parse::<PieceType>()?.index();
+                    (index, &mut params.psts[index])
+                }
+                None => continue,
+            };
+            let tokens: Vec<_> = tokens.collect();
+            if seen[index] || tokens.len() != values.len() {
+                return Err(invalid(line));
+            }
+            seen[index] = true;
+            for (value, token) in values.iter_mut().zip(tokens) {
+                *value = token.parse().map_err(|_| invalid(token))?;
+            }
+        }
+
+        if seen.contains(&false) {
+            return Err(invalid("missing tables"));
+        }
+        Ok(params)
+    }
+}
+
+/// A tapered evaluator, with separate [`EvalParams`] for the
+/// middlegame and the endgame that are blended by the
+/// [phase](Board::phase_value) of the position, so e.g. the king can
+/// hide in the corner while there are pieces to attack it, and head
+/// for the center once they're traded off. Both sets of parameters
+/// can be tuned together with [`crate::tuning`].
+///
+/// The default parameters are the middlegame and endgame material
+/// values of the PeSTO evaluation function, with the piece-square
+/// tables of Tomasz Michniewski's "Simplified Evaluation Function",
+/// where the king and pawns have their own tables for the endgame.
+/// This plays much better than [`MaterialEvaluator`], and is a good
+/// starting point for tuning.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::Board;
+/// # use chess_engine::eval::{Evaluator, TaperedEval};
+/// let eval = TaperedEval::default();
+/// assert_eq!(eval.evaluate(&Board::default_board()), 0);
+///
+/// // the centralized king is better in the endgame
+/// let central = Board::load_fen("4k3/8/8/8/3K4/8/8/8 w - - 0 1").unwrap();
+/// let corner = Board::load_fen("4k3/8/8/8/8/8/8/K7 w - - 0 1").unwrap();
+/// assert!(eval.evaluate(&central) > eval.evaluate(&corner));
+///
+/// // but not in the middlegame
+/// let central = Board::load_fen("rnbqkbnr/pppppppp/8/8/3K4/8/PPPPPPPP/RNBQ1BNR w kq - 0 1").unwrap();
+/// assert!(eval.evaluate(&central) < eval.evaluate(&Board::default_board()));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TaperedEval {
+    /// The parameters used with all the pieces on the board
+    pub middlegame: EvalParams,
+    /// The parameters used with only kings and pawns left
+    pub endgame: EvalParams,
+}
+
+// The piece-square tables of the default evaluator, as seen from
+// white's side, so the first row is the 8th rank, in the order of
+// `EvalParams::PIECES`, with the endgame tables for the pawn and king
+// last
+#[rustfmt::skip]
+const DEFAULT_PSTS: [[i32; 64]; 8] = [
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+         50,  50,  50,  50,  50,  50,  50,  50,
+         10,  10,  20,  30,  30,  20,  10,  10,
+          5,   5,  10,  25,  25,  10,   5,   5,
+          0,   0,   0,  20,  20,   0,   0,   0,
+          5,  -5, -10,   0,   0, -10,  -5,   5,
+          5,  10,  10, -20, -20,  10,  10,   5,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    [
+        -50, -40, -30, -30, -30, -30, -40, -50,
+        -40, -20,   0,   0,   0,   0, -20, -40,
+        -30,   0,  10,  15,  15,  10,   0, -30,
+        -30,   5,  15,  20,  20,  15,   5, -30,
+        -30,   0,  15,  20,  20,  15,   0, -30,
+        -30,   5,  10,  15,  15,  10,   5, -30,
+        -40, -20,   0,   5,   5,   0, -20, -40,
+        -50, -40, -30, -30, -30, -30, -40, -50,
+    ],
+    [
+        -20, -10, -10, -10, -10, -10, -10, -20,
+        -10,   0,   0,   0,   0,   0,   0, -10,
+        -10,   0,   5,  10,  10,   5,   0, -10,
+        -10,   5,   5,  10,  10,   5,   5, -10,
+        -10,   0,  10,  10,  10,  10,   0, -10,
+        -10,  10,  10,  10,  10,  10,  10, -10,
+        -10,   5,   0,   0,   0,   0,   5, -10,
+        -20, -10, -10, -10, -10, -10, -10, -20,
+    ],
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+          5,  10,  10,  10,  10,  10,  10,   5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+          0,   0,   0,   5,   5,   0,   0,   0,
+    ],
+    [
+        -20, -10, -10,  -5,  -5, -10, -10, -20,
+        -10,   0,   0,   0,   0,   0,   0, -10,
+        -10,   0,   5,   5,   5,   5,   0, -10,
+         -5,   0,   5,   5,   5,   5,   0,  -5,
+          0,   0,   5,   5,   5,   5,   0,  -5,
+        -10,   5,   5,   5,   5,   5,   0, -10,
+        -10,   0,   5,   0,   0,   0,   0, -10,
+        -20, -10, -10,  -5,  -5, -10, -10, -20,
+    ],
+    [
+        -30, -40, -40, -50, -50, -40, -40, -30,
+        -30, -40, -40, -50, -50, -40, -40, -30,
+        -30, -40, -40, -50, -50, -40, -40, -30,
+        -30, -40, -40, -50, -50, -40, -40, -30,
+        -20, -30, -30, -40, -40, -30, -30, -20,
+        -10, -20, -20, -20, -20, -20, -20, -10,
+         20,  20,   0,   0,   0,   0,  20,  20,
+         20,  30,  10,   0,   0,  10,  30,  20,
+    ],
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+        100, 100, 100, 100, 100, 100, 100, 100,
+         60,  60,  60,  60,  60,  60,  60,  60,
+         35,  35,  35,  35,  35,  35,  35,  35,
+         20,  20,  20,  20,  20,  20,  20,  20,
+         10,  10,  10,  10,  10,  10,  10,  10,
+          0,   0,   0,   0,   0,   0,   0,   0,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    [
+        -50, -40, -30, -20, -20, -30, -40, -50,
+        -30, -20, -10,   0,   0, -10, -20, -30,
+        -30, -10,  20,  30,  30,  20, -10, -30,
+        -30, -10,  30,  40,  40,  30, -10, -30,
+        -30, -10,  30,  40,  40,  30, -10, -30,
+        -30, -10,  20,  30,  30,  20, -10, -30,
+        -30, -30,   0,   0,   0,   0, -30, -30,
+        -50, -30, -30, -30, -30, -30, -30, -50,
+    ],
+];
+
+// Turn a table as written in `DEFAULT_PSTS` into one indexed by
+// `rank * 8 + file`
+fn from_white_side(table: &[i32; 64]) -> [i32; 64] {
+    let mut result = [0; 64];
+    for (rank, row) in table.chunks(8).rev().enumerate() {
+        result[rank * 8..rank * 8 + 8].copy_from_slice(row);
+    }
+    result
+}
+
+impl Default for TaperedEval {
+    fn default() -> Self {
+        let mut middlegame = EvalParams {
+            material: [82, 337, 365, 477, 1025, 0],
+            psts: [[0; 64]; 6],
+        };
+        for (pst, table) in middlegame.psts.iter_mut().zip(&DEFAULT_PSTS) {
+            *pst = from_white_side(table);
+        }
+        let mut endgame = EvalParams {
+            material: [94, 281, 297, 512, 936, 0],
+            ..middlegame
+        };
+        endgame.psts[0] = from_white_side(&DEFAULT_PSTS[6]);
+        endgame.psts[5] = from_white_side(&DEFAULT_PSTS[7]);
+        TaperedEval {
+            middlegame,
+            endgame,
+        }
+    }
+}
+
+impl TaperedEval {
+    /// The number of parameters, when flattened as in
+    /// [`to_vec`](TaperedEval::to_vec)
+    pub const LEN: usize = 2 * EvalParams::LEN;
+
+    /// Get the parameters flattened, with the middlegame parameters
+    /// followed by the endgame ones, each flattened as in
+    /// [`EvalParams::to_vec`]
+    pub fn to_vec(&self) -> Vec<i32> {
+        let mut params = self.middlegame.to_vec();
+        params.extend(self.endgame.to_vec());
+        params
+    }
+
+    /// Create parameters from a flattened list, as made by
+    /// [`to_vec`](TaperedEval::to_vec). Returns [`None`] if there are
+    /// not exactly [`TaperedEval::LEN`] parameters.
+    pub fn from_slice(params: &[i32]) -> Option<TaperedEval> {
+        if params.len() != TaperedEval::LEN {
+            return None;
+        }
+        let (middlegame, endgame) = params.split_at(EvalParams::LEN);
+        Some(TaperedEval {
+            middlegame: EvalParams::from_slice(middlegame)?,
+            endgame: EvalParams::from_slice(endgame)?,
+        })
+    }
+}
+
+impl Evaluator for TaperedEval {
+    fn evaluate(&self, board: &Board) -> i32 {
+        let phase = board.phase_value() as i32;
+        let max = MAX_PHASE as i32;
+        let white = (self.middlegame.white_score(board) * phase
+            + self.endgame.white_score(board) * (max - phase))
+            / max;
+        match board.turn() {
+            Color::White => white,
+            Color::Black => -white,
+        }
+    }
+}
+
+/// The score in centipawns that [`logistic`] maps to odds of 10 to 1
+/// with a `k` of 1, like a difference of 400 Elo points
+pub const LOGISTIC_SCALE: f64 = 400.0;
+
+/// Map a score in centipawns to a number between 0 and 1 with a
+/// logistic function, where `k` scales how much a centipawn is worth.
+/// With a `k` of 1, a score of 400 maps to about 0.91. This is the
+/// curve both [`win_probability`] and the tuning of evaluation
+/// parameters turn scores into results with.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::eval;
+/// assert_eq!(eval::logistic(0.0, 1.0), 0.5);
+/// assert!((eval::logistic(400.0, 1.0) - 10.0 / 11.0).abs() < 1e-9);
+/// ```
+pub fn logistic(score: f64, k: f64) -> f64 {
+    1.0 / (1.0 + 10_f64.powf(-k * score / LOGISTIC_SCALE))
+}
+
+// The score at which the player to move wins half of the time, at the
+// start of the game and from `WDL_ENDGAME_PLY` on, as advantages get
+// easier to convert once the board empties
+const WDL_MIDPOINT_OPENING: f64 = 200.0;
+const WDL_MIDPOINT_ENDGAME: f64 = 120.0;
+const WDL_ENDGAME_PLY: u32 = 160;
+
+// How quickly the win probability rises around the midpoint, as the
+// `k` of the logistic function
+const WDL_K: f64 = 2.9;
+
+/// Get the probability of winning with a score of `score` centipawns
+/// at ply `ply` of a game, with a logistic model: the probability is
+/// 50% at a score of about two pawns early in the game, and at a bit
+/// over a pawn later on. Draws make up the rest of the probability
+/// that isn't either player's win, so a score of 0 is almost always a
+/// draw.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::eval;
+/// assert!(eval::win_probability(0, 0) < 0.05);
+/// assert!(eval::win_probability(200, 0) > 0.49);
+/// assert!(eval::win_probability(500, 0) > 0.99);
+/// // the same advantage is worth more later in the game
+/// assert!(eval::win_probability(150, 100) > eval::win_probability(150, 0));
+/// ```
+#[allow(clippy::cast_possible_truncation)]
+pub fn win_probability(score: i32, ply: u32) -> f32 {
+    let progress = f64::from(ply.min(WDL_ENDGAME_PLY)) / f64::from(WDL_ENDGAME_PLY);
+    let midpoint = WDL_MIDPOINT_OPENING + (WDL_MIDPOINT_ENDGAME - WDL_MIDPOINT_OPENING) * progress;
+    logistic(f64::from(score) - midpoint, WDL_K) as f32
+}
+
+/// Get the expected score with a score of `score` centipawns at ply
+/// `ply`, i.e. the probability of winning plus half of the probability
+/// of a draw, as given by [`win_probability`]. This is what
+/// "expected score" bars show, and is always between 0 and 1.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::eval;
+/// assert_eq!(eval::expected_score(0, 20), 0.5);
+/// assert!((eval::expected_score(300, 20) + eval::expected_score(-300, 20) - 1.0).abs() < 1e-6);
+/// ```
+pub fn expected_score(score: i32, ply: u32) -> f32 {
+    0.5 + (win_probability(score, ply) - win_probability(-score, ply)) / 2.0
+}
@@ -0,0 +1,90 @@
+//! Module for reading and writing many positions in FEN at once, as
+//! dataset tooling does. Parsing doesn't allocate for valid FEN, and
+//! writing goes straight into a buffer the caller provides, so the
+//! same buffer can be reused for every position.
+//!
+//! # Examples
+//!
+//! ```
+//! # use chess_engine::fen;
+//! let input = "8/8/8/8/8/8/8/K6k w - - 0 1\nnot a fen\n4k3/8/8/8/8/8/8/4K3 b - - 5 40";
+//! let boards: Vec<_> = fen::parse_many(input.lines()).collect();
+//!
+//! assert_eq!(boards.len(), 3);
+//! assert!(boards[1].is_err());
+//!
+//! let mut out = String::new();
+//! for board in boards.iter().flatten() {
+//!     fen::write(board, &mut out).unwrap();
+//!     out.push('\n');
+//! }
+//! assert_eq!(out, "8/8/8/8/8/8/8/K6k w - - 0 1\n4k3/8/8/8/8/8/8/4K3 b - - 5 40\n");
+//! ```
+
+use crate::board::Board;
+use crate::error::Error;
+use std::fmt;
+
+/// Parse a position from FEN, the same as [`Board::load_fen`]
+///
+/// # Errors
+///
+/// Will return an error if the string is not valid FEN
+pub fn parse(s: &str) -> Result<Board, Error> {
+    Board::load_fen(s)
+}
+
+/// Parse positions from FEN, one for each string, lazily and in
+/// order. Invalid FEN gives an error for that string only, so the
+/// rest can still be read.
+pub fn parse_many<'a, I>(fens: I) -> impl Iterator<Item = Result<Board, Error>> + 'a
+where
+    I: IntoIterator<Item = &'a str>,
+    I::IntoIter: 'a,
+{
+    fens.into_iter().map(parse)
+}
+
+/// Write a position as FEN to `out`, the same as
+/// [`Board::write_fen`]
+///
+/// # Errors
+///
+/// Will only return an error if `out` does
+pub fn write<W: fmt::Write + ?Sized>(board: &Board, out: &mut W) -> fmt::Result {
+    board.write_fen(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, write};
+
+    #[test]
+    fn round_trips() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R w Kq - 12 30",
+            "8/8/8/8/8/8/8/K6k w - - 0 1",
+        ];
+        let mut out = String::new();
+        for fen in &fens {
+            out.clear();
+            write(&parse(fen).unwrap(), &mut out).unwrap();
+            assert_eq!(&out, fen);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_placements() {
+        let bad = [
+            "8/8/8/8/8/8/8/8/8 w - - 0 1",
+            "9/8/8/8/8/8/8/8 w - - 0 1",
+            "ppppppppp/8/8/8/8/8/8/8 w - - 0 1",
+            "8/8/8/8/8/8/8/7 w - - 0 1",
+            "8/8/8/8/8/8/8/4x3 w - - 0 1",
+        ];
+        for fen in &bad {
+            assert!(parse(fen).is_err(), "{}", fen);
+        }
+    }
+}
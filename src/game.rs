@@ -1,20 +1,289 @@
 //! Module containing the [`Game`] type, the main way for an application
 //! to create and run a chess game.
 
-use crate::board::{Board, Move};
-use crate::piece::Color;
+use crate::board::{Board, BoardSnapshot, Move, MoveInfo, MoveOutcome};
+use crate::clock::{Clock, ClockSnapshot};
+use crate::error::Error;
+use crate::piece::{Color, PieceType};
+use crate::player::{PlayOutcome, Player, PlayerAction};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 
 /// The struct representing a chess game, starting in the default
 /// position with white going first.
 #[derive(Debug, Clone)]
 pub struct Game {
+    initial: Board,
+    // the boards from ply `first_ply` up to the current one, which is
+    // all of them unless the history is compact
     boards: Vec<Board>,
+    first_ply: usize,
     moves: Vec<Move>,
-    board_state: BoardState,
+    state: GameState,
+    auto_promotion: Option<PieceType>,
+    history: HistoryMode,
+    // the conditional move lines, each starting with a move of the
+    // player to move, and the player whose replies are played
+    // automatically
+    conditionals: Vec<Vec<Move>>,
+    responder: Color,
+}
+
+/// How a [`Game`] stores the boards of its history
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::game::{Game, HistoryMode};
+/// let mut game = Game::new();
+/// game.set_history_mode(HistoryMode::Compact { window: 2 });
+/// for m in &["e2e4", "e7e5", "g1f3", "b8c6"] {
+///     game.make_move(m.parse().unwrap());
+/// }
+///
+/// // only the last two boards are kept, the others are replayed
+/// assert_eq!(game.retained_boards().len(), 2);
+/// assert_eq!(game.boards().count(), 5);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HistoryMode {
+    /// Keep every board of the game, so they can all be borrowed with
+    /// [`Game::get_boards`]. This is the default.
+    Full,
+    /// Keep only the initial board, the moves, and a window of the
+    /// last `window` boards (at least 2). Older boards are rebuilt by
+    /// replaying the moves when needed, which saves memory for long
+    /// games and large collections of games. [`Game::get_boards`]
+    /// panics once boards have been dropped, see
+    /// [`Game::retained_boards`] and [`Game::boards`] instead.
+    Compact {
+        /// How many of the last boards to keep
+        window: usize,
+    },
+}
+
+/// The state of a game, either still being played or finished with
+/// some result.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::game::{DrawReason, Game, GameResult, GameState};
+/// # use chess_engine::board::Board;
+/// let game = Game::new();
+/// assert_eq!(game.state(), GameState::Ongoing { check: false });
+///
+/// let stalemate = Board::load_fen("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1").unwrap();
+/// assert_eq!(
+///     Game::from_board(stalemate).state(),
+///     GameState::Finished(GameResult::Draw(DrawReason::Stalemate))
+/// );
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameState {
+    /// The game is still being played
+    Ongoing {
+        /// Whether the player to move is in check
+        check: bool,
+    },
+    /// The game is over
+    Finished(GameResult),
+}
+
+impl GameState {
+    /// Whether the game is over
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, GameState::Finished(_))
+    }
+
+    /// Get the player who won the game, or [`None`] if it was drawn or
+    /// isn't over yet
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// # use chess_engine::game::Game;
+    /// # use chess_engine::piece::Color;
+    /// // black is checkmated, so white won
+    /// let board = Board::load_fen("R3k3/8/4K3/8/8/8/8/8 b - - 0 1").unwrap();
+    /// let state = Game::from_board(board).state();
+    ///
+    /// assert!(state.is_terminal());
+    /// assert_eq!(state.winner(), Some(Color::White));
+    /// assert_eq!(Game::new().state().winner(), None);
+    /// ```
+    pub fn winner(&self) -> Option<Color> {
+        match self {
+            GameState::Finished(result) => result.winner(),
+            GameState::Ongoing { .. } => None,
+        }
+    }
+}
+
+/// How a game ended
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameResult {
+    /// One of the players won
+    Win {
+        /// The player who won
+        winner: Color,
+        /// Why the player won
+        reason: WinReason,
+    },
+    /// The game was drawn
+    Draw(DrawReason),
+}
+
+/// The reasons a game can be won
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum WinReason {
+    /// The loser was checkmated
+    Checkmate,
+    /// The loser resigned
+    Resignation,
+    /// The loser ran out of time
+    TimeForfeit,
+    /// The loser abandoned the game, e.g. by disconnecting from the
+    /// server
+    Abandoned,
+    /// An arbiter decided the game, e.g. as the position was clearly
+    /// won
+    Adjudication,
+}
+
+/// The reasons a game can be drawn
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum DrawReason {
+    /// The player to move had no legal moves, but wasn't in check
+    Stalemate,
+    /// Fifty moves were played without a capture or pawn move
+    FiftyMoveRule,
+    /// The players agreed to a draw
+    Agreement,
+    /// An arbiter decided the game was drawn, e.g. as the game reached
+    /// a move limit
+    Adjudication,
+}
+
+impl GameResult {
+    /// Get the player who won, or [`None`] for a draw
+    pub fn winner(&self) -> Option<Color> {
+        match *self {
+            GameResult::Win { winner, .. } => Some(winner),
+            GameResult::Draw(_) => None,
+        }
+    }
+
+    /// Get why the game was drawn, or [`None`] if a player won
+    pub fn draw_reason(&self) -> Option<DrawReason> {
+        match *self {
+            GameResult::Draw(reason) => Some(reason),
+            GameResult::Win { .. } => None,
+        }
+    }
+
+    /// Get the value of the PGN `Termination` tag for a game that
+    /// ended with this result, which is `normal` for games ending by
+    /// the rules or by a player's decision
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::game::{DrawReason, GameResult, WinReason};
+    /// # use chess_engine::piece::Color;
+    /// let flagged = GameResult::Win {
+    ///     winner: Color::Black,
+    ///     reason: WinReason::TimeForfeit,
+    /// };
+    ///
+    /// assert_eq!(flagged.termination(), "time forfeit");
+    /// assert_eq!(GameResult::Draw(DrawReason::Agreement).termination(), "normal");
+    /// ```
+    pub fn termination(&self) -> &'static str {
+        match self {
+            GameResult::Win {
+                reason: WinReason::TimeForfeit,
+                ..
+            } => "time forfeit",
+            GameResult::Win {
+                reason: WinReason::Abandoned,
+                ..
+            } => "abandoned",
+            GameResult::Win {
+                reason: WinReason::Adjudication,
+                ..
+            }
+            | GameResult::Draw(DrawReason::Adjudication) => "adjudication",
+            _ => "normal",
+        }
+    }
+}
+
+/// Everything about a [`Game`] in one plain, serializable value, so
+/// in-progress games can be stored, e.g. by a server that needs to
+/// survive restarts, and picked up again with [`Game::restore`].
+///
+/// A game doesn't own a clock, so [`Game::snapshot`] leaves `clock`
+/// empty. Games played with one are stored with
+/// [`Game::snapshot_with_clock`] and picked up again along with their
+/// clock with [`Game::restore_with_clock`].
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::clock::{Clock, TimeControl};
+/// # use chess_engine::game::Game;
+/// # use std::time::Duration;
+/// let mut game = Game::new();
+/// game.make_move("e2e4".parse().unwrap());
+/// let clock = Clock::new(TimeControl {
+///     base: Duration::from_secs(300),
+///     increment: Duration::from_secs(3),
+/// });
+///
+/// let snapshot = game.snapshot_with_clock(&clock);
+///
+/// let (restored, restored_clock) = Game::restore_with_clock(snapshot).unwrap();
+/// assert_eq!(restored.current_board(), game.current_board());
+/// assert_eq!(restored_clock, Some(clock));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameSnapshot {
+    /// The position the game started from
+    pub initial: BoardSnapshot,
+    /// The moves played so far
+    pub moves: Vec<Move>,
+    /// The state of the game, which may be a result decided outside
+    /// of the board by [`Game::adjudicate`]
+    pub state: GameState,
+    /// The piece pawns are promoted to automatically, see
+    /// [`Game::set_auto_promotion`]
+    pub auto_promotion: Option<PieceType>,
+    /// How the game stores the boards of its history
+    pub history: HistoryMode,
+    /// The conditional move lines still being followed, see
+    /// [`Game::add_conditional_moves`]
+    pub conditionals: Vec<Vec<Move>>,
+    /// The player whose replies the conditional move lines play
+    pub responder: Color,
+    /// The clock the game is played with, if any
+    pub clock: Option<ClockSnapshot>,
 }
 
 /// Enum to represent the various different board states, most
 /// importantly the final states.
+#[deprecated(note = "use `GameState`, which tells who won and why the game ended")]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum BoardState {
     /// The game is in a normal state, and you can play as normal
@@ -30,19 +299,103 @@ pub enum BoardState {
     Stalemate,
 }
 
+#[allow(deprecated)]
+impl BoardState {
+    /// Whether the game is over, by checkmate, stalemate or a draw
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            BoardState::Checkmate | BoardState::Draw | BoardState::Stalemate
+        )
+    }
+
+    /// Get the player who won, given the player to move `turn`, or
+    /// [`None`] if the game was drawn or isn't over. Checkmate means
+    /// the player to move lost, so the winner is the other player.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(deprecated)]
+    /// # use chess_engine::board::Board;
+    /// # use chess_engine::game::{BoardState, Game};
+    /// # use chess_engine::piece::Color;
+    /// let board = Board::load_fen("R3k3/8/4K3/8/8/8/8/8 b - - 0 1").unwrap();
+    /// let game = Game::from_board(board);
+    ///
+    /// assert_eq!(game.board_state(), BoardState::Checkmate);
+    /// assert_eq!(game.board_state().winner(game.next_player()), Some(Color::White));
+    /// ```
+    pub fn winner(self, turn: Color) -> Option<Color> {
+        match self {
+            BoardState::Checkmate => Some(turn.opposite()),
+            _ => None,
+        }
+    }
+}
+
 impl Game {
     /// Create a new board initialised to the default chess position
     pub fn new() -> Self {
         Self {
+            initial: Board::default_board(),
             boards: vec![Board::default_board()],
+            first_ply: 0,
             moves: vec![],
-            board_state: BoardState::Normal,
+            state: GameState::Ongoing { check: false },
+            auto_promotion: None,
+            history: HistoryMode::Full,
+            conditionals: vec![],
+            responder: Color::White,
         }
     }
 
+    /// Create a new game starting from the given position
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// # use chess_engine::game::{Game, GameResult, GameState, WinReason};
+    /// # use chess_engine::piece::Color;
+    /// let board = Board::load_fen("R3k3/8/4K3/8/8/8/8/8 b - - 0 1").unwrap();
+    /// let game = Game::from_board(board);
+    ///
+    /// assert_eq!(game.current_board(), &board);
+    /// assert_eq!(
+    ///     game.state(),
+    ///     GameState::Finished(GameResult::Win {
+    ///         winner: Color::White,
+    ///         reason: WinReason::Checkmate
+    ///     })
+    /// );
+    /// ```
+    pub fn from_board(board: Board) -> Self {
+        let mut game = Self {
+            initial: board,
+            boards: vec![board],
+            ..Self::new()
+        };
+        game.update_state();
+        game
+    }
+
+    /// Get the current state of the game
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
     /// Get the current board state
+    #[deprecated(note = "use `Game::state`")]
+    #[allow(deprecated)]
     pub fn board_state(&self) -> BoardState {
-        self.board_state
+        match self.state {
+            GameState::Ongoing { check: false } => BoardState::Normal,
+            GameState::Ongoing { check: true } => BoardState::Check,
+            GameState::Finished(GameResult::Win { .. }) => BoardState::Checkmate,
+            GameState::Finished(GameResult::Draw(DrawReason::Stalemate)) => BoardState::Stalemate,
+            GameState::Finished(GameResult::Draw(_)) => BoardState::Draw,
+        }
     }
 
     /// Get a list of all boards so far
@@ -57,46 +410,529 @@ impl Game {
     ///
     /// assert_eq!(game.get_boards(), &[default]);
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the game has a [compact history](HistoryMode::Compact)
+    /// that has dropped boards already, as they aren't there to borrow.
+    /// Use [`Game::boards`] to replay all of them, or
+    /// [`Game::retained_boards`] for the ones that are kept.
     pub fn get_boards(&self) -> &[Board] {
+        assert!(
+            self.first_ply == 0,
+            "the boards before ply {} have been dropped by the compact history",
+            self.first_ply
+        );
         &self.boards[..]
     }
 
+    /// Get the boards the game keeps, which are the last ones up to
+    /// the current board. With a [full history](HistoryMode::Full)
+    /// that's all of them, like [`Game::get_boards`], and with a
+    /// [compact history](HistoryMode::Compact) the ones in its window.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::game::{Game, HistoryMode};
+    /// let mut game = Game::new();
+    /// game.set_history_mode(HistoryMode::Compact { window: 2 });
+    /// for m in &["e2e4", "e7e5", "g1f3"] {
+    ///     game.make_move(m.parse().unwrap());
+    /// }
+    ///
+    /// assert_eq!(game.retained_boards().len(), 2);
+    /// assert_eq!(game.retained_boards().last(), Some(game.current_board()));
+    /// ```
+    pub fn retained_boards(&self) -> &[Board] {
+        &self.boards[..]
+    }
+
+    /// Iterate over all boards so far, from the initial board to the
+    /// current one, replaying moves for the boards that aren't kept
+    /// with a [compact history](HistoryMode::Compact).
+    pub fn boards(&self) -> impl Iterator<Item = Board> + '_ {
+        let replayed = self.moves[..self.first_ply]
+            .iter()
+            .scan(self.initial, |board, &m| {
+                let current = *board;
+                *board = board.perform_move_unvalidated(m);
+                Some(current)
+            });
+        replayed.chain(self.boards.iter().copied())
+    }
+
+    /// Get the board after `ply` half-moves, where ply 0 is the initial
+    /// board and ply `get_moves().len()` is the current one. Returns
+    /// [`None`] if the game hasn't reached that ply. The board is
+    /// returned by value, as it may have to be replayed with a
+    /// [compact history](HistoryMode::Compact).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::game::Game;
+    /// # use chess_engine::board::Board;
+    /// let mut game = Game::new();
+    /// game.make_move("e2e4".parse().unwrap());
+    ///
+    /// assert_eq!(game.board_at(0), Some(Board::default_board()));
+    /// assert_eq!(game.board_at(1).as_ref(), Some(game.current_board()));
+    /// assert_eq!(game.board_at(2), None);
+    /// ```
+    pub fn board_at(&self, ply: usize) -> Option<Board> {
+        if ply >= self.first_ply {
+            return self.boards.get(ply - self.first_ply).copied();
+        }
+        Some(
+            self.moves[..ply]
+                .iter()
+                .fold(self.initial, |board, &m| board.perform_move_unvalidated(m)),
+        )
+    }
+
+    /// Get the FEN of the board after `ply` half-moves, as for
+    /// [`Game::board_at`]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::game::Game;
+    /// let mut game = Game::new();
+    /// game.make_move("e2e4".parse().unwrap());
+    ///
+    /// assert_eq!(
+    ///     game.fen_at(1).unwrap(),
+    ///     "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+    /// );
+    /// assert_eq!(game.fen_at(2), None);
+    /// ```
+    pub fn fen_at(&self, ply: usize) -> Option<String> {
+        self.board_at(ply).map(|board| board.to_fen())
+    }
+
+    /// Get how the game stores the boards of its history
+    pub fn history_mode(&self) -> HistoryMode {
+        self.history
+    }
+
+    /// Set how the game stores the boards of its history, rebuilding
+    /// or dropping boards as needed
+    pub fn set_history_mode(&mut self, mode: HistoryMode) {
+        self.history = mode;
+        match mode {
+            HistoryMode::Full => {
+                self.boards = self.boards().collect();
+                self.first_ply = 0;
+            }
+            HistoryMode::Compact { .. } => self.trim_history(),
+        }
+    }
+
+    // Drop the boards that have fallen out of a compact history's
+    // window
+    fn trim_history(&mut self) {
+        if let HistoryMode::Compact { window } = self.history {
+            let excess = self.boards.len().saturating_sub(window.max(2));
+            let _ = self.boards.drain(..excess);
+            self.first_ply += excess;
+        }
+    }
+
     /// Get a list of all moves so far
     pub fn get_moves(&self) -> &[Move] {
         &self.moves[..]
     }
 
+    /// Get how many half-moves have been played in the game
+    pub fn ply_count(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// Get the fullmove number of the current position, which is the
+    /// number the next move is written with
+    pub fn fullmove_number(&self) -> u32 {
+        self.current_board().fullmove()
+    }
+
+    /// Get the move number and color of the move played from the board
+    /// after `ply` half-moves, taking into account the fullmove number
+    /// and turn of the initial board. This also works for plies the
+    /// game hasn't reached yet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// # use chess_engine::game::Game;
+    /// # use chess_engine::piece::Color;
+    /// let game = Game::new();
+    /// assert_eq!(game.move_number_of_ply(0), (1, Color::White));
+    /// assert_eq!(game.move_number_of_ply(3), (2, Color::Black));
+    ///
+    /// let board = Board::load_fen("4k3/8/8/8/8/8/8/4K3 b - - 0 30").unwrap();
+    /// let game = Game::from_board(board);
+    /// assert_eq!(game.move_number_of_ply(0), (30, Color::Black));
+    /// assert_eq!(game.move_number_of_ply(1), (31, Color::White));
+    /// ```
+    pub fn move_number_of_ply(&self, ply: usize) -> (u32, Color) {
+        let offset = ply + usize::from(self.initial.turn() == Color::Black);
+        let color = match offset % 2 {
+            0 => Color::White,
+            _ => Color::Black,
+        };
+        (self.initial.fullmove() + (offset / 2) as u32, color)
+    }
+
+    /// Get the ply of the board the move with the given number and
+    /// color is played from, the inverse of [`Game::move_number_of_ply`].
+    /// Returns [`None`] if the move would come before the initial board.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::game::Game;
+    /// # use chess_engine::piece::Color;
+    /// let game = Game::new();
+    ///
+    /// assert_eq!(game.ply_of_move(2, Color::Black), Some(3));
+    /// assert_eq!(game.ply_of_move(0, Color::Black), None);
+    /// ```
+    pub fn ply_of_move(&self, number: u32, color: Color) -> Option<usize> {
+        let moves = number.checked_sub(self.initial.fullmove())? as usize;
+        let offset = 2 * moves + usize::from(color == Color::Black);
+        offset.checked_sub(usize::from(self.initial.turn() == Color::Black))
+    }
+
+    /// Get the last move played along with some information about
+    /// it, such as whether it was a capture or gave check. Returns
+    /// [`None`] if no moves have been played yet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::board::Move;
+    /// # use chess_engine::game::Game;
+    /// let mut game = Game::new();
+    /// assert!(game.last_move().is_none());
+    ///
+    /// let e4 = Move::Normal {
+    ///     from: "e2".parse().unwrap(),
+    ///     to: "e4".parse().unwrap(),
+    /// };
+    /// game.make_move(e4);
+    /// let (m, info) = game.last_move().unwrap();
+    ///
+    /// assert_eq!(m, e4);
+    /// assert!(!info.is_capture() && !info.check);
+    /// ```
+    pub fn last_move(&self) -> Option<(Move, MoveInfo)> {
+        let m = *self.moves.last()?;
+        // the window of a compact history always holds at least the
+        // last two boards
+        let n = self.boards.len();
+        Some((
+            m,
+            MoveInfo::new(&self.boards[n - 2], m, &self.boards[n - 1]),
+        ))
+    }
+
+    /// Set which piece pawns should automatically be promoted to, or
+    /// [`None`] to require an explicit [`Move::Promotion`]. When set,
+    /// a [`Move::Normal`] moving a pawn to the last rank is turned
+    /// into a promotion to the given piece by [`Game::make_move`].
+    /// The piece should be one of [`PieceType::PROMOTION_TARGETS`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::board::Move;
+    /// # use chess_engine::game::Game;
+    /// # use chess_engine::piece::PieceType;
+    /// let mut game = Game::new();
+    /// game.set_auto_promotion(Some(PieceType::Queen));
+    ///
+    /// assert_eq!(game.auto_promotion(), Some(PieceType::Queen));
+    /// ```
+    pub fn set_auto_promotion(&mut self, piece: Option<PieceType>) {
+        self.auto_promotion = piece;
+    }
+
+    /// Get which piece pawns are automatically promoted to, see
+    /// [`Game::set_auto_promotion`]
+    pub fn auto_promotion(&self) -> Option<PieceType> {
+        self.auto_promotion
+    }
+
     /// Make a move, if it is legal, returns a reference to the new
     /// board.  If the move was illegal, [None] is returned
     pub fn make_move(&mut self, next_move: Move) -> Option<&Board> {
-        match self.board_state {
-            BoardState::Draw | BoardState::Stalemate | BoardState::Checkmate => return None,
-            _ => (),
+        if let GameState::Finished(_) = self.state {
+            return None;
         }
 
         let last_board = self.boards[self.boards.len() - 1];
-        let next_board = match last_board.perform_move(next_move) {
-            Some(board) => board,
-            None => return None,
+        let next_move = match (next_move, self.auto_promotion) {
+            (Move::Normal { from, to }, Some(target))
+                if to.rank == last_board.size().promotion_rank(last_board.turn())
+                    && matches!(last_board[from], Some(p) if p.piece == PieceType::Pawn) =>
+            {
+                Move::Promotion { from, to, target }
+            }
+            _ => next_move,
+        };
+        let Some(outcome) = last_board.perform_move_with_outcome(next_move) else {
+            return None;
         };
-        self.boards.push(next_board);
+        self.boards.push(outcome.board);
         self.moves.push(next_move);
-        self.update_boardstate();
+        self.trim_history();
+        self.set_state(outcome);
+        self.follow_conditionals(next_move);
         Some(&self.boards[self.boards.len() - 1])
     }
 
-    fn update_boardstate(&mut self) {
-        let board = self.current_board();
-        let legal_moves = self.get_all_legal_moves();
-        if legal_moves.is_empty() && board.in_check() {
-            self.board_state = BoardState::Checkmate;
-        } else if legal_moves.is_empty() {
-            self.board_state = BoardState::Stalemate;
-        } else if board.in_check() {
-            self.board_state = BoardState::Check;
-        } else if board.halfmove() == 50 {
-            self.board_state = BoardState::Draw;
+    /// Add a conditional move line, as used in correspondence chess:
+    /// the moves alternate between the player to move and their
+    /// opponent, starting with the former, and each move of the
+    /// opponent is played automatically as a reply when the player
+    /// to move has played the move before it. For example, with
+    /// black to move, `[e7e5, g1f3, b8c6, f1b5]` means "if black
+    /// plays e5, reply Nf3, and if black then plays Nc6, reply Bb5".
+    ///
+    /// Lines are dropped as soon as a move is played that doesn't
+    /// follow them, or a move is undone. If several lines expect the
+    /// same move, the reply of the one added first is played.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the game is over, or if any move of the
+    /// line is illegal, in which case the line isn't added
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::game::Game;
+    /// let mut game = Game::new();
+    /// game.make_move("e2e4".parse().unwrap());
+    /// let line = ["e7e5", "g1f3", "b8c6", "f1b5"].map(|m| m.parse().unwrap());
+    /// game.add_conditional_moves(&line).unwrap();
+    ///
+    /// game.make_move("e7e5".parse().unwrap());
+    /// // white replied straight away
+    /// assert_eq!(game.get_moves().last(), Some(&line[1]));
+    ///
+    /// game.make_move("g8f6".parse().unwrap());
+    /// assert_eq!(game.get_moves().len(), 4);
+    /// assert!(game.conditional_moves().is_empty());
+    ///
+    /// assert!(game.add_conditional_moves(&["e1e3".parse().unwrap()]).is_err());
+    /// ```
+    pub fn add_conditional_moves(&mut self, line: &[Move]) -> Result<(), Error> {
+        if let GameState::Finished(_) = self.state {
+            return Err(Error::GameOver);
+        }
+        let mut board = *self.current_board();
+        for &m in line {
+            let Some(next) = board.perform_move(m) else {
+                return Err(Error::IllegalMove(board.to_fen(), m));
+            };
+            board = next;
         }
+        if !line.is_empty() {
+            self.responder = self.next_player().opposite();
+            self.conditionals.push(line.to_vec());
+        }
+        Ok(())
+    }
+
+    /// Get the conditional move lines that are still being followed,
+    /// each starting with the move expected from the player to move
+    /// or, right after the expected move, with the reply to it. See
+    /// [`Game::add_conditional_moves`].
+    pub fn conditional_moves(&self) -> &[Vec<Move>] {
+        &self.conditionals
+    }
+
+    /// Drop all conditional move lines
+    pub fn clear_conditional_moves(&mut self) {
+        self.conditionals.clear();
+    }
+
+    // Move on in the conditional lines following a move that was
+    // just played, dropping the ones it doesn't follow, and reply if
+    // it's the responder's turn
+    fn follow_conditionals(&mut self, played: Move) {
+        self.conditionals
+            .retain(|line| line.len() > 1 && line[0] == played);
+        for line in &mut self.conditionals {
+            let _ = line.remove(0);
+        }
+        if self.next_player() == self.responder {
+            if let Some(&reply) = self.conditionals.first().map(|line| &line[0]) {
+                let _ = self.make_move(reply);
+            }
+        }
+    }
+
+    /// End the game with a result decided outside of the board, e.g.
+    /// by a player resigning or a tournament manager noticing that a
+    /// player disconnected, with the reason given by the result.
+    /// Returns `false` and leaves the game as it is if it was already
+    /// over. Undoing a move reopens the game.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::game::{Game, GameResult, GameState, WinReason};
+    /// # use chess_engine::piece::Color;
+    /// let mut game = Game::new();
+    /// game.make_move("e2e4".parse().unwrap());
+    /// let result = GameResult::Win {
+    ///     winner: Color::White,
+    ///     reason: WinReason::Abandoned,
+    /// };
+    ///
+    /// assert!(game.adjudicate(result));
+    /// assert_eq!(game.state(), GameState::Finished(result));
+    /// assert!(game.make_move("e7e5".parse().unwrap()).is_none());
+    /// ```
+    pub fn adjudicate(&mut self, result: GameResult) -> bool {
+        if let GameState::Finished(_) = self.state {
+            return false;
+        }
+        self.state = GameState::Finished(result);
+        true
+    }
+
+    /// Play uniformly random legal moves from the current position
+    /// until the game is over or `max_plies` moves have been played,
+    /// returning how many moves were played. Useful for generating
+    /// test games and fuzzing the rules.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::game::Game;
+    /// # use rand::SeedableRng;
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+    /// let mut game = Game::new();
+    /// let plies = game.random_playout(&mut rng, 40);
+    ///
+    /// assert!(plies <= 40);
+    /// assert_eq!(game.get_moves().len(), plies as usize);
+    /// ```
+    pub fn random_playout<R: Rng + ?Sized>(&mut self, rng: &mut R, max_plies: u32) -> u32 {
+        for plies in 0..max_plies {
+            let Some(&m) = self.get_all_legal_moves().choose(rng) else {
+                return plies;
+            };
+            if self.make_move(m).is_none() {
+                // the game was drawn without running out of moves
+                return plies;
+            }
+        }
+        max_plies
+    }
+
+    /// Let `white` and `black` play the game from the current
+    /// position, until it's over or it can't go on for now: a player
+    /// resigns, hasn't decided on a move yet, runs out of time or
+    /// tries an illegal move. A game stopped by a pending move can be
+    /// continued by calling this again.
+    ///
+    /// If there's a clock, the time of the player to move is kept
+    /// running while their move is pending.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::game::Game;
+    /// # use chess_engine::piece::Color;
+    /// # use chess_engine::player::{GreedyMaterialPlayer, HumanPending, PlayOutcome};
+    /// let mut game = Game::new();
+    /// let mut human = HumanPending::new();
+    /// let mut engine = GreedyMaterialPlayer;
+    ///
+    /// human.submit("e2e4".parse().unwrap());
+    /// let outcome = game.play_players(&mut human, &mut engine, None);
+    ///
+    /// // the engine replied, and now it's the human's turn again
+    /// assert_eq!(outcome, PlayOutcome::Pending(Color::White));
+    /// assert_eq!(game.get_moves().len(), 2);
+    /// ```
+    pub fn play_players<W, B>(
+        &mut self,
+        white: &mut W,
+        black: &mut B,
+        mut clock: Option<&mut Clock>,
+    ) -> PlayOutcome
+    where
+        W: Player + ?Sized,
+        B: Player + ?Sized,
+    {
+        loop {
+            if let GameState::Finished(result) = self.state {
+                return PlayOutcome::Finished(result);
+            }
+
+            let color = self.next_player();
+            if let Some(clock) = clock.as_deref_mut() {
+                if clock.running() != Some(color) {
+                    clock.start(color);
+                }
+                if clock.is_flagged(color) {
+                    let _ = clock.stop();
+                    return PlayOutcome::TimeForfeit(color);
+                }
+            }
+
+            let action = match color {
+                Color::White => white.choose_move(self, clock.as_deref()),
+                Color::Black => black.choose_move(self, clock.as_deref()),
+            };
+            let m = match action {
+                PlayerAction::Move(m) => m,
+                PlayerAction::Resign => return PlayOutcome::Resigned(color),
+                PlayerAction::Pending => return PlayOutcome::Pending(color),
+            };
+            if let Some(clock) = clock.as_deref_mut() {
+                if !clock.stop() {
+                    return PlayOutcome::TimeForfeit(color);
+                }
+            }
+            if self.make_move(m).is_none() {
+                return PlayOutcome::IllegalMove(color, m);
+            }
+        }
+    }
+
+    fn update_state(&mut self) {
+        self.set_state(MoveOutcome::new(*self.current_board()));
+    }
+
+    // Set the state from the outcome of reaching the current board
+    fn set_state(&mut self, outcome: MoveOutcome) {
+        let board = outcome.board;
+        let result = if outcome.mate {
+            Some(GameResult::Win {
+                winner: board.turn().opposite(),
+                reason: WinReason::Checkmate,
+            })
+        } else if outcome.stalemate {
+            Some(GameResult::Draw(DrawReason::Stalemate))
+        } else if board.halfmove() >= 100 {
+            Some(GameResult::Draw(DrawReason::FiftyMoveRule))
+        } else {
+            None
+        };
+
+        self.state = match result {
+            Some(result) => GameState::Finished(result),
+            None => GameState::Ongoing {
+                check: outcome.check,
+            },
+        };
     }
 
     /// Get which player is supposed to go next
@@ -138,15 +974,135 @@ impl Game {
         self.boards.last().unwrap()
     }
 
+    /// Get how many times each position has occurred in the game, as
+    /// pairs of the position's [Zobrist hash](Board::zobrist) and its
+    /// count, in the order the positions first occurred. Positions
+    /// count as the same for repetitions if their hashes are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::game::Game;
+    /// let mut game = Game::new();
+    /// for _ in 0..2 {
+    ///     for m in &["g1f3", "g8f6", "f3g1", "f6g8"] {
+    ///         game.make_move(m.parse().unwrap());
+    ///     }
+    /// }
+    /// let start = game.get_boards()[0].zobrist();
+    ///
+    /// assert_eq!(game.position_counts().next(), Some((start, 3)));
+    /// assert!(game.position_counts().skip(1).all(|(_, count)| count == 2));
+    /// ```
+    pub fn position_counts(&self) -> impl Iterator<Item = (u64, u32)> {
+        let mut counts: Vec<(u64, u32)> = vec![];
+        let mut indices: HashMap<u64, usize> = HashMap::new();
+        for board in self.boards() {
+            let hash = board.zobrist();
+            if let Some(&i) = indices.get(&hash) {
+                counts[i].1 += 1;
+            } else {
+                let _ = indices.insert(hash, counts.len());
+                counts.push((hash, 1));
+            }
+        }
+        counts.into_iter()
+    }
+
     /// Undo the last move, returning `None` if there was no last
     /// move, and the Board/Move combination if there was.
+    /// The board state is updated to that of the position before the
+    /// move.
     ///
     /// # Panics
     ///
     /// This function should be unable to panic as self must at least
     /// contain one board.
     pub fn undo_move(&mut self) -> Option<(Board, Move)> {
-        self.moves.pop().map(|m| (self.boards.pop().unwrap(), m))
+        self.conditionals.clear();
+        let undone = self.moves.pop().map(|m| (self.boards.pop().unwrap(), m));
+        if self.boards.is_empty() {
+            // the window of a compact history has run out, so refill it
+            // by replaying the moves before it
+            let window = match self.history {
+                HistoryMode::Compact { window } => window.max(2),
+                HistoryMode::Full => 1,
+            };
+            self.first_ply = (self.moves.len() + 1).saturating_sub(window);
+            let mut board = self.initial;
+            for &m in &self.moves[..self.first_ply] {
+                board = board.perform_move_unvalidated(m);
+            }
+            self.boards.push(board);
+            for &m in &self.moves[self.first_ply..] {
+                board = board.perform_move_unvalidated(m);
+                self.boards.push(board);
+            }
+        }
+        self.update_state();
+        undone
+    }
+
+    /// Take a snapshot of the game, see [`GameSnapshot`]. The
+    /// snapshot has no clock, use [`Game::snapshot_with_clock`] for
+    /// games played with one.
+    pub fn snapshot(&self) -> GameSnapshot {
+        GameSnapshot {
+            initial: BoardSnapshot::from(&self.initial),
+            moves: self.moves.clone(),
+            state: self.state,
+            auto_promotion: self.auto_promotion,
+            history: self.history,
+            conditionals: self.conditionals.clone(),
+            responder: self.responder,
+            clock: None,
+        }
+    }
+
+    /// Take a snapshot of the game along with the clock it's played
+    /// with
+    pub fn snapshot_with_clock(&self, clock: &Clock) -> GameSnapshot {
+        GameSnapshot {
+            clock: Some(clock.into()),
+            ..self.snapshot()
+        }
+    }
+
+    /// Rebuild a game from a snapshot taken with [`Game::snapshot`],
+    /// replaying its moves from the initial position. The clock of
+    /// the snapshot isn't part of the game and is ignored, use
+    /// [`Game::restore_with_clock`] to get it back too.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the initial board has a size the engine
+    /// doesn't support, or if any of the moves is illegal, which can
+    /// only happen if the snapshot was changed or corrupted
+    pub fn restore(snapshot: GameSnapshot) -> Result<Game, Error> {
+        let mut game = Game::from_board(Board::try_from(snapshot.initial)?);
+        game.set_history_mode(snapshot.history);
+        for &m in &snapshot.moves {
+            if game.make_move(m).is_none() {
+                return Err(Error::IllegalMove(game.current_board().to_fen(), m));
+            }
+        }
+        game.state = snapshot.state;
+        game.auto_promotion = snapshot.auto_promotion;
+        game.conditionals = snapshot.conditionals;
+        game.responder = snapshot.responder;
+        Ok(game)
+    }
+
+    /// Rebuild a game and its clock from a snapshot taken with
+    /// [`Game::snapshot_with_clock`], see [`Game::restore`]. The clock
+    /// is `None` if the snapshot doesn't have one.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error in the same cases as [`Game::restore`]
+    pub fn restore_with_clock(snapshot: GameSnapshot) -> Result<(Game, Option<Clock>), Error> {
+        let clock = snapshot.clock.map(Clock::from);
+        Ok((Game::restore(snapshot)?, clock))
     }
 }
 
@@ -155,3 +1111,339 @@ impl Default for Game {
         Game::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "fen")]
+    use super::DrawReason;
+    use super::{Game, GameResult, GameState, HistoryMode, WinReason};
+    use crate::board::Move;
+    use crate::clock::{Clock, TimeControl};
+    use crate::piece::{Color, Piece, PieceType};
+    use crate::player::{HumanPending, PlayOutcome, RandomPlayer};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::time::Duration;
+
+    fn play(game: &mut Game, moves: &[(&str, &str)]) {
+        for (from, to) in moves {
+            let m = Move::Normal {
+                from: from.parse().unwrap(),
+                to: to.parse().unwrap(),
+            };
+            assert!(game.make_move(m).is_some(), "{} was illegal", m);
+        }
+    }
+
+    #[test]
+    fn last_move_en_passant() {
+        let mut game = Game::new();
+        play(
+            &mut game,
+            &[
+                ("e2", "e4"),
+                ("d7", "d5"),
+                ("e4", "e5"),
+                ("f7", "f5"),
+                ("e5", "f6"),
+            ],
+        );
+        let (_, info) = game.last_move().unwrap();
+
+        assert!(info.en_passant);
+        assert_eq!(
+            info.captured,
+            Some(Piece::new(PieceType::Pawn, Color::Black))
+        );
+        assert!(!info.check);
+    }
+
+    #[test]
+    fn auto_promotion() {
+        let mut game = Game::new();
+        play(
+            &mut game,
+            &[
+                ("h2", "h4"),
+                ("g7", "g5"),
+                ("h4", "g5"),
+                ("g8", "f6"),
+                ("g5", "g6"),
+                ("f6", "e4"),
+                ("g6", "g7"),
+                ("e4", "d6"),
+            ],
+        );
+        let promotion = Move::Normal {
+            from: "g7".parse().unwrap(),
+            to: "h8".parse().unwrap(),
+        };
+
+        assert!(game.clone().make_move(promotion).is_none());
+
+        game.set_auto_promotion(Some(PieceType::Knight));
+        play(&mut game, &[("g7", "h8")]);
+
+        assert_eq!(
+            game.current_board()["h8"],
+            Some(Piece::new(PieceType::Knight, Color::White))
+        );
+        assert!(matches!(
+            game.last_move(),
+            Some((
+                Move::Promotion {
+                    target: PieceType::Knight,
+                    ..
+                },
+                _
+            ))
+        ));
+    }
+
+    #[test]
+    fn random_playouts_are_legal() {
+        let mut rng = StdRng::seed_from_u64(2342);
+        for _ in 0..10 {
+            let mut game = Game::new();
+            let plies = game.random_playout(&mut rng, 200);
+
+            // every board has to follow from the one before it
+            for (i, m) in game.get_moves().iter().enumerate() {
+                let boards = game.get_boards();
+                assert_eq!(boards[i].perform_move(*m), Some(boards[i + 1]));
+            }
+            if plies < 200 {
+                assert!(matches!(game.state(), GameState::Finished(_)));
+            }
+        }
+    }
+
+    #[test]
+    fn undo_updates_state() {
+        let mut game = Game::new();
+        play(
+            &mut game,
+            &[("f2", "f3"), ("e7", "e5"), ("g2", "g4"), ("d8", "h4")],
+        );
+        assert!(matches!(game.state(), GameState::Finished(_)));
+
+        let _ = game.undo_move();
+        assert_eq!(game.state(), GameState::Ongoing { check: false });
+        play(&mut game, &[("d8", "g5")]);
+    }
+
+    #[test]
+    fn check_is_cleared() {
+        let mut game = Game::new();
+        play(&mut game, &[("e2", "e4"), ("f7", "f6"), ("d1", "h5")]);
+        assert_eq!(game.state(), GameState::Ongoing { check: true });
+
+        play(&mut game, &[("g7", "g6")]);
+        assert_eq!(game.state(), GameState::Ongoing { check: false });
+    }
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn fifty_move_rule() {
+        // the halfmove clock counts plies, so fifty moves are 100 of them
+        let board = crate::board::Board::load_fen("4k3/8/8/8/8/8/8/R3K3 w - - 98 80").unwrap();
+        let mut game = Game::from_board(board);
+        play(&mut game, &[("a1", "a2")]);
+        assert_eq!(game.state(), GameState::Ongoing { check: false });
+
+        play(&mut game, &[("e8", "d8")]);
+        assert_eq!(
+            game.state(),
+            GameState::Finished(GameResult::Draw(DrawReason::FiftyMoveRule))
+        );
+    }
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn fifty_move_rule_from_fen() {
+        let board = crate::board::Board::load_fen("4k3/8/8/8/8/8/8/R3K3 w - - 70 80").unwrap();
+        assert_eq!(
+            Game::from_board(board).state(),
+            GameState::Ongoing { check: false }
+        );
+
+        // the position is already past the limit, so it's drawn even
+        // though the clock never passes exactly 100 in the game
+        let board = crate::board::Board::load_fen("4k3/8/8/8/8/8/8/R3K3 w - - 120 80").unwrap();
+        assert_eq!(
+            Game::from_board(board).state(),
+            GameState::Finished(GameResult::Draw(DrawReason::FiftyMoveRule))
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_board_state() {
+        use super::BoardState;
+
+        let mut game = Game::new();
+        assert_eq!(game.board_state(), BoardState::Normal);
+        play(
+            &mut game,
+            &[("f2", "f3"), ("e7", "e5"), ("g2", "g4"), ("d8", "h4")],
+        );
+        assert_eq!(game.board_state(), BoardState::Checkmate);
+    }
+
+    #[test]
+    fn last_move_checkmate() {
+        let mut game = Game::new();
+        play(
+            &mut game,
+            &[("f2", "f3"), ("e7", "e5"), ("g2", "g4"), ("d8", "h4")],
+        );
+        let (_, info) = game.last_move().unwrap();
+
+        assert!(info.check && info.checkmate);
+        assert_eq!(
+            game.state(),
+            GameState::Finished(GameResult::Win {
+                winner: Color::Black,
+                reason: WinReason::Checkmate
+            })
+        );
+        assert!(!info.is_capture());
+        assert_eq!(info.piece, Piece::new(PieceType::Queen, Color::Black));
+    }
+
+    #[test]
+    fn play_players() {
+        let mut game = Game::new();
+        let mut white = RandomPlayer::new(StdRng::seed_from_u64(3));
+        let mut black = RandomPlayer::new(StdRng::seed_from_u64(4));
+        let outcome = game.play_players(&mut white, &mut black, None);
+        match (outcome, game.state()) {
+            (PlayOutcome::Finished(result), GameState::Finished(state)) => {
+                assert_eq!(result, state);
+            }
+            _ => panic!("{:?}", outcome),
+        }
+
+        let mut game = Game::new();
+        let mut human = HumanPending::new();
+        human.submit("e2e5".parse().unwrap());
+        assert_eq!(
+            game.play_players(&mut human, &mut white, None),
+            PlayOutcome::IllegalMove(Color::White, "e2e5".parse().unwrap())
+        );
+        human.resign();
+        assert_eq!(
+            game.play_players(&mut white, &mut human, None),
+            PlayOutcome::Resigned(Color::Black)
+        );
+    }
+
+    #[test]
+    fn compact_history() {
+        let mut full = Game::new();
+        let _ = full.random_playout(&mut StdRng::seed_from_u64(11), 60);
+        let mut compact = Game::new();
+        compact.set_history_mode(HistoryMode::Compact { window: 3 });
+        for &m in full.get_moves() {
+            let _ = compact.make_move(m).unwrap();
+        }
+
+        assert_eq!(compact.retained_boards().len(), 3);
+        assert!(std::panic::catch_unwind(|| compact.get_boards().len()).is_err());
+        assert_eq!(compact.boards().collect::<Vec<_>>(), full.get_boards());
+        assert_eq!(compact.last_move(), full.last_move());
+        for ply in 0..=full.get_moves().len() + 1 {
+            assert_eq!(compact.board_at(ply), full.board_at(ply));
+        }
+        assert!(compact.position_counts().eq(full.position_counts()));
+
+        for _ in 0..full.get_moves().len() {
+            assert_eq!(compact.undo_move(), full.undo_move());
+            assert_eq!(compact.current_board(), full.current_board());
+            assert_eq!(compact.state(), full.state());
+        }
+        assert_eq!(compact.undo_move(), None);
+
+        let _ = compact.random_playout(&mut StdRng::seed_from_u64(12), 20);
+        let boards: Vec<_> = compact.boards().collect();
+        compact.set_history_mode(HistoryMode::Full);
+        assert_eq!(compact.get_boards(), &boards[..]);
+    }
+
+    #[test]
+    fn conditional_moves() {
+        let moves =
+            |line: &[&str]| -> Vec<Move> { line.iter().map(|m| m.parse().unwrap()).collect() };
+        let mut game = Game::new();
+        game.add_conditional_moves(&moves(&["e2e4", "e7e5", "g1f3", "b8c6"]))
+            .unwrap();
+        game.add_conditional_moves(&moves(&["e2e4", "c7c5", "g1f3"]))
+            .unwrap();
+        game.add_conditional_moves(&moves(&["d2d4", "d7d5"]))
+            .unwrap();
+        assert!(game
+            .add_conditional_moves(&moves(&["e2e4", "e2e4"]))
+            .is_err());
+        assert_eq!(game.conditional_moves().len(), 3);
+
+        // both e4 lines are followed, and the first one's reply is played
+        let _ = game.make_move("e2e4".parse().unwrap()).unwrap();
+        assert_eq!(game.get_moves(), &moves(&["e2e4", "e7e5"])[..]);
+        assert_eq!(game.conditional_moves(), &[moves(&["g1f3", "b8c6"])]);
+
+        let _ = game.make_move("g1f3".parse().unwrap()).unwrap();
+        assert_eq!(game.get_moves().len(), 4);
+        assert!(game.conditional_moves().is_empty());
+
+        // undoing drops the lines
+        game.add_conditional_moves(&moves(&["f1c4", "g8f6"]))
+            .unwrap();
+        let _ = game.undo_move();
+        let _ = game.make_move("b8c6".parse().unwrap()).unwrap();
+        let _ = game.make_move("f1c4".parse().unwrap()).unwrap();
+        assert_eq!(game.get_moves().len(), 5);
+    }
+
+    #[test]
+    fn snapshot_restores_everything() {
+        let mut game = Game::new();
+        game.set_history_mode(HistoryMode::Compact { window: 2 });
+        game.set_auto_promotion(Some(PieceType::Knight));
+        play(&mut game, &[("e2", "e4"), ("e7", "e5")]);
+        game.add_conditional_moves(&["g1f3".parse().unwrap(), "b8c6".parse().unwrap()])
+            .unwrap();
+
+        let restored = Game::restore(game.snapshot()).unwrap();
+        assert_eq!(restored.snapshot(), game.snapshot());
+        assert_eq!(restored.retained_boards(), game.retained_boards());
+
+        let result = GameResult::Win {
+            winner: Color::Black,
+            reason: WinReason::Resignation,
+        };
+        assert!(game.adjudicate(result));
+        let restored = Game::restore(game.snapshot()).unwrap();
+        assert_eq!(restored.state(), GameState::Finished(result));
+
+        let mut snapshot = game.snapshot();
+        snapshot.moves.push("e1e3".parse().unwrap());
+        assert!(Game::restore(snapshot).is_err());
+
+        let clock = Clock::new(TimeControl {
+            base: Duration::from_secs(60),
+            increment: Duration::from_secs(1),
+        });
+        let (restored, restored_clock) =
+            Game::restore_with_clock(game.snapshot_with_clock(&clock)).unwrap();
+        assert_eq!(restored.snapshot(), game.snapshot());
+        assert_eq!(restored_clock, Some(clock));
+        assert_eq!(Game::restore_with_clock(game.snapshot()).unwrap().1, None);
+
+        let mut snapshot = game.snapshot();
+        snapshot.initial.files = 9;
+        assert!(Game::restore(snapshot).is_err());
+        let mut snapshot = game.snapshot();
+        snapshot.initial.ranks = 0;
+        assert!(Game::restore(snapshot).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+//! Module for loading games from the JSON exports of online chess
+//! sites, so real games can be pulled straight into the engine. Needs
+//! the `interop` feature.
+//!
+//! Lichess games are read from the game export API, as well as the
+//! `gameFull` events of the board and bot APIs. Chess.com games are
+//! read from the published-data API, which stores each game as PGN.
+
+use crate::board::{Board, Move};
+use crate::error::{Error, IoError};
+use crate::game::Game;
+use crate::notation::{san, uci};
+use crate::pgn;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LichessGame {
+    moves: Option<String>,
+    state: Option<LichessState>,
+    initial_fen: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LichessState {
+    moves: String,
+}
+
+#[derive(Deserialize)]
+struct ChessComGame {
+    pgn: String,
+    rules: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChessComArchive {
+    games: Vec<ChessComGame>,
+}
+
+/// Load a game exported as JSON from Lichess. The moves are expected
+/// in UCI notation as given by the board and bot APIs, but SAN (the
+/// default of the game export API) is accepted as well. Games from a
+/// custom position are started from their `initialFen`.
+///
+/// # Errors
+///
+/// Will return an error if the JSON is malformed, or if it contains
+/// an invalid position or illegal moves
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::interop;
+/// # use chess_engine::game::GameState;
+/// let json = r#"{
+///     "id": "q7ZvsdUF",
+///     "status": "mate",
+///     "winner": "black",
+///     "moves": "f2f3 e7e5 g2g4 d8h4"
+/// }"#;
+/// let game = interop::lichess_game(json).unwrap();
+///
+/// assert_eq!(game.get_moves().len(), 4);
+/// assert!(matches!(game.state(), GameState::Finished(_)));
+/// ```
+pub fn lichess_game(json: &str) -> Result<Game, IoError> {
+    let export: LichessGame = serde_json::from_str(json)?;
+    let moves = match (export.moves, export.state) {
+        (Some(moves), _) | (None, Some(LichessState { moves })) => moves,
+        (None, None) => String::new(),
+    };
+    Ok(lichess_moves(export.initial_fen.as_deref(), &moves)?)
+}
+
+// Play out the moves of a Lichess game from its initial position
+pub(crate) fn lichess_moves(initial_fen: Option<&str>, moves: &str) -> Result<Game, Error> {
+    let mut game = match initial_fen {
+        None | Some("startpos") => Game::new(),
+        Some(fen) => Game::from_board(Board::load_fen(fen)?),
+    };
+
+    for token in moves.split_whitespace() {
+        let board = game.current_board();
+        let m = parse_lichess_move(board, token)?;
+        if game.make_move(m).is_none() {
+            return Err(Error::IllegalMove(game.current_board().to_fen(), m));
+        }
+    }
+
+    Ok(game)
+}
+
+// Lichess uses UCI or SAN depending on the API, so accept whichever
+// makes sense on the board
+fn parse_lichess_move(board: &Board, s: &str) -> Result<Move, Error> {
+    match uci::parse(board, s) {
+        Ok(m) if board.is_legal(m, board.turn()) => Ok(m),
+        _ => san::parse(board, s),
+    }
+}
+
+/// Load a single game exported as JSON from Chess.com.
+///
+/// # Errors
+///
+/// Will return an error if the JSON is malformed, if the game isn't
+/// standard chess, or if its PGN is invalid
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::interop;
+/// let json = r#"{
+///     "url": "https://www.chess.com/game/live/1",
+///     "pgn": "[Event \"Live Chess\"]\n[Result \"1-0\"]\n\n1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n",
+///     "rules": "chess"
+/// }"#;
+/// let game = interop::chess_com_game(json).unwrap();
+///
+/// assert_eq!(game.get_moves().len(), 7);
+/// ```
+pub fn chess_com_game(json: &str) -> Result<Game, IoError> {
+    Ok(chess_com_to_game(serde_json::from_str(json)?)?)
+}
+
+/// Load all the games of a monthly archive exported as JSON from
+/// Chess.com, i.e. an object with a list of games under `games`.
+///
+/// # Errors
+///
+/// Will return an error if the JSON is malformed, or if any of the
+/// games fails to load as with [`chess_com_game`]
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::interop;
+/// let json = r#"{"games": [
+///     {"pgn": "1. d4 d5 *", "rules": "chess"},
+///     {"pgn": "1. e4 *", "rules": "chess"}
+/// ]}"#;
+/// let games = interop::chess_com_archive(json).unwrap();
+///
+/// assert_eq!(games.len(), 2);
+/// assert_eq!(games[1].get_moves().len(), 1);
+/// ```
+pub fn chess_com_archive(json: &str) -> Result<Vec<Game>, IoError> {
+    let archive: ChessComArchive = serde_json::from_str(json)?;
+    let games = archive.games.into_iter().map(chess_com_to_game);
+    Ok(games.collect::<Result<_, Error>>()?)
+}
+
+fn chess_com_to_game(export: ChessComGame) -> Result<Game, Error> {
+    // variants such as chess960 can't be represented
+    if let Some(rules) = export.rules.filter(|r| r != "chess") {
+        return Err(Error::InvalidPgn(format!("unsupported rules `{}`", rules)));
+    }
+    Ok(pgn::parse(&export.pgn)?.game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{chess_com_game, lichess_game};
+    use crate::board::Board;
+
+    #[test]
+    fn lichess_game_full() {
+        let json = r#"{
+            "type": "gameFull",
+            "id": "5IrD6Gzz",
+            "initialFen": "r3k3/8/8/8/8/8/8/4K2R w Kq - 0 1",
+            "state": {"type": "gameState", "moves": "e1g1 e8c8", "status": "started"}
+        }"#;
+        let game = lichess_game(json).unwrap();
+
+        assert_eq!(
+            game.current_board(),
+            &Board::load_fen("2kr4/8/8/8/8/8/8/5RK1 w - - 2 2").unwrap()
+        );
+    }
+
+    #[test]
+    fn lichess_san_moves() {
+        let json = r#"{"moves": "e4 e5 Nf3 Nc6 Bb5"}"#;
+
+        assert_eq!(lichess_game(json).unwrap().get_moves().len(), 5);
+        assert!(lichess_game(r#"{"moves": "e2e4 e2e4"}"#).is_err());
+        assert!(lichess_game(r#"{"moves": 5}"#).is_err());
+    }
+
+    #[test]
+    fn chess_com_rules() {
+        let json = r#"{"pgn": "1. e4 *", "rules": "chess960"}"#;
+
+        assert!(chess_com_game(json).is_err());
+    }
+}
@@ -6,6 +6,27 @@
 //! the actual game, making sure moves are legal, keeping track of
 //! boards over time, etc. This engine additionally supports loading a
 //! position from FEN notation.
+//!
+//! ## Features
+//!
+//! Everything beyond the rules of chess can be left out with cargo
+//! features, so embedded users can compile only the rules core:
+//!
+//! - `fen` reads positions from FEN. Writing FEN is always possible.
+//! - `san` reads and writes moves in Standard Algebraic Notation.
+//! - `pgn` reads and writes games in PGN, and enables tournaments and
+//!   engine matches. Implies `fen` and `san`.
+//! - `search` enables the built-in search, analysis, puzzles and the
+//!   computer players built on them.
+//! - `serde` derives `Serialize` and `Deserialize` for the crate's
+//!   data types, with squares and moves written as text, see
+//!   [`serialization`](crate::serialization).
+//! - `render` draws boards as SVG.
+//! - `db` stores games in an SQLite database. Implies `pgn`.
+//! - `ml` encodes positions and moves for machine learning.
+//! - `variants` enables the rules of chess variants.
+//!
+//! `fen`, `san`, `pgn` and `search` are enabled by default.
 #![recursion_limit = "256"]
 #![warn(
     rustdoc::missing_crate_level_docs,
@@ -92,15 +113,53 @@
     clippy::cast_possible_wrap,
     clippy::items_after_statements
 )]
-#![feature(label_break_value)]
 
+#[doc(hidden)]
 #[macro_use]
-mod macros;
+pub mod macros;
 
+#[cfg(feature = "search")]
+pub mod analysis;
+#[cfg(any(feature = "proptest", feature = "quickcheck"))]
+pub mod arbitrary;
 pub mod board;
+pub mod clock;
+#[cfg(feature = "db")]
+pub mod db;
+#[cfg(feature = "ml")]
+pub mod encoding;
+#[cfg(feature = "pgn")]
+pub mod engine_match;
 pub mod error;
+pub mod eval;
+#[cfg(feature = "fen")]
+pub mod fen;
 pub mod game;
+#[cfg(feature = "interop")]
+pub mod interop;
+#[cfg(feature = "lichess")]
+pub mod lichess;
+pub mod network;
+pub mod notation;
+#[cfg(feature = "pgn")]
+pub mod pgn;
 pub mod piece;
+pub mod player;
+#[cfg(feature = "search")]
+pub mod puzzle;
+pub mod rating;
+#[cfg(feature = "render")]
+pub mod render;
+#[cfg(feature = "search")]
+pub mod search;
+pub mod selfplay;
+#[cfg(feature = "serde")]
+pub mod serialization;
+pub mod stats;
+#[cfg(feature = "pgn")]
+pub mod tournament;
+#[cfg(feature = "fen")]
+pub mod tuning;
 
 pub use board::{Board, Move, SquareSpec};
 pub use error::Error;
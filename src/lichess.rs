@@ -0,0 +1,449 @@
+//! Module for running an engine as a bot on Lichess through the Bot
+//! API, so any [`Player`] can play online. Needs the `lichess`
+//! feature, and the API token of a Lichess bot account.
+//!
+//! [`LichessBot::run`] is the whole event loop: it accepts or
+//! declines incoming challenges, and plays the games that start one
+//! at a time, sending the player's moves in UCI notation.
+
+use crate::clock::{Clock, TimeControl};
+use crate::error::IoError;
+use crate::game::{Game, GameState};
+use crate::interop;
+use crate::notation::uci;
+use crate::piece::Color;
+use crate::player::{Player, PlayerAction};
+use serde::Deserialize;
+use std::fmt;
+use std::io::{BufRead, BufReader};
+use std::thread;
+use std::time::Duration;
+
+/// The address of the Lichess API
+pub const LICHESS_URL: &str = "https://lichess.org";
+
+// How long to wait before asking a player again whose move is pending
+const PENDING_DELAY: Duration = Duration::from_millis(50);
+
+/// A challenge sent to the bot
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct Challenge {
+    /// The id of the challenge, which is also the id of the game if
+    /// it's accepted
+    pub id: String,
+    /// The user who sent the challenge
+    #[serde(default)]
+    pub challenger: Option<User>,
+    /// The variant to play, where only `standard` can be played by
+    /// this crate
+    #[serde(deserialize_with = "variant_key")]
+    pub variant: String,
+    /// Whether the game would be rated
+    #[serde(default)]
+    pub rated: bool,
+    /// The speed of the game, e.g. `blitz` or `correspondence`
+    #[serde(default)]
+    pub speed: String,
+}
+
+/// A Lichess user
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct User {
+    /// The id of the user, which is their name in lowercase
+    pub id: String,
+    /// The rating of the user in the challenge's speed
+    #[serde(default)]
+    pub rating: Option<u32>,
+}
+
+/// An event of the bot's account
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Event {
+    /// Someone challenged the bot
+    Challenge {
+        /// The challenge
+        challenge: Challenge,
+    },
+    /// A game of the bot started
+    GameStart {
+        /// The game
+        game: GameRef,
+    },
+    /// A game of the bot finished
+    GameFinish {
+        /// The game
+        game: GameRef,
+    },
+    /// Any other event, such as a challenge being canceled
+    #[serde(other)]
+    Other,
+}
+
+/// A reference to a game on Lichess
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct GameRef {
+    /// The id of the game
+    pub id: String,
+}
+
+#[derive(Deserialize)]
+struct Variant {
+    key: String,
+}
+
+fn variant_key<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(Variant::deserialize(deserializer)?.key)
+}
+
+#[derive(Deserialize)]
+struct Account {
+    id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum GameEvent {
+    GameFull {
+        white: GamePlayer,
+        #[serde(rename = "initialFen")]
+        initial_fen: Option<String>,
+        state: GameStateEvent,
+    },
+    GameState(GameStateEvent),
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct GamePlayer {
+    // the Lichess AI has no id
+    id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GameStateEvent {
+    moves: String,
+    // the remaining times and increments are given in milliseconds
+    #[serde(default)]
+    wtime: u64,
+    #[serde(default)]
+    btime: u64,
+    #[serde(default)]
+    winc: u64,
+    #[serde(default)]
+    binc: u64,
+    status: String,
+}
+
+/// A connection to the Lichess Bot API
+///
+/// # Examples
+///
+/// ```no_run
+/// # use chess_engine::eval::MaterialEvaluator;
+/// # use chess_engine::lichess::LichessBot;
+/// # use chess_engine::player::SearchPlayer;
+/// let bot = LichessBot::new("lip_token");
+///
+/// // play every standard game offered with a 3 ply search
+/// bot.run(
+///     |challenge| challenge.variant == "standard",
+///     || SearchPlayer::new(MaterialEvaluator, 3),
+/// )?;
+/// # Ok::<(), chess_engine::error::IoError>(())
+/// ```
+#[derive(Clone)]
+pub struct LichessBot {
+    agent: ureq::Agent,
+    token: String,
+    base_url: String,
+}
+
+impl fmt::Debug for LichessBot {
+    // the token is a secret, so keep it out of logs
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LichessBot")
+            .field("base_url", &self.base_url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LichessBot {
+    /// Connect to Lichess with the API token of a bot account
+    pub fn new(token: &str) -> LichessBot {
+        LichessBot {
+            agent: ureq::Agent::new(),
+            token: token.to_string(),
+            base_url: LICHESS_URL.to_string(),
+        }
+    }
+
+    /// Use another server than [`LICHESS_URL`], e.g. a local
+    /// development instance of Lichess
+    #[must_use]
+    pub fn with_base_url(mut self, base_url: &str) -> LichessBot {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    fn get(&self, path: &str) -> Result<ureq::Response, IoError> {
+        self.agent
+            .get(&format!("{}{}", self.base_url, path))
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .call()
+            .map_err(|e| IoError::Lichess(e.to_string()))
+    }
+
+    fn post(&self, path: &str) -> Result<(), IoError> {
+        self.agent
+            .post(&format!("{}{}", self.base_url, path))
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .call()
+            .map(|_| ())
+            .map_err(|e| IoError::Lichess(e.to_string()))
+    }
+
+    // Read a stream of newline delimited JSON, skipping the empty
+    // lines Lichess sends to keep the connection alive
+    fn stream<T: for<'de> Deserialize<'de>>(
+        &self,
+        path: &str,
+    ) -> Result<impl Iterator<Item = Result<T, IoError>>, IoError> {
+        let reader = BufReader::new(self.get(path)?.into_reader());
+        Ok(reader
+            .lines()
+            .filter(|line| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+            .map(|line| Ok(serde_json::from_str(&line?)?)))
+    }
+
+    /// Get the id of the bot's account
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the request fails
+    pub fn account_id(&self) -> Result<String, IoError> {
+        let account: Account = serde_json::from_reader(self.get("/api/account")?.into_reader())?;
+        Ok(account.id)
+    }
+
+    /// Stream the events of the bot's account. The stream starts with
+    /// the games and challenges that are already going on, and then
+    /// waits for new events.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the request fails, and each event is an
+    /// error if it couldn't be read
+    pub fn events(&self) -> Result<impl Iterator<Item = Result<Event, IoError>>, IoError> {
+        self.stream("/api/stream/event")
+    }
+
+    /// Accept a challenge
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the request fails
+    pub fn accept_challenge(&self, id: &str) -> Result<(), IoError> {
+        self.post(&format!("/api/challenge/{}/accept", id))
+    }
+
+    /// Decline a challenge
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the request fails
+    pub fn decline_challenge(&self, id: &str) -> Result<(), IoError> {
+        self.post(&format!("/api/challenge/{}/decline", id))
+    }
+
+    /// Play a move in UCI notation in a game
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the request fails, e.g. because the
+    /// move is illegal
+    pub fn make_move(&self, game_id: &str, uci: &str) -> Result<(), IoError> {
+        self.post(&format!("/api/bot/game/{}/move/{}", game_id, uci))
+    }
+
+    /// Resign a game
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the request fails
+    pub fn resign(&self, game_id: &str) -> Result<(), IoError> {
+        self.post(&format!("/api/bot/game/{}/resign", game_id))
+    }
+
+    /// Play a game with `player` until it's over, and return it. The
+    /// player gets a clock that follows the one on Lichess, and a
+    /// player whose move is pending is asked again until it decides.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if a request fails, or if Lichess sends a
+    /// position that can't be read
+    pub fn play_game<P: Player + ?Sized>(
+        &self,
+        game_id: &str,
+        player: &mut P,
+    ) -> Result<Game, IoError> {
+        let account = self.account_id()?;
+        let mut setup = None;
+        let mut game = Game::new();
+
+        for event in self.stream(&format!("/api/bot/game/stream/{}", game_id))? {
+            let state = match event? {
+                GameEvent::GameFull {
+                    white,
+                    initial_fen,
+                    state,
+                } => {
+                    let color = if white.id.as_deref() == Some(account.as_str()) {
+                        Color::White
+                    } else {
+                        Color::Black
+                    };
+                    // the bot's own time, since the two sides may be
+                    // given different time controls
+                    let (time, increment) = match color {
+                        Color::White => (state.wtime, state.winc),
+                        Color::Black => (state.btime, state.binc),
+                    };
+                    let time_control = TimeControl {
+                        base: Duration::from_millis(time),
+                        increment: Duration::from_millis(increment),
+                    };
+                    setup = Some((color, initial_fen, Clock::new(time_control)));
+                    state
+                }
+                GameEvent::GameState(state) => state,
+                GameEvent::Other => continue,
+            };
+            let (color, initial_fen, clock) = match &mut setup {
+                Some(setup) => setup,
+                None => continue,
+            };
+
+            game = interop::lichess_moves(initial_fen.as_deref(), &state.moves)?;
+            if state.status != "started" || matches!(game.state(), GameState::Finished(_)) {
+                return Ok(game);
+            }
+            if game.next_player() != *color {
+                continue;
+            }
+
+            clock.set_remaining(Color::White, Duration::from_millis(state.wtime));
+            clock.set_remaining(Color::Black, Duration::from_millis(state.btime));
+            clock.start(*color);
+            let action = loop {
+                match player.choose_move(&game, Some(clock)) {
+                    PlayerAction::Pending => thread::sleep(PENDING_DELAY),
+                    action => break action,
+                }
+            };
+            let _ = clock.stop();
+
+            match action {
+                PlayerAction::Move(m) => {
+                    self.make_move(game_id, &uci::format(game.current_board(), m))?;
+                }
+                _ => self.resign(game_id)?,
+            }
+        }
+
+        Ok(game)
+    }
+
+    /// Run the bot: accept the challenges `accept` returns `true` for
+    /// and decline the others, and play every game that starts with a
+    /// new player from `new_player`. Games are played one at a time,
+    /// and this returns when Lichess closes the event stream.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if a request fails
+    pub fn run<A, F, P>(&self, mut accept: A, mut new_player: F) -> Result<(), IoError>
+    where
+        A: FnMut(&Challenge) -> bool,
+        F: FnMut() -> P,
+        P: Player,
+    {
+        for event in self.events()? {
+            match event? {
+                Event::Challenge { challenge } if accept(&challenge) => {
+                    self.accept_challenge(&challenge.id)?;
+                }
+                Event::Challenge { challenge } => self.decline_challenge(&challenge.id)?,
+                Event::GameStart { game } => {
+                    let _ = self.play_game(&game.id, &mut new_player())?;
+                }
+                Event::GameFinish { .. } | Event::Other => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Event, GameEvent};
+
+    #[test]
+    fn events() {
+        let json = r#"{
+            "type": "challenge",
+            "challenge": {
+                "id": "7pGLxJ4F",
+                "status": "created",
+                "challenger": {"id": "lovlas", "name": "Lovlas", "rating": 1506},
+                "variant": {"key": "standard", "name": "Standard"},
+                "rated": true,
+                "speed": "rapid"
+            }
+        }"#;
+        let challenge = match serde_json::from_str(json).unwrap() {
+            Event::Challenge { challenge } => challenge,
+            e => panic!("{:?}", e),
+        };
+        assert_eq!(challenge.id, "7pGLxJ4F");
+        assert_eq!(challenge.variant, "standard");
+        assert_eq!(challenge.challenger.unwrap().rating, Some(1506));
+
+        let json = r#"{"type": "gameStart", "game": {"id": "5IrD6Gzz", "fen": "startpos"}}"#;
+        assert!(matches!(
+            serde_json::from_str(json).unwrap(),
+            Event::GameStart { game } if game.id == "5IrD6Gzz"
+        ));
+        let json = r#"{"type": "challengeCanceled", "challenge": {"id": "7pGLxJ4F"}}"#;
+        assert_eq!(serde_json::from_str::<Event>(json).unwrap(), Event::Other);
+    }
+
+    #[test]
+    fn game_events() {
+        let json = r#"{
+            "type": "gameFull",
+            "id": "5IrD6Gzz",
+            "white": {"aiLevel": 1},
+            "black": {"id": "bot", "name": "Bot"},
+            "initialFen": "startpos",
+            "state": {"type": "gameState", "moves": "e2e4", "wtime": 60000, "btime": 30000, "winc": 0, "binc": 2000, "status": "started"}
+        }"#;
+        match serde_json::from_str(json).unwrap() {
+            GameEvent::GameFull { white, state, .. } => {
+                assert_eq!(white.id, None);
+                assert_eq!(state.moves, "e2e4");
+                assert_eq!((state.wtime, state.winc), (60000, 0));
+                assert_eq!((state.btime, state.binc), (30000, 2000));
+            }
+            _ => panic!("expected a gameFull event"),
+        }
+
+        let json = r#"{"type": "chatLine", "username": "bot", "text": "hi", "room": "player"}"#;
+        assert!(matches!(
+            serde_json::from_str(json).unwrap(),
+            GameEvent::Other
+        ));
+    }
+}
@@ -1,3 +1,9 @@
+//! Macros for writing squares and moves, plus the internal ones for
+//! setting up boards
+
+use crate::board::{Castling, Move, SquareSpec};
+use crate::piece::PieceType;
+
 macro_rules! row {
     [ $($s:ident $p:ident),* ] => {
         [ $(p!($s $p)),* ]
@@ -42,3 +48,129 @@ macro_rules! p {
         crate::piece::PieceType::Knight
     };
 }
+
+/// Create a [`SquareSpec`](crate::board::SquareSpec) from its name,
+/// like `sq!(e4)`. The name is checked at compile time, so a typo is
+/// a compile error rather than a panic.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::sq;
+/// # use chess_engine::board::SquareSpec;
+/// assert_eq!(sq!(e4), SquareSpec::E4);
+/// assert_eq!(sq!(a8), "a8".parse().unwrap());
+/// ```
+///
+/// ```compile_fail
+/// # use chess_engine::sq;
+/// let square = sq!(i9);
+/// ```
+#[macro_export]
+macro_rules! sq {
+    ($square:ident) => {{
+        const SQUARE: $crate::board::SquareSpec =
+            $crate::macros::square(stringify!($square).as_bytes());
+        SQUARE
+    }};
+}
+
+/// Create a [`Move`](crate::board::Move) from its notation, like
+/// `mv!("e2e4")`. The notation is the same as what [`Move`]'s
+/// [`FromStr`](std::str::FromStr) implementation reads: the squares
+/// moved from and to, optionally followed by a promotion piece as in
+/// `e7e8q` or `e7e8=Q`, or castling written as `O-O` or `O-O-O`. It's
+/// checked at compile time, so a typo is a compile error rather than
+/// a panic.
+///
+/// [`Move`]: crate::board::Move
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::mv;
+/// # use chess_engine::board::{Castling, Move, SquareSpec};
+/// # use chess_engine::piece::PieceType;
+/// assert_eq!(
+///     mv!("e2e4"),
+///     Move::Normal {
+///         from: SquareSpec::E2,
+///         to: SquareSpec::E4,
+///     }
+/// );
+/// assert_eq!(mv!("b7a8=N"), "b7a8n".parse().unwrap());
+/// assert_eq!(mv!("O-O-O"), Move::Castling(Castling::Long));
+/// ```
+///
+/// ```compile_fail
+/// # use chess_engine::mv;
+/// // pawns can't promote to kings
+/// let m = mv!("e7e8=K");
+/// ```
+#[macro_export]
+macro_rules! mv {
+    ($notation:literal) => {{
+        const MOVE: $crate::board::Move = $crate::macros::parse_move($notation.as_bytes());
+        MOVE
+    }};
+}
+
+// The functions below are what `sq!` and `mv!` expand to. They panic
+// on invalid input, which turns into a compile error in the constants
+// the macros define.
+
+#[doc(hidden)]
+pub const fn square(s: &[u8]) -> SquareSpec {
+    assert!(
+        s.len() == 2,
+        "a square is a file from a to h and a rank from 1 to 8"
+    );
+    square_at(s, 0)
+}
+
+#[allow(clippy::cast_lossless)]
+const fn square_at(s: &[u8], at: usize) -> SquareSpec {
+    let (file, rank) = (s[at], s[at + 1]);
+    assert!(
+        file >= b'a' && file <= b'h' && rank >= b'1' && rank <= b'8',
+        "a square is a file from a to h and a rank from 1 to 8"
+    );
+    SquareSpec {
+        rank: (rank - b'1') as u32,
+        file: (file - b'a') as u32,
+    }
+}
+
+// Zeroes are accepted in place of the letter O in castling, as by
+// `Castling`'s `FromStr` implementation
+const fn is_o(c: u8) -> bool {
+    c == b'O' || c == b'0'
+}
+
+#[doc(hidden)]
+pub const fn parse_move(s: &[u8]) -> Move {
+    match s.len() {
+        3 if is_o(s[0]) && s[1] == b'-' && is_o(s[2]) => return Move::Castling(Castling::Short),
+        5 if is_o(s[0]) && s[1] == b'-' && is_o(s[2]) && s[3] == b'-' && is_o(s[4]) => {
+            return Move::Castling(Castling::Long)
+        }
+        4..=6 => {}
+        _ => panic!("a move is two squares and an optional promotion piece, or castling"),
+    }
+
+    let (from, to) = (square_at(s, 0), square_at(s, 2));
+    let piece = match s.len() {
+        4 => return Move::Normal { from, to },
+        5 => s[4],
+        _ if s[4] == b'=' => s[5],
+        _ => panic!("a promotion is written as e.g. e7e8q or e7e8=Q"),
+    };
+    let target = match piece {
+        b'Q' | b'q' => PieceType::Queen,
+        b'R' | b'r' => PieceType::Rook,
+        b'B' | b'b' => PieceType::Bishop,
+        b'N' | b'n' => PieceType::Knight,
+        _ => panic!("pawns can only promote to a queen, rook, bishop or knight"),
+    };
+    Move::Promotion { from, to, target }
+}
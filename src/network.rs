@@ -0,0 +1,519 @@
+//! Module for playing against opponents over the network, as a
+//! [`Player`] that can be mixed with local players in
+//! [`Game::play_players`].
+//!
+//! The protocol is made up of text messages: either `move <move>`,
+//! with the move in [UCI notation](crate::notation::uci), or
+//! `resign`. Each end sends the moves of its local players, and reads
+//! the moves of the remote player. Both ends have to start from the
+//! same position.
+//!
+//! The messages can be sent over any [`Transport`]. Over raw TCP they
+//! are sent as lines, and with the `websocket` feature they can also
+//! be sent as WebSocket text messages, e.g. to and from a browser.
+
+use crate::clock::Clock;
+use crate::game::Game;
+use crate::notation::uci;
+use crate::player::{Player, PlayerAction};
+use std::fmt::Write as _;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+
+/// A connection that the messages of the protocol can be sent over.
+/// Receiving mustn't block, so that the game loop can go on while
+/// waiting for the remote player.
+pub trait Transport {
+    /// Send a message
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the message can't be sent
+    fn send(&mut self, message: &str) -> io::Result<()>;
+
+    /// Receive the next message, or `None` if a whole message hasn't
+    /// arrived yet
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the connection is closed or broken
+    fn receive(&mut self) -> io::Result<Option<String>>;
+}
+
+/// A TCP connection sending messages as lines
+#[derive(Debug)]
+pub struct TcpTransport {
+    stream: TcpStream,
+    // bytes received that don't make up a whole line yet
+    buffer: Vec<u8>,
+}
+
+impl TcpTransport {
+    /// Send messages over `stream`, which is made nonblocking
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the stream can't be made nonblocking
+    pub fn new(stream: TcpStream) -> io::Result<TcpTransport> {
+        stream.set_nonblocking(true)?;
+        Ok(TcpTransport {
+            stream,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Get the underlying stream
+    pub fn stream(&self) -> &TcpStream {
+        &self.stream
+    }
+}
+
+impl Transport for TcpTransport {
+    fn send(&mut self, message: &str) -> io::Result<()> {
+        // the messages are tiny, so just wait if the buffers are full
+        self.stream.set_nonblocking(false)?;
+        let result = self
+            .stream
+            .write_all(message.as_bytes())
+            .and_then(|()| self.stream.write_all(b"\n"));
+        self.stream.set_nonblocking(true)?;
+        result
+    }
+
+    fn receive(&mut self) -> io::Result<Option<String>> {
+        let mut chunk = [0; 256];
+        while !self.buffer.contains(&b'\n') {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(None),
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        let end = self.buffer.iter().position(|&b| b == b'\n').unwrap_or(0);
+        let mut line: Vec<u8> = self.buffer.drain(..=end).collect();
+        let _ = line.pop();
+        String::from_utf8(line)
+            .map(Some)
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
+/// A WebSocket connection sending messages as text messages
+#[cfg(feature = "websocket")]
+#[derive(Debug)]
+pub struct WebSocketTransport {
+    socket: tungstenite::WebSocket<TcpStream>,
+}
+
+#[cfg(feature = "websocket")]
+impl WebSocketTransport {
+    /// Accept a WebSocket connection from a client on `stream`, which
+    /// is made nonblocking after the handshake
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the handshake fails
+    pub fn accept(stream: TcpStream) -> io::Result<WebSocketTransport> {
+        let socket = tungstenite::accept(stream)
+            .map_err(|e| io::Error::new(ErrorKind::Other, e.to_string()))?;
+        WebSocketTransport::new(socket)
+    }
+
+    /// Connect to the WebSocket server at `url`, e.g.
+    /// `ws://localhost:7878`
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the url is invalid, or if connecting or
+    /// the handshake fails
+    pub fn connect(url: &str) -> io::Result<WebSocketTransport> {
+        let invalid = || io::Error::new(ErrorKind::InvalidInput, format!("invalid url `{}`", url));
+        let host = url
+            .strip_prefix("ws://")
+            .ok_or_else(invalid)?
+            .split('/')
+            .next()
+            .ok_or_else(invalid)?;
+        let stream = TcpStream::connect(host)?;
+        let (socket, _) = tungstenite::client(url, stream)
+            .map_err(|e| io::Error::new(ErrorKind::Other, e.to_string()))?;
+        WebSocketTransport::new(socket)
+    }
+
+    /// Send messages over `socket`, whose stream is made nonblocking
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the stream can't be made nonblocking
+    pub fn new(socket: tungstenite::WebSocket<TcpStream>) -> io::Result<WebSocketTransport> {
+        socket.get_ref().set_nonblocking(true)?;
+        Ok(WebSocketTransport { socket })
+    }
+}
+
+#[cfg(feature = "websocket")]
+fn websocket_error(error: tungstenite::Error) -> io::Error {
+    match error {
+        tungstenite::Error::Io(e) => e,
+        tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed => {
+            ErrorKind::UnexpectedEof.into()
+        }
+        e => io::Error::new(ErrorKind::Other, e.to_string()),
+    }
+}
+
+#[cfg(feature = "websocket")]
+impl Transport for WebSocketTransport {
+    fn send(&mut self, message: &str) -> io::Result<()> {
+        // just like for TCP, wait if the buffers are full
+        self.socket.get_ref().set_nonblocking(false)?;
+        let result = self.socket.send(tungstenite::Message::text(message));
+        self.socket.get_ref().set_nonblocking(true)?;
+        result.map_err(websocket_error)
+    }
+
+    fn receive(&mut self) -> io::Result<Option<String>> {
+        loop {
+            match self.socket.read() {
+                Ok(tungstenite::Message::Text(text)) => return Ok(Some(text)),
+                Ok(tungstenite::Message::Close(_)) => return Err(ErrorKind::UnexpectedEof.into()),
+                // pings are answered by tungstenite itself
+                Ok(_) => (),
+                Err(tungstenite::Error::Io(e)) if e.kind() == ErrorKind::WouldBlock => {
+                    return Ok(None)
+                }
+                Err(e) => return Err(websocket_error(e)),
+            }
+        }
+    }
+}
+
+/// A player on the other end of a connection. Receiving doesn't
+/// block, so while the remote player hasn't sent their move, the
+/// player's action is [`PlayerAction::Pending`], and the game loop
+/// can be run again once more data may have arrived.
+///
+/// A lost connection or a malformed message counts as the remote
+/// player resigning.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use chess_engine::game::Game;
+/// # use chess_engine::network::NetworkPlayer;
+/// # use chess_engine::player::{HumanPending, PlayOutcome};
+/// let mut game = Game::new();
+/// let mut human = HumanPending::new();
+/// let mut remote = NetworkPlayer::connect("127.0.0.1:7878")?;
+///
+/// human.submit("e2e4".parse().unwrap());
+/// while let PlayOutcome::Pending(_) = game.play_players(&mut human, &mut remote, None) {
+///     // wait for the remote move, or ask the human for theirs
+///     # break;
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct NetworkPlayer<T = TcpTransport> {
+    transport: T,
+    // how many of the game's moves the other end knows about
+    synced: usize,
+}
+
+impl NetworkPlayer {
+    /// Connect to a remote player over TCP, e.g. one accepted by a
+    /// [`NetworkServer`]
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the connection fails
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<NetworkPlayer> {
+        Ok(NetworkPlayer::new(TcpTransport::new(TcpStream::connect(
+            addr,
+        )?)?))
+    }
+}
+
+impl<T: Transport> NetworkPlayer<T> {
+    /// Play against whoever is on the other end of `transport`
+    pub fn new(transport: T) -> NetworkPlayer<T> {
+        NetworkPlayer {
+            transport,
+            synced: 0,
+        }
+    }
+
+    /// Get the connection to the remote player
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Send the remote player the moves of `game` they don't know
+    /// about yet. This happens on their turn anyway, but should be
+    /// done when the game is over, so they get to see the last move.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if sending fails
+    pub fn sync(&mut self, game: &Game) -> io::Result<()> {
+        let moves = game.boards().zip(game.get_moves());
+        for (board, &m) in moves.skip(self.synced) {
+            let mut message = String::new();
+            let _ = write!(message, "move {}", uci::format(&board, m));
+            self.transport.send(&message)?;
+            self.synced += 1;
+        }
+        Ok(())
+    }
+
+    /// Tell the remote player that the local player resigns
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if sending fails
+    pub fn resign(&mut self) -> io::Result<()> {
+        self.transport.send("resign")
+    }
+
+    // Read the next message, if a whole one has arrived
+    fn receive(&mut self, game: &Game) -> io::Result<Option<PlayerAction>> {
+        let message = match self.transport.receive()? {
+            Some(message) => message,
+            None => return Ok(None),
+        };
+        let invalid = |e: String| io::Error::new(ErrorKind::InvalidData, e);
+
+        match message.split_whitespace().collect::<Vec<_>>()[..] {
+            ["move", m] => {
+                let m = uci::parse(game.current_board(), m).map_err(|e| invalid(e.to_string()))?;
+                // the move only counts as synced once the game accepts
+                // it, so try it on a copy first
+                if game.clone().make_move(m).is_none() {
+                    return Err(invalid(format!("illegal move `{}`", m)));
+                }
+                self.synced += 1;
+                Ok(Some(PlayerAction::Move(m)))
+            }
+            ["resign"] => Ok(Some(PlayerAction::Resign)),
+            _ => Err(invalid(format!("unknown message `{}`", message))),
+        }
+    }
+}
+
+impl<T: Transport> Player for NetworkPlayer<T> {
+    fn choose_move(&mut self, game: &Game, _clock: Option<&Clock>) -> PlayerAction {
+        match self.sync(game).and_then(|()| self.receive(game)) {
+            Ok(Some(action)) => action,
+            Ok(None) => PlayerAction::Pending,
+            Err(_) => PlayerAction::Resign,
+        }
+    }
+}
+
+/// A server accepting remote players to play against
+#[derive(Debug)]
+pub struct NetworkServer {
+    listener: TcpListener,
+}
+
+impl NetworkServer {
+    /// Start listening for players on `addr`
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the address can't be bound
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<NetworkServer> {
+        Ok(NetworkServer {
+            listener: TcpListener::bind(addr)?,
+        })
+    }
+
+    /// Get the address the server is listening on
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the socket has no address
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Wait for the next player to connect over raw TCP
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if accepting the connection fails
+    pub fn accept(&self) -> io::Result<NetworkPlayer> {
+        let (stream, _) = self.listener.accept()?;
+        Ok(NetworkPlayer::new(TcpTransport::new(stream)?))
+    }
+
+    /// Wait for the next player to connect over a WebSocket
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if accepting the connection or the
+    /// WebSocket handshake fails
+    #[cfg(feature = "websocket")]
+    pub fn accept_websocket(&self) -> io::Result<NetworkPlayer<WebSocketTransport>> {
+        let (stream, _) = self.listener.accept()?;
+        Ok(NetworkPlayer::new(WebSocketTransport::accept(stream)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NetworkPlayer, NetworkServer, Transport};
+    use crate::game::Game;
+    use crate::piece::Color;
+    use crate::player::{HumanPending, PlayOutcome, Player};
+    use std::collections::VecDeque;
+    use std::io;
+    use std::thread;
+    use std::time::Duration;
+
+    // Run the game loop until the remote player isn't pending anymore
+    fn play_until<W: Player, B: Player>(
+        game: &mut Game,
+        white: &mut W,
+        black: &mut B,
+        remote: Color,
+    ) -> PlayOutcome {
+        for _ in 0..500 {
+            match game.play_players(white, black, None) {
+                PlayOutcome::Pending(color) if color == remote => {
+                    thread::sleep(Duration::from_millis(10));
+                }
+                outcome => return outcome,
+            }
+        }
+        panic!("the remote player never moved");
+    }
+
+    // A transport receiving prepared messages and keeping the sent ones
+    #[derive(Default)]
+    struct Scripted {
+        incoming: VecDeque<String>,
+        sent: Vec<String>,
+    }
+
+    impl Transport for Scripted {
+        fn send(&mut self, message: &str) -> io::Result<()> {
+            self.sent.push(message.to_string());
+            Ok(())
+        }
+
+        fn receive(&mut self) -> io::Result<Option<String>> {
+            Ok(self.incoming.pop_front())
+        }
+    }
+
+    #[test]
+    fn illegal_remote_moves() {
+        let mut transport = Scripted::default();
+        transport.incoming.push_back("move e7e4".to_string());
+        let mut remote = NetworkPlayer::new(transport);
+        let mut white = HumanPending::new();
+        let mut game = Game::new();
+
+        white.submit("e2e4".parse().unwrap());
+        assert_eq!(
+            game.play_players(&mut white, &mut remote, None),
+            PlayOutcome::Resigned(Color::Black)
+        );
+        assert_eq!(game.get_moves().len(), 1);
+        assert_eq!(remote.synced, 1);
+        assert_eq!(remote.transport().sent, ["move e2e4"]);
+    }
+
+    #[cfg(feature = "websocket")]
+    #[test]
+    fn playing_over_websockets() {
+        use super::WebSocketTransport;
+
+        let server = NetworkServer::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", server.local_addr().unwrap());
+        let accepting = thread::spawn(move || server.accept_websocket().unwrap());
+        let mut black_remote = NetworkPlayer::new(WebSocketTransport::connect(&url).unwrap());
+        let mut white_remote = accepting.join().unwrap();
+
+        let mut white_game = Game::new();
+        let mut black_game = Game::new();
+        let mut white = HumanPending::new();
+        let mut black = HumanPending::new();
+
+        white.submit("d2d4".parse().unwrap());
+        assert_eq!(
+            white_game.play_players(&mut white, &mut black_remote, None),
+            PlayOutcome::Pending(Color::Black)
+        );
+        black.resign();
+        assert_eq!(
+            play_until(&mut black_game, &mut white_remote, &mut black, Color::White),
+            PlayOutcome::Resigned(Color::Black)
+        );
+        white_remote.resign().unwrap();
+        assert_eq!(
+            play_until(&mut white_game, &mut white, &mut black_remote, Color::Black),
+            PlayOutcome::Resigned(Color::Black)
+        );
+        assert_eq!(white_game.get_moves(), black_game.get_moves());
+    }
+
+    #[test]
+    fn playing_over_tcp() {
+        let server = NetworkServer::bind("127.0.0.1:0").unwrap();
+        let mut black_remote = NetworkPlayer::connect(server.local_addr().unwrap()).unwrap();
+        let mut white_remote = server.accept().unwrap();
+
+        let mut white_game = Game::new();
+        let mut black_game = Game::new();
+        let mut white = HumanPending::new();
+        let mut black = HumanPending::new();
+
+        white.submit("e2e4".parse().unwrap());
+        assert_eq!(
+            white_game.play_players(&mut white, &mut black_remote, None),
+            PlayOutcome::Pending(Color::Black)
+        );
+        assert_eq!(
+            play_until(&mut black_game, &mut white_remote, &mut black, Color::White),
+            PlayOutcome::Pending(Color::Black)
+        );
+
+        black.submit("e8e1".parse().unwrap());
+        assert!(matches!(
+            black_game.play_players(&mut white_remote, &mut black, None),
+            PlayOutcome::IllegalMove(Color::Black, _)
+        ));
+        black.submit("e7e5".parse().unwrap());
+        assert_eq!(
+            black_game.play_players(&mut white_remote, &mut black, None),
+            PlayOutcome::Pending(Color::White)
+        );
+        assert_eq!(
+            play_until(&mut white_game, &mut white, &mut black_remote, Color::Black),
+            PlayOutcome::Pending(Color::White)
+        );
+        assert_eq!(white_game.get_moves(), black_game.get_moves());
+
+        white_remote.resign().unwrap();
+        assert_eq!(
+            play_until(&mut white_game, &mut white, &mut black_remote, Color::Black),
+            PlayOutcome::Pending(Color::White)
+        );
+        white.submit("g1f3".parse().unwrap());
+        assert_eq!(
+            play_until(&mut white_game, &mut white, &mut black_remote, Color::Black),
+            PlayOutcome::Resigned(Color::Black)
+        );
+
+        drop(white_remote);
+        assert_eq!(
+            play_until(&mut white_game, &mut white, &mut black_remote, Color::Black),
+            PlayOutcome::Resigned(Color::Black)
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! Reading and writing moves in ICCF numeric notation, used in
+//! correspondence chess. Squares are written as their file and rank
+//! numbers, so `e2e4` is `5254`, and promotions add a digit for the
+//! piece: 1 for a queen, 2 for a rook, 3 for a bishop and 4 for a
+//! knight. Castling is written as the king's move, e.g. `5171`.
+
+use crate::board::{Board, Move, SquareSpec};
+use crate::error::Error;
+use crate::notation::uci;
+use crate::piece::PieceType;
+
+/// Parse a move in ICCF numeric notation as played on `board`. Like
+/// with [`uci::parse`], the board is only needed to tell castling
+/// apart from normal king moves, and the move isn't checked for
+/// legality.
+///
+/// # Errors
+///
+/// Will return an error if the string is not a valid ICCF move
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::{Board, Castling, Move};
+/// # use chess_engine::notation::iccf;
+/// let board = Board::load_fen("4k3/1P6/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+///
+/// assert_eq!(iccf::parse(&board, "5171").unwrap(), Move::Castling(Castling::Short));
+/// assert_eq!(iccf::parse(&board, "27284").unwrap(), "b7b8=N".parse().unwrap());
+/// assert!(iccf::parse(&board, "5979").is_err());
+/// ```
+pub fn parse(board: &Board, s: &str) -> Result<Move, Error> {
+    let invalid = || Error::InvalidMove(s.to_string());
+    let digits = s
+        .chars()
+        .map(|c| c.to_digit(10))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(invalid)?;
+    let square = |file: u32, rank: u32| match (file, rank) {
+        (1..=8, 1..=8) => Ok(SquareSpec::new(rank - 1, file - 1)),
+        _ => Err(invalid()),
+    };
+
+    match *digits.as_slice() {
+        [from_file, from_rank, to_file, to_rank] => {
+            let from = square(from_file, from_rank)?;
+            let to = square(to_file, to_rank)?;
+            // reuse the castling detection of UCI, which writes
+            // castling the same way
+            uci::parse(board, &format!("{}{}", from, to))
+        }
+        [from_file, from_rank, to_file, to_rank, piece] => {
+            let target = match piece {
+                1 => PieceType::Queen,
+                2 => PieceType::Rook,
+                3 => PieceType::Bishop,
+                4 => PieceType::Knight,
+                _ => return Err(invalid()),
+            };
+            Ok(Move::Promotion {
+                from: square(from_file, from_rank)?,
+                to: square(to_file, to_rank)?,
+                target,
+            })
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// Write a move played on `board` in ICCF numeric notation.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::{Board, Castling, Move};
+/// # use chess_engine::notation::iccf;
+/// let board = Board::load_fen("4k3/6P1/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+///
+/// assert_eq!(iccf::format(&board, Move::Castling(Castling::Long)), "5131");
+/// assert_eq!(iccf::format(&board, "g7g8=Q".parse().unwrap()), "77781");
+/// ```
+pub fn format(board: &Board, m: Move) -> String {
+    let (from, to) = (m.from(board.turn()), m.to(board.turn()));
+    let mut s = format!(
+        "{}{}{}{}",
+        from.file + 1,
+        from.rank + 1,
+        to.file + 1,
+        to.rank + 1
+    );
+    if let Move::Promotion { target, .. } = m {
+        s.push(match target {
+            PieceType::Rook => '2',
+            PieceType::Bishop => '3',
+            PieceType::Knight => '4',
+            // pawns and kings can't be promoted to
+            _ => '1',
+        });
+    }
+    s
+}
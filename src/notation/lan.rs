@@ -0,0 +1,177 @@
+//! Reading and writing moves in long algebraic notation, which
+//! writes out both squares of every move, e.g. `Ng1-f3`, `e2-e4`,
+//! `e7xd8=Q+` and `O-O`.
+
+use crate::board::{Board, Castling, Move, SquareSpec};
+use crate::error::Error;
+use crate::piece::PieceType;
+
+/// Parse a move in long algebraic notation as played on `board`.
+/// Check and annotation suffixes (`+`, `#`, `!`, `?`) are ignored,
+/// and the `-` or `x` between the squares is optional, but has to be
+/// right if given. The move has to be legal.
+///
+/// # Errors
+///
+/// Will return an error if the notation is malformed or doesn't fit
+/// the board, e.g. by naming the wrong piece, or if the move is
+/// illegal
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::Board;
+/// # use chess_engine::notation::lan;
+/// let board = Board::default_board();
+///
+/// assert_eq!(lan::parse(&board, "Ng1-f3").unwrap(), "g1f3".parse().unwrap());
+/// assert_eq!(lan::parse(&board, "e2e4").unwrap(), "e2e4".parse().unwrap());
+/// assert!(lan::parse(&board, "Bg1-f3").is_err());
+/// assert!(lan::parse(&board, "e2xe4").is_err());
+/// ```
+pub fn parse(board: &Board, s: &str) -> Result<Move, Error> {
+    let invalid = || Error::InvalidMove(s.to_string());
+    let text = s.trim_end_matches(['+', '#', '!', '?']);
+
+    let m = if let Ok(c) = text.parse() {
+        Move::Castling(c)
+    } else {
+        let (piece, rest) = match text.chars().next() {
+            Some(c @ ('K' | 'Q' | 'R' | 'B' | 'N')) => (c.to_string().parse()?, &text[1..]),
+            Some(_) => (PieceType::Pawn, text),
+            None => return Err(invalid()),
+        };
+        if rest.len() < 4 || !rest.is_char_boundary(2) {
+            return Err(invalid());
+        }
+        let from = rest[..2].parse::<SquareSpec>().map_err(|_| invalid())?;
+        let rest = &rest[2..];
+        let (capture, rest) = match rest.chars().next() {
+            Some('x') => (Some(true), &rest[1..]),
+            Some('-') => (Some(false), &rest[1..]),
+            _ => (None, rest),
+        };
+        if rest.len() < 2 || !rest.is_char_boundary(2) {
+            return Err(invalid());
+        }
+        let to = rest[..2].parse::<SquareSpec>().map_err(|_| invalid())?;
+        let promotion = match &rest[2..] {
+            "" => None,
+            p => Some(p.strip_prefix('=').unwrap_or(p).parse::<PieceType>()?),
+        };
+
+        if board[from].map_or(true, |p| p.piece != piece) {
+            return Err(invalid());
+        }
+        if capture.map_or(false, |capture| capture != is_capture(board, from, to)) {
+            return Err(invalid());
+        }
+        match promotion {
+            Some(target) => Move::Promotion { from, to, target },
+            None => Move::Normal { from, to },
+        }
+    };
+
+    if board.is_legal(m, board.turn()) {
+        Ok(m)
+    } else {
+        Err(Error::IllegalMove(board.to_fen(), m))
+    }
+}
+
+/// Write a legal move played on `board` in long algebraic notation,
+/// including a `+` or `#` suffix for check and checkmate.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::{Board, Castling, Move};
+/// # use chess_engine::notation::lan;
+/// let board = Board::load_fen("3rk3/4P3/8/8/8/8/8/4K1N1 w - - 0 1").unwrap();
+///
+/// assert_eq!(lan::format(&board, "g1f3".parse().unwrap()), "Ng1-f3");
+/// assert_eq!(lan::format(&board, "e7d8=Q".parse().unwrap()), "e7xd8=Q+");
+/// ```
+pub fn format(board: &Board, m: Move) -> String {
+    let mut s = match m {
+        Move::Castling(Castling::Short) => "O-O".to_string(),
+        Move::Castling(Castling::Long) => "O-O-O".to_string(),
+        Move::Normal { from, to } | Move::Promotion { from, to, .. } => {
+            let mut s = String::new();
+            match board[from] {
+                Some(p) if p.piece != PieceType::Pawn => s.push_str(&p.piece.to_string()),
+                _ => (),
+            }
+            s.push_str(&from.to_string());
+            s.push(if is_capture(board, from, to) {
+                'x'
+            } else {
+                '-'
+            });
+            s.push_str(&to.to_string());
+            if let Move::Promotion { target, .. } = m {
+                s.push('=');
+                s.push_str(&target.to_string());
+            }
+            s
+        }
+    };
+
+    match board.perform_move_with_outcome(m) {
+        Some(outcome) if outcome.mate => s.push('#'),
+        Some(outcome) if outcome.check => s.push('+'),
+        _ => (),
+    }
+    s
+}
+
+// Pawns only change file when capturing, which also covers en passant
+fn is_capture(board: &Board, from: SquareSpec, to: SquareSpec) -> bool {
+    board[to].is_some()
+        || (matches!(board[from], Some(p) if p.piece == PieceType::Pawn) && from.file != to.file)
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "fen")]
+    use super::format;
+    use super::parse;
+    use crate::board::Board;
+    #[cfg(feature = "fen")]
+    use crate::board::{Castling, Move};
+
+    #[cfg(feature = "fen")]
+    #[test]
+    fn round_trips() {
+        let board = Board::load_fen("r3k3/1P6/8/3pP3/8/8/8/4K2R w Kq d6 0 1").unwrap();
+        let cases = [
+            ("e5xd6", "e5d6"),
+            ("e5-e6", "e5e6"),
+            ("b7xa8=Q+", "b7a8=Q"),
+            ("b7-b8=N", "b7b8=N"),
+            ("Rh1-h8+", "h1h8"),
+        ];
+
+        for &(lan, m) in &cases {
+            let m = m.parse::<Move>().unwrap();
+            assert_eq!(format(&board, m), lan);
+            assert_eq!(parse(&board, lan).unwrap(), m, "{}", lan);
+        }
+        assert_eq!(format(&board, Move::Castling(Castling::Short)), "O-O");
+        assert_eq!(
+            parse(&board, "O-O").unwrap(),
+            Move::Castling(Castling::Short)
+        );
+    }
+
+    #[test]
+    fn rejects_nonsense() {
+        let board = Board::default_board();
+
+        for s in &[
+            "", "e2", "e2-", "Pe2-e4", "Ng1-f4", "e2-e4=Q", "Ng1xf3", "O-O",
+        ] {
+            assert!(parse(&board, s).is_err(), "{}", s);
+        }
+    }
+}
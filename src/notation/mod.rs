@@ -0,0 +1,119 @@
+//! Module for reading moves written in the common chess notations.
+//! Unlike [`Move`](crate::board::Move)'s own coordinate notation,
+//! these generally need the board the move is played on to be
+//! interpreted.
+
+pub mod iccf;
+pub mod lan;
+#[cfg(feature = "san")]
+pub mod san;
+pub mod uci;
+
+use crate::board::{Board, Move};
+use crate::error::Error;
+
+/// The notations moves can be read and written in, for picking one
+/// at runtime
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Notation {
+    /// Standard Algebraic Notation, see [`san`]
+    #[cfg(feature = "san")]
+    San,
+    /// Long algebraic notation, see [`lan`]
+    Lan,
+    /// The notation of the UCI protocol, see [`uci`]
+    Uci,
+    /// ICCF numeric notation, see [`iccf`]
+    Iccf,
+}
+
+impl Notation {
+    /// All the notations
+    #[cfg(feature = "san")]
+    pub const ALL: [Notation; 4] = [Notation::San, Notation::Lan, Notation::Uci, Notation::Iccf];
+
+    /// All the notations
+    #[cfg(not(feature = "san"))]
+    pub const ALL: [Notation; 3] = [Notation::Lan, Notation::Uci, Notation::Iccf];
+
+    /// Parse a move in this notation as played on `board`. SAN and
+    /// long algebraic notation only accept legal moves, while UCI and
+    /// ICCF notation don't check for legality.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the move isn't valid in the notation
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// # use chess_engine::notation::Notation;
+    /// let board = Board::default_board();
+    /// let moves = Notation::ALL
+    ///     .iter()
+    ///     .zip(&["Nf3", "Ng1-f3", "g1f3", "7163"])
+    ///     .map(|(notation, s)| notation.parse(&board, s).unwrap())
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert!(moves.iter().all(|&m| m == "g1f3".parse().unwrap()));
+    /// ```
+    pub fn parse(self, board: &Board, s: &str) -> Result<Move, Error> {
+        match self {
+            #[cfg(feature = "san")]
+            Notation::San => san::parse(board, s),
+            Notation::Lan => lan::parse(board, s),
+            Notation::Uci => uci::parse(board, s),
+            Notation::Iccf => iccf::parse(board, s),
+        }
+    }
+
+    /// Write a move played on `board` in this notation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// # use chess_engine::notation::Notation;
+    /// let board = Board::default_board();
+    /// let m = "e2e4".parse().unwrap();
+    ///
+    /// assert_eq!(Notation::San.format(&board, m), "e4");
+    /// assert_eq!(Notation::Lan.format(&board, m), "e2-e4");
+    /// assert_eq!(Notation::Iccf.format(&board, m), "5254");
+    /// ```
+    pub fn format(self, board: &Board, m: Move) -> String {
+        match self {
+            #[cfg(feature = "san")]
+            Notation::San => san::format(board, m),
+            Notation::Lan => lan::format(board, m),
+            Notation::Uci => uci::format(board, m),
+            Notation::Iccf => iccf::format(board, m),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "fen"))]
+mod tests {
+    use super::Notation;
+    use crate::board::Board;
+
+    #[test]
+    fn all_notations_round_trip() {
+        let boards = [
+            Board::default_board(),
+            Board::load_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap(),
+            Board::load_fen("r3k2r/8/8/8/3Pp3/8/1p6/R3K2R b KQkq d3 0 1").unwrap(),
+        ];
+
+        for board in &boards {
+            for m in board.get_all_legal_moves() {
+                for &notation in &Notation::ALL {
+                    let s = notation.format(board, m);
+                    let parsed = notation.parse(board, &s).unwrap();
+                    assert_eq!(parsed, m, "{:?} {} on {}", notation, s, board);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,334 @@
+//! Reading and writing moves in Standard Algebraic Notation (SAN), the notation
+//! used by PGN and most chess literature, e.g. `Nf3`, `exd5`,
+//! `Rad1`, `e8=Q+` and `O-O`.
+
+use crate::board::{Board, Castling, Move, SquareSpec};
+use crate::error::Error;
+use crate::piece::PieceType;
+
+/// Parse a move in SAN as played on `board`. Check and annotation
+/// suffixes (`+`, `#`, `!`, `?`) are ignored, and the capture marker
+/// is optional. The move has to be legal, and the notation has to
+/// pick out exactly one legal move.
+///
+/// # Errors
+///
+/// Will return an error if the notation is malformed, or if it
+/// doesn't match exactly one legal move
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::{Board, Castling, Move};
+/// # use chess_engine::notation::san;
+/// # use chess_engine::piece::PieceType;
+/// let board = Board::load_fen("4k3/1P6/8/R7/8/8/8/R3K2R w KQ - 0 1").unwrap();
+///
+/// assert_eq!(san::parse(&board, "Rd1").unwrap(), "a1d1".parse().unwrap());
+/// assert_eq!(
+///     san::parse(&board, "O-O-O").unwrap(),
+///     Move::Castling(Castling::Long)
+/// );
+/// assert_eq!(san::parse(&board, "b8=N+").unwrap(), "b7b8=N".parse().unwrap());
+/// // both rooks on the a-file can go to a3
+/// assert!(san::parse(&board, "Ra3").is_err());
+/// assert_eq!(san::parse(&board, "R1a3").unwrap(), "a1a3".parse().unwrap());
+/// ```
+pub fn parse(board: &Board, s: &str) -> Result<Move, Error> {
+    let invalid = |reason: &str| Error::InvalidSan {
+        text: s.to_string(),
+        reason: reason.to_string(),
+    };
+    let text = s.trim_end_matches(['+', '#', '!', '?']);
+
+    if let Ok(c) = text.parse() {
+        let m = Move::Castling(c);
+        return if board.is_legal(m, board.turn()) {
+            Ok(m)
+        } else {
+            Err(Error::IllegalMove(board.to_fen(), m))
+        };
+    }
+
+    let (piece, rest) = match text.chars().next() {
+        Some(c @ ('K' | 'Q' | 'R' | 'B' | 'N')) => (c.to_string().parse()?, &text[1..]),
+        Some(_) => (PieceType::Pawn, text),
+        None => return Err(invalid("empty move")),
+    };
+    let promotion_target = |target: &str| {
+        target
+            .parse::<PieceType>()
+            .map_err(|_| invalid("invalid promotion piece"))
+    };
+    let (rest, promotion) = match rest.find('=') {
+        Some(i) => (&rest[..i], Some(promotion_target(&rest[i + 1..])?)),
+        // the `=` is sometimes left out, as in `e8Q`
+        None if piece == PieceType::Pawn && rest.ends_with(['Q', 'R', 'B', 'N']) => {
+            let i = rest.len() - 1;
+            (&rest[..i], Some(promotion_target(&rest[i..])?))
+        }
+        None => (rest, None),
+    };
+
+    if rest.len() < 2 || !rest.is_char_boundary(rest.len() - 2) {
+        return Err(invalid("missing destination square"));
+    }
+    let (qualifier, to) = rest.split_at(rest.len() - 2);
+    let to = to
+        .parse::<SquareSpec>()
+        .map_err(|_| invalid("invalid destination square"))?;
+    let qualifier = qualifier.strip_suffix('x').unwrap_or(qualifier);
+
+    let mut file = None;
+    let mut rank = None;
+    for c in qualifier.chars() {
+        match c {
+            'a'..='h' if file.is_none() && rank.is_none() => file = Some(c as u32 - 'a' as u32),
+            '1'..='8' if rank.is_none() => rank = Some(c as u32 - '1' as u32),
+            _ => return Err(invalid("invalid origin square")),
+        }
+    }
+
+    let candidates = board
+        .legal_moves_to(to)
+        .into_iter()
+        .filter(|m| {
+            let target = match *m {
+                Move::Normal { .. } => None,
+                Move::Promotion { target, .. } => Some(target),
+                Move::Castling(_) => return false,
+            };
+            let from = m.from(board.turn());
+            board[from].map_or(false, |p| p.piece == piece)
+                && file.map_or(true, |f| from.file == f)
+                && rank.map_or(true, |r| from.rank == r)
+                && target == promotion
+        })
+        .collect::<Vec<_>>();
+
+    // if several moves fit, the notation is ambiguous
+    match candidates[..] {
+        [m] => Ok(m),
+        [] => Err(invalid("no legal move matches")),
+        _ => Err(invalid("ambiguous move")),
+    }
+}
+
+/// Write a legal move played on `board` in SAN, including a `+` or
+/// `#` suffix for check and checkmate. The origin square is only
+/// qualified as much as needed to tell the move apart from the other
+/// legal moves.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::{Board, Castling, Move};
+/// # use chess_engine::notation::san;
+/// let board = Board::load_fen("6k1/5ppp/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+///
+/// assert_eq!(san::format(&board, "h1d1".parse().unwrap()), "Rhd1");
+/// assert_eq!(san::format(&board, "a1a8".parse().unwrap()), "Ra8#");
+/// assert_eq!(san::format(&board, Move::Castling(Castling::Short)), "O-O");
+/// ```
+pub fn format(board: &Board, m: Move) -> String {
+    format_with(board, m, SanOptions::default())
+}
+
+/// Options for how [`format_with`] writes SAN
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SanOptions {
+    /// Always qualify piece moves with the whole origin square, e.g.
+    /// `Ng1f3`, instead of only as much as is needed to tell the
+    /// move apart from the other legal moves
+    pub long_disambiguation: bool,
+}
+
+/// Write a legal move played on `board` in SAN like [`format`], with
+/// `options` changing how it's written
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::Board;
+/// # use chess_engine::notation::san::{self, SanOptions};
+/// let board = Board::default_board();
+/// let options = SanOptions {
+///     long_disambiguation: true,
+/// };
+///
+/// assert_eq!(san::format_with(&board, "g1f3".parse().unwrap(), options), "Ng1f3");
+/// assert_eq!(san::format_with(&board, "e2e4".parse().unwrap(), options), "e4");
+/// ```
+pub fn format_with(board: &Board, m: Move, options: SanOptions) -> String {
+    let mut s = match m {
+        Move::Castling(Castling::Short) => "O-O".to_string(),
+        Move::Castling(Castling::Long) => "O-O-O".to_string(),
+        Move::Normal { from, to } | Move::Promotion { from, to, .. } => {
+            let piece = board[from].map_or(PieceType::Pawn, |p| p.piece);
+            // pawns only change file when capturing, which also
+            // covers en passant
+            let is_capture =
+                board[to].is_some() || (piece == PieceType::Pawn && from.file != to.file);
+
+            let mut s = String::new();
+            if piece == PieceType::Pawn {
+                if is_capture {
+                    s.push_str(&from.to_string()[..1]);
+                }
+            } else {
+                s.push_str(&piece.to_string());
+                if options.long_disambiguation {
+                    s.push_str(&from.to_string());
+                } else {
+                    s.push_str(&qualifier(board, piece, from, to));
+                }
+            }
+            if is_capture {
+                s.push('x');
+            }
+            s.push_str(&to.to_string());
+            if let Move::Promotion { target, .. } = m {
+                s.push('=');
+                s.push_str(&target.to_string());
+            }
+            s
+        }
+    };
+
+    if board.gives_mate(m) {
+        s.push('#');
+    } else if board.gives_check(m) {
+        s.push('+');
+    }
+    s
+}
+
+// The part of the origin square needed to tell a piece move apart
+// from moves of the same kind of piece to the same square
+fn qualifier(board: &Board, piece: PieceType, from: SquareSpec, to: SquareSpec) -> String {
+    let others = board
+        .legal_moves_to(to)
+        .into_iter()
+        .map(|m| m.from(board.turn()))
+        .filter(|&sq| sq != from && board[sq].map_or(false, |p| p.piece == piece))
+        .collect::<Vec<_>>();
+
+    let square = from.to_string();
+    if others.is_empty() {
+        String::new()
+    } else if others.iter().all(|sq| sq.file != from.file) {
+        square[..1].to_string()
+    } else if others.iter().all(|sq| sq.rank != from.rank) {
+        square[1..].to_string()
+    } else {
+        square
+    }
+}
+
+#[cfg(all(test, feature = "fen"))]
+mod tests {
+    use super::{format, format_with, parse, SanOptions};
+    use crate::board::{Board, Move};
+    use crate::error::Error;
+
+    fn check(fen: &str, san: &str, expected: &str) {
+        let board = Board::load_fen(fen).unwrap();
+        assert_eq!(
+            parse(&board, san).unwrap(),
+            expected.parse::<Move>().unwrap(),
+            "{} on {}",
+            san,
+            fen
+        );
+    }
+
+    #[test]
+    fn pawn_moves() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        check(start, "e4", "e2e4");
+        check(start, "e3", "e2e3");
+        check(
+            "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+            "exd5",
+            "e4d5",
+        );
+        check("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "exd6", "e5d6");
+        check("3rk3/4P3/8/8/8/8/8/4K3 w - - 0 1", "exd8=Q+", "e7d8=Q");
+        check("4k3/2P5/8/8/8/8/8/4K3 w - - 0 1", "c8R", "c7c8=R");
+    }
+
+    #[test]
+    fn disambiguation() {
+        let fen = "4k3/8/8/8/8/1N3N2/8/1N2K3 w - - 0 1";
+        check(fen, "N1d2", "b1d2");
+        check(fen, "Nfd2", "f3d2");
+        check(fen, "Nb3xd2", "b3d2");
+        let board = Board::load_fen(fen).unwrap();
+        assert!(matches!(
+            parse(&board, "Nd2"),
+            Err(Error::InvalidSan { reason, .. }) if reason == "ambiguous move"
+        ));
+        assert!(matches!(
+            parse(&board, "Nbd2"),
+            Err(Error::InvalidSan { reason, .. }) if reason == "ambiguous move"
+        ));
+        assert!(matches!(
+            parse(&board, "Nc2"),
+            Err(Error::InvalidSan { reason, .. }) if reason == "no legal move matches"
+        ));
+    }
+
+    #[test]
+    fn format_round_trips() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "4k3/8/8/8/8/1N3N2/8/1N2K3 w - - 0 1",
+            "3rk3/4P3/8/8/8/8/8/4K3 w - - 0 1",
+            "Q3Q3/8/7k/8/Q7/8/8/4K3 w - - 0 1",
+        ];
+        for fen in fens {
+            let board = Board::load_fen(fen).unwrap();
+            for m in board.get_all_legal_moves() {
+                let san = format(&board, m);
+                assert_eq!(parse(&board, &san).unwrap(), m, "{} on {}", san, fen);
+            }
+        }
+
+        let board = Board::load_fen(fens[4]).unwrap();
+        assert_eq!(format(&board, "a8e4".parse().unwrap()), "Qa8e4");
+        assert_eq!(format(&board, "a8a7".parse().unwrap()), "Q8a7");
+        assert_eq!(format(&board, "a4e4".parse().unwrap()), "Q4e4");
+        assert_eq!(format(&board, "e8h5".parse().unwrap()), "Qh5+");
+    }
+
+    #[test]
+    fn long_disambiguation_round_trips() {
+        let options = SanOptions {
+            long_disambiguation: true,
+        };
+        let board =
+            Board::load_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        for m in board.get_all_legal_moves() {
+            let san = format_with(&board, m, options);
+            assert_eq!(parse(&board, &san).unwrap(), m, "{}", san);
+        }
+        assert_eq!(
+            format_with(&board, "e5f7".parse().unwrap(), options),
+            "Ne5xf7"
+        );
+        assert_eq!(
+            format_with(&board, "d5e6".parse().unwrap(), options),
+            "dxe6"
+        );
+    }
+
+    #[test]
+    fn rejects_nonsense() {
+        let board = Board::default_board();
+        for s in ["", "e", "e5", "Ke2", "Zf3", "exd3", "O-O", "Nf3x"] {
+            assert!(parse(&board, s).is_err(), "{} was accepted", s);
+        }
+    }
+}
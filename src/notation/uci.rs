@@ -0,0 +1,71 @@
+//! Reading and writing moves in the notation used by the UCI
+//! protocol, e.g. `e2e4`, `e7e8q`, and `e1g1` for castling.
+
+use crate::board::{Board, Castling, Move};
+use crate::error::Error;
+use crate::piece::PieceType;
+
+/// Parse a move in UCI notation as played on `board`. The only
+/// difference from [`Move`]'s own coordinate notation is that
+/// castling is written as the king moving two squares, which needs
+/// the board to tell apart from a normal king move. The move isn't
+/// checked for legality.
+///
+/// # Errors
+///
+/// Will return an error if the string is not a valid UCI move
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::{Board, Castling, Move};
+/// # use chess_engine::notation::uci;
+/// let board = Board::load_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+///
+/// assert_eq!(
+///     uci::parse(&board, "e1g1").unwrap(),
+///     Move::Castling(Castling::Short)
+/// );
+/// assert_eq!(
+///     uci::parse(&board, "e1f1").unwrap(),
+///     "e1f1".parse::<Move>().unwrap()
+/// );
+/// assert!(uci::parse(&board, "O-O").is_err());
+/// ```
+pub fn parse(board: &Board, s: &str) -> Result<Move, Error> {
+    // castling is only written as a king move in UCI
+    if s.contains('-') {
+        return Err(Error::InvalidMove(s.to_string()));
+    }
+    let m = s.parse::<Move>()?;
+    if let Move::Normal { from, to } = m {
+        let is_king = matches!(board[from], Some(p) if p.piece == PieceType::King);
+        let turn = board.turn();
+        for &c in &Castling::ALL {
+            if is_king && from == c.king_from(turn) && to == c.king_to(turn) {
+                return Ok(Move::Castling(c));
+            }
+        }
+    }
+    Ok(m)
+}
+
+/// Write a move played on `board` in UCI notation.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::{Board, Castling, Move};
+/// # use chess_engine::notation::uci;
+/// let board = Board::load_fen("4k3/6P1/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+///
+/// assert_eq!(uci::format(&board, Move::Castling(Castling::Long)), "e1c1");
+/// assert_eq!(uci::format(&board, "g7g8=N".parse().unwrap()), "g7g8n");
+/// ```
+pub fn format(board: &Board, m: Move) -> String {
+    let color = board.turn();
+    match m {
+        Move::Promotion { from, to, target } => format!("{}{}{}", from, to, target).to_lowercase(),
+        _ => format!("{}{}", m.from(color), m.to(color)),
+    }
+}
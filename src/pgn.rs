@@ -0,0 +1,926 @@
+//! Module for reading and writing games in Portable Game Notation
+//! (PGN).
+//!
+//! Comments, variations and numeric annotation glyphs are read into
+//! the [`Annotation`]s of a game's moves, and written back out along
+//! with the moves.
+
+use crate::board::{Board, Move};
+use crate::error::Error;
+use crate::game::{Game, GameResult, GameState};
+use crate::notation::san;
+use crate::piece::Color;
+use std::fmt;
+
+// The longest a line of movetext is allowed to be when writing PGN
+const LINE_LENGTH: usize = 80;
+
+/// A game read from PGN, along with its tag pairs
+#[derive(Debug, Clone)]
+pub struct PgnGame {
+    /// The tag pairs of the game, such as `("White", "Carlsen")`, in
+    /// the order they were written
+    pub tags: Vec<(String, String)>,
+    /// The game itself, with all moves of the main line played
+    pub game: Game,
+    /// The game termination marker, e.g. `1-0` or `*`, if there was
+    /// one
+    pub result: Option<String>,
+    /// Comments before the first move of the game
+    pub comments: Vec<String>,
+    /// The annotations of the moves of the game, in the order the
+    /// moves were played. Moves past the end have no annotations.
+    pub annotations: Vec<Annotation>,
+}
+
+/// The annotations of a move in PGN
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Annotation {
+    /// The numeric annotation glyphs of the move, e.g. 1 for a good
+    /// move, written `$1` or `!`
+    pub nags: Vec<u8>,
+    /// The comments after the move, with their whitespace collapsed
+    pub comments: Vec<String>,
+    /// Lines that could have been played instead of the move, from
+    /// the position before it
+    pub variations: Vec<Variation>,
+}
+
+/// A line of moves in PGN that could have been played instead of a
+/// move of the game, or of another variation
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Variation {
+    /// Comments before the first move of the variation
+    pub comments: Vec<String>,
+    /// The moves of the variation
+    pub moves: Vec<Move>,
+    /// The annotations of each of the moves
+    pub annotations: Vec<Annotation>,
+}
+
+impl PgnGame {
+    /// Create a PGN game with the seven tags every PGN game should
+    /// have, all set to unknown (`?`) except for the result, which is
+    /// taken from the state of the game. If the game didn't end
+    /// normally, e.g. on time or by adjudication, a `Termination` tag
+    /// saying so is added as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::game::Game;
+    /// # use chess_engine::pgn::PgnGame;
+    /// let mut game = Game::new();
+    /// for m in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+    ///     game.make_move(m.parse().unwrap());
+    /// }
+    /// let mut pgn = PgnGame::new(game);
+    /// pgn.tags[0].1 = "Casual game".to_string();
+    ///
+    /// assert_eq!(
+    ///     pgn.to_string(),
+    ///     "[Event \"Casual game\"]\n\
+    ///      [Site \"?\"]\n\
+    ///      [Date \"????.??.??\"]\n\
+    ///      [Round \"?\"]\n\
+    ///      [White \"?\"]\n\
+    ///      [Black \"?\"]\n\
+    ///      [Result \"0-1\"]\n\
+    ///      \n\
+    ///      1. f3 e5 2. g4 Qh4# 0-1\n"
+    /// );
+    /// ```
+    pub fn new(game: Game) -> PgnGame {
+        let result = match game.state() {
+            GameState::Finished(GameResult::Win { winner, .. }) => match winner {
+                Color::White => "1-0",
+                Color::Black => "0-1",
+            },
+            GameState::Finished(GameResult::Draw(_)) => "1/2-1/2",
+            GameState::Ongoing { .. } => "*",
+        };
+        let tags = [
+            ("Event", "?"),
+            ("Site", "?"),
+            ("Date", "????.??.??"),
+            ("Round", "?"),
+            ("White", "?"),
+            ("Black", "?"),
+            ("Result", result),
+        ];
+
+        let mut tags: Vec<_> = tags
+            .iter()
+            .map(|(n, v)| (n.to_string(), v.to_string()))
+            .collect();
+        if let GameState::Finished(result) = game.state() {
+            if result.termination() != "normal" {
+                tags.push(("Termination".to_string(), result.termination().to_string()));
+            }
+        }
+
+        PgnGame {
+            tags,
+            game,
+            result: Some(result.to_string()),
+            comments: Vec::new(),
+            annotations: Vec::new(),
+        }
+    }
+
+    /// Get the value of a tag, if the game has it
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+impl fmt::Display for PgnGame {
+    /// Write the game as PGN. If the game doesn't start from the
+    /// default position, the `SetUp` and `FEN` tags are added unless
+    /// they are already there.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in &self.tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{} \"{}\"]", name, value)?;
+        }
+        let boards: Vec<_> = self.game.boards().collect();
+        let start = boards[0];
+        if start != Board::default_board() && self.tag("FEN").is_none() {
+            writeln!(f, "[SetUp \"1\"]")?;
+            writeln!(f, "[FEN \"{}\"]", start.to_fen())?;
+        }
+        writeln!(f)?;
+
+        let mut tokens = Vec::new();
+        write_line(
+            &mut tokens,
+            start,
+            &self.comments,
+            self.game.get_moves(),
+            &self.annotations,
+        );
+        tokens.push(self.result.clone().unwrap_or_else(|| "*".to_string()));
+
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.len() > LINE_LENGTH {
+                writeln!(f)?;
+                line_length = 0;
+            } else if line_length > 0 {
+                write!(f, " ")?;
+                line_length += 1;
+            }
+            write!(f, "{}", token)?;
+            line_length += token.len();
+        }
+        writeln!(f)
+    }
+}
+
+// Write the tokens of a line of moves played from `board`, with their
+// annotations and the comments before them. Comments are split into
+// words so that long ones can be wrapped.
+fn write_line(
+    tokens: &mut Vec<String>,
+    mut board: Board,
+    comments: &[String],
+    moves: &[Move],
+    annotations: &[Annotation],
+) {
+    for comment in comments {
+        write_comment(tokens, comment);
+    }
+    // black moves need their number written after anything but the
+    // white move before them
+    let mut needs_number = true;
+    for (i, &m) in moves.iter().enumerate() {
+        match board.turn() {
+            Color::White => tokens.push(format!("{}.", board.fullmove())),
+            Color::Black if needs_number => tokens.push(format!("{}...", board.fullmove())),
+            Color::Black => {}
+        }
+        tokens.push(san::format(&board, m));
+        needs_number = false;
+
+        if let Some(annotation) = annotations.get(i) {
+            tokens.extend(annotation.nags.iter().map(|nag| format!("${}", nag)));
+            for comment in &annotation.comments {
+                write_comment(tokens, comment);
+                needs_number = true;
+            }
+            for variation in &annotation.variations {
+                let first = tokens.len();
+                write_line(
+                    tokens,
+                    board,
+                    &variation.comments,
+                    &variation.moves,
+                    &variation.annotations,
+                );
+                if tokens.len() == first {
+                    tokens.push("()".to_string());
+                } else {
+                    tokens[first].insert(0, '(');
+                    tokens.last_mut().unwrap().push(')');
+                }
+                needs_number = true;
+            }
+        }
+        board = board.perform_move_unvalidated(m);
+    }
+}
+
+// Write a comment in braces. PGN has no way to escape a `}` in a
+// comment, so braces in it are written as parentheses instead.
+fn write_comment(tokens: &mut Vec<String>, comment: &str) {
+    let first = tokens.len();
+    tokens.extend(
+        comment
+            .split_whitespace()
+            .map(|word| word.replace('{', "(").replace('}', ")")),
+    );
+    if tokens.len() == first {
+        tokens.push("{}".to_string());
+    } else {
+        tokens[first].insert(0, '{');
+        tokens.last_mut().unwrap().push('}');
+    }
+}
+
+/// Read a single game in PGN. If the game has a `FEN` tag, the game
+/// starts from that position instead of the default one.
+///
+/// # Errors
+///
+/// Will return an error if the PGN is malformed or contains illegal
+/// moves
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::pgn;
+/// let pgn = r#"[Event "Casual game"]
+/// [Result "0-1"]
+///
+/// 1. f3 e5 {a comment} 2. g4 (2. e4) 2... Qh4# 0-1"#;
+/// let game = pgn::parse(pgn).unwrap();
+///
+/// assert_eq!(game.tag("Event"), Some("Casual game"));
+/// assert_eq!(game.result.as_deref(), Some("0-1"));
+/// assert_eq!(game.game.get_moves().len(), 4);
+/// assert_eq!(game.annotations[1].comments, ["a comment"]);
+/// assert_eq!(game.annotations[2].variations[0].moves, ["e2e4".parse().unwrap()]);
+/// ```
+pub fn parse(s: &str) -> Result<PgnGame, Error> {
+    let lines: Vec<_> = s.lines().enumerate().collect();
+    parse_game(0, &lines).map_err(|diagnostic| diagnostic.error)
+}
+
+/// Where and why reading a game of PGN failed
+#[derive(Debug)]
+pub struct PgnDiagnostic {
+    /// The index of the game in the input, starting from 0
+    pub game: usize,
+    /// The line the offending token is on, starting from 1
+    pub line: usize,
+    /// The column the offending token starts at, in characters
+    /// starting from 1
+    pub column: usize,
+    /// The offending token, e.g. an illegal move or a malformed tag
+    /// pair, which is empty if the game ended too early
+    pub token: String,
+    /// What was wrong with the token
+    pub error: Error,
+}
+
+impl fmt::Display for PgnDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "game {}, line {}, column {}: {} (at `{}`)",
+            self.game, self.line, self.column, self.error, self.token
+        )
+    }
+}
+
+impl std::error::Error for PgnDiagnostic {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Read all games of a PGN database, stopping at the first game that
+/// can't be read. A new game starts at each tag pair after the
+/// movetext of the game before it.
+///
+/// # Errors
+///
+/// Will return a diagnostic pointing at the first malformed part of
+/// the PGN, or at the first illegal move
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::pgn;
+/// let pgn = "[White \"Tal\"]\n\n1. e4 e5 1-0\n\n[White \"Botvinnik\"]\n\n1. d4 Ke7 0-1\n";
+/// let error = pgn::parse_all(pgn).unwrap_err();
+///
+/// assert_eq!((error.game, error.line, error.column), (1, 7, 7));
+/// assert_eq!(error.token, "Ke7");
+/// ```
+pub fn parse_all(s: &str) -> Result<Vec<PgnGame>, PgnDiagnostic> {
+    split_games(s)
+        .iter()
+        .enumerate()
+        .map(|(index, lines)| parse_game(index, lines))
+        .collect()
+}
+
+/// Read all games of a PGN database like [`parse_all`], but skip the
+/// games that can't be read, returning a diagnostic for each of them
+/// along with the games that could. This is meant for scraped
+/// databases, where a few broken games shouldn't stop the rest from
+/// being read.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::pgn;
+/// let pgn = "1. e4 e5 *\n\n[Round \"2\"]\n\n1. e4 {oops *\n\n[Round \"3\"]\n\n1. d4 *\n";
+/// let (games, diagnostics) = pgn::parse_all_lossy(pgn);
+///
+/// assert_eq!(games.len(), 2);
+/// assert_eq!(games[1].tag("Round"), Some("3"));
+/// assert_eq!(diagnostics.len(), 1);
+/// assert_eq!((diagnostics[0].game, diagnostics[0].line), (1, 5));
+/// ```
+pub fn parse_all_lossy(s: &str) -> (Vec<PgnGame>, Vec<PgnDiagnostic>) {
+    let mut games = Vec::new();
+    let mut diagnostics = Vec::new();
+    for (index, lines) in split_games(s).iter().enumerate() {
+        match parse_game(index, lines) {
+            Ok(game) => games.push(game),
+            Err(diagnostic) => diagnostics.push(diagnostic),
+        }
+    }
+    (games, diagnostics)
+}
+
+// Split the lines of a PGN database into the numbered lines of each
+// game, where a tag pair after some movetext starts a new game
+fn split_games(s: &str) -> Vec<Vec<(usize, &str)>> {
+    let mut games = Vec::new();
+    let mut game = Vec::new();
+    let mut has_movetext = false;
+    for (number, line) in s.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && has_movetext {
+            games.push(std::mem::take(&mut game));
+            has_movetext = false;
+        }
+        has_movetext = has_movetext
+            || !(trimmed.is_empty() || trimmed.starts_with('[') || trimmed.starts_with('%'));
+        game.push((number, line));
+    }
+    if game.iter().any(|(_, line)| !line.trim().is_empty()) {
+        games.push(game);
+    }
+    games
+}
+
+// Read the game with index `index` from its numbered lines
+fn parse_game(index: usize, lines: &[(usize, &str)]) -> Result<PgnGame, PgnDiagnostic> {
+    let diagnostic = |line: usize, column: usize, token: &str, error: Error| PgnDiagnostic {
+        game: index,
+        line: line + 1,
+        column: column + 1,
+        token: token.to_string(),
+        error,
+    };
+
+    let mut tags = Vec::new();
+    let mut tag_lines = Vec::new();
+    let mut movetext = String::new();
+    // where each line of the movetext starts in it, and on which line
+    // and column of the input
+    let mut origins = Vec::new();
+    for &(number, raw) in lines {
+        let line = raw.trim();
+        let column = raw[..raw.len() - raw.trim_start().len()].chars().count();
+        if line.starts_with('[') && movetext.trim().is_empty() {
+            tags.push(parse_tag(line).map_err(|e| diagnostic(number, column, line, e))?);
+            tag_lines.push((number, column, line));
+        } else if !line.starts_with('%') {
+            // lines starting with `%` are escaped and should be ignored
+            origins.push((movetext.len(), number, column));
+            movetext.push_str(line);
+            movetext.push('\n');
+        }
+    }
+
+    let mut game = match tags.iter().position(|(n, _)| n == "FEN") {
+        Some(i) => {
+            let (number, column, line) = tag_lines[i];
+            let board =
+                Board::load_fen(&tags[i].1).map_err(|e| diagnostic(number, column, line, e))?;
+            Game::from_board(board)
+        }
+        None => Game::new(),
+    };
+
+    let end = lines.last().map_or(0, |&(number, _)| number);
+    let locate = |offset: usize, token: &str, error: Error| {
+        let (start, number, column) = origins
+            .iter()
+            .rev()
+            .find(|&&(start, _, _)| start <= offset)
+            .copied()
+            .unwrap_or((0, end, 0));
+        let column = column + movetext[start..offset].chars().count();
+        diagnostic(number, column, token, error)
+    };
+    let at_last = |tokens: &Tokens<'_>, error: Error| match tokens.last {
+        Some((offset, token)) => locate(offset, &token.to_string(), error),
+        None => locate(movetext.len(), "", error),
+    };
+
+    let tokens = tokenize(&movetext).map_err(|(offset, error)| {
+        let token = movetext[offset..].split_whitespace().next().unwrap_or("");
+        locate(offset, token, error)
+    })?;
+    let mut tokens = Tokens {
+        tokens: tokens.into_iter(),
+        last: None,
+    };
+    let mut result = None;
+    let board = *game.current_board();
+    let line = parse_line(board, &mut tokens, &mut result, Some(&mut game))
+        .map_err(|error| at_last(&tokens, error))?;
+    if let Some(token) = tokens.next() {
+        let error = Error::InvalidPgn(format!("`{}` after the game termination marker", token));
+        return Err(at_last(&tokens, error));
+    }
+
+    Ok(PgnGame {
+        tags,
+        game,
+        result,
+        comments: line.comments,
+        annotations: line.annotations,
+    })
+}
+
+// Parse a line of moves played from `board`, up to the game
+// termination marker, which is stored in `result`, if it's the main
+// line of `game`, or else up to the end of the variation. The moves
+// of the main line are played in `game` as they're read.
+fn parse_line(
+    mut board: Board,
+    tokens: &mut Tokens<'_>,
+    result: &mut Option<String>,
+    mut game: Option<&mut Game>,
+) -> Result<Variation, Error> {
+    let nested = game.is_none();
+    let mut line = Variation::default();
+    // the board before the last move, where its variations start
+    let mut before = board;
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Move(s) => {
+                let m = san::parse(&board, s)?;
+                before = board;
+                board = board
+                    .perform_move(m)
+                    .ok_or_else(|| Error::IllegalMove(board.to_fen(), m))?;
+                if let Some(game) = game.as_deref_mut() {
+                    if game.make_move(m).is_none() {
+                        return Err(Error::IllegalMove(game.current_board().to_fen(), m));
+                    }
+                }
+                line.moves.push(m);
+                line.annotations.push(Annotation::default());
+            }
+            Token::Nag(nag) => match line.annotations.last_mut() {
+                Some(annotation) => annotation.nags.push(nag),
+                None => return Err(Error::InvalidPgn(format!("`${}` before any move", nag))),
+            },
+            Token::Comment(comment) => {
+                let comment = comment.split_whitespace().collect::<Vec<_>>().join(" ");
+                match line.annotations.last_mut() {
+                    Some(annotation) => annotation.comments.push(comment),
+                    None => line.comments.push(comment),
+                }
+            }
+            Token::Open => {
+                let variation = parse_line(before, tokens, result, None)?;
+                match line.annotations.last_mut() {
+                    Some(annotation) => annotation.variations.push(variation),
+                    None => return Err(Error::InvalidPgn("variation before any move".to_string())),
+                }
+            }
+            Token::Close if nested => return Ok(line),
+            Token::Close => return Err(Error::InvalidPgn("unmatched `)`".to_string())),
+            Token::Result(r) if !nested => {
+                *result = Some(r.to_string());
+                return Ok(line);
+            }
+            Token::Result(r) => {
+                return Err(Error::InvalidPgn(format!("`{}` inside a variation", r)))
+            }
+        }
+    }
+
+    if nested {
+        Err(Error::InvalidPgn("unterminated variation".to_string()))
+    } else {
+        Ok(line)
+    }
+}
+
+// The tokens of movetext along with where they start in it. The last
+// token taken is kept, so that errors can point at it.
+struct Tokens<'a> {
+    tokens: std::vec::IntoIter<(usize, Token<'a>)>,
+    last: Option<(usize, Token<'a>)>,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let next = self.tokens.next();
+        if next.is_some() {
+            self.last = next;
+        }
+        next.map(|(_, token)| token)
+    }
+}
+
+// A token of movetext. Move numbers aren't tokens, as they're
+// implied by the moves.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Move(&'a str),
+    Nag(u8),
+    Comment(&'a str),
+    Open,
+    Close,
+    Result(&'a str),
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Move(s) | Token::Result(s) => write!(f, "{}", s),
+            Token::Nag(nag) => write!(f, "${}", nag),
+            Token::Comment(comment) => write!(f, "{{{}}}", comment),
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+        }
+    }
+}
+
+// The suffix annotations and the glyphs they stand for
+const SUFFIX_NAGS: [(&str, u8); 6] = [
+    ("!", 1),
+    ("?", 2),
+    ("!!", 3),
+    ("??", 4),
+    ("!?", 5),
+    ("?!", 6),
+];
+
+// Parse a tag pair like `[White "Carlsen"]`
+fn parse_tag(line: &str) -> Result<(String, String), Error> {
+    let invalid = || Error::InvalidPgn(format!("`{}` is not a valid tag pair", line));
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .ok_or_else(invalid)?
+        .trim();
+    let (name, value) = inner.split_once(char::is_whitespace).ok_or_else(invalid)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+    Ok((
+        name.to_string(),
+        value.replace("\\\"", "\"").replace("\\\\", "\\"),
+    ))
+}
+
+// Split movetext into tokens, along with where they start in it,
+// skipping move numbers. Errors come with where they happened.
+fn tokenize(movetext: &str) -> Result<Vec<(usize, Token<'_>)>, (usize, Error)> {
+    let mut tokens = Vec::new();
+    let mut rest = movetext;
+    while let Some(c) = rest.chars().next() {
+        let offset = movetext.len() - rest.len();
+        let skip = match c {
+            '{' => match rest.find('}') {
+                Some(end) => {
+                    tokens.push((offset, Token::Comment(&rest[1..end])));
+                    end + 1
+                }
+                None => {
+                    let error = Error::InvalidPgn("unterminated comment".to_string());
+                    return Err((offset, error));
+                }
+            },
+            ';' => {
+                let end = rest.find('\n').unwrap_or(rest.len());
+                tokens.push((offset, Token::Comment(&rest[1..end])));
+                end
+            }
+            '(' => {
+                tokens.push((offset, Token::Open));
+                1
+            }
+            ')' => {
+                tokens.push((offset, Token::Close));
+                1
+            }
+            '}' => {
+                let error = Error::InvalidPgn("`}` outside of a comment".to_string());
+                return Err((offset, error));
+            }
+            _ if c.is_whitespace() => c.len_utf8(),
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || "{};()".contains(c))
+                    .unwrap_or(rest.len());
+                let mut token = &rest[..end];
+                // move numbers, which may be written right next to the
+                // move as in `1.e4`, or as `1...` before a black move
+                if let Some(i) = token.find('.') {
+                    if token[..i].chars().all(|c| c.is_ascii_digit()) {
+                        token = token[i..].trim_start_matches('.');
+                    }
+                }
+                let offset = offset + end - token.len();
+                push_word(&mut tokens, offset, token).map_err(|e| (offset, e))?;
+                end
+            }
+        };
+        // every token is at least a character long, so the loop ends
+        debug_assert!(skip > 0, "no progress at offset {}", offset);
+        rest = &rest[skip..];
+    }
+    Ok(tokens)
+}
+
+// Push the tokens of a word of movetext, which is a move, possibly
+// followed by a suffix annotation, a glyph or a termination marker
+fn push_word<'a>(
+    tokens: &mut Vec<(usize, Token<'a>)>,
+    offset: usize,
+    word: &'a str,
+) -> Result<(), Error> {
+    if word.is_empty() {
+        return Ok(());
+    }
+    if let Some(nag) = word.strip_prefix('$') {
+        let nag = nag
+            .parse()
+            .map_err(|_| Error::InvalidPgn(format!("`{}` is not a valid glyph", word)))?;
+        tokens.push((offset, Token::Nag(nag)));
+        return Ok(());
+    }
+    if let "1-0" | "0-1" | "1/2-1/2" | "*" = word {
+        tokens.push((offset, Token::Result(word)));
+        return Ok(());
+    }
+
+    let m = word.trim_end_matches(['!', '?']);
+    if !m.is_empty() {
+        tokens.push((offset, Token::Move(m)));
+    }
+    let suffix = &word[m.len()..];
+    if !suffix.is_empty() {
+        let &(_, nag) = SUFFIX_NAGS
+            .iter()
+            .find(|&&(s, _)| s == suffix)
+            .ok_or_else(|| Error::InvalidPgn(format!("`{}` is not a valid annotation", suffix)))?;
+        tokens.push((offset + m.len(), Token::Nag(nag)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, parse_all, parse_all_lossy, PgnGame};
+    use crate::board::Board;
+    use crate::game::{DrawReason, Game, GameResult, WinReason};
+    use crate::piece::Color;
+
+    #[test]
+    fn full_game() {
+        let pgn = r#"[Event "Opera Game"]
+[Site "Paris FRA"]
+[White "Paul Morphy"]
+[Black "Duke Karl / Count Isouard"]
+[Result "1-0"]
+
+1.e4 e5 2.Nf3 d6 3.d4 Bg4 4.dxe5 Bxf3 5.Qxf3 dxe5 6.Bc4 Nf6 7.Qb3 Qe7
+8.Nc3 c6 9.Bg5 b5 10.Nxb5 cxb5 11.Bxb5+ Nbd7 12.O-O-O Rd8
+13.Rxd7 Rxd7 14.Rd1 Qe6 15.Bxd7+ Nxd7 16.Qb8+ $1 Nxb8 17.Rd8# 1-0
+"#;
+        let game = parse(pgn).unwrap();
+
+        assert_eq!(game.tag("White"), Some("Paul Morphy"));
+        assert_eq!(game.tag("Round"), None);
+        assert_eq!(game.result.as_deref(), Some("1-0"));
+        assert_eq!(game.game.get_moves().len(), 33);
+        assert_eq!(
+            game.game.current_board(),
+            &Board::load_fen("1n1Rkb1r/p4ppp/4q3/4p1B1/4P3/8/PPP2PPP/2K5 b k - 1 17").unwrap()
+        );
+    }
+
+    #[test]
+    fn from_fen_tag() {
+        let pgn = r#"[FEN "4k3/8/4K3/8/8/8/8/7R w - - 0 1"]
+
+1. Rh8# *"#;
+        let game = parse(pgn).unwrap();
+
+        assert_eq!(game.result.as_deref(), Some("*"));
+        assert_eq!(
+            game.game.get_boards()[0],
+            Board::load_fen("4k3/8/4K3/8/8/8/8/7R w - - 0 1").unwrap()
+        );
+    }
+
+    #[test]
+    fn write_round_trips() {
+        let pgn = r#"[FEN "4k3/8/8/8/8/8/4P3/4K3 b - - 0 30"]
+
+30... Kd7 31. e4 Kc6 32. e5 *"#;
+        let game = parse(pgn).unwrap();
+        let written = game.to_string();
+
+        assert!(written.ends_with("30... Kd7 31. e4 Kc6 32. e5 *\n"));
+        let reread = parse(&written).unwrap();
+        assert_eq!(reread.tags, game.tags);
+        assert_eq!(reread.game.get_boards(), game.game.get_boards());
+
+        let mut game = PgnGame::new(Game::from_board(game.game.get_boards()[0]));
+        game.result = None;
+        assert!(game.to_string().contains("[SetUp \"1\"]"));
+        assert!(game.to_string().ends_with("\n\n*\n"));
+    }
+
+    #[test]
+    fn adjudicated_games() {
+        let mut game = Game::new();
+        let _ = game.make_move("e2e4".parse().unwrap()).unwrap();
+        assert!(game.adjudicate(GameResult::Win {
+            winner: Color::White,
+            reason: WinReason::Abandoned,
+        }));
+        let pgn = PgnGame::new(game.clone());
+        assert_eq!(pgn.tag("Result"), Some("1-0"));
+        assert_eq!(pgn.tag("Termination"), Some("abandoned"));
+        assert!(pgn.to_string().ends_with("1. e4 1-0\n"));
+
+        let _ = game.undo_move();
+        assert!(game.adjudicate(GameResult::Draw(DrawReason::Agreement)));
+        let pgn = PgnGame::new(game);
+        assert_eq!(pgn.tag("Result"), Some("1/2-1/2"));
+        assert_eq!(pgn.tag("Termination"), None);
+    }
+
+    #[test]
+    fn long_games_are_wrapped() {
+        let mut game = Game::new();
+        for _ in 0..24 {
+            for m in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                let _ = game.make_move(m.parse().unwrap());
+            }
+        }
+        let pgn = PgnGame::new(game).to_string();
+
+        assert!(pgn.lines().all(|l| l.len() <= 80));
+        assert_eq!(parse(&pgn).unwrap().game.get_moves().len(), 96);
+    }
+
+    #[test]
+    fn annotations_round_trip() {
+        let pgn = r#"{Opening} 1. e4 $1 {best by test} (1. d4 d5 (1... Nf6 2. c4) 2. c4 {the
+Queen's Gambit}) 1... e5!? ; an old reply
+2. Nf3 ?! *"#;
+        let game = parse(pgn).unwrap();
+
+        assert_eq!(game.comments, ["Opening"]);
+        let e4 = &game.annotations[0];
+        assert_eq!(e4.nags, [1]);
+        assert_eq!(e4.comments, ["best by test"]);
+        let d4 = &e4.variations[0];
+        assert_eq!(d4.moves.len(), 3);
+        assert_eq!(d4.annotations[1].variations[0].moves.len(), 2);
+        assert_eq!(d4.annotations[2].comments, ["the Queen's Gambit"]);
+        assert_eq!(game.annotations[1].nags, [5]);
+        assert_eq!(game.annotations[1].comments, ["an old reply"]);
+        assert_eq!(game.annotations[2].nags, [6]);
+
+        let written = game.to_string();
+        assert!(written.ends_with(
+            "{Opening} 1. e4 $1 {best by test} (1. d4 d5 (1... Nf6 2. c4) 2. c4 {the Queen's\n\
+             Gambit}) 1... e5 $5 {an old reply} 2. Nf3 $6 *\n"
+        ));
+        let reread = parse(&written).unwrap();
+        assert_eq!(reread.comments, game.comments);
+        assert_eq!(reread.annotations, game.annotations);
+    }
+
+    #[test]
+    fn braces_in_comments() {
+        let mut game = parse("1. e4 *").unwrap();
+        game.comments = vec!["see {this} too".to_string()];
+        game.annotations[0].comments = vec!["}".to_string()];
+
+        let reread = parse(&game.to_string()).unwrap();
+        assert_eq!(reread.comments, ["see (this) too"]);
+        assert_eq!(reread.annotations[0].comments, [")"]);
+    }
+
+    #[test]
+    fn diagnostics() {
+        let pgn = "[Event \"ok\"]\n\n1. e4 *\n\n[Event broken]\n\n1. e4 *\n\n\
+                   [Event \"late\"]\n\n1. e4 e5\n  2. Nf3 (2. Bc4\n\n\
+                   [Event \"extra\"]\n\n1. d4 1-0 d5\n\n[Event \"fine\"]\n\n1. c4 *\n";
+        let (games, diagnostics) = parse_all_lossy(pgn);
+
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[1].tag("Event"), Some("fine"));
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.game, d.line, d.column, d.token.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (1, 5, 1, "[Event broken]"),
+                (2, 12, 14, "Bc4"),
+                (3, 16, 11, "d5"),
+            ]
+        );
+        assert!(diagnostics[1]
+            .to_string()
+            .contains("unterminated variation"));
+        assert_eq!(parse_all(pgn).unwrap_err().game, 1);
+    }
+
+    #[test]
+    fn lossy_skips_stray_braces() {
+        let pgn = "[Round \"1\"]\n\n1. e4 e5 *\n\n\
+                   [Round \"2\"]\n\n1. e4 } e5 *\n\n\
+                   [Round \"3\"]\n\n1. d4 d5 *\n";
+        let (games, diagnostics) = parse_all_lossy(pgn);
+
+        let rounds: Vec<_> = games.iter().map(|g| g.tag("Round")).collect();
+        assert_eq!(rounds, [Some("1"), Some("3")]);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            (
+                diagnostics[0].game,
+                diagnostics[0].line,
+                diagnostics[0].column
+            ),
+            (1, 7, 7)
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parse("1. e4 e5 2. Ke3").is_err());
+        assert!(parse("1. e4 {never closed").is_err());
+        assert!(parse("1. e4 (1. d4 d5").is_err());
+        assert!(parse("1. e4 1-0 e5").is_err());
+        assert!(parse("[White Carlsen]\n\n1. e4").is_err());
+        assert!(parse("1. e4 (1. d4 1-0) *").is_err());
+        assert!(parse("(1. d4) 1. e4 *").is_err());
+        assert!(parse("1. e4 e5?!! *").is_err());
+        assert!(parse("1. e4 (1. Ke2) *").is_err());
+    }
+
+    #[test]
+    fn unbalanced_brackets() {
+        for pgn in &["1. e4 } e5 *", "}", "1. e4 {a} } *", "1. e4 ) e5 *", ")"] {
+            assert!(parse(pgn).is_err(), "{}", pgn);
+        }
+        let error = parse_all(
+            "1. e4 e5
+2. Nf3 } Nc6 *",
+        )
+        .unwrap_err();
+        assert_eq!(
+            (error.line, error.column, error.token.as_str()),
+            (2, 8, "}")
+        );
+    }
+}
@@ -3,6 +3,7 @@ use std::fmt;
 
 /// The general piece type
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Piece {
     /// Which kind of piece this is
     pub piece: PieceType,
@@ -15,10 +16,28 @@ impl Piece {
     pub fn new(piece: PieceType, color: Color) -> Piece {
         Piece { piece, color }
     }
+
+    /// Get the index of the piece among the twelve kinds of pieces,
+    /// for tables with an entry per piece: the white pieces come
+    /// first, in the order of [`PieceType::ALL`], and then the black
+    /// ones
+    ///
+    /// ```
+    /// # use chess_engine::piece::{Color, Piece, PieceType};
+    /// assert_eq!(Piece::new(PieceType::Pawn, Color::White).index(), 0);
+    /// assert_eq!(Piece::new(PieceType::King, Color::Black).index(), 11);
+    /// ```
+    pub fn index(&self) -> usize {
+        match self.color {
+            Color::White => self.piece.index(),
+            Color::Black => self.piece.index() + PieceType::ALL.len(),
+        }
+    }
 }
 
 /// The different kinds of pieces representable in this backend
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum PieceType {
     Pawn,
@@ -29,8 +48,83 @@ pub enum PieceType {
     King,
 }
 
+impl PieceType {
+    /// All the piece types, from the least to the most valuable
+    pub const ALL: [PieceType; 6] = [
+        PieceType::Pawn,
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+        PieceType::King,
+    ];
+
+    /// All the piece types a pawn can be promoted to, in the order
+    /// the legal move generation produces them.
+    pub const PROMOTION_TARGETS: [PieceType; 4] = [
+        PieceType::Queen,
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+    ];
+
+    /// Get the index of the piece type in [`PieceType::ALL`]
+    ///
+    /// ```
+    /// # use chess_engine::piece::PieceType;
+    /// assert_eq!(PieceType::Pawn.index(), 0);
+    /// assert_eq!(PieceType::ALL[PieceType::Rook.index()], PieceType::Rook);
+    /// ```
+    pub fn index(&self) -> usize {
+        match *self {
+            PieceType::Pawn => 0,
+            PieceType::Knight => 1,
+            PieceType::Bishop => 2,
+            PieceType::Rook => 3,
+            PieceType::Queen => 4,
+            PieceType::King => 5,
+        }
+    }
+
+    /// Get the standard value of the piece type in centipawns, with
+    /// pawns worth 100, knights and bishops 300, rooks 500 and queens
+    /// 900. Kings can't be traded, so they're worth 0.
+    ///
+    /// ```
+    /// # use chess_engine::piece::PieceType;
+    /// assert_eq!(PieceType::Rook.value(), 500);
+    /// assert_eq!(PieceType::King.value(), 0);
+    /// ```
+    pub fn value(&self) -> u32 {
+        match *self {
+            PieceType::Pawn => 100,
+            PieceType::Knight | PieceType::Bishop => 300,
+            PieceType::Rook => 500,
+            PieceType::Queen => 900,
+            PieceType::King => 0,
+        }
+    }
+
+    /// Check whether the piece type slides any number of squares
+    /// along its lines until it's blocked, which is the case for
+    /// rooks, bishops and queens
+    ///
+    /// ```
+    /// # use chess_engine::piece::PieceType;
+    /// assert!(PieceType::Bishop.is_slider());
+    /// assert!(!PieceType::Knight.is_slider());
+    /// ```
+    pub fn is_slider(&self) -> bool {
+        matches!(
+            *self,
+            PieceType::Rook | PieceType::Bishop | PieceType::Queen
+        )
+    }
+}
+
 /// Enum representing the two colors in chess
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum Color {
     White,
@@ -69,15 +163,41 @@ impl Color {
             Color::Black => 6,
         }
     }
+
+    /// Convert a rank between board indices and the color's own
+    /// perspective, where its home rank is 0. This leaves ranks as
+    /// they are for white and mirrors them for black, so converting
+    /// twice gives back the original rank.
+    ///
+    /// ```
+    /// # use chess_engine::piece::Color;
+    /// assert_eq!(Color::White.relative_rank(1), 1);
+    /// assert_eq!(Color::Black.relative_rank(1), 6);
+    /// assert_eq!(Color::Black.relative_rank(Color::Black.home_rank()), 0);
+    /// ```
+    pub fn relative_rank(&self, rank: u32) -> u32 {
+        match *self {
+            Color::White => rank,
+            Color::Black => 7 - rank,
+        }
+    }
 }
 
 impl fmt::Display for Piece {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut s = format!("{}", self.piece);
-        if self.color == Color::Black {
-            s = s.to_lowercase();
+        use PieceType::*;
+        let c = match self.piece {
+            Pawn => 'P',
+            Rook => 'R',
+            Bishop => 'B',
+            Queen => 'Q',
+            Knight => 'N',
+            King => 'K',
+        };
+        match self.color {
+            Color::White => write!(f, "{}", c),
+            Color::Black => write!(f, "{}", c.to_ascii_lowercase()),
         }
-        write!(f, "{}", s)
     }
 }
 
@@ -114,3 +234,24 @@ impl std::str::FromStr for PieceType {
         })
     }
 }
+
+impl std::str::FromStr for Color {
+    type Err = crate::error::Error;
+
+    /// Parse a color, either as used in FEN (`w`/`b`) or written out
+    /// (`white`/`black`), ignoring case.
+    ///
+    /// ```
+    /// # use chess_engine::piece::Color;
+    /// assert_eq!("w".parse::<Color>().unwrap(), Color::White);
+    /// assert_eq!("Black".parse::<Color>().unwrap(), Color::Black);
+    /// assert!("red".parse::<Color>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Color, crate::error::Error> {
+        match s.to_ascii_lowercase().as_str() {
+            "w" | "white" => Ok(Color::White),
+            "b" | "black" => Ok(Color::Black),
+            _ => Err(crate::error::Error::InvalidColor(s.to_string())),
+        }
+    }
+}
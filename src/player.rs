@@ -0,0 +1,148 @@
+//! Module for players, which is anything that can decide on moves in
+//! a game: humans at a UI, engines, or opponents over the network.
+//! Applications can mix them freely and let
+//! [`Game::play_players`] run the game.
+//!
+//! Every [`MoveSelector`] is a player that ignores the clock and
+//! resigns when it has no move to select.
+
+use crate::board::Move;
+use crate::clock::Clock;
+#[cfg(feature = "search")]
+use crate::eval::MaterialEvaluator;
+use crate::game::{Game, GameResult};
+use crate::piece::Color;
+#[cfg(feature = "search")]
+use crate::search;
+#[cfg(feature = "search")]
+use crate::selfplay::SearchSelector;
+use crate::selfplay::{MoveSelector, RandomSelector};
+
+/// What a player decided to do on their turn
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PlayerAction {
+    /// Play a move
+    Move(Move),
+    /// Give up the game
+    Resign,
+    /// The player hasn't decided yet, e.g. because a human hasn't
+    /// entered their move. The game loop should stop and ask again
+    /// later.
+    Pending,
+}
+
+/// Why [`Game::play_players`] stopped
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[allow(variant_size_differences)]
+pub enum PlayOutcome {
+    /// The game is over by the rules
+    Finished(GameResult),
+    /// A player resigned
+    Resigned(Color),
+    /// A player hasn't decided on their move yet
+    Pending(Color),
+    /// A player ran out of time
+    TimeForfeit(Color),
+    /// A player tried to make an illegal move
+    IllegalMove(Color, Move),
+}
+
+/// Something that plays one side of a game
+pub trait Player {
+    /// Decide what to do in `game`, where it's this player's turn.
+    /// `clock` is the game's clock, if it has one, with this player's
+    /// time running.
+    fn choose_move(&mut self, game: &Game, clock: Option<&Clock>) -> PlayerAction;
+}
+
+impl<S: MoveSelector + ?Sized> Player for S {
+    fn choose_move(&mut self, game: &Game, _clock: Option<&Clock>) -> PlayerAction {
+        match self.select_move(game) {
+            Some(m) => PlayerAction::Move(m),
+            None => PlayerAction::Resign,
+        }
+    }
+}
+
+/// A player making uniformly random legal moves
+pub type RandomPlayer<R> = RandomSelector<R>;
+
+/// A player making the first move of the best line found by a search
+#[cfg(feature = "search")]
+pub type SearchPlayer<E> = SearchSelector<E>;
+
+/// A player that grabs as much material as it can with each move, or
+/// mates if it can, without looking any further ahead
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::Board;
+/// # use chess_engine::game::Game;
+/// # use chess_engine::player::{GreedyMaterialPlayer, Player, PlayerAction};
+/// let board = Board::load_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+/// let action = GreedyMaterialPlayer.choose_move(&Game::from_board(board), None);
+///
+/// assert_eq!(action, PlayerAction::Move("d1d5".parse().unwrap()));
+/// ```
+#[cfg(feature = "search")]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct GreedyMaterialPlayer;
+
+#[cfg(feature = "search")]
+impl MoveSelector for GreedyMaterialPlayer {
+    fn select_move(&mut self, game: &Game) -> Option<Move> {
+        let (_, line) = search::best_line(game.current_board(), &MaterialEvaluator, 1);
+        line.first().copied()
+    }
+}
+
+/// A player whose moves come from outside the game loop, such as a
+/// human clicking in a UI. Until a move is submitted, the player's
+/// action is [`PlayerAction::Pending`].
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::game::Game;
+/// # use chess_engine::player::{HumanPending, Player, PlayerAction};
+/// let game = Game::new();
+/// let mut human = HumanPending::new();
+/// assert_eq!(human.choose_move(&game, None), PlayerAction::Pending);
+///
+/// human.submit("e2e4".parse().unwrap());
+/// assert_eq!(
+///     human.choose_move(&game, None),
+///     PlayerAction::Move("e2e4".parse().unwrap())
+/// );
+/// // the move is only played once
+/// assert_eq!(human.choose_move(&game, None), PlayerAction::Pending);
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct HumanPending {
+    action: Option<PlayerAction>,
+}
+
+impl HumanPending {
+    /// Create a player that hasn't decided on anything yet
+    pub fn new() -> HumanPending {
+        HumanPending { action: None }
+    }
+
+    /// Submit the next move to play, replacing any move or
+    /// resignation submitted before
+    pub fn submit(&mut self, m: Move) {
+        self.action = Some(PlayerAction::Move(m));
+    }
+
+    /// Resign the game on the player's next turn
+    pub fn resign(&mut self) {
+        self.action = Some(PlayerAction::Resign);
+    }
+}
+
+impl Player for HumanPending {
+    fn choose_move(&mut self, _game: &Game, _clock: Option<&Clock>) -> PlayerAction {
+        self.action.take().unwrap_or(PlayerAction::Pending)
+    }
+}
@@ -0,0 +1,247 @@
+//! Module for tactics puzzles: verifying that a puzzle's solution is
+//! forced, and generating puzzles from the mistakes made in a
+//! [`Game`].
+
+use crate::board::{Board, Move};
+use crate::eval::{Evaluator, MaterialEvaluator};
+use crate::game::Game;
+use crate::search;
+
+/// How much better (in centipawns) the solution has to be than any
+/// alternative for a non-mating puzzle to count as having a unique
+/// solution.
+pub const UNIQUENESS_MARGIN: i32 = 200;
+
+/// How much (in centipawns) a move has to lose compared to the best
+/// move to be considered a blunder by [`find_puzzles`].
+pub const BLUNDER_THRESHOLD: i32 = 200;
+
+/// A tactics puzzle, consisting of a starting position and the
+/// solution line. The solution alternates between the solver's moves
+/// and the opponent's replies, starting and ending with a move by
+/// the solver (the player to move in `board`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Puzzle {
+    /// The position the puzzle starts from
+    pub board: Board,
+    /// The solution line
+    pub solution: Vec<Move>,
+}
+
+impl Puzzle {
+    /// Create a new puzzle
+    pub fn new(board: Board, solution: Vec<Move>) -> Puzzle {
+        Puzzle { board, solution }
+    }
+
+    /// Check that the puzzle is well formed and that every one of
+    /// the solver's moves is forced, i.e. it is the only move that
+    /// works.
+    ///
+    /// If the solution ends in checkmate, each of the solver's moves
+    /// must be the only one forcing mate within the remaining number
+    /// of moves, except for the final move where any mate is
+    /// accepted. Otherwise each of the solver's moves must be better
+    /// than every alternative by at least [`UNIQUENESS_MARGIN`]
+    /// according to a material search.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::board::{Board, Move};
+    /// # use chess_engine::puzzle::Puzzle;
+    /// let board = Board::load_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    /// let ra8 = Move::Normal {
+    ///     from: "a1".parse().unwrap(),
+    ///     to: "a8".parse().unwrap(),
+    /// };
+    /// let ra7 = Move::Normal {
+    ///     from: "a1".parse().unwrap(),
+    ///     to: "a7".parse().unwrap(),
+    /// };
+    ///
+    /// assert!(Puzzle::new(board, vec![ra8]).verify());
+    /// assert!(!Puzzle::new(board, vec![ra7]).verify());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function should be unable to panic as the list of boards
+    /// always contains at least the starting position.
+    pub fn verify(&self) -> bool {
+        if self.solution.len() % 2 != 1 {
+            return false;
+        }
+
+        let mut boards = vec![self.board];
+        for &m in &self.solution {
+            match boards.last().unwrap().perform_move(m) {
+                Some(board) => boards.push(board),
+                None => return false,
+            }
+        }
+
+        let last = boards.last().unwrap();
+        let ends_in_mate = last.is_checkmate();
+
+        (0..self.solution.len()).step_by(2).all(|i| {
+            let remaining = (self.solution.len() - i) as u32;
+            if ends_in_mate {
+                is_only_mate(&boards[i], self.solution[i], remaining)
+            } else {
+                is_only_winning_move(&boards[i], self.solution[i], remaining)
+            }
+        })
+    }
+}
+
+// Whether `m` is the only move forcing mate within `plies` plies. The
+// final mating move doesn't have to be unique.
+fn is_only_mate(board: &Board, m: Move, plies: u32) -> bool {
+    let forces_mate = |candidate: Move| {
+        let next = board.perform_move_unvalidated(candidate);
+        let replies = next.get_all_legal_moves();
+        if replies.is_empty() {
+            return next.in_check();
+        }
+        plies >= 3
+            && replies.into_iter().all(|reply| {
+                search::find_mate(&next.perform_move_unvalidated(reply), plies - 2).is_some()
+            })
+    };
+
+    if !forces_mate(m) {
+        return false;
+    }
+    plies == 1
+        || board
+            .get_all_legal_moves()
+            .into_iter()
+            .filter(|&other| other != m)
+            .all(|other| !forces_mate(other))
+}
+
+// Whether `m` is better than every other move by at least
+// `UNIQUENESS_MARGIN`, searching one ply past the end of the line so
+// that the opponent's immediate recapture is taken into account.
+fn is_only_winning_move(board: &Board, m: Move, plies: u32) -> bool {
+    let score = |candidate: Move| {
+        -search::best_line(
+            &board.perform_move_unvalidated(candidate),
+            &MaterialEvaluator,
+            plies,
+        )
+        .0
+    };
+
+    let solution_score = score(m);
+    board
+        .get_all_legal_moves()
+        .into_iter()
+        .filter(|&other| other != m)
+        .all(|other| solution_score - score(other) >= UNIQUENESS_MARGIN)
+}
+
+/// Scan a game for blunders and turn the positions right after them
+/// into puzzles for the opponent. A move is considered a blunder if
+/// it scores at least [`BLUNDER_THRESHOLD`] worse than the best move
+/// when searched `depth` plies deep with the given evaluator. Only
+/// puzzles which pass [`Puzzle::verify`] are returned.
+pub fn find_puzzles<E: Evaluator + ?Sized>(game: &Game, evaluator: &E, depth: u32) -> Vec<Puzzle> {
+    let boards: Vec<_> = game.boards().collect();
+    let mut puzzles = vec![];
+
+    for (before, after) in boards.iter().zip(&boards[1..]) {
+        if !after.has_legal_moves() {
+            continue;
+        }
+
+        let (best_score, _) = search::best_line(before, evaluator, depth);
+        let played_score = -search::best_line(after, evaluator, depth.saturating_sub(1)).0;
+        if best_score - played_score < BLUNDER_THRESHOLD {
+            continue;
+        }
+
+        let (_, mut solution) = search::best_line(after, evaluator, depth);
+        if solution.len() % 2 != 1 {
+            let _ = solution.pop();
+        }
+
+        let puzzle = Puzzle::new(*after, solution);
+        if puzzle.verify() {
+            puzzles.push(puzzle);
+        }
+    }
+
+    puzzles
+}
+
+#[cfg(all(test, feature = "fen"))]
+mod tests {
+    use super::{find_puzzles, Puzzle};
+    use crate::board::{Board, Move, SquareSpec};
+    use crate::eval::MaterialEvaluator;
+    use crate::game::Game;
+
+    fn mv(from: &str, to: &str) -> Move {
+        Move::Normal {
+            from: from.parse::<SquareSpec>().unwrap(),
+            to: to.parse::<SquareSpec>().unwrap(),
+        }
+    }
+
+    #[test]
+    fn unique_material_win() {
+        // the rook on d5 is hanging, and nothing else wins anything
+        let board = Board::load_fen("4k3/8/8/3r4/8/8/8/3QK3 w - - 0 1").unwrap();
+
+        assert!(Puzzle::new(board, vec![mv("d1", "d5")]).verify());
+        assert!(!Puzzle::new(board, vec![mv("d1", "d4")]).verify());
+    }
+
+    #[test]
+    fn solution_must_be_legal_and_end_with_solver() {
+        let board = Board::load_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+
+        assert!(!Puzzle::new(board, vec![]).verify());
+        assert!(!Puzzle::new(board, vec![mv("a1", "b2")]).verify());
+        assert!(!Puzzle::new(board, vec![mv("a1", "a2"), mv("g8", "f8")]).verify());
+    }
+
+    #[test]
+    fn mate_in_two_puzzle() {
+        let board = Board::load_fen("1k6/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+        let solution = vec![mv("h1", "a1"), mv("b8", "c8"), mv("a1", "a8")];
+
+        assert!(Puzzle::new(board, solution).verify());
+    }
+
+    #[test]
+    fn mate_in_two_with_alternative() {
+        // Kc7 mates in two just as well
+        let board = Board::load_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+        let solution = vec![mv("c6", "b6"), mv("a8", "b8"), mv("h1", "h8")];
+
+        assert!(!Puzzle::new(board, solution).verify());
+    }
+
+    #[test]
+    fn finds_missed_mate() {
+        // 1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6?? allows Qxf7#
+        let mut game = Game::new();
+        for (from, to) in [
+            ("e2", "e4"),
+            ("e7", "e5"),
+            ("d1", "h5"),
+            ("b8", "c6"),
+            ("f1", "c4"),
+            ("g8", "f6"),
+        ] {
+            assert!(game.make_move(mv(from, to)).is_some());
+        }
+
+        let puzzles = find_puzzles(&game, &MaterialEvaluator, 2);
+
+        assert!(puzzles.contains(&Puzzle::new(*game.current_board(), vec![mv("h5", "f7")])));
+    }
+}
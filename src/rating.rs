@@ -0,0 +1,245 @@
+//! Module for rating players from their game results, with the Elo
+//! system and with Glicko-2, which also keeps track of how reliable
+//! each rating is.
+//!
+//! Scores are always 1 for a win, 0.5 for a draw and 0 for a loss.
+
+use std::f64::consts::PI;
+
+/// Get the expected score of a player rated `rating` against one
+/// rated `opponent` under the Elo system
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::rating;
+/// assert_eq!(rating::expected_score(1500.0, 1500.0), 0.5);
+/// assert!((rating::expected_score(1900.0, 1500.0) - 10.0 / 11.0).abs() < 1e-9);
+/// ```
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10_f64.powf((opponent - rating) / 400.0))
+}
+
+/// Get the Elo difference that gives an expected score, the inverse of
+/// [`expected_score`]. This is e.g. how much stronger one engine is
+/// than another given the score of a match between them. Scores of 0
+/// and 1 give infinite differences.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::rating;
+/// assert_eq!(rating::elo_difference(0.5), 0.0);
+/// assert!((rating::elo_difference(10.0 / 11.0) - 400.0).abs() < 1e-9);
+/// ```
+pub fn elo_difference(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// Update an Elo rating after playing some games, with `results`
+/// giving the rating of each opponent and the score against them.
+/// All games are rated together, against the rating from before them,
+/// and the change is scaled by the K-factor `k`, which is commonly
+/// between 10 and 40.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::rating;
+/// // beating an equally rated player gains half of the K-factor
+/// assert_eq!(rating::update_elo(1500.0, &[(1500.0, 1.0)], 32.0), 1516.0);
+/// // and a draw and a win against them gain a quarter of it each
+/// assert_eq!(rating::update_elo(1500.0, &[(1500.0, 0.5), (1500.0, 1.0)], 32.0), 1516.0);
+/// ```
+pub fn update_elo(rating: f64, results: &[(f64, f64)], k: f64) -> f64 {
+    let change: f64 = results
+        .iter()
+        .map(|&(opponent, score)| score - expected_score(rating, opponent))
+        .sum();
+    rating + k * change
+}
+
+/// The usual system constant τ of Glicko-2, constraining how quickly
+/// the volatility of ratings changes
+pub const GLICKO2_TAU: f64 = 0.5;
+
+// The factor between ratings on the Glicko scale and the Glicko-2 scale
+const GLICKO2_SCALE: f64 = 173.7178;
+
+// How precisely the new volatility is worked out
+const GLICKO2_EPSILON: f64 = 0.000_001;
+
+/// A rating in the Glicko-2 system, which is on the same scale as Elo
+/// ratings but also has a rating deviation, saying how uncertain the
+/// rating is, and a volatility, saying how erratic the player's
+/// results are
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::rating::{Glicko2Rating, GLICKO2_TAU};
+/// let player = Glicko2Rating::default();
+/// let opponent = Glicko2Rating::default();
+/// let updated = player.update(&[(opponent, 1.0)], GLICKO2_TAU);
+///
+/// assert!(updated.rating > player.rating);
+/// // the rating is more certain after playing a game
+/// assert!(updated.deviation < player.deviation);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Glicko2Rating {
+    /// The rating itself
+    pub rating: f64,
+    /// The rating deviation, where the player's strength is about 95%
+    /// likely to be within twice the deviation of the rating
+    pub deviation: f64,
+    /// The volatility, the degree of expected fluctuation in the
+    /// player's rating
+    pub volatility: f64,
+}
+
+impl Default for Glicko2Rating {
+    /// The rating of a new player: 1500 with a deviation of 350 and a
+    /// volatility of 0.06
+    fn default() -> Self {
+        Glicko2Rating {
+            rating: 1500.0,
+            deviation: 350.0,
+            volatility: 0.06,
+        }
+    }
+}
+
+impl Glicko2Rating {
+    /// Update the rating after a rating period, with `results` giving
+    /// each opponent's rating at the start of the period and the score
+    /// against them. The rating deviation grows over periods without
+    /// any games. `tau` is the system constant, usually
+    /// [`GLICKO2_TAU`].
+    pub fn update(&self, results: &[(Glicko2Rating, f64)], tau: f64) -> Glicko2Rating {
+        let mu = (self.rating - 1500.0) / GLICKO2_SCALE;
+        let phi = self.deviation / GLICKO2_SCALE;
+        if results.is_empty() {
+            return Glicko2Rating {
+                deviation: phi.hypot(self.volatility) * GLICKO2_SCALE,
+                ..*self
+            };
+        }
+
+        // the estimated variance of the rating from the games alone,
+        // and the sum the improvement of the rating is based on
+        let mut inverse_variance = 0.0;
+        let mut improvement = 0.0;
+        for (opponent, score) in results {
+            let mu_j = (opponent.rating - 1500.0) / GLICKO2_SCALE;
+            let phi_j = opponent.deviation / GLICKO2_SCALE;
+            let g = 1.0 / (1.0 + 3.0 * phi_j.powi(2) / PI.powi(2)).sqrt();
+            let expected = 1.0 / (1.0 + (-g * (mu - mu_j)).exp());
+            inverse_variance += g.powi(2) * expected * (1.0 - expected);
+            improvement += g * (score - expected);
+        }
+        let variance = 1.0 / inverse_variance;
+        let delta = variance * improvement;
+
+        let volatility = new_volatility(self.volatility, phi, variance, delta, tau);
+        let phi_star = phi.hypot(volatility);
+        let new_phi = 1.0 / (1.0 / phi_star.powi(2) + 1.0 / variance).sqrt();
+        let new_mu = mu + new_phi.powi(2) * improvement;
+
+        Glicko2Rating {
+            rating: new_mu * GLICKO2_SCALE + 1500.0,
+            deviation: new_phi * GLICKO2_SCALE,
+            volatility,
+        }
+    }
+}
+
+// Work out the new volatility by finding the root of the function
+// from step 5 of the Glicko-2 paper with the Illinois algorithm
+fn new_volatility(sigma: f64, phi: f64, variance: f64, delta: f64, tau: f64) -> f64 {
+    let a = sigma.powi(2).ln();
+    let f = |x: f64| {
+        let ex = x.exp();
+        let denominator = phi.powi(2) + variance + ex;
+        ex * (delta.powi(2) - phi.powi(2) - variance - ex) / (2.0 * denominator.powi(2))
+            - (x - a) / tau.powi(2)
+    };
+
+    let mut big_a = a;
+    let mut big_b = if delta.powi(2) > phi.powi(2) + variance {
+        (delta.powi(2) - phi.powi(2) - variance).ln()
+    } else {
+        let mut k = 1.0;
+        while f(a - k * tau) < 0.0 {
+            k += 1.0;
+        }
+        a - k * tau
+    };
+    let (mut f_a, mut f_b) = (f(big_a), f(big_b));
+    while (big_b - big_a).abs() > GLICKO2_EPSILON {
+        let big_c = big_a + (big_a - big_b) * f_a / (f_b - f_a);
+        let f_c = f(big_c);
+        if f_c * f_b <= 0.0 {
+            big_a = big_b;
+            f_a = f_b;
+        } else {
+            f_a /= 2.0;
+        }
+        big_b = big_c;
+        f_b = f_c;
+    }
+    (big_a / 2.0).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{update_elo, Glicko2Rating};
+
+    #[test]
+    fn glicko2_example() {
+        // the example from Glickman's description of Glicko-2
+        let player = Glicko2Rating {
+            rating: 1500.0,
+            deviation: 200.0,
+            volatility: 0.06,
+        };
+        let opponent = |rating, deviation| Glicko2Rating {
+            rating,
+            deviation,
+            volatility: 0.06,
+        };
+        let results = [
+            (opponent(1400.0, 30.0), 1.0),
+            (opponent(1550.0, 100.0), 0.0),
+            (opponent(1700.0, 300.0), 0.0),
+        ];
+        let updated = player.update(&results, 0.5);
+
+        assert!((updated.rating - 1464.06).abs() < 0.01);
+        assert!((updated.deviation - 151.52).abs() < 0.01);
+        assert!((updated.volatility - 0.05999).abs() < 0.00001);
+    }
+
+    #[test]
+    fn glicko2_without_games() {
+        let player = Glicko2Rating {
+            rating: 1700.0,
+            deviation: 50.0,
+            volatility: 0.06,
+        };
+        let updated = player.update(&[], 0.5);
+
+        assert!((updated.rating - 1700.0).abs() < f64::EPSILON);
+        assert!(updated.deviation > player.deviation);
+    }
+
+    #[test]
+    fn elo_is_zero_sum() {
+        let (a, b) = (1650.0, 1420.0);
+        let new_a = update_elo(a, &[(b, 0.5)], 20.0);
+        let new_b = update_elo(b, &[(a, 0.5)], 20.0);
+
+        assert!(new_a < a);
+        assert!((new_a + new_b - a - b).abs() < 1e-9);
+    }
+}
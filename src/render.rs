@@ -0,0 +1,398 @@
+//! Module for drawing boards as images, e.g. for thumbnails in bots
+//! and web services. Needs the `render` feature, and the
+//! `render-png` feature for raster images.
+//!
+//! Pieces are drawn with the chess symbols of Unicode, so the result
+//! depends on the fonts available where the image is viewed.
+
+use crate::board::{Board, BoardSize, Move, SquareSpec};
+use crate::piece::{Color, PieceType};
+use std::fmt::{self, Write};
+
+// Fonts are listed explicitly, as the generic families often map to
+// fonts without the chess symbols, or to no font at all when drawing
+// PNG images
+const PIECE_FONTS: &str = "DejaVu Sans, Segoe UI Symbol, Arial Unicode MS, serif";
+const COORDINATE_FONTS: &str = "DejaVu Sans, Arial, Helvetica, sans-serif";
+
+/// Options for how a board should be drawn
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// The width and height of a single square, in pixels
+    pub square_size: u32,
+    /// The color of the light squares, as a CSS color
+    pub light: String,
+    /// The color of the dark squares, as a CSS color
+    pub dark: String,
+    /// The color used to highlight the last move, as a CSS color
+    pub highlight: String,
+    /// The color of arrows, as a CSS color
+    pub arrow: String,
+    /// Whether to write the rank and file names along the edges
+    pub coordinates: bool,
+    /// Which side should be at the bottom of the image
+    pub perspective: Color,
+    /// The move that led to the position, whose squares are
+    /// highlighted. The move is taken to be played by the player who
+    /// isn't to move.
+    pub last_move: Option<Move>,
+    /// Arrows to draw, as pairs of squares to draw them from and to.
+    /// Arrows to or from squares that aren't on the board are left
+    /// out.
+    pub arrows: Vec<(SquareSpec, SquareSpec)>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions {
+            square_size: 45,
+            light: "#f0d9b5".to_string(),
+            dark: "#b58863".to_string(),
+            highlight: "#9bc700".to_string(),
+            arrow: "#15781b".to_string(),
+            coordinates: true,
+            perspective: Color::White,
+            last_move: None,
+            arrows: vec![],
+        }
+    }
+}
+
+/// Draw the board as an SVG image.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::Board;
+/// # use chess_engine::render::{self, RenderOptions};
+/// let board = Board::load_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 1").unwrap();
+/// let options = RenderOptions {
+///     last_move: Some("e2e4".parse().unwrap()),
+///     arrows: vec![("e8".parse().unwrap(), "e7".parse().unwrap())],
+///     ..RenderOptions::default()
+/// };
+/// let svg = render::svg(&board, &options);
+///
+/// assert!(svg.starts_with("<svg"));
+/// // the white pieces take two symbols each
+/// assert_eq!(svg.matches("<text").count(), 5 + 16);
+/// ```
+pub fn svg(board: &Board, options: &RenderOptions) -> String {
+    let size = options.square_size;
+    let board_size = board.size();
+    let squares = || SquareSpec::all().filter(move |&sq| board_size.contains(sq));
+
+    let mut s = String::new();
+    // writing to a string can't fail
+    let _ = write!(
+        s,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+        size * board_size.files,
+        size * board_size.ranks
+    );
+    let _ = write!(
+        s,
+        r#"<defs><marker id="arrowhead" viewBox="0 0 4 4" refX="2" refY="2" markerWidth="4" markerHeight="4" orient="auto"><path d="M0,0 L4,2 L0,4 z" fill="{}"/></marker></defs>"#,
+        Attribute(&options.arrow)
+    );
+
+    for sq in squares() {
+        let (x, y) = corner(sq, board_size, options);
+        let fill = if is_light(sq) {
+            &options.light
+        } else {
+            &options.dark
+        };
+        let _ = write!(
+            s,
+            r#"<rect x="{}" y="{}" width="{2}" height="{2}" fill="{3}"/>"#,
+            x,
+            y,
+            size,
+            Attribute(fill)
+        );
+    }
+
+    if let Some(m) = options.last_move {
+        let color = board.turn().opposite();
+        for sq in [m.from(color), m.to(color)] {
+            if !board_size.contains(sq) {
+                continue;
+            }
+            let (x, y) = corner(sq, board_size, options);
+            let _ = write!(
+                s,
+                r#"<rect x="{}" y="{}" width="{2}" height="{2}" fill="{3}" fill-opacity="0.4"/>"#,
+                x,
+                y,
+                size,
+                Attribute(&options.highlight)
+            );
+        }
+    }
+
+    if options.coordinates {
+        write_coordinates(&mut s, board_size, options);
+    }
+
+    for sq in squares() {
+        let piece = match board[sq] {
+            Some(piece) => piece,
+            None => continue,
+        };
+        let (x, y) = center(sq, board_size, options);
+        // white pieces are drawn as the outlined symbol on top of a
+        // white silhouette, so squares don't show through them
+        let glyphs: &[(char, &str)] = match piece.color {
+            Color::White => &[
+                (symbol(piece.piece, Color::Black), "#ffffff"),
+                (symbol(piece.piece, Color::White), "#000000"),
+            ],
+            Color::Black => &[(symbol(piece.piece, Color::Black), "#000000")],
+        };
+        for (glyph, fill) in glyphs {
+            let _ = write!(
+                s,
+                r#"<text x="{}" y="{}" font-size="{}" font-family="{}" text-anchor="middle" dominant-baseline="central" fill="{}">{}&#xFE0E;</text>"#,
+                x,
+                y,
+                size * 4 / 5,
+                PIECE_FONTS,
+                fill,
+                glyph
+            );
+        }
+    }
+
+    for &(from, to) in &options.arrows {
+        if !board_size.contains(from) || !board_size.contains(to) {
+            continue;
+        }
+        let (x1, y1) = center(from, board_size, options);
+        let (x2, y2) = center(to, board_size, options);
+        let _ = write!(
+            s,
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}" stroke-opacity="0.8" stroke-linecap="round" marker-end="url(#arrowhead)"/>"#,
+            x1,
+            y1,
+            x2,
+            y2,
+            Attribute(&options.arrow),
+            size / 6 + 1
+        );
+    }
+
+    s.push_str("</svg>");
+    s
+}
+
+// Write the file names along the bottom edge and the rank names
+// along the left edge
+fn write_coordinates(s: &mut String, board_size: BoardSize, options: &RenderOptions) {
+    let size = options.square_size;
+    let font_size = size / 4;
+    let (bottom_rank, left_file) = match options.perspective {
+        Color::White => (0, 0),
+        Color::Black => (board_size.ranks - 1, board_size.files - 1),
+    };
+    for sq in SquareSpec::all().filter(|&sq| board_size.contains(sq)) {
+        let (x, y) = corner(sq, board_size, options);
+        // the coordinates are written in the color of the other kind
+        // of square, so they stand out
+        let fill = if is_light(sq) {
+            &options.dark
+        } else {
+            &options.light
+        };
+        if sq.rank == bottom_rank {
+            let _ = write!(
+                s,
+                r#"<text x="{}" y="{}" font-size="{}" font-family="{}" text-anchor="end" fill="{}">{}</text>"#,
+                x + size - 2,
+                y + size - 3,
+                font_size,
+                COORDINATE_FONTS,
+                Attribute(fill),
+                (b'a' + sq.file as u8) as char
+            );
+        }
+        if sq.file == left_file {
+            let _ = write!(
+                s,
+                r#"<text x="{}" y="{}" font-size="{}" font-family="{}" fill="{}">{}</text>"#,
+                x + 2,
+                y + font_size + 1,
+                font_size,
+                COORDINATE_FONTS,
+                Attribute(fill),
+                sq.rank + 1
+            );
+        }
+    }
+}
+
+// The position of the top left corner of a square in the image of a
+// board of size `board_size`
+fn corner(sq: SquareSpec, board_size: BoardSize, options: &RenderOptions) -> (u32, u32) {
+    let size = options.square_size;
+    match options.perspective {
+        Color::White => (sq.file * size, (board_size.ranks - 1 - sq.rank) * size),
+        Color::Black => ((board_size.files - 1 - sq.file) * size, sq.rank * size),
+    }
+}
+
+// The position of the center of a square in the image
+fn center(sq: SquareSpec, board_size: BoardSize, options: &RenderOptions) -> (u32, u32) {
+    let (x, y) = corner(sq, board_size, options);
+    (x + options.square_size / 2, y + options.square_size / 2)
+}
+
+// A string from the options written as the value of an attribute, so
+// that e.g. a quote in a color can't end the attribute
+struct Attribute<'a>(&'a str);
+
+impl fmt::Display for Attribute<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '&' => f.write_str("&amp;")?,
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '"' => f.write_str("&quot;")?,
+                '\'' => f.write_str("&apos;")?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_light(sq: SquareSpec) -> bool {
+    (sq.rank + sq.file) % 2 == 1
+}
+
+// The Unicode chess symbol of the piece, where the white symbols are
+// outlined and the black ones filled
+fn symbol(piece: PieceType, color: Color) -> char {
+    let black = match piece {
+        PieceType::King => '\u{265a}',
+        PieceType::Queen => '\u{265b}',
+        PieceType::Rook => '\u{265c}',
+        PieceType::Bishop => '\u{265d}',
+        PieceType::Knight => '\u{265e}',
+        PieceType::Pawn => '\u{265f}',
+    };
+    match color {
+        // the white symbols come right before the black ones
+        Color::White => char::from_u32(black as u32 - 6).unwrap_or(black),
+        Color::Black => black,
+    }
+}
+
+/// Draw the board as a PNG image, using the fonts installed on the
+/// system for the pieces and coordinates. Needs the `render-png`
+/// feature.
+///
+/// # Errors
+///
+/// Will return an error if the image couldn't be drawn, e.g. because
+/// the square size is zero
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::Board;
+/// # use chess_engine::render::{self, RenderOptions};
+/// let png = render::png(&Board::default_board(), &RenderOptions::default()).unwrap();
+///
+/// assert!(png.starts_with(b"\x89PNG"));
+/// ```
+#[cfg(feature = "render-png")]
+pub fn png(board: &Board, options: &RenderOptions) -> Result<Vec<u8>, crate::error::IoError> {
+    use crate::error::IoError;
+    use resvg::{tiny_skia, usvg};
+
+    let mut usvg_options = usvg::Options::default();
+    usvg_options.fontdb_mut().load_system_fonts();
+    let tree = usvg::Tree::from_str(&svg(board, options), &usvg_options)
+        .map_err(|e| IoError::Render(e.to_string()))?;
+
+    let size = tree.size().to_int_size();
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| IoError::Render("the image can't be empty".to_string()))?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    pixmap
+        .encode_png()
+        .map_err(|e| IoError::Render(e.to_string()))
+}
+
+#[cfg(all(test, feature = "fen"))]
+mod tests {
+    use super::{svg, RenderOptions};
+    use crate::board::{Board, SquareSpec};
+    use crate::piece::Color;
+
+    #[test]
+    fn perspective() {
+        let board = Board::load_fen("k7/8/8/8/8/8/8/7K w - - 0 1").unwrap();
+        let options = RenderOptions {
+            coordinates: false,
+            square_size: 10,
+            ..RenderOptions::default()
+        };
+
+        // the white king on h1 is in the bottom right corner
+        assert!(svg(&board, &options).contains(r#"<text x="75" y="75""#));
+
+        let options = RenderOptions {
+            perspective: Color::Black,
+            ..options
+        };
+        assert!(svg(&board, &options).contains(r#"<text x="5" y="5""#));
+    }
+
+    #[test]
+    fn untrusted_options() {
+        let board = Board::default_board();
+        let options = RenderOptions {
+            light: r#"red"/><script>alert(1)</script><rect fill="x"#.to_string(),
+            arrows: vec![
+                (SquareSpec::new(8, 0), SquareSpec::E4),
+                (SquareSpec::E2, SquareSpec::E4),
+            ],
+            ..RenderOptions::default()
+        };
+        let image = svg(&board, &options);
+
+        assert!(!image.contains("<script>"));
+        assert!(image.contains(r#"fill="red&quot;/&gt;&lt;script&gt;"#));
+        assert_eq!(image.matches("<line").count(), 1);
+    }
+
+    #[cfg(feature = "variants")]
+    #[test]
+    fn small_boards() {
+        let board = Board::load_fen("4k/5/5/5/K4 w - - 0 1").unwrap();
+        let options = RenderOptions {
+            coordinates: false,
+            square_size: 10,
+            ..RenderOptions::default()
+        };
+        let image = svg(&board, &options);
+
+        assert!(image.contains(r#"width="50" height="50""#));
+        assert_eq!(image.matches("<rect").count(), 25);
+        // the white king on a1 is in the bottom left corner
+        assert!(image.contains(r#"<text x="5" y="45""#));
+
+        let options = RenderOptions {
+            perspective: Color::Black,
+            coordinates: true,
+            ..options
+        };
+        let image = svg(&board, &options);
+        assert!(image.contains(r#"<text x="45" y="5""#));
+        // the files are written from e to a along the bottom
+        assert!(image.contains(">e</text>") && !image.contains(">f</text>"));
+    }
+}
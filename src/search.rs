@@ -0,0 +1,1253 @@
+//! Module containing searches over the game tree, such as finding
+//! forced mates or the best line according to an [`Evaluator`].
+
+use crate::board::{Board, Move, SquareSpec};
+use crate::eval::Evaluator;
+use crate::piece::{Color, Piece, PieceType};
+use std::cmp::Reverse;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The score of delivering checkmate. Mates found further down the
+/// tree score one less per ply, so that shorter mates are preferred.
+pub const MATE_SCORE: i32 = 1_000_000;
+
+/// Search the position to a fixed depth with alpha-beta, returning
+/// the score for the player to move (from the `evaluator`'s point of
+/// view) along with the principal variation. The principal variation
+/// is empty if `depth` is 0 or the game is already over.
+///
+/// This is plain alpha-beta, without any of the [`SearchOptions`],
+/// see [`best_line_with`] to turn them on.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::Board;
+/// # use chess_engine::eval::MaterialEvaluator;
+/// # use chess_engine::search;
+/// // the queen on d5 is hanging
+/// let board = Board::load_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+/// let (score, line) = search::best_line(&board, &MaterialEvaluator, 2);
+///
+/// assert_eq!(score, 500);
+/// assert_eq!(line[0].resolve(&board).to, "d5".parse().unwrap());
+/// ```
+pub fn best_line<E: Evaluator + ?Sized>(
+    board: &Board,
+    evaluator: &E,
+    depth: u32,
+) -> (i32, Vec<Move>) {
+    best_line_with(board, evaluator, depth, SearchOptions::NONE)
+}
+
+/// Search like [`best_line`], with the enhancements in `options`.
+/// The moves beyond `depth` that quiescence search looks at aren't
+/// part of the principal variation.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::Board;
+/// # use chess_engine::eval::MaterialEvaluator;
+/// # use chess_engine::search::{self, SearchOptions};
+/// let board = Board::default_board();
+/// let plain = SearchOptions::NONE;
+/// let (score, line) = search::best_line_with(&board, &MaterialEvaluator, 3, plain);
+///
+/// assert_eq!(score, 0);
+/// assert_eq!(line.len(), 3);
+/// ```
+pub fn best_line_with<E: Evaluator + ?Sized>(
+    board: &Board,
+    evaluator: &E,
+    depth: u32,
+    options: SearchOptions,
+) -> (i32, Vec<Move>) {
+    Search::new(evaluator, options).alpha_beta(board, depth, 0, -MATE_SCORE - 1, MATE_SCORE + 1)
+}
+
+/// The deepest [`search`] goes when it isn't limited otherwise
+pub const MAX_DEPTH: u32 = 64;
+
+// Time kept in reserve for sending the move, so that a player using
+// all of their budget doesn't lose on time
+const MOVE_OVERHEAD: Duration = Duration::from_millis(20);
+
+/// Limits on how long [`search`] may think. Every limit that's set
+/// has to hold, and with no limits at all the search goes to
+/// [`MAX_DEPTH`].
+///
+/// The remaining times and increments are used to budget the time for
+/// the player to move, like a UCI `go wtime .. btime ..` command.
+///
+/// The limits also hold the [`SearchOptions`] to search with.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SearchLimits {
+    /// The deepest depth to search, in plies
+    pub depth: Option<u32>,
+    /// The most positions to visit
+    pub nodes: Option<u64>,
+    /// Exactly how long to search for
+    pub movetime: Option<Duration>,
+    /// The time white has left on the clock
+    pub wtime: Option<Duration>,
+    /// The time black has left on the clock
+    pub btime: Option<Duration>,
+    /// The increment white gets after each move
+    pub winc: Duration,
+    /// The increment black gets after each move
+    pub binc: Duration,
+    /// Whether the search has to be reproducible, visiting the same
+    /// positions and finding the same result on every run and
+    /// platform. The time limits are ignored then, so the search only
+    /// stops at the depth or node limit, or when it's told to by a
+    /// [`SearchControl`]. Without any of those it goes to
+    /// [`MAX_DEPTH`].
+    pub deterministic: bool,
+    /// Which enhancements the search uses
+    pub options: SearchOptions,
+}
+
+/// Switches for the enhancements of the search over plain alpha-beta,
+/// to study their impact, e.g. by playing them against each other with
+/// [`crate::engine_match`], or to turn them off for debugging. They're
+/// all on by default.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+// the options are independent switches, which read best as fields
+#[allow(clippy::struct_excessive_bools)]
+pub struct SearchOptions {
+    /// Whether to keep searching captures and promotions past the
+    /// depth, until the position is quiet, so that e.g. a queen isn't
+    /// counted as won when it's recaptured right after the last ply
+    pub quiescence: bool,
+    /// Whether to search one ply deeper when in check, so that mates
+    /// and checks that win material at the horizon aren't missed
+    pub check_extensions: bool,
+    /// Whether to try the two latest quiet moves that caused a cutoff
+    /// at the same ply before the other quiet moves
+    pub killers: bool,
+    /// Whether to order quiet moves by how often the same move, from
+    /// and to the same squares, has caused cutoffs in the search so far
+    pub history: bool,
+    /// Whether to search late quiet moves, which are unlikely to be
+    /// best with good move ordering, less deep at first, and only
+    /// search them fully if they turn out better than expected
+    pub late_move_reductions: bool,
+    /// Whether to let the opponent move twice in a row, searched less
+    /// deep, and give up on a position if the player to move is still
+    /// doing well enough for the opponent to avoid it. It's not done
+    /// in check, or with only pawns left, where having to move can be
+    /// a disadvantage.
+    pub null_move_pruning: bool,
+}
+
+impl SearchOptions {
+    /// Plain alpha-beta, with every enhancement turned off
+    pub const NONE: SearchOptions = SearchOptions {
+        quiescence: false,
+        check_extensions: false,
+        killers: false,
+        history: false,
+        late_move_reductions: false,
+        null_move_pruning: false,
+    };
+
+    /// Every enhancement turned on, the default
+    pub const ALL: SearchOptions = SearchOptions {
+        quiescence: true,
+        check_extensions: true,
+        killers: true,
+        history: true,
+        late_move_reductions: true,
+        null_move_pruning: true,
+    };
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions::ALL
+    }
+}
+
+impl SearchLimits {
+    /// Limits to search exactly `depth` plies deep
+    pub fn depth(depth: u32) -> SearchLimits {
+        SearchLimits {
+            depth: Some(depth),
+            ..SearchLimits::default()
+        }
+    }
+
+    /// Limits to visit at most `nodes` positions, deterministically,
+    /// so that the same position always gives the same result
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::board::Board;
+    /// # use chess_engine::eval::MaterialEvaluator;
+    /// # use chess_engine::search::{self, SearchInfo, SearchLimits};
+    /// let board = Board::default_board();
+    /// let limits = SearchLimits::nodes(5000);
+    /// let mut run = || search::search(&board, &MaterialEvaluator, &limits, &mut |_: &SearchInfo| ());
+    /// let (first, second) = (run(), run());
+    ///
+    /// assert_eq!((first.depth, first.score, first.nodes), (second.depth, second.score, second.nodes));
+    /// assert_eq!(first.pv, second.pv);
+    /// ```
+    pub fn nodes(nodes: u64) -> SearchLimits {
+        SearchLimits {
+            nodes: Some(nodes),
+            deterministic: true,
+            ..SearchLimits::default()
+        }
+    }
+
+    /// Limits to search for `movetime`
+    pub fn movetime(movetime: Duration) -> SearchLimits {
+        SearchLimits {
+            movetime: Some(movetime),
+            ..SearchLimits::default()
+        }
+    }
+
+    /// Limits following the clock, where both players have `time`
+    /// left and get `increment` after each move
+    pub fn clock(time: Duration, increment: Duration) -> SearchLimits {
+        SearchLimits {
+            wtime: Some(time),
+            btime: Some(time),
+            winc: increment,
+            binc: increment,
+            ..SearchLimits::default()
+        }
+    }
+
+    /// Get the soft and hard time limits for `color` to move. No new
+    /// depth is started after the soft limit, and the search is cut
+    /// off at the hard limit. Returns [`None`] if the time isn't
+    /// limited, or the search is
+    /// [deterministic](SearchLimits::deterministic).
+    pub fn time_budget(&self, color: Color) -> Option<(Duration, Duration)> {
+        if self.deterministic {
+            return None;
+        }
+        if let Some(movetime) = self.movetime {
+            return Some((movetime, movetime));
+        }
+        let (time, increment) = match color {
+            Color::White => (self.wtime?, self.winc),
+            Color::Black => (self.btime?, self.binc),
+        };
+        let usable = time.saturating_sub(MOVE_OVERHEAD);
+        let hard = (usable / 10 + increment).min(usable);
+        let soft = (usable / 40 + increment / 2).min(hard);
+        Some((soft, hard))
+    }
+}
+
+/// What a search found after finishing a depth
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchInfo {
+    /// The depth searched, in plies
+    pub depth: u32,
+    /// The score for the player to move, like for [`best_line`]
+    pub score: i32,
+    /// The principal variation, starting with the best move
+    pub pv: Vec<Move>,
+    /// How many positions were visited in total
+    pub nodes: u64,
+    /// How long the search has taken in total
+    pub time: Duration,
+}
+
+impl SearchInfo {
+    /// Get how many positions were visited per second
+    pub fn nps(&self) -> u64 {
+        match self.time.as_micros() {
+            0 => self.nodes,
+            micros => (u128::from(self.nodes) * 1_000_000 / micros) as u64,
+        }
+    }
+}
+
+/// Something following the progress of a [`search`], such as a GUI
+/// showing what the engine is thinking. Closures taking a
+/// [`SearchInfo`] are observers, and so are channel senders, for
+/// following a search running on another thread.
+pub trait SearchObserver {
+    /// Called each time the search has finished a depth, with what it
+    /// found so far
+    fn depth_completed(&mut self, info: &SearchInfo);
+}
+
+impl<F: FnMut(&SearchInfo)> SearchObserver for F {
+    fn depth_completed(&mut self, info: &SearchInfo) {
+        self(info);
+    }
+}
+
+impl SearchObserver for Sender<SearchInfo> {
+    fn depth_completed(&mut self, info: &SearchInfo) {
+        // the search goes on even if no one is listening anymore
+        let _ = self.send(info.clone());
+    }
+}
+
+/// Where a search controlled by a [`SearchControl`] is at
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SearchState {
+    /// Searching the position after the move the opponent is expected
+    /// to play, on their time. The limits don't apply yet, and the
+    /// search doesn't return until it's told about a ponderhit or to
+    /// stop.
+    Pondering,
+    /// Searching normally, within the limits
+    Searching,
+    /// Told to stop as soon as possible
+    Stopped,
+}
+
+/// A handle to control a search running on another thread, e.g. to
+/// implement the UCI `go ponder`, `ponderhit` and `stop` commands.
+/// Clones of the handle control the same search.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::Board;
+/// # use chess_engine::eval::MaterialEvaluator;
+/// # use chess_engine::search::{self, SearchControl, SearchInfo, SearchLimits, SearchState};
+/// # use std::thread;
+/// let control = SearchControl::pondering();
+/// let pondering = {
+///     let control = control.clone();
+///     thread::spawn(move || {
+///         let board = Board::default_board();
+///         let limits = SearchLimits::depth(2);
+///         let mut observer = |_: &SearchInfo| ();
+///         let reply = "e2e4".parse().unwrap();
+///         search::ponder(&board, reply, &MaterialEvaluator, &limits, &mut observer, &control)
+///     })
+/// };
+///
+/// // the opponent played the expected move
+/// assert!(control.ponderhit());
+/// assert_eq!(control.state(), SearchState::Searching);
+/// let info = pondering.join().unwrap().unwrap();
+/// assert_eq!(info.depth, 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SearchControl {
+    // the state, and when the limits started to apply
+    shared: Arc<Mutex<(SearchState, Instant)>>,
+}
+
+impl Default for SearchControl {
+    fn default() -> Self {
+        SearchControl::new()
+    }
+}
+
+impl SearchControl {
+    /// Create a handle for a normal search, whose limits apply from
+    /// now on
+    pub fn new() -> SearchControl {
+        SearchControl {
+            shared: Arc::new(Mutex::new((SearchState::Searching, Instant::now()))),
+        }
+    }
+
+    /// Create a handle for a search that starts out pondering
+    pub fn pondering() -> SearchControl {
+        SearchControl {
+            shared: Arc::new(Mutex::new((SearchState::Pondering, Instant::now()))),
+        }
+    }
+
+    fn get(&self) -> (SearchState, Instant) {
+        *self.shared.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Get where the search is at
+    pub fn state(&self) -> SearchState {
+        self.get().0
+    }
+
+    /// Tell a pondering search that the opponent played the expected
+    /// move, turning it into a normal search whose limits apply from
+    /// now on. Returns `false` if the search wasn't pondering.
+    pub fn ponderhit(&self) -> bool {
+        let mut shared = self.shared.lock().unwrap_or_else(PoisonError::into_inner);
+        if shared.0 != SearchState::Pondering {
+            return false;
+        }
+        *shared = (SearchState::Searching, Instant::now());
+        true
+    }
+
+    /// Tell the search to stop as soon as possible. It still finishes
+    /// the first depth, so that there's a move to play.
+    pub fn stop(&self) {
+        self.shared.lock().unwrap_or_else(PoisonError::into_inner).0 = SearchState::Stopped;
+    }
+}
+
+/// Search the position with iterative deepening, going one ply deeper
+/// at a time until `limits` say to stop, and return what the deepest
+/// finished depth found. Depth 1 is always finished, so there's a move
+/// to play if the game isn't over. `observer` is told about each
+/// finished depth.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::Board;
+/// # use chess_engine::eval::MaterialEvaluator;
+/// # use chess_engine::search::{self, SearchInfo, SearchLimits};
+/// # use std::time::Duration;
+/// let board = Board::load_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+/// let limits = SearchLimits {
+///     depth: Some(3),
+///     movetime: Some(Duration::from_secs(5)),
+///     ..SearchLimits::default()
+/// };
+/// let mut depths = vec![];
+/// let info = search::search(&board, &MaterialEvaluator, &limits, &mut |info: &SearchInfo| {
+///     depths.push(info.depth);
+/// });
+///
+/// assert_eq!(depths, [1, 2, 3]);
+/// assert_eq!(info.pv[0], "d1d5".parse().unwrap());
+/// ```
+pub fn search<E, O>(
+    board: &Board,
+    evaluator: &E,
+    limits: &SearchLimits,
+    observer: &mut O,
+) -> SearchInfo
+where
+    E: Evaluator + ?Sized,
+    O: SearchObserver + ?Sized,
+{
+    search_with_control(board, evaluator, limits, observer, &SearchControl::new())
+}
+
+/// Search like [`search`], but controlled through `control` from
+/// another thread, which can stop the search early. If `control` is
+/// pondering, the search doesn't return before a ponderhit or stop.
+pub fn search_with_control<E, O>(
+    board: &Board,
+    evaluator: &E,
+    limits: &SearchLimits,
+    observer: &mut O,
+    control: &SearchControl,
+) -> SearchInfo
+where
+    E: Evaluator + ?Sized,
+    O: SearchObserver + ?Sized,
+{
+    let mut search = Search::new(evaluator, limits.options);
+    let budget = limits.time_budget(board.turn());
+    let max_depth = limits.depth.unwrap_or(MAX_DEPTH).max(1);
+    search.control = Some(control);
+    search.hard_limit = budget.map(|(_, hard)| hard);
+    search.node_limit = limits.nodes;
+
+    let mut best = SearchInfo {
+        depth: 0,
+        score: 0,
+        pv: vec![],
+        nodes: 0,
+        time: Duration::from_secs(0),
+    };
+    for depth in 1..=max_depth {
+        let (score, pv) = search.alpha_beta(board, depth, 0, -MATE_SCORE - 1, MATE_SCORE + 1);
+        if search.aborted {
+            break;
+        }
+        best = SearchInfo {
+            depth,
+            score,
+            pv,
+            nodes: search.nodes,
+            time: search.start.elapsed(),
+        };
+        observer.depth_completed(&best);
+
+        // depth 1 has been searched, so the deeper searches can be cut
+        // off from now on
+        search.can_abort = true;
+        search.root_move = best.pv.first().copied();
+        let (state, since) = control.get();
+        let out_of_time = budget.map_or(false, |(soft, _)| since.elapsed() >= soft);
+        let out_of_nodes = limits.nodes.map_or(false, |nodes| best.nodes >= nodes);
+        let out_of_limits = state == SearchState::Searching && (out_of_time || out_of_nodes);
+        if state == SearchState::Stopped || out_of_limits || best.pv.is_empty() {
+            break;
+        }
+    }
+
+    // the result of pondering may only be used once the opponent has
+    // moved, even if there's nothing left to search
+    while control.state() == SearchState::Pondering {
+        thread::sleep(Duration::from_millis(1));
+    }
+    best
+}
+
+/// Ponder: search the position after the opponent plays
+/// `expected_reply` on `board`, while waiting for them to move.
+/// `control` should start out [pondering](SearchControl::pondering),
+/// and the search goes on without limits until it's told about a
+/// ponderhit, when it carries on as a normal search within `limits`,
+/// or to stop, e.g. because the opponent played another move.
+///
+/// Returns [`None`] if `expected_reply` is illegal.
+pub fn ponder<E, O>(
+    board: &Board,
+    expected_reply: Move,
+    evaluator: &E,
+    limits: &SearchLimits,
+    observer: &mut O,
+    control: &SearchControl,
+) -> Option<SearchInfo>
+where
+    E: Evaluator + ?Sized,
+    O: SearchObserver + ?Sized,
+{
+    let next = board.perform_move(expected_reply)?;
+    Some(search_with_control(
+        &next, evaluator, limits, observer, control,
+    ))
+}
+
+// The state of a search, used to cut it off once it hits its limits
+#[allow(clippy::struct_excessive_bools)]
+struct Search<'a, E: ?Sized> {
+    evaluator: &'a E,
+    options: SearchOptions,
+    nodes: u64,
+    start: Instant,
+    control: Option<&'a SearchControl>,
+    // the state of the control when it was last checked
+    pondering: bool,
+    limits_start: Instant,
+    hard_limit: Option<Duration>,
+    node_limit: Option<u64>,
+    can_abort: bool,
+    aborted: bool,
+    // the best move of the last depth, which is searched first
+    root_move: Option<Move>,
+    // the two latest quiet moves causing a cutoff at each ply
+    killers: Vec<[Option<Move>; 2]>,
+    // how much the quiet moves between each pair of squares, by index,
+    // have caused cutoffs
+    history: Vec<[u32; 64]>,
+    // whether the last move was a null move, so the next one isn't
+    after_null: bool,
+}
+
+// Scores beyond this are mates, which null move pruning mustn't cut
+// off on, as passing can't prove a mate
+const MATE_BOUND: i32 = MATE_SCORE - 2 * MAX_DEPTH as i32;
+
+// How many plies less null moves and reduced late moves are searched
+const NULL_MOVE_REDUCTION: u32 = 2;
+const LATE_MOVE_REDUCTION: u32 = 1;
+// How many moves are searched fully before later ones are reduced
+const FULL_DEPTH_MOVES: usize = 3;
+
+// The value of a piece capturing another, for ordering captures by
+// the most valuable victim first and then the least valuable attacker
+fn attacker_value(piece: PieceType) -> u32 {
+    match piece {
+        PieceType::King => 1000,
+        piece => piece.value(),
+    }
+}
+
+// Whether a move captures a piece, including en passant
+fn is_capture(board: &Board, m: Move) -> bool {
+    match m {
+        Move::Normal { from, to } => {
+            board[to].is_some()
+                || (board[from].map(|p| p.piece) == Some(PieceType::Pawn) && from.file != to.file)
+        }
+        Move::Promotion { to, .. } => board[to].is_some(),
+        Move::Castling(_) => false,
+    }
+}
+
+// The key to sort captures by, most valuable victim first and then
+// least valuable attacker. En passant captures land on an empty
+// square, so the victim is at least worth a pawn.
+fn capture_order(board: &Board, m: Move) -> (Reverse<u32>, u32) {
+    let turn = board.turn();
+    let victim = board[m.to(turn)].map_or(0, |p| p.piece.value());
+    let attacker = board[m.from(turn)].map_or(0, |p| attacker_value(p.piece));
+    (Reverse(victim.max(PieceType::Pawn.value())), attacker)
+}
+
+// Whether the player to move has anything besides pawns and their king
+fn has_pieces(board: &Board) -> bool {
+    board
+        .get_board()
+        .iter()
+        .flatten()
+        .flatten()
+        .any(|p| p.color == board.turn() && !matches!(p.piece, PieceType::Pawn | PieceType::King))
+}
+
+impl<'a, E: Evaluator + ?Sized> Search<'a, E> {
+    fn new(evaluator: &'a E, options: SearchOptions) -> Search<'a, E> {
+        Search {
+            evaluator,
+            options,
+            nodes: 0,
+            start: Instant::now(),
+            control: None,
+            pondering: false,
+            limits_start: Instant::now(),
+            hard_limit: None,
+            node_limit: None,
+            can_abort: false,
+            aborted: false,
+            root_move: None,
+            killers: vec![],
+            history: vec![[0; 64]; 64],
+            after_null: false,
+        }
+    }
+
+    fn should_abort(&mut self) -> bool {
+        if !self.can_abort {
+            return false;
+        }
+        // checking the time and the control is slow, so only do it
+        // every 1024 nodes
+        if self.nodes.trailing_zeros() >= 10 {
+            if let Some(control) = self.control {
+                let (state, since) = control.get();
+                if state == SearchState::Stopped {
+                    return true;
+                }
+                self.pondering = state == SearchState::Pondering;
+                self.limits_start = since;
+            }
+            let out_of_time = self
+                .hard_limit
+                .map_or(false, |limit| self.limits_start.elapsed() >= limit);
+            if out_of_time && !self.pondering {
+                return true;
+            }
+        }
+        !self.pondering && self.node_limit.map_or(false, |limit| self.nodes >= limit)
+    }
+
+    fn killers(&self, ply: u32) -> [Option<Move>; 2] {
+        self.killers.get(ply as usize).copied().unwrap_or_default()
+    }
+
+    // Order the moves to search the most promising first: the best
+    // move of the last depth at the root, then captures, killers and
+    // the other quiet moves by their history
+    fn order_moves(&self, board: &Board, moves: &mut [Move], ply: u32) {
+        let turn = board.turn();
+        let root_move = if ply == 0 { self.root_move } else { None };
+        let killers = self.killers(ply);
+        moves.sort_by_cached_key(|&m| {
+            if Some(m) == root_move {
+                (0, Reverse(0), 0)
+            } else if is_capture(board, m) {
+                let (victim, attacker) = capture_order(board, m);
+                (1, victim, attacker)
+            } else if self.options.killers && killers.contains(&Some(m)) {
+                (2, Reverse(0), 0)
+            } else if self.options.history {
+                let (from, to) = (m.from(turn).index(), m.to(turn).index());
+                (3, Reverse(self.history[from as usize][to as usize]), 0)
+            } else {
+                (3, Reverse(0), 0)
+            }
+        });
+    }
+
+    // Remember a quiet move that caused a cutoff
+    fn record_cutoff(&mut self, board: &Board, m: Move, depth: u32, ply: u32) {
+        if self.options.killers {
+            let ply = ply as usize;
+            if self.killers.len() <= ply {
+                self.killers.resize(ply + 1, [None; 2]);
+            }
+            let killers = &mut self.killers[ply];
+            if killers[0] != Some(m) {
+                killers[1] = killers[0];
+                killers[0] = Some(m);
+            }
+        }
+        if self.options.history {
+            let turn = board.turn();
+            let (from, to) = (m.from(turn).index(), m.to(turn).index());
+            let entry = &mut self.history[from as usize][to as usize];
+            *entry = entry.saturating_add(depth * depth);
+        }
+    }
+
+    fn alpha_beta(
+        &mut self,
+        board: &Board,
+        mut depth: u32,
+        ply: u32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> (i32, Vec<Move>) {
+        self.nodes += 1;
+        let after_null = std::mem::replace(&mut self.after_null, false);
+        if self.should_abort() {
+            self.aborted = true;
+            return (0, vec![]);
+        }
+
+        let in_check = board.in_check();
+        // the side giving check has to spend a ply doing so, so the
+        // extensions can't go on forever
+        if in_check && self.options.check_extensions && ply < MAX_DEPTH {
+            depth += 1;
+        }
+
+        // mates right at the horizon would otherwise be scored as normal
+        // positions, and it's only possible to be mated while in check
+        if depth == 0 && !in_check {
+            return (self.quiesce(board, alpha, beta), vec![]);
+        }
+
+        let mut moves = board.get_all_legal_moves();
+        if moves.is_empty() {
+            return if in_check {
+                (-(MATE_SCORE - ply as i32), vec![])
+            } else {
+                (0, vec![])
+            };
+        }
+
+        if depth == 0 {
+            return (self.quiesce(board, alpha, beta), vec![]);
+        }
+
+        let try_null_move = self.options.null_move_pruning
+            && ply > 0
+            && !after_null
+            && !in_check
+            && depth > NULL_MOVE_REDUCTION
+            && beta.abs() < MATE_BOUND
+            && has_pieces(board);
+        if try_null_move {
+            self.after_null = true;
+            let (score, _) = self.alpha_beta(
+                &board.null_move(),
+                depth - 1 - NULL_MOVE_REDUCTION,
+                ply + 1,
+                -beta,
+                -beta + 1,
+            );
+            if self.aborted {
+                return (0, vec![]);
+            }
+            if -score >= beta {
+                return (beta, vec![]);
+            }
+        }
+
+        self.order_moves(board, &mut moves, ply);
+
+        let mut best_line = vec![];
+        for (i, m) in moves.into_iter().enumerate() {
+            let next = board.perform_move_unvalidated(m);
+            let quiet = !is_capture(board, m) && !matches!(m, Move::Promotion { .. });
+            let reduce = self.options.late_move_reductions
+                && i >= FULL_DEPTH_MOVES
+                && depth > LATE_MOVE_REDUCTION + 1
+                && quiet
+                && !in_check
+                && !next.in_check();
+
+            let mut result = None;
+            if reduce {
+                // only check whether the move beats alpha, with a null
+                // window
+                let (score, line) = self.alpha_beta(
+                    &next,
+                    depth - 1 - LATE_MOVE_REDUCTION,
+                    ply + 1,
+                    -alpha - 1,
+                    -alpha,
+                );
+                if self.aborted {
+                    return (0, vec![]);
+                }
+                if -score <= alpha {
+                    result = Some((score, line));
+                }
+            }
+            let (score, line) = match result {
+                Some(result) => result,
+                None => self.alpha_beta(&next, depth - 1, ply + 1, -beta, -alpha),
+            };
+            if self.aborted {
+                return (0, vec![]);
+            }
+            let score = -score;
+
+            if score > alpha || best_line.is_empty() {
+                best_line = vec![m];
+                best_line.extend(line);
+            }
+            if score > alpha {
+                alpha = score;
+            }
+            if alpha >= beta {
+                if quiet {
+                    self.record_cutoff(board, m, depth, ply);
+                }
+                break;
+            }
+        }
+
+        (alpha, best_line)
+    }
+
+    // Search only the captures and promotions, until the position is
+    // quiet. The player to move can always "stand pat" and take the
+    // static evaluation instead, as they don't have to capture.
+    fn quiesce(&mut self, board: &Board, mut alpha: i32, beta: i32) -> i32 {
+        let stand_pat = self.evaluator.evaluate(board);
+        if !self.options.quiescence || stand_pat >= beta {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let turn = board.turn();
+        let mut moves = board.generate_captures();
+        moves.sort_by_key(|&m| capture_order(board, m));
+        // promoting without capturing, from the rank the opponent's
+        // pawns start on
+        for sq in SquareSpec::rank_iter(turn.opposite().pawn_home_rank()) {
+            if board[sq] == Some(Piece::new(PieceType::Pawn, turn)) {
+                moves.extend(board.get_legal_moves(sq).into_iter().filter(|&m| match m {
+                    Move::Promotion { to, target, .. } => {
+                        target == PieceType::Queen && board[to].is_none()
+                    }
+                    _ => false,
+                }));
+            }
+        }
+
+        for m in moves {
+            self.nodes += 1;
+            if self.should_abort() {
+                self.aborted = true;
+                return 0;
+            }
+            let score = -self.quiesce(&board.perform_move_unvalidated(m), -beta, -alpha);
+            if self.aborted {
+                return 0;
+            }
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+}
+
+/// Find a forced mate for the player to move within `max_plies`
+/// plies (half-moves), returning the shortest forced line. The line
+/// starts with the attacker's move and alternates between the two
+/// players, with the last move delivering mate. The defender's
+/// replies in the line are the ones that delay mate the longest.
+///
+/// Returns [`None`] if there is no forced mate within the given
+/// number of plies.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::board::{Board, Move};
+/// # use chess_engine::search;
+/// let board = Board::load_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+/// let line = search::find_mate(&board, 3).unwrap();
+///
+/// assert_eq!(
+///     line,
+///     vec![Move::Normal {
+///         from: "a1".parse().unwrap(),
+///         to: "a8".parse().unwrap(),
+///     }]
+/// );
+/// ```
+pub fn find_mate(board: &Board, max_plies: u32) -> Option<Vec<Move>> {
+    // a mate is always delivered by the attacker, so only odd depths
+    // need to be searched. Searching them in increasing order means
+    // the first line found is also the shortest one
+    (1..=max_plies)
+        .step_by(2)
+        .find_map(|plies| mate_within(board, plies))
+}
+
+// Find a move for the player to move which forces mate within
+// `plies` plies, where `plies` is odd. The first such move is
+// returned, which doesn't have to be the one mating the fastest.
+fn mate_within(board: &Board, plies: u32) -> Option<Vec<Move>> {
+    'attack: for m in board.get_all_legal_moves() {
+        let next = board.perform_move_unvalidated(m);
+        let replies = next.get_all_legal_moves();
+
+        if replies.is_empty() {
+            if next.in_check() {
+                return Some(vec![m]);
+            }
+            // stalemate, not what we're looking for
+            continue;
+        }
+
+        if plies < 3 {
+            continue;
+        }
+
+        // every single reply has to lead to mate, and we keep the one
+        // that holds out the longest as the principal line. The mate
+        // after each reply has to be the shortest one, or a reply could
+        // look like it holds out longer than it does.
+        let mut longest: Option<Vec<Move>> = None;
+        for reply in replies {
+            let line = match find_mate(&next.perform_move_unvalidated(reply), plies - 2) {
+                Some(line) => line,
+                None => continue 'attack,
+            };
+            if longest.as_ref().map_or(0, Vec::len) < line.len() + 1 {
+                let mut new_line = vec![reply];
+                new_line.extend(line);
+                longest = Some(new_line);
+            }
+        }
+
+        let mut line = vec![m];
+        line.extend(longest.unwrap_or_default());
+        return Some(line);
+    }
+
+    None
+}
+
+#[cfg(all(test, feature = "fen"))]
+mod tests {
+    use super::{
+        best_line, best_line_with, find_mate, ponder, search, SearchControl, SearchInfo,
+        SearchLimits, SearchOptions, SearchState, MATE_SCORE,
+    };
+    use crate::board::Board;
+    use crate::eval::MaterialEvaluator;
+    use crate::piece::Color;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    fn assert_mating_line(board: &Board, line: &[super::Move]) {
+        let mut board = *board;
+        for &m in line {
+            board = board
+                .perform_move(m)
+                .expect("line contained an illegal move");
+        }
+        assert!(board.in_check(), "last move didn't give check");
+        assert!(
+            board.get_all_legal_moves().is_empty(),
+            "line doesn't end in mate"
+        );
+    }
+
+    #[test]
+    fn mate_in_one() {
+        let board = Board::load_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let line = find_mate(&board, 1).unwrap();
+
+        assert_eq!(line.len(), 1);
+        assert_mating_line(&board, &line);
+    }
+
+    #[test]
+    fn mate_in_two() {
+        let board = Board::load_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+
+        assert!(find_mate(&board, 1).is_none());
+
+        let line = find_mate(&board, 3).unwrap();
+        assert_eq!(line.len(), 3);
+        assert_mating_line(&board, &line);
+    }
+
+    #[test]
+    fn mate_in_three() {
+        let board = Board::load_fen("k7/2R5/8/8/2K5/8/8/8 w - - 0 1").unwrap();
+
+        assert!(find_mate(&board, 3).is_none());
+
+        let line = find_mate(&board, 7).unwrap();
+        assert_eq!(line.len(), 5);
+        assert_mating_line(&board, &line);
+        // the defender's reply holds out as long as possible
+        let after_reply = board
+            .perform_move(line[0])
+            .and_then(|b| b.perform_move(line[1]))
+            .unwrap();
+        assert!(find_mate(&after_reply, 1).is_none());
+    }
+
+    #[test]
+    fn no_mate_with_bare_kings() {
+        let board = Board::load_fen("k7/8/8/8/8/8/8/K7 w - - 0 1").unwrap();
+
+        assert!(find_mate(&board, 3).is_none());
+    }
+
+    #[test]
+    fn stalemate_is_not_mate() {
+        // Qb6 leaves black without moves, but it's stalemate
+        let board = Board::load_fen("k7/8/8/1Q6/8/8/8/K7 w - - 0 1").unwrap();
+
+        assert!(find_mate(&board, 1).is_none());
+    }
+
+    #[test]
+    fn search_matches_fixed_depth() {
+        let board =
+            Board::load_fen("r3k2r/ppp2ppp/2n5/3q4/3P4/2N5/PPP2PPP/R2QK2R w KQkq - 0 1").unwrap();
+        let info = search(
+            &board,
+            &MaterialEvaluator,
+            &SearchLimits::depth(3),
+            &mut |_: &SearchInfo| (),
+        );
+
+        assert_eq!(info.depth, 3);
+        assert_eq!(
+            info.score,
+            best_line_with(&board, &MaterialEvaluator, 3, SearchOptions::ALL).0
+        );
+    }
+
+    #[test]
+    fn quiescence_and_check_extensions() {
+        let search_with = |fen: &str, limits: SearchLimits| {
+            let board = Board::load_fen(fen).unwrap();
+            search(
+                &board,
+                &MaterialEvaluator,
+                &limits,
+                &mut |_: &SearchInfo| (),
+            )
+        };
+        let off = SearchLimits {
+            options: SearchOptions {
+                quiescence: false,
+                check_extensions: false,
+                ..SearchOptions::default()
+            },
+            ..SearchLimits::depth(1)
+        };
+
+        // the pawn on d5 is defended
+        let defended = "4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1";
+        let capture = "d1d5".parse().unwrap();
+        assert_eq!(search_with(defended, off).pv[0], capture);
+        assert_ne!(search_with(defended, SearchLimits::depth(1)).pv[0], capture);
+
+        // smothered mate: Qg8+ Rxg8 Nf7#
+        let smothered = "5r1k/6pp/7N/3Q4/8/8/8/6K1 w - - 0 1";
+        let info = search_with(smothered, SearchLimits::depth(2));
+        assert_eq!(info.score, MATE_SCORE - 3);
+        assert_eq!(info.pv[0], "d5g8".parse().unwrap());
+        let info = search_with(
+            smothered,
+            SearchLimits {
+                depth: Some(2),
+                ..off
+            },
+        );
+        assert!(info.score < MATE_SCORE - 3);
+    }
+
+    #[test]
+    fn enhancements_prune_without_changing_the_tactics() {
+        let board =
+            Board::load_fen("r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4")
+                .unwrap();
+        let base = SearchOptions {
+            quiescence: true,
+            check_extensions: true,
+            ..SearchOptions::NONE
+        };
+        let only = |f: fn(&mut SearchOptions)| {
+            let mut options = base;
+            f(&mut options);
+            options
+        };
+        let variants = [
+            base,
+            only(|o| o.killers = true),
+            only(|o| o.history = true),
+            only(|o| o.late_move_reductions = true),
+            only(|o| o.null_move_pruning = true),
+            SearchOptions::ALL,
+        ];
+        let scholars_mate = "h5f7".parse().unwrap();
+        for &options in &variants {
+            let (score, line) = best_line_with(&board, &MaterialEvaluator, 3, options);
+            assert_eq!(line[0], scholars_mate, "{:?}", options);
+            assert_eq!(score, MATE_SCORE - 1, "{:?}", options);
+        }
+
+        let nodes = |options| {
+            let limits = SearchLimits {
+                options,
+                ..SearchLimits::depth(4)
+            };
+            let board =
+                Board::load_fen("r3k2r/ppp2ppp/2n5/3q4/3P4/2N5/PPP2PPP/R2QK2R w KQkq - 0 1")
+                    .unwrap();
+            search(
+                &board,
+                &MaterialEvaluator,
+                &limits,
+                &mut |_: &SearchInfo| (),
+            )
+            .nodes
+        };
+        assert!(nodes(SearchOptions::ALL) < nodes(base));
+    }
+
+    #[test]
+    fn search_limits() {
+        let board = Board::default_board();
+
+        let limits = SearchLimits {
+            nodes: Some(1000),
+            ..SearchLimits::default()
+        };
+        let info = search(
+            &board,
+            &MaterialEvaluator,
+            &limits,
+            &mut |_: &SearchInfo| (),
+        );
+        assert!(info.depth >= 1 && info.depth < 5);
+        assert!(!info.pv.is_empty());
+
+        let start = Instant::now();
+        let limits = SearchLimits::movetime(Duration::from_millis(100));
+        let info = search(
+            &board,
+            &MaterialEvaluator,
+            &limits,
+            &mut |_: &SearchInfo| (),
+        );
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert!(!info.pv.is_empty());
+
+        let limits = SearchLimits::clock(Duration::from_secs(60), Duration::from_secs(1));
+        let (soft, hard) = limits.time_budget(Color::Black).unwrap();
+        assert!(soft < hard && hard < Duration::from_secs(60));
+        assert_eq!(SearchLimits::depth(2).time_budget(Color::White), None);
+    }
+
+    #[test]
+    fn deterministic_search() {
+        let board =
+            Board::load_fen("r3k2r/ppp2ppp/2n5/3q4/3P4/2N5/PPP2PPP/R2QK2R w KQkq - 0 1").unwrap();
+        let run = |limits: &SearchLimits| {
+            let info = search(&board, &MaterialEvaluator, limits, &mut |_: &SearchInfo| ());
+            (info.depth, info.score, info.pv, info.nodes)
+        };
+
+        let limits = SearchLimits::nodes(5_000);
+        let expected = run(&limits);
+        assert!(expected.0 >= 2);
+        // a time limit far too short to reach the node limit is ignored
+        let limits = SearchLimits {
+            movetime: Some(Duration::from_millis(1)),
+            ..limits
+        };
+        assert_eq!(limits.time_budget(Color::White), None);
+        assert_eq!(run(&limits), expected);
+        assert_eq!(run(&limits), expected);
+    }
+
+    #[test]
+    fn observing_over_a_channel() {
+        let board = Board::default_board();
+        let (mut sender, receiver) = mpsc::channel();
+        let info = search(
+            &board,
+            &MaterialEvaluator,
+            &SearchLimits::depth(3),
+            &mut sender,
+        );
+        drop(sender);
+
+        let infos: Vec<_> = receiver.iter().collect();
+        assert_eq!(infos.len(), 3);
+        assert_eq!(infos[2], info);
+        assert!(infos.windows(2).all(|w| w[0].nodes < w[1].nodes));
+        assert!(info.nps() > 0);
+    }
+
+    #[test]
+    fn stopping_a_ponder_search() {
+        let control = SearchControl::pondering();
+        let pondering = {
+            let control = control.clone();
+            thread::spawn(move || {
+                let board = Board::default_board();
+                let limits = SearchLimits::default();
+                let mut observer = |_: &SearchInfo| ();
+                let reply = "d2d4".parse().unwrap();
+                ponder(
+                    &board,
+                    reply,
+                    &MaterialEvaluator,
+                    &limits,
+                    &mut observer,
+                    &control,
+                )
+            })
+        };
+
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(control.state(), SearchState::Pondering);
+        control.stop();
+        assert!(!control.ponderhit());
+        let info = pondering.join().unwrap().unwrap();
+        assert!(info.depth >= 1);
+        assert!(!info.pv.is_empty());
+
+        let board = Board::default_board();
+        let control = SearchControl::pondering();
+        let mut observer = |_: &SearchInfo| ();
+        let illegal = "e2e5".parse().unwrap();
+        let limits = SearchLimits::depth(1);
+        assert!(ponder(
+            &board,
+            illegal,
+            &MaterialEvaluator,
+            &limits,
+            &mut observer,
+            &control
+        )
+        .is_none());
+    }
+}
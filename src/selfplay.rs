@@ -0,0 +1,351 @@
+//! Module for letting move selectors, such as the engine's search or
+//! a random mover, play games against each other. Useful for
+//! generating test games and datasets, and for fuzzing the rules.
+
+use crate::board::Move;
+#[cfg(feature = "search")]
+use crate::eval::Evaluator;
+use crate::game::{Game, GameState};
+use crate::piece::Color;
+#[cfg(feature = "search")]
+use crate::search::{self, SearchOptions};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+/// Something that picks which move to play in a game
+pub trait MoveSelector {
+    /// Pick a move for the player to move in `game`, or [`None`] to
+    /// stop playing. The move has to be legal.
+    fn select_move(&mut self, game: &Game) -> Option<Move>;
+}
+
+impl<F: FnMut(&Game) -> Option<Move>> MoveSelector for F {
+    fn select_move(&mut self, game: &Game) -> Option<Move> {
+        self(game)
+    }
+}
+
+/// Selects uniformly random legal moves
+#[derive(Debug, Clone)]
+pub struct RandomSelector<R> {
+    rng: R,
+}
+
+impl<R: Rng> RandomSelector<R> {
+    /// Create a selector drawing its moves with `rng`
+    pub fn new(rng: R) -> Self {
+        RandomSelector { rng }
+    }
+}
+
+impl RandomSelector<StdRng> {
+    /// Create a selector drawing its moves from a generator seeded
+    /// with `seed`, so that its games can be reproduced
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::game::Game;
+    /// # use chess_engine::selfplay::{MoveSelector, RandomSelector};
+    /// let game = Game::new();
+    /// let first = RandomSelector::from_seed(42).select_move(&game);
+    ///
+    /// assert_eq!(RandomSelector::from_seed(42).select_move(&game), first);
+    /// ```
+    pub fn from_seed(seed: u64) -> Self {
+        RandomSelector::new(StdRng::seed_from_u64(seed))
+    }
+}
+
+impl<R: Rng> MoveSelector for RandomSelector<R> {
+    fn select_move(&mut self, game: &Game) -> Option<Move> {
+        game.current_board()
+            .get_all_legal_moves()
+            .choose(&mut self.rng)
+            .copied()
+    }
+}
+
+/// Selects the first move of the best line found by
+/// [`search::best_line`]
+#[cfg(feature = "search")]
+#[derive(Debug, Clone)]
+pub struct SearchSelector<E> {
+    evaluator: E,
+    depth: u32,
+    options: SearchOptions,
+    handicap: Option<Handicap>,
+}
+
+/// The Elo rating at and above which [`SearchSelector::with_elo`]
+/// plays at full strength
+#[cfg(feature = "search")]
+pub const FULL_STRENGTH_ELO: u32 = 2000;
+
+// How a selector weakened to an Elo rating plays worse than it could:
+// it picks a uniformly random move `error_rate` of the time, and
+// otherwise any move scoring within `margin` centipawns of the best.
+#[cfg(feature = "search")]
+#[derive(Debug, Clone)]
+struct Handicap {
+    margin: i32,
+    error_rate: f64,
+    rng: StdRng,
+}
+
+#[cfg(feature = "search")]
+impl<E: Evaluator> SearchSelector<E> {
+    /// Create a selector searching `depth` plies with `evaluator`
+    pub fn new(evaluator: E, depth: u32) -> Self {
+        SearchSelector {
+            evaluator,
+            depth,
+            options: SearchOptions::default(),
+            handicap: None,
+        }
+    }
+
+    /// Create a selector that plays roughly at the strength of a
+    /// player rated `target_elo`, e.g. for beginner-friendly computer
+    /// opponents. The lower the rating, the shallower it searches,
+    /// the worse the moves it settles for when they're close to the
+    /// best one, and the more often it plays a random move. From
+    /// [`FULL_STRENGTH_ELO`] up, it always plays the best move found
+    /// 4 plies deep.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::eval::MaterialEvaluator;
+    /// # use chess_engine::game::Game;
+    /// # use chess_engine::player::SearchPlayer;
+    /// # use chess_engine::selfplay::MoveSelector;
+    /// let game = Game::new();
+    /// let mut beginner = SearchPlayer::with_elo(MaterialEvaluator, 800);
+    /// let m = beginner.select_move(&game).unwrap();
+    ///
+    /// assert!(game.current_board().is_legal(m, game.next_player()));
+    /// ```
+    pub fn with_elo(evaluator: E, target_elo: u32) -> Self {
+        let elo = target_elo.min(FULL_STRENGTH_ELO);
+        let depth = (elo / 400).saturating_sub(1).clamp(1, 4);
+        let weakness = FULL_STRENGTH_ELO - elo;
+        let handicap = (weakness > 0).then(|| Handicap {
+            margin: (weakness / 5) as i32,
+            error_rate: (f64::from(weakness) / 4000.0).min(0.5),
+            rng: StdRng::from_entropy(),
+        });
+        SearchSelector {
+            evaluator,
+            depth,
+            options: SearchOptions::default(),
+            handicap,
+        }
+    }
+
+    /// Search with the enhancements in `options`, e.g. to measure how
+    /// much one of them helps by playing a match against a selector
+    /// without it
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::engine_match::{self, MatchConfig};
+    /// # use chess_engine::eval::MaterialEvaluator;
+    /// # use chess_engine::search::SearchOptions;
+    /// # use chess_engine::selfplay::SearchSelector;
+    /// let mut with = SearchSelector::new(MaterialEvaluator, 2);
+    /// let options = SearchOptions {
+    ///     null_move_pruning: false,
+    ///     ..SearchOptions::default()
+    /// };
+    /// let mut without = SearchSelector::new(MaterialEvaluator, 2).with_options(options);
+    /// let config = MatchConfig {
+    ///     games: 2,
+    ///     max_plies: 20,
+    ///     ..MatchConfig::default()
+    /// };
+    /// let report = engine_match::play_match(&mut with, &mut without, &config);
+    ///
+    /// assert_eq!(report.games.len(), 2);
+    /// ```
+    pub fn with_options(mut self, options: SearchOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Seed the random choices of a selector created with
+    /// [`SearchSelector::with_elo`], so that its games can be
+    /// reproduced. Full strength selectors don't make random choices.
+    pub fn seeded(mut self, seed: u64) -> Self {
+        if let Some(handicap) = &mut self.handicap {
+            handicap.rng = StdRng::seed_from_u64(seed);
+        }
+        self
+    }
+}
+
+#[cfg(feature = "search")]
+impl<E: Evaluator> MoveSelector for SearchSelector<E> {
+    fn select_move(&mut self, game: &Game) -> Option<Move> {
+        let board = game.current_board();
+        let (evaluator, depth, options) = (&self.evaluator, self.depth, self.options);
+        let Some(handicap) = &mut self.handicap else {
+            let (_, line) = search::best_line_with(board, evaluator, depth, options);
+            return line.first().copied();
+        };
+
+        let moves = board.get_all_legal_moves();
+        if handicap.rng.gen_bool(handicap.error_rate) {
+            return moves.choose(&mut handicap.rng).copied();
+        }
+        let scored: Vec<_> = moves
+            .into_iter()
+            .map(|m| {
+                let after = board.perform_move_unvalidated(m);
+                let score = -search::best_line_with(&after, evaluator, depth - 1, options).0;
+                (m, score)
+            })
+            .collect();
+        let best = scored.iter().map(|&(_, score)| score).max()?;
+        let candidates: Vec<_> = scored
+            .into_iter()
+            .filter(|&(_, score)| score >= best.saturating_sub(handicap.margin))
+            .map(|(m, _)| m)
+            .collect();
+        candidates.choose(&mut handicap.rng).copied()
+    }
+}
+
+/// Play `game` out with `white` and `black` selecting the moves of
+/// each side, until the game is over, a selector stops, or
+/// `max_plies` moves have been played. Returns how many moves were
+/// played.
+///
+/// # Panics
+///
+/// Panics if a selector picks an illegal move.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::eval::MaterialEvaluator;
+/// # use chess_engine::game::Game;
+/// # use chess_engine::selfplay::{self, RandomSelector, SearchSelector};
+/// # use rand::SeedableRng;
+/// let mut game = Game::new();
+/// let mut white = SearchSelector::new(MaterialEvaluator, 1);
+/// let mut black = RandomSelector::new(rand::rngs::StdRng::seed_from_u64(1));
+/// let plies = selfplay::play(&mut game, &mut white, &mut black, 20);
+///
+/// assert_eq!(game.get_moves().len(), plies as usize);
+/// ```
+pub fn play<W, B>(game: &mut Game, white: &mut W, black: &mut B, max_plies: u32) -> u32
+where
+    W: MoveSelector + ?Sized,
+    B: MoveSelector + ?Sized,
+{
+    for plies in 0..max_plies {
+        if let GameState::Finished(_) = game.state() {
+            return plies;
+        }
+
+        let m = match game.next_player() {
+            Color::White => white.select_move(game),
+            Color::Black => black.select_move(game),
+        };
+        let Some(m) = m else {
+            return plies;
+        };
+        assert!(
+            game.make_move(m).is_some(),
+            "the move selector picked the illegal move {}",
+            m
+        );
+    }
+    max_plies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{play, RandomSelector};
+    #[cfg(all(feature = "fen", feature = "search"))]
+    use super::{MoveSelector, SearchSelector};
+    use crate::board::Move;
+    #[cfg(all(feature = "fen", feature = "search"))]
+    use crate::eval::MaterialEvaluator;
+    use crate::game::{Game, GameResult, GameState, WinReason};
+    use crate::piece::Color;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::cell::RefCell;
+
+    #[test]
+    fn stops_at_checkmate() {
+        // both sides play from the same script, which has a move
+        // to spare after the mate
+        let script = RefCell::new(vec!["a2a3", "d8h4", "g2g4", "e7e5", "f2f3"]);
+        let mut next = |_: &Game| {
+            script
+                .borrow_mut()
+                .pop()
+                .map(|m| m.parse::<Move>().unwrap())
+        };
+        let mut white = next;
+
+        let mut game = Game::new();
+        assert_eq!(play(&mut game, &mut white, &mut next, 100), 4);
+        assert_eq!(
+            game.state(),
+            GameState::Finished(GameResult::Win {
+                winner: Color::Black,
+                reason: WinReason::Checkmate
+            })
+        );
+    }
+
+    #[test]
+    fn stops_when_a_selector_does() {
+        let mut game = Game::new();
+        let mut random = RandomSelector::new(StdRng::seed_from_u64(0));
+        let mut resign = |_: &Game| None;
+
+        assert_eq!(play(&mut game, &mut random, &mut resign, 100), 1);
+        assert_eq!(play(&mut game, &mut random, &mut resign, 0), 0);
+    }
+
+    #[test]
+    fn seeded_games_are_reproducible() {
+        let random_game = || {
+            let mut game = Game::new();
+            let mut white = RandomSelector::from_seed(1);
+            let mut black = RandomSelector::from_seed(2);
+            let _ = play(&mut game, &mut white, &mut black, 60);
+            game.get_moves().to_vec()
+        };
+
+        assert_eq!(random_game(), random_game());
+    }
+
+    #[cfg(all(feature = "fen", feature = "search"))]
+    #[test]
+    fn elo_handicap() {
+        let board = crate::board::Board::load_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+        let game = Game::from_board(board);
+        let capture = "d1d5".parse().unwrap();
+
+        // at full strength, the hanging queen is always taken
+        let mut strong = SearchSelector::with_elo(MaterialEvaluator, 2800);
+        assert_eq!(strong.depth, 4);
+        assert!(strong.handicap.is_none());
+        assert_eq!(strong.select_move(&game), Some(capture));
+
+        // while a beginner misses it some of the time
+        let mut weak = SearchSelector::with_elo(MaterialEvaluator, 400).seeded(7);
+        assert_eq!(weak.depth, 1);
+        let moves: Vec<_> = (0..50).map(|_| weak.select_move(&game).unwrap()).collect();
+        assert!(moves.contains(&capture));
+        assert!(moves.iter().any(|&m| m != capture));
+    }
+}
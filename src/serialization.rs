@@ -0,0 +1,248 @@
+//! Module for choosing how squares and moves are serialized with
+//! serde. Needs the `serde` feature.
+//!
+//! By default, squares are serialized as text like `"e4"` and moves
+//! in the coordinate notation of [`Move`]'s `Display`, like `"e2e4"`,
+//! `"e7e8=Q"` and `"O-O"`, so that e.g. JSON APIs built on the crate
+//! are readable. The modules here can be used with
+//! `#[serde(with = "...")]` to pick a representation explicitly,
+//! where the structured ones have the squares' ranks and files as
+//! numbers.
+//!
+//! # Examples
+//!
+//! ```
+//! # use chess_engine::board::{Move, SquareSpec};
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Arrow {
+//!     // serialized as "e2e4"
+//!     m: Move,
+//!     // serialized as {"rank": 3, "file": 4}
+//!     #[serde(with = "chess_engine::serialization::square_struct")]
+//!     highlight: SquareSpec,
+//! }
+//! ```
+
+use crate::board::{Castling, Move, SquareSpec};
+use crate::piece::PieceType;
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+fn serialize_text<T: fmt::Display, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+fn deserialize_text<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+    D: Deserializer<'de>,
+{
+    let s = Cow::<'de, str>::deserialize(deserializer)?;
+    s.parse().map_err(de::Error::custom)
+}
+
+/// Serialize a [`SquareSpec`] as text, like `"e4"`, which is the
+/// default
+pub mod square_text {
+    use super::{Deserializer, Serializer, SquareSpec};
+
+    /// Serialize the square as text
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the serializer does
+    pub fn serialize<S: Serializer>(sq: &SquareSpec, serializer: S) -> Result<S::Ok, S::Error> {
+        super::serialize_text(sq, serializer)
+    }
+
+    /// Deserialize a square from text
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the text isn't a valid square
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SquareSpec, D::Error> {
+        super::deserialize_text(deserializer)
+    }
+}
+
+/// Serialize a [`SquareSpec`] as a struct with its rank and file as
+/// numbers, both starting from 0
+pub mod square_struct {
+    use super::{de, Deserialize, Deserializer, Serialize, Serializer, SquareSpec};
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct Square {
+        rank: u32,
+        file: u32,
+    }
+
+    /// Serialize the square as a struct
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the serializer does
+    pub fn serialize<S: Serializer>(sq: &SquareSpec, serializer: S) -> Result<S::Ok, S::Error> {
+        Square {
+            rank: sq.rank,
+            file: sq.file,
+        }
+        .serialize(serializer)
+    }
+
+    /// Deserialize a square from a struct
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the struct is malformed, or the square
+    /// is off the board
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SquareSpec, D::Error> {
+        let Square { rank, file } = Square::deserialize(deserializer)?;
+        SquareSpec::try_new(rank, file).ok_or_else(|| de::Error::custom("square is off the board"))
+    }
+}
+
+/// Serialize a [`Move`] as text in coordinate notation, like
+/// `"e2e4"`, which is the default
+pub mod move_text {
+    use super::{Deserializer, Move, Serializer};
+
+    /// Serialize the move as text
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the serializer does
+    pub fn serialize<S: Serializer>(m: &Move, serializer: S) -> Result<S::Ok, S::Error> {
+        super::serialize_text(m, serializer)
+    }
+
+    /// Deserialize a move from text
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the text isn't a valid move
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Move, D::Error> {
+        super::deserialize_text(deserializer)
+    }
+}
+
+/// Serialize a [`Move`] as an enum with its squares as structs, the
+/// same as [`square_struct`]
+pub mod move_struct {
+    use super::{square_struct, Castling, Deserializer, Move, PieceType, Serializer, SquareSpec};
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[serde(remote = "Move")]
+    enum MoveDef {
+        Normal {
+            #[serde(with = "square_struct")]
+            from: SquareSpec,
+            #[serde(with = "square_struct")]
+            to: SquareSpec,
+        },
+        Castling(Castling),
+        Promotion {
+            #[serde(with = "square_struct")]
+            from: SquareSpec,
+            #[serde(with = "square_struct")]
+            to: SquareSpec,
+            target: PieceType,
+        },
+    }
+
+    /// Serialize the move as an enum
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the serializer does
+    pub fn serialize<S: Serializer>(m: &Move, serializer: S) -> Result<S::Ok, S::Error> {
+        MoveDef::serialize(m, serializer)
+    }
+
+    /// Deserialize a move from an enum
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the enum is malformed
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Move, D::Error> {
+        MoveDef::deserialize(deserializer)
+    }
+}
+
+impl Serialize for SquareSpec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        square_text::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SquareSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SquareSpec, D::Error> {
+        square_text::deserialize(deserializer)
+    }
+}
+
+impl Serialize for Move {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        move_text::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Move {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Move, D::Error> {
+        move_text::deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Move, SquareSpec};
+    use serde::de::value::{Error, StrDeserializer};
+    use serde::de::IntoDeserializer;
+    use serde::Deserialize;
+
+    fn text(s: &str) -> StrDeserializer<'_, Error> {
+        s.into_deserializer()
+    }
+
+    #[test]
+    fn reads_text() {
+        assert_eq!(SquareSpec::deserialize(text("e4")).unwrap(), SquareSpec::E4);
+        assert!(SquareSpec::deserialize(text("i9")).is_err());
+        assert_eq!(
+            Move::deserialize(text("e7e8=Q")).unwrap(),
+            "e7e8q".parse().unwrap()
+        );
+        assert!(Move::deserialize(text("e4")).is_err());
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn json_representations() {
+        #[derive(Debug, PartialEq, serde::Serialize, Deserialize)]
+        struct Both {
+            text: Move,
+            #[serde(with = "super::move_struct")]
+            structured: Move,
+            #[serde(with = "super::square_struct")]
+            square: SquareSpec,
+        }
+        let both = Both {
+            text: "e2e4".parse().unwrap(),
+            structured: "e2e4".parse().unwrap(),
+            square: SquareSpec::E4,
+        };
+
+        let json = serde_json::to_string(&both).unwrap();
+        assert_eq!(
+            json,
+            r#"{"text":"e2e4","structured":{"Normal":{"from":{"rank":1,"file":4},"to":{"rank":3,"file":4}}},"square":{"rank":3,"file":4}}"#
+        );
+        assert_eq!(serde_json::from_str::<Both>(&json).unwrap(), both);
+    }
+}
@@ -0,0 +1,138 @@
+//! Module for summary statistics of a game, such as how many captures
+//! and checks each player made, for frontends to show after a game.
+
+use crate::board::{Board, MoveInfo};
+use crate::eval::{Evaluator, MaterialEvaluator};
+use crate::game::Game;
+use crate::piece::Color;
+
+/// The statistics of one player in a game
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SideStats {
+    /// How many moves the player made
+    pub moves: u32,
+    /// How many of the moves were captures, including en passant
+    pub captures: u32,
+    /// How many of the moves gave check, including checkmate
+    pub checks: u32,
+    /// How many times the player castled, which is at most once
+    pub castles: u32,
+    /// How many of the moves were promotions
+    pub promotions: u32,
+    /// The average number of legal moves the player had to choose
+    /// from before each of their moves, or 0 if they haven't moved
+    pub average_mobility: f64,
+}
+
+/// The statistics of a game, as produced by [`Game::stats`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GameStats {
+    /// The statistics of white
+    pub white: SideStats,
+    /// The statistics of black
+    pub black: SideStats,
+    /// The material balance in centipawns after each ply, starting
+    /// with the initial board, positive when white is ahead. Pieces
+    /// are counted with the standard 1/3/3/5/9 values.
+    pub material: Vec<i32>,
+}
+
+impl GameStats {
+    /// Get the statistics of a player
+    pub fn side(&self, color: Color) -> &SideStats {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        }
+    }
+}
+
+impl Game {
+    /// Get the statistics of the game so far
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::game::Game;
+    /// # use chess_engine::piece::Color;
+    /// let mut game = Game::new();
+    /// // 1. e4 d5 2. exd5 Qxd5 3. Nc3 Qe5+
+    /// for m in &["e2e4", "d7d5", "e4d5", "d8d5", "b1c3", "d5e5"] {
+    ///     game.make_move(m.parse().unwrap());
+    /// }
+    /// let stats = game.stats();
+    ///
+    /// assert_eq!(stats.white.captures, 1);
+    /// assert_eq!(stats.side(Color::Black).captures, 1);
+    /// assert_eq!(stats.black.checks, 1);
+    /// assert_eq!(stats.white.average_mobility, (20 + 30 + 30) as f64 / 3.0);
+    /// assert_eq!(stats.material, [0, 0, 0, 100, 0, 0, 0]);
+    /// ```
+    #[allow(clippy::cast_precision_loss)]
+    pub fn stats(&self) -> GameStats {
+        let boards: Vec<_> = self.boards().collect();
+        let mut stats = GameStats {
+            material: boards.iter().map(material).collect(),
+            ..GameStats::default()
+        };
+        // the total number of legal moves white and black had
+        let (mut white_mobility, mut black_mobility) = (0, 0);
+
+        for (pair, &m) in boards.windows(2).zip(self.get_moves()) {
+            let (before, after) = (&pair[0], &pair[1]);
+            let info = MoveInfo::new(before, m, after);
+            let (side, mobility) = match before.turn() {
+                Color::White => (&mut stats.white, &mut white_mobility),
+                Color::Black => (&mut stats.black, &mut black_mobility),
+            };
+
+            side.moves += 1;
+            side.captures += u32::from(info.is_capture());
+            side.checks += u32::from(info.check);
+            side.castles += u32::from(info.castling.is_some());
+            side.promotions += u32::from(info.promotion.is_some());
+            *mobility += before.get_all_legal_moves().len();
+        }
+
+        for (side, mobility) in [
+            (&mut stats.white, white_mobility),
+            (&mut stats.black, black_mobility),
+        ] {
+            if side.moves > 0 {
+                side.average_mobility = mobility as f64 / f64::from(side.moves);
+            }
+        }
+        stats
+    }
+}
+
+// The material balance of a board for white
+fn material(board: &Board) -> i32 {
+    let score = MaterialEvaluator.evaluate(board);
+    match board.turn() {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
+
+#[cfg(all(test, feature = "fen"))]
+mod tests {
+    use crate::board::Board;
+    use crate::game::Game;
+
+    #[test]
+    fn castling_and_promotion() {
+        let board = Board::load_fen("4k3/1P6/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let mut game = Game::from_board(board);
+        for m in &["O-O", "e8d7", "b7b8q"] {
+            let _ = game.make_move(m.parse().unwrap()).unwrap();
+        }
+        let stats = game.stats();
+
+        assert_eq!((stats.white.moves, stats.black.moves), (2, 1));
+        assert_eq!(stats.white.castles, 1);
+        assert_eq!(stats.white.promotions, 1);
+        assert_eq!(stats.white.checks, 0);
+        assert_eq!(stats.material, [600, 600, 600, 1400]);
+    }
+}
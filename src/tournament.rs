@@ -0,0 +1,479 @@
+//! Module for tournaments between any number of players, keeping
+//! track of the games of each round, pairing the players round robin
+//! or with the Swiss system, and working out the standings.
+//!
+//! The games are ordinary [`Game`]s, which are played by making moves
+//! on them, e.g. as the moves come in to a server. A game counts once
+//! it's over, also when it was ended with [`Game::adjudicate`].
+
+use crate::game::{Game, GameResult, GameState};
+use crate::pgn::PgnGame;
+use crate::piece::Color;
+use std::cmp::Ordering;
+
+/// How the players of a tournament are paired
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TournamentFormat {
+    /// Every player plays every other player once. With an odd number
+    /// of players, one player gets a bye each round.
+    RoundRobin,
+    /// Each round, players with the same score or as close to it as
+    /// possible are paired, avoiding rematches where possible
+    Swiss {
+        /// How many rounds to play
+        rounds: u32,
+    },
+}
+
+/// A game of a tournament
+#[derive(Clone, Debug)]
+pub struct TournamentGame {
+    /// The index of the player playing white
+    pub white: usize,
+    /// The index of the player playing black
+    pub black: usize,
+    /// The game itself
+    pub game: Game,
+}
+
+impl TournamentGame {
+    /// Get the score of a player in this game: 1 for a win, 0.5 for a
+    /// draw and 0 for a loss. Returns [`None`] if the game isn't over
+    /// yet or the player isn't playing in it.
+    pub fn score(&self, player: usize) -> Option<f64> {
+        let color = if player == self.white {
+            Color::White
+        } else if player == self.black {
+            Color::Black
+        } else {
+            return None;
+        };
+        match self.game.state() {
+            GameState::Finished(GameResult::Win { winner, .. }) if winner == color => Some(1.0),
+            GameState::Finished(GameResult::Win { .. }) => Some(0.0),
+            GameState::Finished(GameResult::Draw(_)) => Some(0.5),
+            GameState::Ongoing { .. } => None,
+        }
+    }
+
+    /// Check whether the game is over
+    pub fn is_finished(&self) -> bool {
+        matches!(self.game.state(), GameState::Finished(_))
+    }
+}
+
+/// A round of a tournament
+#[derive(Clone, Debug, Default)]
+pub struct Round {
+    /// The games of the round
+    pub games: Vec<TournamentGame>,
+    /// The player who sat out the round, if any, which counts as a win
+    pub bye: Option<usize>,
+}
+
+/// The standing of a player in a tournament, as produced by
+/// [`Tournament::standings`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Standing {
+    /// The index of the player
+    pub player: usize,
+    /// The player's score, counting byes as wins
+    pub points: f64,
+    /// The Sonneborn-Berger score, the sum of the scores of the
+    /// opponents the player beat and half of those they drew with,
+    /// which breaks ties
+    pub tiebreak: f64,
+    /// The number of finished games the player won
+    pub wins: u32,
+    /// The number of finished games the player drew
+    pub draws: u32,
+    /// The number of finished games the player lost
+    pub losses: u32,
+}
+
+/// A tournament between a number of players
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::tournament::{Tournament, TournamentFormat};
+/// # use chess_engine::game::{DrawReason, GameResult};
+/// let mut tournament = Tournament::new(
+///     "Club championship",
+///     &["Alice", "Bob", "Carol", "Dave"],
+///     TournamentFormat::RoundRobin,
+/// );
+/// assert_eq!(tournament.total_rounds(), 3);
+///
+/// while tournament.pair_next_round().is_some() {
+///     for game in &mut tournament.rounds_mut().last_mut().unwrap().games {
+///         // the players make their moves here
+///         game.game.adjudicate(GameResult::Draw(DrawReason::Agreement));
+///     }
+/// }
+///
+/// assert!(tournament.is_finished());
+/// let standings = tournament.standings();
+/// assert!(standings.iter().all(|s| s.points == 1.5 && s.draws == 3));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Tournament {
+    event: String,
+    players: Vec<String>,
+    format: TournamentFormat,
+    rounds: Vec<Round>,
+}
+
+impl Tournament {
+    /// Create a tournament with no rounds played yet
+    pub fn new<S: AsRef<str>>(event: &str, players: &[S], format: TournamentFormat) -> Tournament {
+        Tournament {
+            event: event.to_string(),
+            players: players.iter().map(|p| p.as_ref().to_string()).collect(),
+            format,
+            rounds: vec![],
+        }
+    }
+
+    /// Get the name of the tournament
+    pub fn event(&self) -> &str {
+        &self.event
+    }
+
+    /// Get the names of the players, whose indices identify them
+    /// elsewhere
+    pub fn players(&self) -> &[String] {
+        &self.players
+    }
+
+    /// Get how the players are paired
+    pub fn format(&self) -> TournamentFormat {
+        self.format
+    }
+
+    /// Get how many rounds the tournament has in total
+    pub fn total_rounds(&self) -> u32 {
+        match self.format {
+            TournamentFormat::RoundRobin => {
+                let n = self.players.len() as u32;
+                (n + n % 2).saturating_sub(1)
+            }
+            TournamentFormat::Swiss { rounds } => rounds,
+        }
+    }
+
+    /// Get the rounds that have been paired so far
+    pub fn rounds(&self) -> &[Round] {
+        &self.rounds
+    }
+
+    /// Get the rounds that have been paired so far, to play their games
+    pub fn rounds_mut(&mut self) -> &mut [Round] {
+        &mut self.rounds
+    }
+
+    /// Check whether all rounds have been paired and all their games
+    /// are over
+    pub fn is_finished(&self) -> bool {
+        self.rounds.len() as u32 == self.total_rounds() && self.is_round_finished()
+    }
+
+    // Whether all games of the last round are over
+    fn is_round_finished(&self) -> bool {
+        self.rounds.last().map_or(true, |round| {
+            round.games.iter().all(TournamentGame::is_finished)
+        })
+    }
+
+    /// Pair the players for the next round, and return it. Returns
+    /// [`None`] if the games of the current round aren't all over
+    /// yet, or if all rounds have been played.
+    pub fn pair_next_round(&mut self) -> Option<&Round> {
+        if !self.is_round_finished() || self.rounds.len() as u32 >= self.total_rounds() {
+            return None;
+        }
+        let round = match self.format {
+            TournamentFormat::RoundRobin => self.round_robin_round(self.rounds.len()),
+            TournamentFormat::Swiss { .. } => self.swiss_round(),
+        };
+        self.rounds.push(round);
+        self.rounds.last()
+    }
+
+    // Pair a round of a round robin with the circle method: the first
+    // player stays put while the others rotate around them, and a
+    // missing player stands for a bye
+    fn round_robin_round(&self, index: usize) -> Round {
+        let mut circle: Vec<Option<usize>> = (0..self.players.len()).map(Some).collect();
+        if circle.len() % 2 == 1 {
+            circle.push(None);
+        }
+        let n = circle.len();
+        circle[1..].rotate_right(index % (n - 1));
+
+        let mut round = Round::default();
+        for i in 0..n / 2 {
+            match (circle[i], circle[n - 1 - i]) {
+                (Some(a), Some(b)) => {
+                    let (white, black) = self.colors(a, b, i % 2 == index % 2);
+                    round.games.push(TournamentGame {
+                        white,
+                        black,
+                        game: Game::new(),
+                    });
+                }
+                (Some(player), None) | (None, Some(player)) => round.bye = Some(player),
+                (None, None) => {}
+            }
+        }
+        round
+    }
+
+    // Pair a Swiss round: the players are ranked by score, the one
+    // ranked lowest that hasn't had a bye yet gets one if needed, and
+    // then each player is paired with the next one down they haven't
+    // played yet, or just the next one if they've played them all
+    fn swiss_round(&self) -> Round {
+        let mut ranked: Vec<usize> = self.standings().iter().map(|s| s.player).collect();
+        let mut round = Round::default();
+        if ranked.len() % 2 == 1 {
+            let had_bye = |player: usize| self.rounds.iter().any(|r| r.bye == Some(player));
+            let bye = ranked
+                .iter()
+                .rposition(|&player| !had_bye(player))
+                .unwrap_or(ranked.len() - 1);
+            round.bye = Some(ranked.remove(bye));
+        }
+
+        while !ranked.is_empty() {
+            let player = ranked.remove(0);
+            let opponent = ranked
+                .iter()
+                .position(|&other| !self.have_played(player, other))
+                .unwrap_or(0);
+            let opponent = ranked.remove(opponent);
+            let (white, black) = self.colors(player, opponent, true);
+            round.games.push(TournamentGame {
+                white,
+                black,
+                game: Game::new(),
+            });
+        }
+        round
+    }
+
+    // Give white to whichever player has had it less often, or to `a`
+    // if they've had it equally often and `a_first` is set
+    fn colors(&self, a: usize, b: usize, a_first: bool) -> (usize, usize) {
+        let a_white = match self.white_games(a).cmp(&self.white_games(b)) {
+            Ordering::Less => true,
+            Ordering::Greater => false,
+            Ordering::Equal => a_first,
+        };
+        if a_white {
+            (a, b)
+        } else {
+            (b, a)
+        }
+    }
+
+    // Iterate over all games of the tournament
+    fn games(&self) -> impl Iterator<Item = &TournamentGame> {
+        self.rounds.iter().flat_map(|round| &round.games)
+    }
+
+    fn have_played(&self, a: usize, b: usize) -> bool {
+        self.games()
+            .any(|g| (g.white == a && g.black == b) || (g.white == b && g.black == a))
+    }
+
+    fn white_games(&self, player: usize) -> usize {
+        self.games().filter(|g| g.white == player).count()
+    }
+
+    /// Get the standings of the players, ranked by points and then by
+    /// the Sonneborn-Berger tiebreak. Only finished games count.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<_> = (0..self.players.len())
+            .map(|player| Standing {
+                player,
+                points: 0.0,
+                tiebreak: 0.0,
+                wins: 0,
+                draws: 0,
+                losses: 0,
+            })
+            .collect();
+
+        for round in &self.rounds {
+            if let Some(bye) = round.bye {
+                standings[bye].points += 1.0;
+            }
+            for game in &round.games {
+                for &player in &[game.white, game.black] {
+                    let standing = &mut standings[player];
+                    match game.score(player) {
+                        Some(score) if score > 0.75 => standing.wins += 1,
+                        Some(score) if score > 0.25 => standing.draws += 1,
+                        Some(_) => standing.losses += 1,
+                        None => continue,
+                    }
+                    standing.points += game.score(player).unwrap_or(0.0);
+                }
+            }
+        }
+
+        let points: Vec<_> = standings.iter().map(|s| s.points).collect();
+        for game in self.games() {
+            for &(player, opponent) in &[(game.white, game.black), (game.black, game.white)] {
+                if let Some(score) = game.score(player) {
+                    standings[player].tiebreak += score * points[opponent];
+                }
+            }
+        }
+
+        standings.sort_by(|a, b| {
+            b.points
+                .partial_cmp(&a.points)
+                .unwrap_or(Ordering::Equal)
+                .then(
+                    b.tiebreak
+                        .partial_cmp(&a.tiebreak)
+                        .unwrap_or(Ordering::Equal),
+                )
+                .then(a.player.cmp(&b.player))
+        });
+        standings
+    }
+
+    /// Write all games of the tournament as PGN, round by round, with
+    /// the event, round and player tags filled in
+    pub fn to_pgn(&self) -> String {
+        let mut s = String::new();
+        for (number, round) in self.rounds.iter().enumerate() {
+            for game in &round.games {
+                let mut pgn = PgnGame::new(game.game.clone());
+                for (name, value) in &mut pgn.tags {
+                    let new_value = match name.as_str() {
+                        "Event" => self.event.clone(),
+                        "Round" => (number + 1).to_string(),
+                        "White" => self.players[game.white].clone(),
+                        "Black" => self.players[game.black].clone(),
+                        _ => continue,
+                    };
+                    *value = new_value;
+                }
+
+                if !s.is_empty() {
+                    s.push('\n');
+                }
+                s.push_str(&pgn.to_string());
+            }
+        }
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Tournament, TournamentFormat};
+    use crate::game::{GameResult, WinReason};
+    use crate::piece::Color;
+    use std::collections::HashSet;
+
+    // Finish the games of the last round, with the lower-numbered
+    // player winning
+    fn play_round(tournament: &mut Tournament) {
+        for game in &mut tournament.rounds_mut().last_mut().unwrap().games {
+            let winner = if game.white < game.black {
+                Color::White
+            } else {
+                Color::Black
+            };
+            assert!(game.game.adjudicate(GameResult::Win {
+                winner,
+                reason: WinReason::Adjudication,
+            }));
+        }
+    }
+
+    #[test]
+    fn round_robin() {
+        let players = ["a", "b", "c", "d", "e"];
+        let mut tournament = Tournament::new("Test", &players, TournamentFormat::RoundRobin);
+        assert_eq!(tournament.total_rounds(), 5);
+
+        while tournament.pair_next_round().is_some() {
+            // the next round can't be paired before this one is over
+            assert!(tournament.pair_next_round().is_none());
+            play_round(&mut tournament);
+        }
+
+        let mut pairs = HashSet::new();
+        for round in tournament.rounds() {
+            assert_eq!(round.games.len(), 2);
+            assert!(round.bye.is_some());
+            for game in &round.games {
+                assert!(pairs.insert((game.white.min(game.black), game.white.max(game.black))));
+            }
+        }
+        assert_eq!(pairs.len(), 10);
+
+        // the colors are balanced: nobody played all or none of their
+        // four games as white
+        for player in 0..players.len() {
+            let whites = tournament
+                .rounds()
+                .iter()
+                .flat_map(|r| &r.games)
+                .filter(|g| g.white == player)
+                .count();
+            assert!(
+                (1..=3).contains(&whites),
+                "{} played {} as white",
+                player,
+                whites
+            );
+        }
+
+        let standings = tournament.standings();
+        let order: Vec<_> = standings.iter().map(|s| s.player).collect();
+        assert_eq!(order, [0, 1, 2, 3, 4]);
+        // four wins and a bye
+        assert!((standings[0].points - 5.0).abs() < f64::EPSILON);
+        assert_eq!((standings[4].wins, standings[4].losses), (0, 4));
+
+        let pgn = tournament.to_pgn();
+        assert_eq!(pgn.matches("[Event \"Test\"]").count(), 10);
+        assert!(pgn.contains("[Round \"5\"]"));
+        assert!(pgn.contains("[Termination \"adjudication\"]"));
+    }
+
+    #[test]
+    fn swiss() {
+        let players = ["a", "b", "c", "d", "e", "f", "g"];
+        let mut tournament =
+            Tournament::new("Swiss", &players, TournamentFormat::Swiss { rounds: 3 });
+
+        while tournament.pair_next_round().is_some() {
+            play_round(&mut tournament);
+        }
+        assert!(tournament.is_finished());
+
+        let mut pairs = HashSet::new();
+        let mut byes = HashSet::new();
+        for round in tournament.rounds() {
+            assert_eq!(round.games.len(), 3);
+            assert!(byes.insert(round.bye.unwrap()));
+            for game in &round.games {
+                assert!(pairs.insert((game.white.min(game.black), game.white.max(game.black))));
+            }
+        }
+
+        // the first player won every game
+        let standings = tournament.standings();
+        assert_eq!(standings[0].player, 0);
+        assert!((standings[0].points - 3.0).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,350 @@
+//! Module for tuning the parameters of an [`EvalParams`] or
+//! [`TaperedEval`] evaluator with Texel tuning: the evaluation of
+//! each training position is mapped to an expected result with a
+//! logistic function, and the parameters are optimized to minimize
+//! the squared error against the results of the games the positions
+//! came from.
+//!
+//! The evaluation is linear in its parameters, so the features of
+//! each position are only worked out once, and the parameters are
+//! optimized with gradient descent.
+
+use crate::board::Board;
+use crate::board::MAX_PHASE;
+use crate::error::Error;
+use crate::eval::{self, EvalParams, TaperedEval};
+
+/// Trait for evaluators whose evaluation is linear in their
+/// parameters, which is what makes them tunable with [`tune`]
+pub trait Tunable: Sized {
+    /// Get the features of a position, such that the evaluation from
+    /// white's point of view is the sum of each feature's coefficient
+    /// times its parameter, in the order of
+    /// [`to_vec`](Tunable::to_vec)
+    fn features(board: &Board) -> Vec<(usize, f64)>;
+
+    /// Get the parameters flattened into a list
+    fn to_vec(&self) -> Vec<i32>;
+
+    /// Create parameters from a flattened list, as made by
+    /// [`to_vec`](Tunable::to_vec), or [`None`] if it's the wrong
+    /// length
+    fn from_slice(params: &[i32]) -> Option<Self>;
+}
+
+impl Tunable for EvalParams {
+    fn features(board: &Board) -> Vec<(usize, f64)> {
+        EvalParams::features(board)
+            .into_iter()
+            .map(|(index, coefficient)| (index, f64::from(coefficient)))
+            .collect()
+    }
+
+    fn to_vec(&self) -> Vec<i32> {
+        EvalParams::to_vec(self)
+    }
+
+    fn from_slice(params: &[i32]) -> Option<EvalParams> {
+        EvalParams::from_slice(params)
+    }
+}
+
+impl Tunable for TaperedEval {
+    /// The features of both sets of parameters, scaled by how much of
+    /// each the [phase](Board::phase_value) blends in
+    fn features(board: &Board) -> Vec<(usize, f64)> {
+        let middlegame = f64::from(board.phase_value()) / f64::from(MAX_PHASE);
+        let endgame = 1.0 - middlegame;
+        let mut features = vec![];
+        for (index, coefficient) in EvalParams::features(board) {
+            let coefficient = f64::from(coefficient);
+            features.push((index, coefficient * middlegame));
+            features.push((EvalParams::LEN + index, coefficient * endgame));
+        }
+        features
+    }
+
+    fn to_vec(&self) -> Vec<i32> {
+        TaperedEval::to_vec(self)
+    }
+
+    fn from_slice(params: &[i32]) -> Option<TaperedEval> {
+        TaperedEval::from_slice(params)
+    }
+}
+
+/// A position from a game, along with the result of the game
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TrainingPosition {
+    /// The position
+    pub board: Board,
+    /// The result of the game for white: 1 for a win, 0.5 for a draw
+    /// and 0 for a loss
+    pub result: f64,
+}
+
+impl TrainingPosition {
+    /// Parse a position written as FEN followed by the result, as
+    /// `1-0`, `0-1`, `1/2-1/2` or a number between 0 and 1. The
+    /// result may be quoted and followed by a `;`, and preceded by a
+    /// `c9` opcode, as in EPD files.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the FEN or the result is invalid
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chess_engine::tuning::TrainingPosition;
+    /// let line = r#"4k3/8/8/8/8/8/8/3QK3 w - - 0 1 c9 "1-0";"#;
+    /// let position = TrainingPosition::parse(line).unwrap();
+    ///
+    /// assert_eq!(position.result, 1.0);
+    /// assert!(TrainingPosition::parse("4k3/8/8/8/8/8/8/3QK3 w - - 0 1 2-0").is_err());
+    /// ```
+    pub fn parse(line: &str) -> Result<TrainingPosition, Error> {
+        let invalid = || Error::InvalidTrainingData(line.to_string());
+        let mut tokens: Vec<_> = line
+            .trim()
+            .trim_end_matches(';')
+            .split_whitespace()
+            .collect();
+
+        let result = match tokens.pop().map(|t| t.trim_matches(&['"', '[', ']'][..])) {
+            Some("1-0") => 1.0,
+            Some("0-1") => 0.0,
+            Some("1/2-1/2") => 0.5,
+            Some(result) => match result.parse::<f64>() {
+                Ok(result) if (0.0..=1.0).contains(&result) => result,
+                _ => return Err(invalid()),
+            },
+            None => return Err(invalid()),
+        };
+        if tokens.last() == Some(&"c9") {
+            let _ = tokens.pop();
+        }
+
+        Ok(TrainingPosition {
+            board: Board::load_fen(&tokens.join(" "))?,
+            result,
+        })
+    }
+}
+
+/// Parse training positions, one per line as for
+/// [`TrainingPosition::parse`]. Empty lines and lines starting with
+/// `#` are skipped.
+///
+/// # Errors
+///
+/// Will return an error if any line is invalid
+pub fn parse_positions(text: &str) -> Result<Vec<TrainingPosition>, Error> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(TrainingPosition::parse)
+        .collect()
+}
+
+/// Options for [`tune`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TuningOptions {
+    /// How many steps of gradient descent to take
+    pub iterations: u32,
+    /// How far to move the parameters each step, in centipawns
+    pub learning_rate: f64,
+    /// The scaling of the logistic function, see [`eval::logistic`]
+    pub k: f64,
+}
+
+impl Default for TuningOptions {
+    fn default() -> Self {
+        TuningOptions {
+            iterations: 1000,
+            learning_rate: 1.0,
+            k: 1.0,
+        }
+    }
+}
+
+/// Get the mean squared error of the expected results of `params`
+/// against the actual results of the positions
+#[allow(clippy::cast_precision_loss)]
+pub fn error<P: Tunable>(positions: &[TrainingPosition], params: &P, k: f64) -> f64 {
+    if positions.is_empty() {
+        return 0.0;
+    }
+    let params: Vec<_> = params.to_vec().into_iter().map(f64::from).collect();
+    let total: f64 = positions
+        .iter()
+        .map(|position| {
+            let score = score(&P::features(&position.board), &params);
+            (position.result - eval::logistic(score, k)).powi(2)
+        })
+        .sum();
+    total / positions.len() as f64
+}
+
+// The evaluation for white of a position with the given features
+fn score(features: &[(usize, f64)], params: &[f64]) -> f64 {
+    features
+        .iter()
+        .map(|&(index, coefficient)| params[index] * coefficient)
+        .sum()
+}
+
+/// Tune the parameters of an evaluator, starting from `initial`, to
+/// predict the results of the positions, with Adam gradient descent.
+/// Without any positions, `initial` is returned unchanged.
+///
+/// # Examples
+///
+/// ```
+/// # use chess_engine::eval::EvalParams;
+/// # use chess_engine::tuning::{self, TuningOptions};
+/// // white wins whenever it has an extra pawn on the 7th rank
+/// let positions = tuning::parse_positions(
+///     "4k3/P7/8/8/8/8/8/4K3 w - - 0 1 1-0
+///      4k3/8/8/8/8/8/P7/4K3 w - - 0 1 1/2-1/2",
+/// )
+/// .unwrap();
+/// let initial = EvalParams::default();
+/// let tuned = tuning::tune(&positions, &initial, &TuningOptions::default());
+///
+/// assert!(tuning::error(&positions, &tuned, 1.0) < tuning::error(&positions, &initial, 1.0));
+/// assert!(tuned.psts[0][6 * 8] > tuned.psts[0][8]);
+/// ```
+#[allow(clippy::cast_precision_loss)]
+pub fn tune<P: Tunable + Copy>(
+    positions: &[TrainingPosition],
+    initial: &P,
+    options: &TuningOptions,
+) -> P {
+    const BETA1: f64 = 0.9;
+    const BETA2: f64 = 0.999;
+    const EPSILON: f64 = 1e-8;
+
+    if positions.is_empty() {
+        return *initial;
+    }
+    let features: Vec<_> = positions
+        .iter()
+        .map(|position| P::features(&position.board))
+        .collect();
+    let mut params: Vec<_> = initial.to_vec().into_iter().map(f64::from).collect();
+    let mut momentum = vec![0.0; params.len()];
+    let mut velocity = vec![0.0; params.len()];
+    // the slope of the logistic function at a score, per centipawn
+    let slope = options.k * 10_f64.ln() / eval::LOGISTIC_SCALE;
+
+    for step in 1..=options.iterations {
+        let mut gradient = vec![0.0; params.len()];
+        for (position, features) in positions.iter().zip(&features) {
+            let expected = eval::logistic(score(features, &params), options.k);
+            let factor = -2.0 * (position.result - expected) * expected * (1.0 - expected) * slope;
+            for &(index, coefficient) in features {
+                gradient[index] += factor * coefficient;
+            }
+        }
+
+        for i in 0..params.len() {
+            let g = gradient[i] / positions.len() as f64;
+            momentum[i] = BETA1 * momentum[i] + (1.0 - BETA1) * g;
+            velocity[i] = BETA2 * velocity[i] + (1.0 - BETA2) * g * g;
+            let m = momentum[i] / (1.0 - BETA1.powi(step as i32));
+            let v = velocity[i] / (1.0 - BETA2.powi(step as i32));
+            params[i] -= options.learning_rate * m / (v.sqrt() + EPSILON);
+        }
+    }
+
+    let params: Vec<_> = params.iter().map(|p| p.round() as i32).collect();
+    P::from_slice(&params).unwrap_or(*initial)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{error, parse_positions, tune, TrainingPosition, TuningOptions};
+    use crate::eval::{EvalParams, TaperedEval};
+
+    #[test]
+    fn parsing_positions() {
+        let positions = parse_positions(
+            "# a comment
+            rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1 0.5
+
+            4k3/8/8/8/8/8/8/3QK3 w - - 0 1 [0-1]",
+        )
+        .unwrap();
+
+        assert_eq!(positions.len(), 2);
+        assert!((positions[0].result - 0.5).abs() < f64::EPSILON);
+        assert!(positions[1].result.abs() < f64::EPSILON);
+        assert!(TrainingPosition::parse("1-0").is_err());
+        assert!(TrainingPosition::parse("4k3/8/8/8/8/8/8/3QK3 w - - 0 1 1.5").is_err());
+    }
+
+    #[test]
+    fn tuning_material() {
+        // an extra knight always wins, but the queen doesn't matter
+        let positions = parse_positions(
+            "4k3/8/8/8/8/8/8/1N2K3 w - - 0 1 1-0
+             1n2k3/8/8/8/8/8/8/4K3 w - - 0 1 0-1
+             4k3/8/8/8/8/8/8/3QK3 b - - 0 1 1/2-1/2
+             3qk3/8/8/8/8/8/8/4K3 b - - 0 1 1/2-1/2",
+        )
+        .unwrap();
+        let initial = EvalParams::default();
+        let options = TuningOptions {
+            iterations: 300,
+            learning_rate: 5.0,
+            k: 1.0,
+        };
+        let tuned = tune(&positions, &initial, &options);
+
+        assert!(error(&positions, &tuned, 1.0) < error(&positions, &initial, 1.0));
+        assert!(tuned.material[1] > initial.material[1]);
+        assert!(tuned.material[4] < initial.material[4]);
+        assert_eq!(
+            tune(
+                &positions,
+                &initial,
+                &TuningOptions {
+                    iterations: 0,
+                    ..options
+                }
+            ),
+            initial
+        );
+    }
+
+    #[test]
+    fn tuning_tapered() {
+        // pawn endings only use the endgame parameters
+        let positions = parse_positions(
+            "4k3/8/8/8/8/8/P7/4K3 w - - 0 1 1-0
+             4k3/p7/8/8/8/8/8/4K3 w - - 0 1 0-1
+             4k3/8/8/8/8/8/8/4K3 w - - 0 1 1/2-1/2",
+        )
+        .unwrap();
+        let initial = TaperedEval::default();
+        let options = TuningOptions {
+            iterations: 100,
+            learning_rate: 5.0,
+            k: 1.0,
+        };
+        let tuned = tune(&positions, &initial, &options);
+
+        assert!(error(&positions, &tuned, 1.0) < error(&positions, &initial, 1.0));
+        assert_eq!(tuned.middlegame, initial.middlegame);
+        assert!(tuned.endgame.material[0] > initial.endgame.material[0]);
+    }
+
+    #[test]
+    fn tuning_without_positions() {
+        let initial = EvalParams::default();
+        assert_eq!(tune(&[], &initial, &TuningOptions::default()), initial);
+        let initial = TaperedEval::default();
+        assert_eq!(tune(&[], &initial, &TuningOptions::default()), initial);
+    }
+}